@@ -1,14 +1,18 @@
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::{__cpuid, __rdtscp, _mm_lfence, _mm_mfence, _mm_pause, _mm_sfence, _rdtsc};
 use std::{
-    arch::{
-        asm,
-        x86_64::{__cpuid, _mm_lfence, _mm_mfence, _mm_sfence},
-    },
-    thread::yield_now,
+    arch::asm,
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
     time::{Duration, Instant},
 };
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, Bencher, BenchmarkId, Criterion};
+use crossbeam_utils::CachePadded;
 use futures::future::join_all;
+use instruction_benchmark::{bench::TagFilter, config::Config, watchdog::Watchdog};
 use lazy_static::lazy_static;
 use tokio::runtime::{Builder, Runtime};
 
@@ -24,44 +28,133 @@ fn fibonacci(n: u64) -> u64 {
     }
 }
 
-const FIB_N: u64 = 30;
-const SLEEP_MS: u64 = 25;
-
 lazy_static! {
-    static ref NUM_THREADS_SMALL: usize = core_affinity::get_core_ids().unwrap().len() / 2;
-    static ref NUM_THREADS_LARGE: usize = *NUM_THREADS_SMALL * 8;
-    static ref NUM_THREADS_HUGE: usize = *NUM_THREADS_SMALL * 256;
+    static ref CONFIG: Config = Config::from_env().unwrap();
+    static ref FIB_N: u64 = CONFIG.fib_n;
+    static ref SLEEP_MS: u64 = CONFIG.sleep_ms;
+    static ref WORK_THREADS: Vec<usize> = Config::thread_counts(&CONFIG.sweep.work_threads);
+    static ref SLEEP_THREADS: Vec<usize> = Config::thread_counts(&CONFIG.sweep.sleep_threads);
+    static ref SLEEPERS: Vec<Sleepers> = SLEEP_THREADS
+        .iter()
+        .flat_map(|&threads| {
+            CONFIG
+                .sweep
+                .sleep_ms
+                .iter()
+                .map(move |&ms| Sleepers { threads, ms })
+        })
+        .collect();
+    static ref TAG_FILTER: TagFilter = TagFilter::from_env();
+    static ref WATCHDOG: Option<Watchdog> = CONFIG
+        .timeout_secs
+        .map(|secs| Watchdog::spawn(Duration::from_secs(secs)));
+}
+
+/// A point of the sleeping thread count × sleep duration matrix.
+#[derive(Clone, Copy)]
+struct Sleepers {
+    threads: usize,
+    ms: u64,
+}
+
+impl Sleepers {
+    fn duration(&self) -> Duration {
+        Duration::from_millis(self.ms)
+    }
+}
+
+impl fmt::Display for Sleepers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}ms", self.threads, self.ms)
+    }
+}
+
+/// `Criterion::bench_function`, skipped unless `tags` pass the `MICROBENCH_TAGS` and
+/// `MICROBENCH_SKIP_TAGS` filters, and aborted if it outlasts the configured `timeout_secs`.
+fn tagged_bench<F>(c: &mut Criterion, tags: &[&str], id: &str, f: F)
+where
+    F: FnMut(&mut Bencher),
+{
+    if TAG_FILTER.matches(tags) {
+        let _armed = WATCHDOG.as_ref().map(|w| w.arm(id));
+        c.bench_function(id, f);
+    }
+}
+
+/// Runs `f` once per parameter as the Criterion group `name`, so that results (and Criterion's
+/// plots) are grouped per parameter value. Filtered and watched like `tagged_bench`.
+fn tagged_sweep<P, F>(c: &mut Criterion, tags: &[&str], name: &str, params: &[P], mut f: F)
+where
+    P: fmt::Display,
+    F: FnMut(&mut Bencher, &P),
+{
+    if !TAG_FILTER.matches(tags) {
+        return;
+    }
+
+    let mut group = c.benchmark_group(name);
+    for param in params {
+        let _armed = WATCHDOG.as_ref().map(|w| w.arm(&format!("{name}/{param}")));
+        group.bench_with_input(BenchmarkId::from_parameter(param), param, &mut f);
+    }
+    group.finish();
 }
 
 fn fib_benchmark(c: &mut Criterion) {
-    c.bench_function("fibonacci(FIB_N)", |b| {
-        b.iter(|| fibonacci(FIB_N));
-    });
+    if !CONFIG.suite_enabled("fib") {
+        return;
+    }
+
+    tagged_sweep(
+        c,
+        &["cpu", "sweep"],
+        "fibonacci",
+        &CONFIG.sweep.fib_n,
+        |b, &n| {
+            b.iter(|| fibonacci(n));
+        },
+    );
 }
 
 fn system_benchmark(c: &mut Criterion) {
-    c.bench_function("spawn os thread", |b| {
+    if !CONFIG.suite_enabled("system") {
+        return;
+    }
+
+    tagged_bench(c, &["thread"], "spawn os thread", |b| {
         b.iter(|| std::thread::spawn(|| {}).join().unwrap())
     });
 
-    c.bench_function("spawn single os thread expensive calculation", |b| {
-        b.iter(|| std::thread::spawn(|| fibonacci(FIB_N)).join().unwrap())
-    });
+    tagged_bench(
+        c,
+        &["thread"],
+        "spawn single os thread expensive calculation",
+        |b| b.iter(|| std::thread::spawn(|| fibonacci(*FIB_N)).join().unwrap()),
+    );
 
-    c.bench_function("spawn single os thread sleep", |b| {
-        b.iter(|| {
-            std::thread::spawn(|| std::thread::sleep(Duration::from_millis(SLEEP_MS)))
-                .join()
-                .unwrap()
-        })
-    });
+    tagged_sweep(
+        c,
+        &["thread", "sleep", "sweep"],
+        "spawn single os thread sleep",
+        &CONFIG.sweep.sleep_ms,
+        |b, &ms| {
+            b.iter(|| {
+                std::thread::spawn(move || std::thread::sleep(Duration::from_millis(ms)))
+                    .join()
+                    .unwrap()
+            })
+        },
+    );
 
-    c.bench_function(
-        "spawn small multiple os thread expensive calculation",
-        |b| {
+    tagged_sweep(
+        c,
+        &["thread", "slow", "sweep"],
+        "spawn multiple os thread expensive calculation",
+        &WORK_THREADS,
+        |b, &threads| {
             b.iter(|| {
-                let threads = (0..*NUM_THREADS_SMALL)
-                    .map(|_| std::thread::spawn(|| fibonacci(FIB_N)))
+                let threads = (0..threads)
+                    .map(|_| std::thread::spawn(|| fibonacci(*FIB_N)))
                     .collect::<Vec<_>>();
 
                 threads.into_iter().map(|t| t.join().unwrap()).sum::<u64>();
@@ -69,73 +162,45 @@ fn system_benchmark(c: &mut Criterion) {
         },
     );
 
-    c.bench_function("spawn small multiple os thread sleep", |b| {
-        b.iter(|| {
-            let threads = (0..*NUM_THREADS_SMALL)
-                .map(|_| std::thread::spawn(|| std::thread::sleep(Duration::from_millis(SLEEP_MS))))
-                .collect::<Vec<_>>();
-
-            threads
-                .into_iter()
-                .map(|t| t.join().unwrap())
-                .for_each(|_| {});
-        })
-    });
-
-    c.bench_function(
-        "spawn large multiple os thread expensive calculation",
-        |b| {
+    tagged_sweep(
+        c,
+        &["thread", "sleep", "slow", "sweep"],
+        "spawn multiple os thread sleep",
+        &SLEEPERS,
+        |b, sleepers| {
             b.iter(|| {
-                let threads = (0..*NUM_THREADS_LARGE)
-                    .map(|_| std::thread::spawn(|| fibonacci(FIB_N)))
+                let threads = (0..sleepers.threads)
+                    .map(|_| {
+                        let duration = sleepers.duration();
+                        std::thread::spawn(move || std::thread::sleep(duration))
+                    })
                     .collect::<Vec<_>>();
 
-                threads.into_iter().map(|t| t.join().unwrap()).sum::<u64>();
+                threads
+                    .into_iter()
+                    .map(|t| t.join().unwrap())
+                    .for_each(|_| {});
             })
         },
     );
 
-    c.bench_function("spawn large multiple os thread sleep", |b| {
-        b.iter(|| {
-            let threads = (0..*NUM_THREADS_LARGE)
-                .map(|_| std::thread::spawn(|| std::thread::sleep(Duration::from_millis(SLEEP_MS))))
-                .collect::<Vec<_>>();
-
-            threads
-                .into_iter()
-                .map(|t| t.join().unwrap())
-                .for_each(|_| {});
-        })
-    });
-
-    c.bench_function("spawn huge multiple os thread sleep", |b| {
-        b.iter(|| {
-            let threads = (0..*NUM_THREADS_HUGE)
-                .map(|_| std::thread::spawn(|| std::thread::sleep(Duration::from_millis(SLEEP_MS))))
-                .collect::<Vec<_>>();
-
-            threads
-                .into_iter()
-                .map(|t| t.join().unwrap())
-                .for_each(|_| {});
-        })
-    });
-
-    c.bench_function(
+    tagged_bench(
+        c,
+        &["thread", "sleep", "slow"],
         "spawn os thread large worker huge sleep complex workload",
         |b| {
             b.iter(|| {
-                let work_threads = (0..*NUM_THREADS_LARGE)
+                let work_threads = (0..*WORK_THREADS.last().unwrap())
                     .map(|_| {
                         std::thread::spawn(|| {
-                            fibonacci(FIB_N);
+                            fibonacci(*FIB_N);
                         })
                     })
                     .collect::<Vec<_>>();
 
-                let sleep_threads = (0..*NUM_THREADS_HUGE)
+                let sleep_threads = (0..*SLEEP_THREADS.last().unwrap())
                     .map(|_| {
-                        std::thread::spawn(|| std::thread::sleep(Duration::from_millis(SLEEP_MS)))
+                        std::thread::spawn(|| std::thread::sleep(Duration::from_millis(*SLEEP_MS)))
                     })
                     .collect::<Vec<_>>();
 
@@ -147,128 +212,206 @@ fn system_benchmark(c: &mut Criterion) {
             })
         },
     );
-}
 
-fn tokio_benchmark(c: &mut Criterion) {
-    c.bench_function("spawn tokio thread", |b| {
-        b.to_async(multi_thread_tokio_runtime())
-            .iter(|| async { tokio::task::spawn(async {}).await.unwrap() });
-    });
+    false_sharing_benchmarks(c);
+}
 
-    c.bench_function("spawn single tokio thread expensive calculation", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            tokio::task::spawn(async { fibonacci(FIB_N) })
-                .await
-                .unwrap()
-        });
+/// Thread counts of the false sharing benchmarks, at most a cache line's worth of counters.
+const SHARING_THREADS: [usize; 4] = [1, 2, 4, 8];
+/// Increments per thread and iteration, enough to dwarf spawning the threads.
+const INCREMENTS: u64 = 1 << 16;
+
+/// One counter per thread, all in one cache line.
+#[repr(align(64))]
+#[derive(Default)]
+struct Packed([AtomicU64; 8]);
+
+/// Has a thread per counter increment its own, `INCREMENTS` times.
+fn increment<'a>(counters: impl Iterator<Item = &'a AtomicU64>) {
+    std::thread::scope(|scope| {
+        for counter in counters {
+            scope.spawn(move || {
+                for _ in 0..INCREMENTS {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
     });
+}
 
-    c.bench_function("spawn single tokio thread sleep", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            tokio::task::spawn(async { tokio::time::sleep(Duration::from_millis(SLEEP_MS)).await })
-                .await
-                .unwrap()
-        });
-    });
+/// Mean time per iteration over every call of a Criterion `iter_custom` routine.
+#[derive(Default)]
+struct Mean {
+    total: Duration,
+    iterations: u64,
+}
 
-    c.bench_function("spawn small tokio thread expensive calculation", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            let tasks = (0..*NUM_THREADS_SMALL)
-                .map(|_| tokio::task::spawn(async { fibonacci(FIB_N) }))
-                .collect::<Vec<_>>();
+impl Mean {
+    fn time(&mut self, iterations: u64, mut f: impl FnMut()) -> Duration {
+        let start = Instant::now();
+        for _ in 0..iterations {
+            f();
+        }
+        let elapsed = start.elapsed();
+        self.total += elapsed;
+        self.iterations += iterations;
+        elapsed
+    }
 
-            join_all(tasks)
-                .await
-                .into_iter()
-                .map(|res| res.unwrap())
-                .sum::<u64>()
-        });
-    });
+    fn get(&self) -> f64 {
+        self.total.as_secs_f64() / self.iterations.max(1) as f64
+    }
+}
 
-    c.bench_function("spawn small tokio thread sleep", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            let tasks = (0..*NUM_THREADS_SMALL)
-                .map(|_| {
-                    tokio::task::spawn(async {
-                        tokio::time::sleep(Duration::from_millis(SLEEP_MS)).await
-                    })
-                })
+/// Threads incrementing their own counter, packed into one cache line (false sharing) and
+/// padded to a line each, then the packed slowdown per thread count.
+fn false_sharing_benchmarks(c: &mut Criterion) {
+    let means = RefCell::new(BTreeMap::<(&str, usize), Mean>::new());
+    tagged_sweep(
+        c,
+        &["thread", "sharing", "sweep"],
+        "false sharing packed",
+        &SHARING_THREADS,
+        |b, &threads| {
+            let counters = Packed::default();
+            let mut means = means.borrow_mut();
+            let mean = means.entry(("packed", threads)).or_default();
+            b.iter_custom(|iterations| {
+                mean.time(iterations, || increment(counters.0[..threads].iter()))
+            });
+        },
+    );
+    tagged_sweep(
+        c,
+        &["thread", "sharing", "sweep"],
+        "false sharing padded",
+        &SHARING_THREADS,
+        |b, &threads| {
+            let counters = (0..threads)
+                .map(|_| CachePadded::new(AtomicU64::new(0)))
                 .collect::<Vec<_>>();
+            let mut means = means.borrow_mut();
+            let mean = means.entry(("padded", threads)).or_default();
+            b.iter_custom(|iterations| {
+                mean.time(iterations, || increment(counters.iter().map(|c| &**c)))
+            });
+        },
+    );
 
-            join_all(tasks)
-                .await
-                .into_iter()
-                .map(|res| res.unwrap())
-                .for_each(|_| {});
-        });
-    });
+    let means = means.into_inner();
+    for threads in SHARING_THREADS {
+        if let (Some(packed), Some(padded)) = (
+            means.get(&("packed", threads)),
+            means.get(&("padded", threads)),
+        ) {
+            println!(
+                "false sharing slowdown with {threads} threads: {:.2}x",
+                packed.get() / padded.get()
+            );
+        }
+    }
+}
 
-    c.bench_function("spawn large tokio thread expensive calculation", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            let tasks = (0..*NUM_THREADS_LARGE)
-                .map(|_| tokio::task::spawn(async { fibonacci(FIB_N) }))
-                .collect::<Vec<_>>();
+fn tokio_benchmark(c: &mut Criterion) {
+    if !CONFIG.suite_enabled("tokio") {
+        return;
+    }
 
-            join_all(tasks)
-                .await
-                .into_iter()
-                .map(|res| res.unwrap())
-                .sum::<u64>()
-        });
+    tagged_bench(c, &["tokio", "async"], "spawn tokio thread", |b| {
+        b.to_async(multi_thread_tokio_runtime())
+            .iter(|| async { tokio::task::spawn(async {}).await.unwrap() });
     });
 
-    c.bench_function("spawn large tokio thread sleep", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            let tasks = (0..*NUM_THREADS_LARGE)
-                .map(|_| {
-                    tokio::task::spawn(async {
-                        tokio::time::sleep(Duration::from_millis(SLEEP_MS)).await
-                    })
-                })
-                .collect::<Vec<_>>();
-
-            join_all(tasks)
-                .await
-                .into_iter()
-                .map(|res| res.unwrap())
-                .for_each(|_| {});
-        });
-    });
+    tagged_bench(
+        c,
+        &["tokio", "async"],
+        "spawn single tokio thread expensive calculation",
+        |b| {
+            b.to_async(multi_thread_tokio_runtime()).iter(|| async {
+                tokio::task::spawn(async { fibonacci(*FIB_N) })
+                    .await
+                    .unwrap()
+            });
+        },
+    );
 
-    c.bench_function("spawn huge tokio thread sleep", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            let tasks = (0..*NUM_THREADS_HUGE)
-                .map(|_| {
-                    tokio::task::spawn(async {
-                        tokio::time::sleep(Duration::from_millis(SLEEP_MS)).await
+    tagged_sweep(
+        c,
+        &["tokio", "async", "sleep", "sweep"],
+        "spawn single tokio thread sleep",
+        &CONFIG.sweep.sleep_ms,
+        |b, &ms| {
+            b.to_async(multi_thread_tokio_runtime())
+                .iter(|| async move {
+                    tokio::task::spawn(async move {
+                        tokio::time::sleep(Duration::from_millis(ms)).await
                     })
-                })
-                .collect::<Vec<_>>();
+                    .await
+                    .unwrap()
+                });
+        },
+    );
 
-            join_all(tasks)
-                .await
-                .into_iter()
-                .map(|res| res.unwrap())
-                .for_each(|_| {});
-        });
-    });
+    tagged_sweep(
+        c,
+        &["tokio", "async", "slow", "sweep"],
+        "spawn multiple tokio thread expensive calculation",
+        &WORK_THREADS,
+        |b, &threads| {
+            b.to_async(multi_thread_tokio_runtime())
+                .iter(|| async move {
+                    let tasks = (0..threads)
+                        .map(|_| tokio::task::spawn(async { fibonacci(*FIB_N) }))
+                        .collect::<Vec<_>>();
+
+                    join_all(tasks)
+                        .await
+                        .into_iter()
+                        .map(|res| res.unwrap())
+                        .sum::<u64>()
+                });
+        },
+    );
 
-    c.bench_function(
+    tagged_sweep(
+        c,
+        &["tokio", "async", "sleep", "slow", "sweep"],
+        "spawn multiple tokio thread sleep",
+        &SLEEPERS,
+        |b, &sleepers| {
+            b.to_async(multi_thread_tokio_runtime())
+                .iter(|| async move {
+                    let tasks = (0..sleepers.threads)
+                        .map(|_| tokio::task::spawn(tokio::time::sleep(sleepers.duration())))
+                        .collect::<Vec<_>>();
+
+                    join_all(tasks)
+                        .await
+                        .into_iter()
+                        .map(|res| res.unwrap())
+                        .for_each(|_| {});
+                });
+        },
+    );
+
+    tagged_bench(
+        c,
+        &["tokio", "async", "sleep", "slow"],
         "spawn tokio thread large worker huge sleep complex workload worker tasks first",
         |b| {
             b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-                let work_tasks = (0..*NUM_THREADS_LARGE)
+                let work_tasks = (0..*WORK_THREADS.last().unwrap())
                     .map(|_| {
                         tokio::task::spawn(async {
-                            fibonacci(FIB_N);
+                            fibonacci(*FIB_N);
                         })
                     })
                     .collect::<Vec<_>>();
 
-                let sleep_tasks = (0..*NUM_THREADS_HUGE)
+                let sleep_tasks = (0..*SLEEP_THREADS.last().unwrap())
                     .map(|_| {
                         tokio::task::spawn(async {
-                            tokio::time::sleep(Duration::from_millis(SLEEP_MS)).await
+                            tokio::time::sleep(Duration::from_millis(*SLEEP_MS)).await
                         })
                     })
                     .collect::<Vec<_>>();
@@ -282,22 +425,24 @@ fn tokio_benchmark(c: &mut Criterion) {
         },
     );
 
-    c.bench_function(
+    tagged_bench(
+        c,
+        &["tokio", "async", "sleep", "slow"],
         "spawn tokio thread large worker huge sleep complex workload sleep tasks first",
         |b| {
             b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-                let sleep_tasks = (0..*NUM_THREADS_HUGE)
+                let sleep_tasks = (0..*SLEEP_THREADS.last().unwrap())
                     .map(|_| {
                         tokio::task::spawn(async {
-                            tokio::time::sleep(Duration::from_millis(SLEEP_MS)).await
+                            tokio::time::sleep(Duration::from_millis(*SLEEP_MS)).await
                         })
                     })
                     .collect::<Vec<_>>();
 
-                let work_tasks = (0..*NUM_THREADS_LARGE)
+                let work_tasks = (0..*WORK_THREADS.last().unwrap())
                     .map(|_| {
                         tokio::task::spawn(async {
-                            fibonacci(FIB_N);
+                            fibonacci(*FIB_N);
                         })
                     })
                     .collect::<Vec<_>>();
@@ -311,134 +456,109 @@ fn tokio_benchmark(c: &mut Criterion) {
         },
     );
 
-    c.bench_function("spawn tokio blocking thread", |b| {
-        b.to_async(multi_thread_tokio_runtime())
-            .iter(|| async { tokio::task::spawn_blocking(|| {}).await.unwrap() });
-    });
-
-    c.bench_function(
-        "spawn single tokio blocking thread expensive calculation",
+    tagged_bench(
+        c,
+        &["tokio", "async", "blocking"],
+        "spawn tokio blocking thread",
         |b| {
-            b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-                tokio::task::spawn_blocking(|| fibonacci(FIB_N))
-                    .await
-                    .unwrap()
-            });
+            b.to_async(multi_thread_tokio_runtime())
+                .iter(|| async { tokio::task::spawn_blocking(|| {}).await.unwrap() });
         },
     );
 
-    c.bench_function("spawn single tokio blocking thread sleep", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            tokio::task::spawn_blocking(|| std::thread::sleep(Duration::from_millis(SLEEP_MS)))
-                .await
-                .unwrap()
-        });
-    });
-
-    c.bench_function(
-        "spawn small tokio blocking thread expensive calculation",
+    tagged_bench(
+        c,
+        &["tokio", "async", "blocking"],
+        "spawn single tokio blocking thread expensive calculation",
         |b| {
             b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-                let tasks = (0..*NUM_THREADS_SMALL)
-                    .map(|_| tokio::task::spawn_blocking(|| fibonacci(FIB_N)))
-                    .collect::<Vec<_>>();
-
-                join_all(tasks)
+                tokio::task::spawn_blocking(|| fibonacci(*FIB_N))
                     .await
-                    .into_iter()
-                    .map(|res| res.unwrap())
-                    .sum::<u64>();
+                    .unwrap()
             });
         },
     );
 
-    c.bench_function("spawn small tokio blocking thread sleep", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            let tasks = (0..*NUM_THREADS_SMALL)
-                .map(|_| {
-                    tokio::task::spawn_blocking(|| {
-                        std::thread::sleep(Duration::from_millis(SLEEP_MS))
+    tagged_sweep(
+        c,
+        &["tokio", "async", "blocking", "sleep", "sweep"],
+        "spawn single tokio blocking thread sleep",
+        &CONFIG.sweep.sleep_ms,
+        |b, &ms| {
+            b.to_async(multi_thread_tokio_runtime())
+                .iter(|| async move {
+                    tokio::task::spawn_blocking(move || {
+                        std::thread::sleep(Duration::from_millis(ms))
                     })
-                })
-                .collect::<Vec<_>>();
-
-            join_all(tasks)
-                .await
-                .into_iter()
-                .map(|res| res.unwrap())
-                .for_each(|_| {});
-        });
-    });
-
-    c.bench_function(
-        "spawn large tokio blocking thread expensive calculation",
-        |b| {
-            b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-                let tasks = (0..*NUM_THREADS_LARGE)
-                    .map(|_| tokio::task::spawn_blocking(|| fibonacci(FIB_N)))
-                    .collect::<Vec<_>>();
-
-                join_all(tasks)
                     .await
-                    .into_iter()
-                    .map(|res| res.unwrap())
-                    .sum::<u64>();
-            });
+                    .unwrap()
+                });
         },
     );
 
-    c.bench_function("spawn large tokio blocking thread sleep", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            let tasks = (0..*NUM_THREADS_LARGE)
-                .map(|_| {
-                    tokio::task::spawn_blocking(|| {
-                        std::thread::sleep(Duration::from_millis(SLEEP_MS))
-                    })
-                })
-                .collect::<Vec<_>>();
-
-            join_all(tasks)
-                .await
-                .into_iter()
-                .map(|res| res.unwrap())
-                .for_each(|_| {});
-        });
-    });
-
-    c.bench_function("spawn huge tokio blocking thread sleep", |b| {
-        b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-            let tasks = (0..*NUM_THREADS_HUGE)
-                .map(|_| {
-                    tokio::task::spawn_blocking(|| {
-                        std::thread::sleep(Duration::from_millis(SLEEP_MS))
-                    })
-                })
-                .collect::<Vec<_>>();
+    tagged_sweep(
+        c,
+        &["tokio", "async", "blocking", "slow", "sweep"],
+        "spawn multiple tokio blocking thread expensive calculation",
+        &WORK_THREADS,
+        |b, &threads| {
+            b.to_async(multi_thread_tokio_runtime())
+                .iter(|| async move {
+                    let tasks = (0..threads)
+                        .map(|_| tokio::task::spawn_blocking(|| fibonacci(*FIB_N)))
+                        .collect::<Vec<_>>();
+
+                    join_all(tasks)
+                        .await
+                        .into_iter()
+                        .map(|res| res.unwrap())
+                        .sum::<u64>();
+                });
+        },
+    );
 
-            join_all(tasks)
-                .await
-                .into_iter()
-                .map(|res| res.unwrap())
-                .for_each(|_| {});
-        });
-    });
+    tagged_sweep(
+        c,
+        &["tokio", "async", "blocking", "sleep", "slow", "sweep"],
+        "spawn multiple tokio blocking thread sleep",
+        &SLEEPERS,
+        |b, &sleepers| {
+            b.to_async(multi_thread_tokio_runtime())
+                .iter(|| async move {
+                    let tasks = (0..sleepers.threads)
+                        .map(|_| {
+                            let duration = sleepers.duration();
+                            tokio::task::spawn_blocking(move || std::thread::sleep(duration))
+                        })
+                        .collect::<Vec<_>>();
+
+                    join_all(tasks)
+                        .await
+                        .into_iter()
+                        .map(|res| res.unwrap())
+                        .for_each(|_| {});
+                });
+        },
+    );
 
-    c.bench_function(
+    tagged_bench(
+        c,
+        &["tokio", "async", "blocking", "sleep", "slow"],
         "spawn tokio blocking thread large worker huge sleep complex workload",
         |b| {
             b.to_async(multi_thread_tokio_runtime()).iter(|| async {
-                let work_tasks = (0..*NUM_THREADS_LARGE)
+                let work_tasks = (0..*WORK_THREADS.last().unwrap())
                     .map(|_| {
                         tokio::task::spawn_blocking(|| {
-                            fibonacci(FIB_N);
+                            fibonacci(*FIB_N);
                         })
                     })
                     .collect::<Vec<_>>();
 
-                let sleep_tasks = (0..*NUM_THREADS_HUGE)
+                let sleep_tasks = (0..*SLEEP_THREADS.last().unwrap())
                     .map(|_| {
                         tokio::task::spawn(async {
-                            tokio::time::sleep(Duration::from_millis(SLEEP_MS)).await
+                            tokio::time::sleep(Duration::from_millis(*SLEEP_MS)).await
                         })
                     })
                     .collect::<Vec<_>>();
@@ -454,27 +574,98 @@ fn tokio_benchmark(c: &mut Criterion) {
 }
 
 fn instruction_benchmarks(c: &mut Criterion) {
-    c.bench_function("instruction syscall getpid", |b| {
-        b.iter(|| unsafe { syscalls::raw_syscall!(syscalls::Sysno::getpid) });
+    if !CONFIG.suite_enabled("instruction") {
+        return;
+    }
+
+    #[cfg(target_os = "linux")]
+    tagged_bench(
+        c,
+        &["instruction", "syscall"],
+        "instruction syscall getpid",
+        |b| {
+            b.iter(|| unsafe { syscalls::raw_syscall!(syscalls::Sysno::getpid) });
+        },
+    );
+
+    #[cfg(target_arch = "x86_64")]
+    tagged_bench(c, &["instruction"], "instruction cpuid", |b| {
+        b.iter(|| __cpuid(0));
     });
 
-    c.bench_function("instruction cpuid", |b| {
-        b.iter(|| unsafe { __cpuid(0) });
+    #[cfg(target_arch = "x86_64")]
+    x86_instruction_benchmarks(c);
+
+    #[cfg(target_arch = "aarch64")]
+    tagged_bench(c, &["instruction"], "instruction isb", |b| {
+        b.iter(|| unsafe { asm!("isb") });
     });
 
-    c.bench_function("instruction nop", |b| {
+    tagged_bench(c, &["instruction"], "instruction nop", |b| {
         b.iter(|| unsafe { asm!("nop") });
     });
 
-    c.bench_function("instruction nops", |b| {
+    tagged_bench(c, &["instruction"], "instruction nops", |b| {
         b.iter(|| unsafe { asm!("nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;") });
     });
 
-    c.bench_function("instruction empty", |b| {
+    tagged_bench(c, &["instruction"], "instruction empty", |b| {
         b.iter(|| unsafe { asm!("") });
     });
 }
 
+/// A CPUID leaf, shown in hex.
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy)]
+struct CpuidLeaf(u32);
+
+#[cfg(target_arch = "x86_64")]
+impl fmt::Display for CpuidLeaf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn x86_instruction_benchmarks(c: &mut Criterion) {
+    tagged_bench(c, &["instruction", "timer"], "instruction rdtsc", |b| {
+        b.iter(|| unsafe { _rdtsc() });
+    });
+
+    if instruction_benchmark::arch::has_rdtscp() {
+        tagged_bench(c, &["instruction", "timer"], "instruction rdtscp", |b| {
+            let mut aux = 0;
+            b.iter(|| unsafe { __rdtscp(&mut aux) });
+        });
+    }
+
+    tagged_bench(c, &["instruction"], "instruction pause", |b| {
+        b.iter(_mm_pause);
+    });
+
+    tagged_bench(c, &["instruction", "fence"], "instruction lfence", |b| {
+        b.iter(|| unsafe { _mm_lfence() });
+    });
+
+    tagged_bench(c, &["instruction", "fence"], "instruction mfence", |b| {
+        b.iter(|| unsafe { _mm_mfence() });
+    });
+
+    tagged_bench(c, &["instruction", "fence"], "instruction sfence", |b| {
+        b.iter(|| unsafe { _mm_sfence() });
+    });
+
+    tagged_sweep(
+        c,
+        &["instruction", "cpuid", "sweep"],
+        "instruction cpuid leaf",
+        &[0, 1, 7, 0xb, 0x8000_0001].map(CpuidLeaf),
+        |b, &CpuidLeaf(leaf)| {
+            b.iter(|| __cpuid(leaf));
+        },
+    );
+}
+
 criterion_group!(
     benches,
     fib_benchmark,
@@ -0,0 +1,51 @@
+//! Embeds the source state (commit, branch, uncommitted changes) into the binary, so recorded
+//! results can be traced back to the exact code that produced them, wherever it runs.
+
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn main() {
+    let commit = git(&["rev-parse", "HEAD"]);
+    let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"]);
+    let dirty = commit
+        .as_ref()
+        .and_then(|_| git(&["status", "--porcelain", "--untracked-files=no"]))
+        .map(|status| !status.is_empty());
+
+    println!(
+        "cargo:rustc-env=MICROBENCH_GIT_COMMIT={}",
+        commit.unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=MICROBENCH_GIT_BRANCH={}",
+        branch.unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=MICROBENCH_GIT_DIRTY={}",
+        dirty.map_or("", |dirty| if dirty { "true" } else { "false" })
+    );
+
+    // Commits, checkouts and staging move these; edits to tracked files change the sources.
+    for path in ["HEAD", "logs/HEAD", "index"] {
+        if let Some(path) = git(&["rev-parse", "--git-path", path]) {
+            println!("cargo:rerun-if-changed={path}");
+        }
+    }
+    for path in [
+        "build.rs",
+        "Cargo.toml",
+        "src",
+        "benches",
+        "examples",
+        "tests",
+    ] {
+        println!("cargo:rerun-if-changed={path}");
+    }
+}
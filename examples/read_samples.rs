@@ -0,0 +1,45 @@
+//! Loads a `--report samples` dump and prints a few order statistics per benchmark.
+//!
+//! ```bash
+//! cargo run --release -- --report samples
+//! cargo run --example read_samples -- target/microbench/samples
+//! ```
+
+use std::path::PathBuf;
+
+use instruction_benchmark::report::samples::{read_index, read_samples};
+
+fn main() {
+    let dir = PathBuf::from(
+        std::env::args()
+            .nth(1)
+            .unwrap_or_else(|| "target/microbench/samples".into()),
+    );
+    let index = read_index(&dir).unwrap();
+
+    println!(
+        "measured on {} ({})",
+        index.environment.hostname, index.environment.cpu_model
+    );
+    for bench in index.benches {
+        let mut samples = read_samples(&dir.join(&bench.file)).unwrap();
+        assert_eq!(samples.len(), bench.count, "{} is truncated", bench.file);
+        if samples.is_empty() {
+            println!("{:<40} no samples", bench.name);
+            continue;
+        }
+        samples.sort_unstable();
+
+        let at = |q: f64| samples[((samples.len() - 1) as f64 * q) as usize];
+        println!(
+            "{:<40} n={:<9} min={} p50={} p99={} max={} {}",
+            bench.name,
+            samples.len(),
+            at(0.0),
+            at(0.5),
+            at(0.99),
+            at(1.0),
+            bench.unit
+        );
+    }
+}
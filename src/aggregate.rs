@@ -0,0 +1,80 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compare::run_once,
+    stats::{variance_components, Summary, VarianceComponents},
+};
+
+/// One benchmark across processes, in nanoseconds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Aggregate {
+    pub name: String,
+    pub processes: usize,
+    pub components: VarianceComponents,
+}
+
+/// Runs `binary` `processes` times, each in a fresh process (new allocator state, address
+/// layout and page tables), and separates within-process from between-process variance.
+pub fn aggregate(
+    binary: &Path,
+    processes: usize,
+    run_args: &[String],
+    work_dir: &Path,
+) -> io::Result<Vec<Aggregate>> {
+    fs::create_dir_all(work_dir)?;
+
+    let mut runs = BTreeMap::<String, Vec<Summary>>::new();
+    for process in 0..processes {
+        println!("process {}/{processes}", process + 1);
+        let report = run_once(
+            binary,
+            run_args,
+            &work_dir.join(format!("process-{process}")),
+        )?;
+        for result in report.results {
+            let nanos = |ticks: f64| result.to_nanos(ticks);
+            let summary = Summary {
+                mean: nanos(result.summary.mean),
+                stddev: nanos(result.summary.stddev),
+                ..result.summary.clone()
+            };
+            runs.entry(result.name.clone()).or_default().push(summary);
+        }
+    }
+
+    let aggregates = runs
+        .into_iter()
+        .filter_map(|(name, summaries)| {
+            Some(Aggregate {
+                components: variance_components(&summaries)?,
+                processes: summaries.len(),
+                name,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let path = work_dir.join("aggregate.json");
+    fs::write(&path, serde_json::to_string_pretty(&aggregates).unwrap())?;
+    println!("report written to {}", path.display());
+    Ok(aggregates)
+}
+
+pub fn print_table(aggregates: &[Aggregate]) {
+    println!(
+        "{:<40}  {:>9}  {:>12}  {:>12}  {:>13}",
+        "benchmark", "processes", "mean ns", "within σ ns", "between σ ns"
+    );
+    for aggregate in aggregates {
+        let components = &aggregate.components;
+        println!(
+            "{:<40}  {:>9}  {:>12.1}  {:>12.1}  {:>13.1}",
+            aggregate.name,
+            aggregate.processes,
+            components.grand_mean,
+            components.within.sqrt(),
+            components.between.sqrt(),
+        );
+    }
+}
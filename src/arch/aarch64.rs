@@ -0,0 +1,48 @@
+use core::arch::asm;
+
+#[inline(always)]
+pub fn serialize() {
+    unsafe { asm!("dsb sy", "isb", options(nostack, preserves_flags)) };
+}
+
+/// Reads the virtual count of the generic timer. This ticks at `counter_frequency()`,
+/// typically well below the core clock (24MHz on Apple Silicon, 1GHz on Graviton 3).
+#[cfg(not(feature = "pmccntr"))]
+#[inline(always)]
+fn counter() -> u64 {
+    let value: u64;
+    unsafe { asm!("mrs {}, cntvct_el0", out(reg) value, options(nomem, nostack, preserves_flags)) };
+    value
+}
+
+/// Reads the PMU cycle counter. The kernel must have enabled EL0 access
+/// (e.g. via `echo 1 > /proc/sys/kernel/perf_user_access` and an open perf event),
+/// otherwise this traps with SIGILL.
+#[cfg(feature = "pmccntr")]
+#[inline(always)]
+fn counter() -> u64 {
+    let value: u64;
+    unsafe {
+        asm!("mrs {}, pmccntr_el0", out(reg) value, options(nomem, nostack, preserves_flags))
+    };
+    value
+}
+
+/// Frequency of the generic timer in Hz. The PMU cycle counter has no architectural frequency.
+pub fn counter_frequency() -> Option<u64> {
+    if cfg!(feature = "pmccntr") {
+        return None;
+    }
+
+    let value: u64;
+    unsafe { asm!("mrs {}, cntfrq_el0", out(reg) value, options(nomem, nostack, preserves_flags)) };
+    Some(value)
+}
+
+#[inline(always)]
+pub fn serialized_time() -> u64 {
+    serialize();
+    let result = counter();
+    serialize();
+    result
+}
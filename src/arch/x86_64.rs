@@ -0,0 +1,47 @@
+use core::arch::x86_64::*;
+
+#[inline(always)]
+pub fn serialize() {
+    unsafe {
+        _mm_lfence();
+        _mm_mfence();
+        _mm_sfence();
+        __cpuid(0);
+        _mm_lfence();
+        _mm_mfence();
+        _mm_sfence();
+    }
+}
+
+#[inline(always)]
+pub fn serialized_time() -> u64 {
+    serialize();
+    let result = unsafe { _rdtsc() };
+    serialize();
+    result
+}
+
+/// The TSC rate is not reliably exposed (CPUID leaf 0x15 is often zero), so callers calibrate.
+pub fn counter_frequency() -> Option<u64> {
+    None
+}
+
+/// Whether the CPU has `rdtscp` (CPUID 0x8000_0001, EDX bit 27), which hypervisors may hide.
+pub fn has_rdtscp() -> bool {
+    __cpuid(0x8000_0000).eax >= 0x8000_0001 && __cpuid(0x8000_0001).edx & (1 << 27) != 0
+}
+
+/// Whether the CPU has `clflushopt` (CPUID 7, EBX bit 23).
+pub fn has_clflushopt() -> bool {
+    __cpuid(0).eax >= 7 && __cpuid_count(7, 0).ebx & (1 << 23) != 0
+}
+
+/// Whether the CPU has enhanced `rep movsb`/`stosb` (CPUID 7, EBX bit 9).
+pub fn has_ermsb() -> bool {
+    __cpuid(0).eax >= 7 && __cpuid_count(7, 0).ebx & (1 << 9) != 0
+}
+
+/// Whether the CPU has fast short `rep movsb` (CPUID 7, EDX bit 4).
+pub fn has_fsrm() -> bool {
+    __cpuid(0).eax >= 7 && __cpuid_count(7, 0).edx & (1 << 4) != 0
+}
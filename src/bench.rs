@@ -0,0 +1,522 @@
+use std::{collections::BTreeMap, hint::black_box};
+
+#[cfg(target_os = "linux")]
+use crate::{counters::Counters, profile::Profiler};
+use crate::{
+    os::{self, ContextSwitches},
+    stream::Batches,
+    timer::{Monotonic, SerializedCounter, Timer, TimerKind},
+    watchdog::Deadline,
+};
+
+const SUITES_PREFIX: &str = concat!(env!("CARGO_CRATE_NAME"), "::suites::");
+
+/// Tag of benchmarks that run threads of their own, which `--jobs` never runs side by side.
+pub const MULTI_THREADED: &str = "multi-threaded";
+
+pub struct Bench {
+    pub path: &'static str,
+    pub tags: &'static [&'static str],
+    pub run: fn(&mut Bencher),
+}
+
+inventory::collect!(Bench);
+
+impl Bench {
+    /// Name relative to the `suites` module, e.g. `syscall::getpid`.
+    pub fn name(&self) -> &'static str {
+        self.path.trim_start_matches(SUITES_PREFIX)
+    }
+
+    /// First component of the name, e.g. `syscall`.
+    pub fn suite(&self) -> &'static str {
+        self.name().split("::").next().unwrap_or_default()
+    }
+
+    pub fn multi_threaded(&self) -> bool {
+        self.tags.contains(&MULTI_THREADED)
+    }
+
+    pub fn matches(&self, filters: &[String]) -> bool {
+        filters.is_empty() || filters.iter().any(|f| self.name().contains(f.as_str()))
+    }
+}
+
+/// Selects benchmarks by tag: any of `include` (if given) and none of `exclude`.
+#[derive(Clone, Debug, Default)]
+pub struct TagFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl TagFilter {
+    /// Reads comma-separated tags from `MICROBENCH_TAGS` and `MICROBENCH_SKIP_TAGS`, for the
+    /// Criterion suite whose command line belongs to Criterion.
+    pub fn from_env() -> Self {
+        let tags = |var| {
+            std::env::var(var)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        Self {
+            include: tags("MICROBENCH_TAGS"),
+            exclude: tags("MICROBENCH_SKIP_TAGS"),
+        }
+    }
+
+    pub fn matches(&self, tags: &[&str]) -> bool {
+        let has = |wanted: &String| tags.contains(&wanted.as_str());
+        (self.include.is_empty() || self.include.iter().any(has)) && !self.exclude.iter().any(has)
+    }
+}
+
+/// All registered benchmarks, sorted by name.
+pub fn benches() -> Vec<&'static Bench> {
+    let mut benches = inventory::iter::<Bench>.into_iter().collect::<Vec<_>>();
+    benches.sort_by_key(|b| b.name());
+    benches
+}
+
+/// `count` pseudo-random numbers (splitmix64), reproducibly for a given `seed`.
+pub fn random_u64s(seed: u64, count: usize) -> Vec<u64> {
+    let mut state = seed;
+    (0..count).map(|_| splitmix64(&mut state)).collect()
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Shuffles `items` in place (Fisher-Yates over splitmix64), reproducibly for a given `seed`.
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    for i in (1..items.len()).rev() {
+        items.swap(i, (splitmix64(&mut state) % (i as u64 + 1)) as usize);
+    }
+}
+
+/// Registers a measured function with the runner.
+///
+/// ```ignore
+/// fn getpid(b: &mut Bencher) {
+///     b.iter(|| unsafe { syscalls::raw_syscall!(syscalls::Sysno::getpid) });
+/// }
+///
+/// register_bench!(getpid, tags = ["syscall"]);
+/// ```
+#[macro_export]
+macro_rules! register_bench {
+    ($run:path) => {
+        $crate::register_bench!($run, tags = []);
+    };
+    ($run:path, tags = [$($tag:literal),* $(,)?]) => {
+        $crate::inventory::submit! {
+            $crate::bench::Bench {
+                path: concat!(module_path!(), "::", stringify!($run)),
+                tags: &[$($tag),*],
+                run: $run,
+            }
+        }
+    };
+}
+
+/// Values `opaque` can hide in a register.
+pub trait Register: Copy {
+    fn hide(self) -> Self;
+}
+
+impl Register for u64 {
+    #[inline(always)]
+    fn hide(mut self) -> Self {
+        unsafe {
+            core::arch::asm!("/* {0} */", inout(reg) self, options(nomem, nostack, preserves_flags))
+        };
+        self
+    }
+}
+
+macro_rules! impl_register_via_u64 {
+    ($($ty:ty),*) => {$(
+        impl Register for $ty {
+            #[inline(always)]
+            fn hide(self) -> Self {
+                (self as u64).hide() as $ty
+            }
+        }
+    )*};
+}
+
+impl_register_via_u64!(u32, i32, i64, usize);
+
+impl Register for u128 {
+    #[inline(always)]
+    fn hide(self) -> Self {
+        let (low, high) = ((self as u64).hide(), ((self >> 64) as u64).hide());
+        u128::from(high) << 64 | u128::from(low)
+    }
+}
+
+impl Register for i128 {
+    #[inline(always)]
+    fn hide(self) -> Self {
+        (self as u128).hide() as i128
+    }
+}
+
+/// Floats stay in vector registers where the architecture has a class for them, rather than
+/// taking a round trip through a general purpose one.
+macro_rules! impl_register_float {
+    ($($ty:ty => $aarch64:literal),*) => {$(
+        impl Register for $ty {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            #[inline(always)]
+            fn hide(mut self) -> Self {
+                unsafe {
+                    core::arch::asm!(
+                        "/* {0} */",
+                        inout(xmm_reg) self,
+                        options(nomem, nostack, preserves_flags)
+                    )
+                };
+                self
+            }
+
+            #[cfg(target_arch = "aarch64")]
+            #[inline(always)]
+            fn hide(mut self) -> Self {
+                unsafe {
+                    core::arch::asm!(
+                        concat!("/* {0:", $aarch64, "} */"),
+                        inout(vreg) self,
+                        options(nomem, nostack, preserves_flags)
+                    )
+                };
+                self
+            }
+
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+            #[inline(always)]
+            fn hide(self) -> Self {
+                <$ty>::from_bits((self.to_bits() as u64).hide() as _)
+            }
+        }
+    )*};
+}
+
+impl_register_float!(f32 => "s", f64 => "d");
+
+/// Hides `x` from the optimizer without the store and reload of `black_box`, so a chain of
+/// operations on it stays in registers and is neither folded nor vectorized.
+#[inline(always)]
+pub fn opaque<T: Register>(x: T) -> T {
+    x.hide()
+}
+
+const OVERHEAD_RUNS: u64 = 10_000;
+/// Iterations between two context switch readings; small enough to attribute a switch to a
+/// handful of samples, large enough that the extra syscall does not disturb the loop.
+const SWITCH_BLOCK: u64 = 1_000;
+
+/// The only code callgrind counts in `--cachegrind` mode, hence never inlined.
+#[inline(never)]
+fn simulate<R>(iterations: u64, mut f: impl FnMut() -> R) {
+    for _ in 0..iterations {
+        black_box(f());
+    }
+}
+
+pub struct Bencher {
+    timer: TimerKind,
+    overhead: u64,
+    iterations: u64,
+    samples: Vec<u64>,
+    simulate: bool,
+    switches: ContextSwitches,
+    discard_preempted: bool,
+    discarded: usize,
+    stream: Option<Batches>,
+    deadline: Option<Deadline>,
+    timed_out: bool,
+    skipped: Option<String>,
+    ops: u64,
+    bytes: u64,
+    events: BTreeMap<&'static str, u64>,
+    #[cfg(target_os = "linux")]
+    profiler: Option<Profiler>,
+    #[cfg(target_os = "linux")]
+    counters: Option<Counters>,
+}
+
+impl Bencher {
+    pub fn new(timer: TimerKind, overhead: u64, iterations: u64) -> Self {
+        Self {
+            timer,
+            overhead,
+            iterations,
+            samples: Vec::with_capacity(iterations as usize),
+            simulate: false,
+            switches: ContextSwitches::default(),
+            discard_preempted: false,
+            discarded: 0,
+            stream: None,
+            deadline: None,
+            timed_out: false,
+            skipped: None,
+            ops: 1,
+            bytes: 0,
+            events: BTreeMap::new(),
+            #[cfg(target_os = "linux")]
+            profiler: None,
+            #[cfg(target_os = "linux")]
+            counters: None,
+        }
+    }
+
+    /// Runs the benchmark body untimed, for counting under callgrind (see `cachegrind`).
+    pub fn simulated(iterations: u64) -> Self {
+        Self {
+            iterations,
+            simulate: true,
+            ..Self::new(TimerKind::Cycles, 0, 0)
+        }
+    }
+
+    /// Drops, for every context switch during a block of iterations, that block's slowest
+    /// sample, which is the one the switch landed in.
+    pub fn with_discard_preempted(mut self, discard: bool) -> Self {
+        self.discard_preempted = discard;
+        self
+    }
+
+    /// Hands every block of samples to a live `--stream`.
+    pub fn with_stream(mut self, batches: Batches) -> Self {
+        self.stream = Some(batches);
+        self
+    }
+
+    /// Stops measuring, keeping the samples taken so far, once `deadline` expires.
+    pub fn with_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Records the measurement loops (and nothing else) with `profiler`.
+    #[cfg(target_os = "linux")]
+    pub fn with_profiler(mut self, profiler: Profiler) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn take_profiler(&mut self) -> Option<Profiler> {
+        self.profiler.take()
+    }
+
+    /// Counts hardware events during the measurement loops with `counters`, opened on the
+    /// thread that will measure.
+    #[cfg(target_os = "linux")]
+    pub fn with_counters(mut self, counters: Counters) -> Self {
+        self.counters = Some(counters);
+        self
+    }
+
+    /// Hardware events counted over all iterations, if counting, and the benchmark's own (see
+    /// `count`).
+    pub fn counts(&self) -> BTreeMap<String, u64> {
+        #[cfg(target_os = "linux")]
+        let mut counts = self
+            .counters
+            .as_ref()
+            .and_then(|counters| counters.read().ok())
+            .unwrap_or_default();
+        #[cfg(not(target_os = "linux"))]
+        let mut counts = BTreeMap::new();
+        counts.extend(
+            self.events
+                .iter()
+                .map(|(&event, &n)| (event.to_string(), n)),
+        );
+        counts
+    }
+
+    /// Cost of an empty measurement with `timer`, to be subtracted from every sample.
+    pub fn overhead(timer: TimerKind) -> u64 {
+        let mut bencher = Bencher::new(timer, 0, OVERHEAD_RUNS);
+        bencher.iter(|| {});
+        bencher.samples.into_iter().min().unwrap_or(0)
+    }
+
+    #[inline(always)]
+    pub fn iter<R>(&mut self, f: impl FnMut() -> R) {
+        if self.simulate {
+            return simulate(self.iterations, f);
+        }
+        match self.timer {
+            TimerKind::Cycles => self.measure(&SerializedCounter, f),
+            TimerKind::Monotonic => self.measure(&Monotonic, f),
+            #[cfg(windows)]
+            TimerKind::Qpc => self.measure(&crate::timer::PerformanceCounter, f),
+            #[cfg(target_os = "macos")]
+            TimerKind::Mach => self.measure(&crate::timer::MachAbsoluteTime, f),
+        }
+    }
+
+    /// Like `iter` for benchmarks that time only part of each iteration themselves: `f` gets
+    /// the timer and returns the ticks it measured, from which the overhead is still subtracted.
+    pub fn iter_custom(&mut self, mut f: impl FnMut(&dyn Timer) -> u64) {
+        let timer = self.timer.timer();
+        if self.simulate {
+            return simulate(self.iterations, || f(timer));
+        }
+        self.sample(|overhead| f(timer).saturating_sub(overhead));
+    }
+
+    #[inline(always)]
+    fn measure<R>(&mut self, timer: &impl Timer, mut f: impl FnMut() -> R) {
+        self.sample(|overhead| {
+            let start = timer.now();
+            black_box(f());
+            let end = timer.now();
+            (end - start).saturating_sub(overhead)
+        });
+    }
+
+    /// Collects `iterations` samples, each from `sample` given the overhead to subtract.
+    #[inline(always)]
+    fn sample(&mut self, mut sample: impl FnMut(u64) -> u64) {
+        #[cfg(target_os = "linux")]
+        if let Some(profiler) = &mut self.profiler {
+            profiler.enable();
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(counters) = &mut self.counters {
+            counters.enable();
+        }
+
+        let mut remaining = self.iterations;
+        while remaining > 0 {
+            let block = remaining.min(SWITCH_BLOCK);
+            let first = self.samples.len();
+            let before = os::context_switches();
+
+            for _ in 0..block {
+                self.samples.push(sample(self.overhead));
+                if self.deadline.as_ref().is_some_and(Deadline::expired) {
+                    self.timed_out = true;
+                    break;
+                }
+            }
+
+            if let (Some(before), Some(after)) = (before, os::context_switches()) {
+                let switches = after - before;
+                self.switches += switches;
+                if self.discard_preempted && switches.total() > 0 {
+                    self.discard_slowest(first, switches.total() as usize);
+                }
+            }
+            if let Some(stream) = &mut self.stream {
+                stream.send(&self.samples[first..]);
+            }
+            if self.timed_out {
+                break;
+            }
+            remaining -= block;
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(counters) = &mut self.counters {
+            counters.disable();
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(profiler) = &mut self.profiler {
+            profiler.disable();
+        }
+    }
+
+    fn discard_slowest(&mut self, first: usize, count: usize) {
+        let block = &self.samples[first..];
+        let mut slowest = (0..block.len()).collect::<Vec<_>>();
+        slowest.sort_unstable_by_key(|&i| std::cmp::Reverse(block[i]));
+        slowest.truncate(count);
+
+        let mut index = 0;
+        self.samples.retain(|_| {
+            let keep = index < first || !slowest.contains(&(index - first));
+            index += 1;
+            keep
+        });
+        self.discarded += slowest.len();
+    }
+
+    /// Adds `n` to the benchmark's count of `event`, which is reported per sample like the
+    /// hardware events of `--counters`.
+    pub fn count(&mut self, event: &'static str, n: u64) {
+        *self.events.entry(event).or_default() += n;
+    }
+
+    /// Number of operations one iteration performs, for benchmarks that repeat a cheap
+    /// operation to lift it above the timer's resolution; results are also shown per operation.
+    pub fn set_ops(&mut self, ops: u64) {
+        self.ops = ops.max(1);
+    }
+
+    pub fn ops(&self) -> u64 {
+        self.ops
+    }
+
+    /// Number of bytes one iteration reads or writes, for bandwidth benchmarks; results are
+    /// also shown in GB/s.
+    pub fn set_bytes(&mut self, bytes: u64) {
+        self.bytes = bytes;
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Caps the number of samples, for benchmarks whose iterations take milliseconds.
+    pub fn limit_iterations(&mut self, max: u64) {
+        self.iterations = self.iterations.min(max);
+    }
+
+    /// Marks the benchmark as not applicable here, e.g. for a missing CPU feature, instead of
+    /// calling `iter`. It is reported as skipped rather than as a result.
+    pub fn skip(&mut self, reason: impl Into<String>) {
+        self.skipped = Some(reason.into());
+    }
+
+    pub fn skipped(&self) -> Option<&str> {
+        self.skipped.as_deref()
+    }
+
+    /// Context switches during the measurement loops.
+    pub fn context_switches(&self) -> ContextSwitches {
+        self.switches
+    }
+
+    /// Samples dropped by `with_discard_preempted`.
+    pub fn discarded(&self) -> usize {
+        self.discarded
+    }
+
+    /// Whether the deadline cut the measurement short.
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    pub fn samples(&self) -> &[u64] {
+        &self.samples
+    }
+
+    pub fn into_samples(self) -> Vec<u64> {
+        self.samples
+    }
+}
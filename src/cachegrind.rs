@@ -0,0 +1,121 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::report::file_stem;
+
+/// Only calls below this function are counted; see `Bencher::simulated`.
+const COLLECT: &str = "*instruction_benchmark::bench::simulate*";
+
+/// Simulated event counts per iteration of one benchmark's body, e.g. `Ir` (instructions),
+/// `D1mr` (L1 data read misses) or `DLmr` (last-level data read misses).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CacheCounts {
+    pub name: String,
+    pub events: BTreeMap<String, f64>,
+}
+
+/// Runs benchmark bodies under callgrind's cache simulator. Each benchmark is run by a fresh
+/// child (`binary --simulate <name>`) with `iterations` and then twice as many iterations, and
+/// the difference is reported, so one-time costs such as cold caches and lazy initialization
+/// cancel out and the numbers are the same on every run.
+pub struct Cachegrind {
+    pub binary: PathBuf,
+    pub iterations: u64,
+    pub output_dir: PathBuf,
+}
+
+impl Cachegrind {
+    pub fn measure(&self, name: &str) -> io::Result<CacheCounts> {
+        let once = self.run(name, self.iterations)?;
+        let twice = self.run(name, 2 * self.iterations)?;
+        let events = twice
+            .iter()
+            .map(|(event, &count)| {
+                let base = once.get(event).copied().unwrap_or(0);
+                let delta = count.saturating_sub(base);
+                (event.clone(), delta as f64 / self.iterations as f64)
+            })
+            .collect();
+        Ok(CacheCounts {
+            name: name.to_string(),
+            events,
+        })
+    }
+
+    fn run(&self, name: &str, iterations: u64) -> io::Result<BTreeMap<String, u64>> {
+        fs::create_dir_all(&self.output_dir)?;
+        let out_file = self
+            .output_dir
+            .join(format!("{}.{iterations}.callgrind", file_stem(name)));
+        let status = Command::new("valgrind")
+            .args([
+                "--tool=callgrind",
+                "--cache-sim=yes",
+                "--collect-atstart=no",
+            ])
+            .arg(format!("--toggle-collect={COLLECT}"))
+            .arg(format!("--callgrind-out-file={}", out_file.display()))
+            .arg(&self.binary)
+            .args(["--simulate", name, "--simulate-iterations"])
+            .arg(iterations.to_string())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| io::Error::new(e.kind(), format!("failed to run valgrind: {e}")))?;
+        if !status.success() {
+            return Err(io::Error::other(format!("valgrind failed on {name}")));
+        }
+        parse_totals(&fs::read_to_string(out_file)?)
+    }
+}
+
+/// Event totals from a callgrind output file's `events:` and `summary:`/`totals:` lines.
+fn parse_totals(output: &str) -> io::Result<BTreeMap<String, u64>> {
+    let field = |key: &str| {
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .map(str::split_whitespace)
+    };
+    let (Some(events), Some(totals)) = (
+        field("events:"),
+        field("totals:").or_else(|| field("summary:")),
+    ) else {
+        return Err(io::Error::other("callgrind output has no totals"));
+    };
+    Ok(events
+        .zip(totals)
+        .map(|(event, total)| (event.to_string(), total.parse().unwrap_or(0)))
+        .collect())
+}
+
+pub fn print_table(counts: &[CacheCounts]) {
+    const EVENTS: [&str; 6] = ["Ir", "Dr", "Dw", "I1mr", "D1mr", "DLmr"];
+    print!("{:<40}", "benchmark (per iteration)");
+    for event in EVENTS {
+        print!("  {event:>10}");
+    }
+    println!();
+    for bench in counts {
+        print!("{:<40}", bench.name);
+        for event in EVENTS {
+            print!(
+                "  {:>10.2}",
+                bench.events.get(event).copied().unwrap_or(0.0)
+            );
+        }
+        println!();
+    }
+}
+
+pub fn write_json(counts: &[CacheCounts], path: &Path) -> io::Result<()> {
+    fs::write(path, serde_json::to_string_pretty(counts).unwrap())?;
+    println!("report written to {}", path.display());
+    Ok(())
+}
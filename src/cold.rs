@@ -0,0 +1,41 @@
+use std::{
+    io,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// What a cold child prints, followed by the reason, instead of a sample when the benchmark
+/// skips itself.
+pub const SKIPPED: &str = "skipped: ";
+
+/// First-call latencies of benchmark `name`, one per fresh process (`binary --cold-child
+/// <name>`), so caches, TLB, branch predictors, page mappings and lazily initialized state such
+/// as a runtime are all cold, unlike in the steady-state loop; or the reason a run skipped it.
+pub fn cold_samples(
+    binary: &Path,
+    name: &str,
+    runs: usize,
+    child_args: &[String],
+) -> io::Result<Result<Vec<u64>, String>> {
+    let mut samples = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let output = Command::new(binary)
+            .args(child_args)
+            .args(["--no-history", "--cold-child", name])
+            .stderr(Stdio::inherit())
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!("cold run of {name} failed")));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout.lines().last().unwrap_or_default().trim();
+        if let Some(reason) = line.strip_prefix(SKIPPED) {
+            return Ok(Err(reason.to_string()));
+        }
+        let sample = line
+            .parse()
+            .map_err(|_| io::Error::other(format!("cold run of {name} printed no sample")))?;
+        samples.push(sample);
+    }
+    Ok(Ok(samples))
+}
@@ -0,0 +1,185 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::{report::Report, stats::welch_t_test};
+
+const SIGNIFICANCE: f64 = 0.05;
+
+/// One side of a comparison: a git revision to build or an already built binary.
+pub enum Side {
+    Revision(String),
+    Binary(PathBuf),
+}
+
+impl Side {
+    pub fn label(&self) -> String {
+        match self {
+            Side::Revision(rev) => rev.clone(),
+            Side::Binary(path) => path.display().to_string(),
+        }
+    }
+
+    /// Builds the revision in a throwaway worktree, returning the release binary.
+    pub fn binary(&self, work_dir: &Path) -> io::Result<PathBuf> {
+        let rev = match self {
+            Side::Binary(path) => return Ok(path.clone()),
+            Side::Revision(rev) => rev,
+        };
+
+        let commit = git(&["rev-parse", "--verify", &format!("{rev}^{{commit}}")])?;
+        let worktree = work_dir.join(format!("worktree-{commit}"));
+        let target_dir = work_dir.join(format!("target-{commit}"));
+        let binary = target_dir.join("release").join(format!(
+            "instruction-benchmark{}",
+            std::env::consts::EXE_SUFFIX
+        ));
+        if binary.exists() {
+            return Ok(binary);
+        }
+
+        if worktree.exists() {
+            git(&["worktree", "remove", "--force", &worktree.to_string_lossy()])?;
+        }
+        git(&[
+            "worktree",
+            "add",
+            "--detach",
+            &worktree.to_string_lossy(),
+            &commit,
+        ])?;
+        // Cargo.lock is not tracked; build both sides against the same dependency versions.
+        let lockfile = worktree.join("Cargo.lock");
+        if !lockfile.exists() && Path::new("Cargo.lock").exists() {
+            fs::copy("Cargo.lock", &lockfile)?;
+        }
+        println!("building {rev} ({})", &commit[..12]);
+        let status = Command::new(env!("CARGO"))
+            .args(["build", "--release", "--bin", "instruction-benchmark"])
+            .arg("--target-dir")
+            .arg(&target_dir)
+            .current_dir(&worktree)
+            .status();
+        git(&["worktree", "remove", "--force", &worktree.to_string_lossy()])?;
+
+        if !status?.success() {
+            return Err(io::Error::other(format!("failed to build {rev}")));
+        }
+        Ok(binary)
+    }
+}
+
+fn git(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("git {} failed", args.join(" "))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Runs `binary` once with `run_args`, returning its JSON report.
+pub fn run_once(binary: &Path, run_args: &[String], output_dir: &Path) -> io::Result<Report> {
+    let status = Command::new(binary)
+        .args(run_args)
+        .args(["--report", "json", "--no-history", "--output-dir"])
+        .arg(output_dir)
+        .stdout(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} failed", binary.display())));
+    }
+    let json = fs::read_to_string(output_dir.join("results.json"))?;
+    serde_json::from_str(&json).map_err(io::Error::other)
+}
+
+/// Per-round median latencies in nanoseconds, keyed by benchmark name.
+type Rounds = BTreeMap<String, Vec<f64>>;
+
+/// Runs both binaries `rounds` times, alternating which goes first (ABBA) so that drift such as
+/// thermal ramp-up affects both sides equally, then prints a table of median differences.
+pub fn compare(
+    base: &Side,
+    head: &Side,
+    rounds: usize,
+    run_args: &[String],
+    work_dir: &Path,
+) -> io::Result<()> {
+    fs::create_dir_all(work_dir)?;
+    let work_dir = work_dir.canonicalize()?;
+    let base_binary = base.binary(&work_dir)?;
+    let head_binary = head.binary(&work_dir)?;
+
+    let mut base_rounds = Rounds::new();
+    let mut head_rounds = Rounds::new();
+    for round in 0..rounds {
+        let mut order = [
+            (&base_binary, &mut base_rounds, "base"),
+            (&head_binary, &mut head_rounds, "head"),
+        ];
+        if round % 2 == 1 {
+            order.reverse();
+        }
+
+        for (binary, samples, label) in order {
+            println!("round {}/{rounds}: {label}", round + 1);
+            let report = run_once(binary, run_args, &work_dir.join(label))?;
+            for result in report.results {
+                samples
+                    .entry(result.name.clone())
+                    .or_default()
+                    .push(result.to_nanos(result.summary.p50 as f64));
+            }
+        }
+    }
+
+    println!();
+    println!("base: {}", base.label());
+    println!("head: {}", head.label());
+    print_table(&base_rounds, &head_rounds);
+    Ok(())
+}
+
+fn print_table(base: &Rounds, head: &Rounds) {
+    println!(
+        "{:<40}  {:>12}  {:>12}  {:>9}  {:>8}  verdict",
+        "benchmark", "base p50 ns", "head p50 ns", "delta", "p-value"
+    );
+
+    let names = base
+        .keys()
+        .chain(head.keys())
+        .collect::<std::collections::BTreeSet<_>>();
+    for name in names {
+        let (Some(base), Some(head)) = (base.get(name), head.get(name)) else {
+            let side = if base.contains_key(name) {
+                "base"
+            } else {
+                "head"
+            };
+            println!("{name:<40}  only in {side}");
+            continue;
+        };
+
+        let base_mean = base.iter().sum::<f64>() / base.len() as f64;
+        let head_mean = head.iter().sum::<f64>() / head.len() as f64;
+        let delta = (head_mean - base_mean) / base_mean * 100.0;
+        let test = welch_t_test(base, head);
+        let verdict = match test {
+            Some(test) if test.p < SIGNIFICANCE && delta > 0.0 => "slower",
+            Some(test) if test.p < SIGNIFICANCE => "faster",
+            Some(_) => "no change",
+            None => "too few rounds",
+        };
+
+        println!(
+            "{name:<40}  {base_mean:>12.1}  {head_mean:>12.1}  {delta:>+8.1}%  {:>8}  {verdict}",
+            test.map(|t| format!("{:.3}", t.p)).unwrap_or_default(),
+        );
+    }
+}
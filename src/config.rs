@@ -0,0 +1,139 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+pub const DEFAULT_PATH: &str = "microbench.toml";
+
+/// Benchmark parameters from `microbench.toml`, shared by the runner and the Criterion suite.
+///
+/// ```toml
+/// iterations = 100000
+/// fib_n = 30
+/// sleep_ms = 25
+/// cores = [2]
+/// suites = ["syscall", "tokio"]
+/// timeout_secs = 300
+///
+/// [sweep]
+/// fib_n = [20, 25, 30]
+/// sleep_ms = [1, 25]
+/// work_threads = [0.5, 4.0]
+/// sleep_threads = [0.5, 4.0, 128.0]
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Samples taken per benchmark by the runner.
+    pub iterations: u64,
+    /// Input of the fixed-size Criterion benchmarks; sweeps take theirs from `sweep`.
+    pub fib_n: u64,
+    pub sleep_ms: u64,
+    /// Cores the runner may pin to; it measures on the first. Empty picks an isolated or
+    /// tick-less core if the kernel has any.
+    pub cores: Vec<usize>,
+    /// Suites to run (the first component of a runner name, or a Criterion group such as
+    /// `tokio`); empty runs all of them.
+    pub suites: Vec<String>,
+    /// Wall-clock budget per benchmark, enforced by a `Watchdog`; none if unset.
+    pub timeout_secs: Option<u64>,
+    pub sweep: Sweep,
+}
+
+/// Parameter values the Criterion sweeps run over, each reported as its own point of a group.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sweep {
+    pub fib_n: Vec<u64>,
+    pub sleep_ms: Vec<u64>,
+    /// Counts of compute-bound threads/tasks, as multiples of the logical core count.
+    pub work_threads: Vec<f64>,
+    /// Counts of sleeping threads/tasks, as multiples of the logical core count.
+    pub sleep_threads: Vec<f64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            iterations: 100_000,
+            fib_n: 30,
+            sleep_ms: 25,
+            cores: Vec::new(),
+            suites: Vec::new(),
+            timeout_secs: None,
+            sweep: Sweep::default(),
+        }
+    }
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self {
+            fib_n: vec![20, 25, 30],
+            sleep_ms: vec![25],
+            work_threads: vec![0.5, 4.0],
+            sleep_threads: vec![0.5, 4.0, 128.0],
+        }
+    }
+}
+
+impl Sweep {
+    /// The thread sweeps size their background load by the largest count, so they need one.
+    fn validate(&self) -> Result<(), String> {
+        if self.work_threads.is_empty() {
+            return Err("sweep.work_threads is empty".into());
+        }
+        if self.sleep_threads.is_empty() {
+            return Err("sweep.sleep_threads is empty".into());
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Reads `path`, falling back to the defaults if it does not exist.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid = |e: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {e}", path.display()),
+            )
+        };
+        match fs::read_to_string(path) {
+            Ok(toml) => {
+                let config: Self = toml::from_str(&toml).map_err(|e| invalid(e.to_string()))?;
+                config.sweep.validate().map_err(invalid)?;
+                Ok(config)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Loads `MICROBENCH_CONFIG`, or `microbench.toml` in the working directory, for the
+    /// Criterion suite whose command line belongs to Criterion.
+    pub fn from_env() -> io::Result<Self> {
+        let path = std::env::var_os("MICROBENCH_CONFIG")
+            .map_or_else(|| PathBuf::from(DEFAULT_PATH), PathBuf::from);
+        Self::load(&path)
+    }
+
+    pub fn suite_enabled(&self, suite: &str) -> bool {
+        self.suites.is_empty() || self.suites.iter().any(|s| s == suite)
+    }
+
+    /// `multipliers` times the CPUs available to this process (at least one), ascending and
+    /// without duplicates.
+    pub fn thread_counts(multipliers: &[f64]) -> Vec<usize> {
+        let cores = crate::os::cpu_limits().available();
+        let mut counts = multipliers
+            .iter()
+            .map(|m| ((cores as f64 * m).round() as usize).max(1))
+            .collect::<Vec<_>>();
+        counts.sort_unstable();
+        counts.dedup();
+        counts
+    }
+}
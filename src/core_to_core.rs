@@ -0,0 +1,136 @@
+//! Core-to-core latency: a cache line bounced between two threads pinned to different cores,
+//! for every ordered pair. The round trip grows with the distance between the cores, so the
+//! matrix shows which share a cluster (CCX), a die or a socket.
+
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use crate::{bench::Bencher, os, report::BenchResult};
+
+/// Round trips per sample, reported per round trip.
+pub const ROUND_TRIPS: u64 = 100;
+/// Untimed round trips first, so the responder is up and both caches are warm.
+const WARMUP: u64 = 1000;
+const STOP: u64 = u64::MAX;
+
+/// A flag alone on its cache line (and the adjacent-line prefetcher's pair).
+#[repr(align(128))]
+struct Flag(AtomicU64);
+
+/// Measures round trips from the calling thread, already pinned, to a responder pinned to
+/// `to`: the caller writes an odd value to the flag and waits for the responder to write the
+/// next even one.
+pub fn ping_pong(bencher: &mut Bencher, to: usize) {
+    bencher.set_ops(ROUND_TRIPS);
+    let flag = Flag(AtomicU64::new(0));
+    let (pinned_tx, pinned_rx) = mpsc::channel();
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let pinned = os::pin_to_core(to);
+            pinned_tx.send(pinned).unwrap();
+            if !pinned {
+                return;
+            }
+            loop {
+                match flag.0.load(Ordering::Acquire) {
+                    STOP => break,
+                    ping if ping % 2 == 1 => flag.0.store(ping + 1, Ordering::Release),
+                    _ => std::hint::spin_loop(),
+                }
+            }
+        });
+        assert!(pinned_rx.recv().unwrap(), "failed to pin to core {to}");
+
+        let mut value = 0;
+        let mut round_trip = || {
+            flag.0.store(value + 1, Ordering::Release);
+            while flag.0.load(Ordering::Acquire) != value + 2 {
+                std::hint::spin_loop();
+            }
+            value += 2;
+        };
+        for _ in 0..WARMUP {
+            round_trip();
+        }
+        bencher.iter(|| {
+            for _ in 0..ROUND_TRIPS {
+                round_trip();
+            }
+        });
+        flag.0.store(STOP, Ordering::Release);
+    });
+}
+
+/// Name of the result for the pair, e.g. `core_to_core::0_5`.
+pub fn name(from: usize, to: usize) -> String {
+    format!("core_to_core::{from}_{to}")
+}
+
+/// Mean round trip in nanoseconds by `[from][to]` index into `cores`; `None` on the diagonal.
+pub fn matrix(cores: &[usize], results: &[BenchResult]) -> Vec<Vec<Option<f64>>> {
+    cores
+        .iter()
+        .map(|&from| {
+            cores
+                .iter()
+                .map(|&to| {
+                    results
+                        .iter()
+                        .find(|r| r.name == name(from, to))
+                        .map(|r| r.to_nanos(r.mean_per_op()))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn print_matrix(cores: &[usize], matrix: &[Vec<Option<f64>>]) {
+    println!("round trip in ns (rows: from, columns: to)");
+    let mut header = format!("{:>6}", "");
+    for core in cores {
+        write!(header, " {core:>7}").unwrap();
+    }
+    println!("{header}");
+    for (from, row) in cores.iter().zip(matrix) {
+        let mut line = format!("{from:>6}");
+        for cell in row {
+            match cell {
+                Some(ns) => write!(line, " {ns:>7.1}").unwrap(),
+                None => write!(line, " {:>7}", "-").unwrap(),
+            }
+        }
+        println!("{line}");
+    }
+}
+
+/// Writes the matrix as CSV, with the cores as the header row and first column, ready for a
+/// heat map.
+pub fn write_csv(path: &Path, cores: &[usize], matrix: &[Vec<Option<f64>>]) -> io::Result<()> {
+    let mut text = String::from("from\\to");
+    for core in cores {
+        write!(text, ",{core}").unwrap();
+    }
+    text.push('\n');
+    for (from, row) in cores.iter().zip(matrix) {
+        write!(text, "{from}").unwrap();
+        for cell in row {
+            match cell {
+                Some(ns) => write!(text, ",{ns:.2}").unwrap(),
+                None => text.push(','),
+            }
+        }
+        text.push('\n');
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, text)
+}
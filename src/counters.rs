@@ -0,0 +1,126 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Read},
+    os::fd::{AsRawFd, FromRawFd},
+};
+
+/// Generic hardware events counted by `--counters`, in group order.
+pub const EVENTS: [(&str, u64); 4] = [
+    ("cycles", 0),        // PERF_COUNT_HW_CPU_CYCLES
+    ("instructions", 1),  // PERF_COUNT_HW_INSTRUCTIONS
+    ("branches", 4),      // PERF_COUNT_HW_BRANCH_INSTRUCTIONS
+    ("branch-misses", 5), // PERF_COUNT_HW_BRANCH_MISSES
+];
+
+const PERF_TYPE_HARDWARE: u32 = 0;
+const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+const PERF_FORMAT_GROUP: u64 = 1 << 3;
+// Flag bits of `perf_event_attr`.
+const DISABLED: u64 = 1 << 0;
+const EXCLUDE_KERNEL: u64 = 1 << 5;
+const EXCLUDE_HV: u64 = 1 << 6;
+const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+const PERF_EVENT_IOC_DISABLE: libc::c_ulong = 0x2401;
+const PERF_IOC_FLAG_GROUP: libc::c_ulong = 1;
+const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+/// The leading fields of the kernel's `perf_event_attr`; the rest stay zero.
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    rest: [u64; 10],
+}
+
+/// `EVENTS` of the calling thread in user space, as one group so they are scheduled, and
+/// multiplexed, together. Like the profiler they count only while a measurement loop runs.
+pub struct Counters {
+    /// The group leader first.
+    events: Vec<File>,
+}
+
+impl Counters {
+    /// Opens the counters on the calling thread, which must be the one that measures. Fails
+    /// where the kernel or hypervisor exposes no hardware counters or `perf_event_paranoid`
+    /// forbids them.
+    pub fn open() -> io::Result<Self> {
+        let mut events = Vec::<File>::new();
+        for (_, config) in EVENTS {
+            let attr = PerfEventAttr {
+                kind: PERF_TYPE_HARDWARE,
+                size: std::mem::size_of::<PerfEventAttr>() as u32,
+                config,
+                read_format: PERF_FORMAT_GROUP
+                    | PERF_FORMAT_TOTAL_TIME_ENABLED
+                    | PERF_FORMAT_TOTAL_TIME_RUNNING,
+                flags: if events.is_empty() { DISABLED } else { 0 } | EXCLUDE_KERNEL | EXCLUDE_HV,
+                ..Default::default()
+            };
+            let leader = events.first().map_or(-1, |leader| leader.as_raw_fd());
+            let fd = unsafe {
+                libc::syscall(
+                    libc::SYS_perf_event_open,
+                    &attr as *const PerfEventAttr,
+                    0,
+                    -1,
+                    leader,
+                    PERF_FLAG_FD_CLOEXEC,
+                )
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            events.push(unsafe { File::from_raw_fd(fd as libc::c_int) });
+        }
+        Ok(Self { events })
+    }
+
+    pub fn enable(&mut self) {
+        self.ioctl(PERF_EVENT_IOC_ENABLE);
+    }
+
+    pub fn disable(&mut self) {
+        self.ioctl(PERF_EVENT_IOC_DISABLE);
+    }
+
+    fn ioctl(&self, request: libc::c_ulong) {
+        unsafe {
+            libc::ioctl(
+                self.events[0].as_raw_fd(),
+                request as _,
+                PERF_IOC_FLAG_GROUP,
+            )
+        };
+    }
+
+    /// Counts so far by event name, scaled up if the group shared the hardware with others and
+    /// only ran part of the time.
+    pub fn read(&self) -> io::Result<BTreeMap<String, u64>> {
+        let mut buffer = [0u8; 8 * (3 + EVENTS.len())];
+        (&self.events[0]).read_exact(&mut buffer)?;
+        let words = buffer
+            .as_chunks::<8>()
+            .0
+            .iter()
+            .map(|&word| u64::from_ne_bytes(word))
+            .collect::<Vec<_>>();
+        let (enabled, running) = (words[1], words[2]);
+        let scale = match running {
+            0 => 0.0,
+            running => enabled as f64 / running as f64,
+        };
+        Ok(EVENTS
+            .iter()
+            .zip(&words[3..])
+            .map(|(&(name, _), &count)| (name.to_string(), (count as f64 * scale) as u64))
+            .collect())
+    }
+}
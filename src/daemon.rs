@@ -0,0 +1,226 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+    time::Duration,
+};
+
+use crate::{env, history::History};
+
+/// When the next round of benchmarks should run.
+pub enum Trigger {
+    Interval(Duration),
+    /// Whenever the file is written, e.g. `touch request` from a CI job.
+    Watch(PathBuf),
+}
+
+pub struct Regression {
+    pub bench: String,
+    pub baseline_ns: f64,
+    pub latest_ns: f64,
+}
+
+impl Regression {
+    pub fn percent(&self) -> f64 {
+        (self.latest_ns - self.baseline_ns) / self.baseline_ns * 100.0
+    }
+}
+
+pub struct Daemon {
+    pub trigger: Trigger,
+    pub history: PathBuf,
+    /// Arguments for each benchmark run, e.g. filters.
+    pub run_args: Vec<String>,
+    /// How many earlier runs form the baseline.
+    pub window: usize,
+    /// Relative slowdown of the mean that counts as a regression, e.g. 0.1 for 10%.
+    pub threshold: f64,
+    /// Shell command run for every regression, with details in `MICROBENCH_*` variables.
+    pub alert_command: Option<String>,
+}
+
+impl Daemon {
+    pub fn run(&self) -> io::Result<()> {
+        let binary = std::env::current_exe()?;
+        // Watched for the daemon's lifetime, so writes during a run trigger the next one.
+        let mut watcher = match &self.trigger {
+            Trigger::Watch(path) => Some(Watcher::new(path)?),
+            Trigger::Interval(_) => None,
+        };
+        loop {
+            if let Some(watcher) = &mut watcher {
+                watcher.wait()?;
+            }
+
+            self.run_once(&binary)?;
+
+            if let Trigger::Interval(interval) = self.trigger {
+                thread::sleep(interval);
+            }
+        }
+    }
+
+    fn run_once(&self, binary: &Path) -> io::Result<()> {
+        let status = Command::new(binary)
+            .args(&self.run_args)
+            .arg("--history")
+            .arg(&self.history)
+            .status()?;
+        if !status.success() {
+            eprintln!("benchmark run failed: {status}");
+            return Ok(());
+        }
+
+        let history = History::open(&self.history).map_err(io::Error::other)?;
+        let host = env::hostname();
+        for regression in regressions(&history, &host, self.window, self.threshold)? {
+            self.alert(&regression)?;
+        }
+        Ok(())
+    }
+
+    fn alert(&self, regression: &Regression) -> io::Result<()> {
+        eprintln!(
+            "REGRESSION {}: mean {:.1} ns vs baseline {:.1} ns ({:+.1}%)",
+            regression.bench,
+            regression.latest_ns,
+            regression.baseline_ns,
+            regression.percent()
+        );
+
+        if let Some(command) = &self.alert_command {
+            Command::new("sh")
+                .args(["-c", command])
+                .env("MICROBENCH_BENCH", &regression.bench)
+                .env("MICROBENCH_BASELINE_NS", regression.baseline_ns.to_string())
+                .env("MICROBENCH_LATEST_NS", regression.latest_ns.to_string())
+                .env("MICROBENCH_PERCENT", format!("{:.1}", regression.percent()))
+                .status()?;
+        }
+        Ok(())
+    }
+}
+
+/// Benchmarks whose mean in the latest run on `host` exceeds the median of the `window` runs on
+/// `host` before it by more than `threshold`; other machines sharing the history do not count.
+pub fn regressions(
+    history: &History,
+    host: &str,
+    window: usize,
+    threshold: f64,
+) -> io::Result<Vec<Regression>> {
+    let Some(latest) = history.latest_run(Some(host)).map_err(io::Error::other)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut regressions = Vec::new();
+    for bench in history.bench_names(latest).map_err(io::Error::other)? {
+        let trend = history
+            .trend(&bench, Some(host))
+            .map_err(io::Error::other)?;
+        let Some((last, earlier)) = trend.split_last() else {
+            continue;
+        };
+        if last.run_id != latest || earlier.is_empty() {
+            continue;
+        }
+
+        let mut baseline = earlier
+            .iter()
+            .rev()
+            .take(window)
+            .map(|p| p.mean_ns)
+            .collect::<Vec<_>>();
+        baseline.sort_by(f64::total_cmp);
+        let baseline_ns = baseline[baseline.len() / 2];
+
+        if last.mean_ns > baseline_ns * (1.0 + threshold) {
+            regressions.push(Regression {
+                bench,
+                baseline_ns,
+                latest_ns: last.mean_ns,
+            });
+        }
+    }
+    Ok(regressions)
+}
+
+/// Writes to a file, watched from creation to drop so none is missed between waits.
+#[cfg(target_os = "linux")]
+struct Watcher {
+    fd: libc::c_int,
+}
+
+#[cfg(target_os = "linux")]
+impl Watcher {
+    fn new(path: &Path) -> io::Result<Self> {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        if !path.exists() {
+            std::fs::write(path, "")?;
+        }
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let watcher = Self { fd };
+        let c_path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let watch = unsafe {
+            libc::inotify_add_watch(fd, c_path.as_ptr(), libc::IN_CLOSE_WRITE | libc::IN_ATTRIB)
+        };
+        if watch < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(watcher)
+    }
+
+    /// Returns once the file has been written since the last wait, or since `new`.
+    fn wait(&mut self) -> io::Result<()> {
+        let mut buffer = [0u8; 4096];
+        let read = unsafe { libc::read(self.fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+        if read < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+/// Writes to a file, polled for a new modification time every second.
+#[cfg(not(target_os = "linux"))]
+struct Watcher {
+    path: PathBuf,
+    modified: Option<std::time::SystemTime>,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Watcher {
+    fn new(path: &Path) -> io::Result<Self> {
+        let path = path.to_path_buf();
+        let modified = Self::modified(&path);
+        Ok(Self { path, modified })
+    }
+
+    fn modified(path: &Path) -> Option<std::time::SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    /// Returns once the file has been written since the last wait, or since `new`.
+    fn wait(&mut self) -> io::Result<()> {
+        loop {
+            let modified = Self::modified(&self.path);
+            if modified != self.modified {
+                self.modified = modified;
+                return Ok(());
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::{collections::BTreeMap, fs};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{os, timer::Timer};
+
+/// Where and how a set of results was measured.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Environment {
+    /// Source state the binary was built from, embedded by `build.rs`.
+    pub git_commit: String,
+    pub git_branch: String,
+    /// Whether tracked files had uncommitted changes, if known.
+    pub git_dirty: Option<bool>,
+    pub hostname: String,
+    pub os: String,
+    pub kernel: String,
+    pub arch: String,
+    pub cpu_model: String,
+    pub microcode: String,
+    /// The kernel's Spectre v2 mitigation (retpolines, IBRS, ...), which taxes indirect branches.
+    pub spectre_v2: String,
+    pub logical_cpus: usize,
+    pub available_cpus: usize,
+    pub cpuset: String,
+    pub cpu_quota: String,
+    /// Core the measurements ran on, and why it was chosen.
+    pub core: String,
+    /// Node the run was bound to with `--numa-node`.
+    pub numa_node: Option<usize>,
+    /// Whether address space layout randomization was on, if known.
+    pub aslr: Option<bool>,
+    pub mitigations: Mitigations,
+    /// Filter installed with `--seccomp`.
+    pub seccomp: Option<String>,
+    pub timer: String,
+    pub timer_frequency: u64,
+}
+
+impl Environment {
+    pub fn collect(timer: &dyn Timer) -> Self {
+        let cpuinfo = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+        let (hostname, kernel) = uname();
+        let limits = os::cpu_limits();
+
+        Self {
+            git_commit: build_info(env!("MICROBENCH_GIT_COMMIT")),
+            git_branch: build_info(env!("MICROBENCH_GIT_BRANCH")),
+            git_dirty: match env!("MICROBENCH_GIT_DIRTY") {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            },
+            hostname,
+            os: std::env::consts::OS.to_string(),
+            kernel,
+            arch: std::env::consts::ARCH.to_string(),
+            cpu_model: cpuinfo_field(&cpuinfo, "model name")
+                .or_else(|| cpuinfo_field(&cpuinfo, "CPU part"))
+                .unwrap_or_else(|| "unknown".to_string()),
+            microcode: cpuinfo_field(&cpuinfo, "microcode").unwrap_or_else(|| "unknown".into()),
+            spectre_v2: fs::read_to_string("/sys/devices/system/cpu/vulnerabilities/spectre_v2")
+                .map_or_else(|_| "unknown".into(), |status| status.trim().to_string()),
+            logical_cpus: limits.online,
+            available_cpus: limits.available(),
+            cpuset: limits.cpuset_list(),
+            cpu_quota: limits
+                .quota
+                .map_or_else(|| "none".into(), |q| format!("{q:.2}")),
+            core: "unpinned".into(),
+            numa_node: None,
+            aslr: os::aslr_enabled(),
+            mitigations: Mitigations::collect(),
+            seccomp: None,
+            timer: timer.name().to_string(),
+            timer_frequency: timer.frequency(),
+        }
+    }
+
+    /// Label/value pairs in display order.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("git commit", self.git_commit.clone()),
+            ("git branch", self.git_branch.clone()),
+            (
+                "uncommitted changes",
+                match self.git_dirty {
+                    Some(true) => "yes",
+                    Some(false) => "no",
+                    None => "unknown",
+                }
+                .into(),
+            ),
+            ("hostname", self.hostname.clone()),
+            ("os", self.os.clone()),
+            ("kernel", self.kernel.clone()),
+            ("arch", self.arch.clone()),
+            ("cpu", self.cpu_model.clone()),
+            ("microcode", self.microcode.clone()),
+            ("spectre v2", self.spectre_v2.clone()),
+            ("logical cpus", self.logical_cpus.to_string()),
+            ("available cpus", self.available_cpus.to_string()),
+            ("cpuset", self.cpuset.clone()),
+            ("cpu quota", self.cpu_quota.clone()),
+            ("core", self.core.clone()),
+            (
+                "numa node",
+                self.numa_node
+                    .map_or_else(|| "unbound".into(), |n| n.to_string()),
+            ),
+            (
+                "aslr",
+                match self.aslr {
+                    Some(true) => "on",
+                    Some(false) => "off",
+                    None => "unknown",
+                }
+                .into(),
+            ),
+            ("mitigations", self.mitigations.setting()),
+            ("kpti", self.mitigations.kpti().into()),
+            (
+                "seccomp",
+                self.seccomp.clone().unwrap_or_else(|| "none".into()),
+            ),
+            ("timer", self.timer.clone()),
+            ("timer frequency", format!("{} Hz", self.timer_frequency)),
+        ]
+    }
+}
+
+/// Kernel parameters that switch CPU vulnerability mitigations on or off.
+const MITIGATION_PARAMETERS: [&str; 24] = [
+    "mitigations",
+    "pti",
+    "nopti",
+    "kpti",
+    "nospectre_v1",
+    "nospectre_v2",
+    "spectre_v2",
+    "spectre_v2_user",
+    "spectre_bhi",
+    "nospectre_bhb",
+    "spec_store_bypass_disable",
+    "nospec_store_bypass_disable",
+    "ssbd",
+    "l1tf",
+    "l1d_flush",
+    "mds",
+    "tsx",
+    "tsx_async_abort",
+    "mmio_stale_data",
+    "retbleed",
+    "spec_rstack_overflow",
+    "srbds",
+    "gather_data_sampling",
+    "reg_file_data_sampling",
+];
+
+/// How the kernel guards against CPU vulnerabilities, which taxes every syscall (KPTI switches
+/// page tables on each entry and exit) and indirect branch.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mitigations {
+    /// The mitigation parameters on the kernel command line, space-separated, or `None` where it
+    /// is unreadable (and in runs recorded before this was).
+    pub boot_flags: Option<String>,
+    /// Every file of `/sys/devices/system/cpu/vulnerabilities` and its status line.
+    pub vulnerabilities: BTreeMap<String, String>,
+}
+
+impl Mitigations {
+    pub fn collect() -> Self {
+        let boot_flags = fs::read_to_string("/proc/cmdline").ok().map(|cmdline| {
+            cmdline
+                .split_whitespace()
+                .filter(|parameter| {
+                    let name = parameter.split('=').next().unwrap();
+                    MITIGATION_PARAMETERS.contains(&name)
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        let vulnerabilities = fs::read_dir("/sys/devices/system/cpu/vulnerabilities")
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| {
+                        let status = fs::read_to_string(entry.path()).ok()?;
+                        let name = entry.file_name().into_string().ok()?;
+                        Some((name, status.trim().to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            boot_flags,
+            vulnerabilities,
+        }
+    }
+
+    /// The boot setting, what `mitigations` groups runs by: the flags, or `default` if none.
+    pub fn setting(&self) -> String {
+        match self.boot_flags.as_deref() {
+            None => "unknown".into(),
+            Some("") => "default".into(),
+            Some(flags) => flags.into(),
+        }
+    }
+
+    /// Whether kernel page table isolation is on, from the Meltdown status.
+    pub fn kpti(&self) -> &'static str {
+        match self.vulnerabilities.get("meltdown").map(String::as_str) {
+            None => "unknown",
+            Some("Not affected") => "not needed",
+            Some(status) if status.contains("PTI") => "on",
+            Some(_) => "off",
+        }
+    }
+
+    /// The setting and KPTI state in one label.
+    pub fn label(&self) -> String {
+        format!("{} (kpti {})", self.setting(), self.kpti())
+    }
+}
+
+/// The name of this machine, as recorded with every run.
+pub fn hostname() -> String {
+    uname().0
+}
+
+fn build_info(value: &str) -> String {
+    match value {
+        "" => "unknown".into(),
+        value => value.into(),
+    }
+}
+
+fn cpuinfo_field(cpuinfo: &str, key: &str) -> Option<String> {
+    cpuinfo
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(k, _)| k.trim() == key)
+        .map(|(_, v)| v.trim().to_string())
+}
+
+#[cfg(unix)]
+fn uname() -> (String, String) {
+    let mut uts = unsafe { std::mem::zeroed::<libc::utsname>() };
+    if unsafe { libc::uname(&mut uts) } != 0 {
+        return ("unknown".into(), "unknown".into());
+    }
+
+    let field = |chars: &[libc::c_char]| {
+        unsafe { std::ffi::CStr::from_ptr(chars.as_ptr()) }
+            .to_string_lossy()
+            .into_owned()
+    };
+
+    (
+        field(&uts.nodename),
+        format!("{} {}", field(&uts.release), field(&uts.version)),
+    )
+}
+
+#[cfg(not(unix))]
+fn uname() -> (String, String) {
+    (
+        std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".into()),
+        "unknown".into(),
+    )
+}
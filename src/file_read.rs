@@ -0,0 +1,218 @@
+//! Reading a large file start to end three ways: through `BufReader` and its 8 KiB buffer,
+//! with `O_DIRECT` reads of a MiB into an aligned buffer, which bypass the page cache, and
+//! through an `mmap` of the whole file advised as sequential. Each touches a byte of every
+//! cache line it gets, so all of them bring the data into the cache. With `drop_cache` the
+//! file's pages are dropped from the page cache before every sample, so the buffered and mapped
+//! reads come from the disk too. Besides the bandwidth, every result counts the syscalls and
+//! page faults of a sample, which `print_table` shows per MB.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    os::{fd::AsRawFd, unix::fs::OpenOptionsExt},
+    path::Path,
+    ptr, slice,
+};
+
+use crate::{bench::Bencher, report::BenchResult, timer::TimerKind};
+
+/// Bytes per `O_DIRECT` read.
+const DIRECT_BLOCK: usize = 1 << 20;
+const SYSCALLS: &str = "syscalls";
+const PAGE_FAULTS: &str = "page-faults";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Buffered,
+    Direct,
+    Mmap,
+}
+
+impl Method {
+    pub const ALL: [Method; 3] = [Method::Buffered, Method::Direct, Method::Mmap];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Method::Buffered => "buffered",
+            Method::Direct => "direct",
+            Method::Mmap => "mmap",
+        }
+    }
+}
+
+/// A page, for buffers `O_DIRECT` accepts.
+#[derive(Clone, Copy)]
+#[repr(C, align(4096))]
+struct Page([u8; 4096]);
+
+/// Writes `len` bytes of a repeating pattern to `path`.
+pub fn create(path: &Path, len: usize) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    let chunk = (0..1 << 20).map(|i| i as u8).collect::<Vec<_>>();
+    for start in (0..len).step_by(chunk.len()) {
+        file.write_all(&chunk[..chunk.len().min(len - start)])?;
+    }
+    file.sync_all()
+}
+
+/// The sum of the first byte of every cache line of `data`.
+fn touch(data: &[u8]) -> u64 {
+    data.iter().step_by(64).map(|&byte| byte as u64).sum()
+}
+
+/// Page faults of the calling thread so far.
+fn page_faults() -> u64 {
+    // Safety: zero is a valid `rusage`.
+    let mut usage = unsafe { std::mem::zeroed::<libc::rusage>() };
+    // Safety: fills `usage`.
+    unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut usage) };
+    (usage.ru_minflt + usage.ru_majflt) as u64
+}
+
+/// Reads `path` once with `method`, returning the checksum and the syscalls made.
+fn read(method: Method, path: &Path, len: usize, direct: &mut [Page]) -> io::Result<(u64, u64)> {
+    // Opening and closing the file.
+    let mut syscalls = 2;
+    let mut sum = 0;
+    match method {
+        Method::Buffered => {
+            let mut reader = BufReader::new(File::open(path)?);
+            loop {
+                let chunk = reader.fill_buf()?;
+                syscalls += 1;
+                if chunk.is_empty() {
+                    break;
+                }
+                sum += touch(chunk);
+                let consumed = chunk.len();
+                reader.consume(consumed);
+            }
+        }
+        Method::Direct => {
+            let mut file = File::options()
+                .read(true)
+                .custom_flags(libc::O_DIRECT)
+                .open(path)?;
+            // Safety: the pages are plain bytes, and borrowed for as long as the slice.
+            let buffer = unsafe {
+                slice::from_raw_parts_mut(direct.as_mut_ptr().cast::<u8>(), size_of_val(direct))
+            };
+            loop {
+                let read = file.read(buffer)?;
+                syscalls += 1;
+                if read == 0 {
+                    break;
+                }
+                sum += touch(&buffer[..read]);
+            }
+        }
+        Method::Mmap => {
+            let file = File::open(path)?;
+            // Safety: a fresh read-only mapping of the whole file, checked below.
+            let map = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ,
+                    libc::MAP_PRIVATE,
+                    file.as_raw_fd(),
+                    0,
+                )
+            };
+            if map == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            // Safety: the mapping holds `len` readable bytes until unmapped below.
+            unsafe {
+                libc::madvise(map, len, libc::MADV_SEQUENTIAL);
+                sum = touch(slice::from_raw_parts(map.cast(), len));
+                libc::munmap(map, len);
+            }
+            syscalls += 3;
+        }
+    }
+    Ok((sum, syscalls))
+}
+
+/// Reads of `path` with `method` into `file_read::<method>_<cached|uncached>`, dropping the
+/// file from the page cache before every sample if `drop_cache`; an error where the method
+/// fails, as `O_DIRECT` does on tmpfs.
+pub fn measure(
+    method: Method,
+    path: &Path,
+    drop_cache: bool,
+    timer: TimerKind,
+    overhead: u64,
+    samples: u64,
+) -> io::Result<BenchResult> {
+    let len = std::fs::metadata(path)?.len() as usize;
+    let mut direct = vec![Page([0; 4096]); DIRECT_BLOCK / size_of::<Page>()];
+    // Fails early where the method cannot read the file at all.
+    read(method, path, len, &mut direct)?;
+
+    let mut bencher = Bencher::new(timer, overhead, samples);
+    bencher.set_bytes(len as u64);
+    let (mut syscalls, faults_before) = (0, page_faults());
+    bencher.iter_custom(|timer| {
+        if drop_cache {
+            let file = File::open(path).unwrap();
+            // Safety: drops the file's clean pages from the page cache.
+            unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+        }
+        let start = timer.now();
+        let (sum, calls) = read(method, path, len, &mut direct).unwrap();
+        let end = timer.now();
+        std::hint::black_box(sum);
+        syscalls += calls;
+        end - start
+    });
+    let faults = page_faults() - faults_before;
+
+    let timer = timer.timer();
+    let samples = bencher.samples().to_vec();
+    let count = samples.len().max(1) as f64;
+    let mut result = BenchResult::new(
+        &name(method, drop_cache),
+        timer.unit(),
+        timer.frequency(),
+        samples,
+    );
+    result.context_switches = bencher.context_switches();
+    result.bytes = bencher.bytes();
+    result
+        .counters
+        .insert(SYSCALLS.into(), syscalls as f64 / count);
+    result
+        .counters
+        .insert(PAGE_FAULTS.into(), faults as f64 / count);
+    Ok(result)
+}
+
+pub fn name(method: Method, drop_cache: bool) -> String {
+    let cache = if drop_cache { "uncached" } else { "cached" };
+    format!("file_read::{}_{cache}", method.name())
+}
+
+/// Prints the bandwidth, syscalls and page faults per MB of every method, `-` for those that
+/// failed.
+pub fn print_table(drop_cache: bool, results: &[BenchResult]) {
+    println!(
+        "{:>10} {:>10} {:>14} {:>16}",
+        "method", "GB/s", "syscalls/MB", "page faults/MB"
+    );
+    for method in Method::ALL {
+        let Some(result) = results.iter().find(|r| r.name == name(method, drop_cache)) else {
+            println!("{:>10} {:>10} {:>14} {:>16}", method.name(), "-", "-", "-");
+            continue;
+        };
+        let megabytes = result.bytes as f64 / 1e6;
+        let per_mb = |counter| result.counters.get(counter).copied().unwrap_or(0.0) / megabytes;
+        println!(
+            "{:>10} {:>10.2} {:>14.2} {:>16.2}",
+            method.name(),
+            result.bandwidth().unwrap_or(0.0),
+            per_mb(SYSCALLS),
+            per_mb(PAGE_FAULTS),
+        );
+    }
+}
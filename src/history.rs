@@ -0,0 +1,414 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    path::Path,
+};
+
+use rusqlite::{params, Connection};
+
+use crate::{
+    env::Environment,
+    report::{Report, ResultSink},
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    git_commit TEXT NOT NULL,
+    git_branch TEXT NOT NULL DEFAULT '',
+    git_dirty INTEGER,
+    hostname TEXT NOT NULL,
+    kernel TEXT NOT NULL,
+    cpu TEXT NOT NULL,
+    microcode TEXT NOT NULL,
+    timer TEXT NOT NULL,
+    environment TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    name TEXT NOT NULL,
+    unit TEXT NOT NULL,
+    frequency INTEGER NOT NULL,
+    count INTEGER NOT NULL,
+    mean REAL NOT NULL,
+    stddev REAL NOT NULL,
+    min INTEGER NOT NULL,
+    p50 INTEGER NOT NULL,
+    p90 INTEGER NOT NULL,
+    p99 INTEGER NOT NULL,
+    p999 INTEGER NOT NULL,
+    max INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS results_name ON results(name, run_id);
+";
+
+/// Columns added to `runs` since the first schema, for databases created before them.
+const ADDED_COLUMNS: [(&str, &str); 2] = [
+    ("git_branch", "TEXT NOT NULL DEFAULT ''"),
+    ("git_dirty", "INTEGER"),
+];
+
+/// Every run's summaries and environment, kept across kernel and microcode updates.
+pub struct History {
+    connection: Connection,
+}
+
+pub struct Run {
+    pub id: i64,
+    pub timestamp: String,
+    pub git_commit: String,
+    pub git_dirty: Option<bool>,
+    pub hostname: String,
+    pub kernel: String,
+    pub microcode: String,
+    pub benches: usize,
+}
+
+pub struct TrendPoint {
+    pub run_id: i64,
+    pub timestamp: String,
+    pub git_commit: String,
+    pub git_dirty: Option<bool>,
+    pub kernel: String,
+    pub microcode: String,
+    pub unit: String,
+    pub mean_ns: f64,
+    pub p50_ns: f64,
+    pub p99_ns: f64,
+}
+
+/// Runs sharing a mitigation setting, and their results.
+struct Group {
+    setting: String,
+    label: String,
+    runs: Vec<i64>,
+    /// Every run's mean in nanoseconds, by benchmark.
+    means: BTreeMap<String, Vec<f64>>,
+}
+
+impl History {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        for (column, definition) in ADDED_COLUMNS {
+            let exists = connection
+                .prepare("SELECT 1 FROM pragma_table_info('runs') WHERE name = ?1")?
+                .exists([column])?;
+            if !exists {
+                connection.execute_batch(&format!(
+                    "ALTER TABLE runs ADD COLUMN {column} {definition}"
+                ))?;
+            }
+        }
+        Ok(Self { connection })
+    }
+
+    pub fn record(&mut self, report: &Report) -> rusqlite::Result<i64> {
+        let env = &report.environment;
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO runs (git_commit, git_branch, git_dirty, hostname, kernel, cpu, microcode,
+                               timer, environment)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                env.git_commit,
+                env.git_branch,
+                env.git_dirty,
+                env.hostname,
+                env.kernel,
+                env.cpu_model,
+                env.microcode,
+                env.timer,
+                serde_json::to_string(env).unwrap(),
+            ],
+        )?;
+        let run_id = transaction.last_insert_rowid();
+
+        for result in &report.results {
+            let s = &result.summary;
+            transaction.execute(
+                "INSERT INTO results
+                 (run_id, name, unit, frequency, count, mean, stddev, min, p50, p90, p99, p999, max)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    run_id,
+                    result.name,
+                    result.unit,
+                    result.frequency as i64,
+                    s.count as i64,
+                    s.mean,
+                    s.stddev,
+                    s.min as i64,
+                    s.p50 as i64,
+                    s.p90 as i64,
+                    s.p99 as i64,
+                    s.p999 as i64,
+                    s.max as i64,
+                ],
+            )?;
+        }
+
+        transaction.commit()?;
+        Ok(run_id)
+    }
+
+    pub fn runs(&self) -> rusqlite::Result<Vec<Run>> {
+        let mut statement = self.connection.prepare(
+            "SELECT runs.id, timestamp, git_commit, hostname, kernel, microcode, COUNT(results.name),
+                    git_dirty
+             FROM runs LEFT JOIN results ON results.run_id = runs.id
+             GROUP BY runs.id ORDER BY runs.id",
+        )?;
+        let runs = statement.query_map([], |row| {
+            Ok(Run {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                git_commit: row.get(2)?,
+                hostname: row.get(3)?,
+                kernel: row.get(4)?,
+                microcode: row.get(5)?,
+                benches: row.get::<_, i64>(6)? as usize,
+                git_dirty: row.get(7)?,
+            })
+        })?;
+        runs.collect()
+    }
+
+    /// The most recent run, only those from `host` if given.
+    pub fn latest_run(&self, host: Option<&str>) -> rusqlite::Result<Option<i64>> {
+        self.connection.query_row(
+            "SELECT MAX(id) FROM runs WHERE ?1 IS NULL OR hostname = ?1",
+            [host],
+            |row| row.get(0),
+        )
+    }
+
+    /// Mean latency in nanoseconds of every benchmark in the most recent run.
+    pub fn latest_means(&self) -> rusqlite::Result<BTreeMap<String, f64>> {
+        let mut statement = self.connection.prepare(
+            "SELECT name, mean * 1e9 / frequency FROM results
+             WHERE run_id = (SELECT MAX(id) FROM runs)",
+        )?;
+        let means = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        means.collect()
+    }
+
+    /// Mean latency in nanoseconds of every benchmark in run `run_id`.
+    pub fn means(&self, run_id: i64) -> rusqlite::Result<BTreeMap<String, f64>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT name, mean * 1e9 / frequency FROM results WHERE run_id = ?1")?;
+        let means = statement.query_map([run_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        means.collect()
+    }
+
+    /// Every run's id and environment, oldest first, only those from `host` if given.
+    pub fn environments(&self, host: Option<&str>) -> rusqlite::Result<Vec<(i64, Environment)>> {
+        let mut statement = self.connection.prepare(
+            "SELECT id, environment FROM runs WHERE ?1 IS NULL OR hostname = ?1 ORDER BY id",
+        )?;
+        let runs = statement.query_map([host], |row| {
+            let environment: String = row.get(1)?;
+            Ok((
+                row.get(0)?,
+                serde_json::from_str(&environment).unwrap_or_default(),
+            ))
+        })?;
+        runs.collect()
+    }
+
+    pub fn bench_names(&self, run_id: i64) -> rusqlite::Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT name FROM results WHERE run_id = ?1 ORDER BY name")?;
+        let names = statement.query_map([run_id], |row| row.get(0))?;
+        names.collect()
+    }
+
+    /// Every run's result for benchmark `name`, oldest first, only those from `host` if given.
+    pub fn trend(&self, name: &str, host: Option<&str>) -> rusqlite::Result<Vec<TrendPoint>> {
+        let mut statement = self.connection.prepare(
+            "SELECT runs.id, timestamp, git_commit, kernel, microcode, unit,
+                    mean * 1e9 / frequency, p50 * 1e9 / frequency, p99 * 1e9 / frequency,
+                    git_dirty
+             FROM results JOIN runs ON results.run_id = runs.id
+             WHERE name = ?1 AND (?2 IS NULL OR hostname = ?2) ORDER BY runs.id",
+        )?;
+        let points = statement.query_map(params![name, host], |row| {
+            Ok(TrendPoint {
+                run_id: row.get(0)?,
+                timestamp: row.get(1)?,
+                git_commit: row.get(2)?,
+                kernel: row.get(3)?,
+                microcode: row.get(4)?,
+                unit: row.get(5)?,
+                mean_ns: row.get(6)?,
+                p50_ns: row.get(7)?,
+                p99_ns: row.get(8)?,
+                git_dirty: row.get(9)?,
+            })
+        })?;
+        points.collect()
+    }
+
+    pub fn print_runs(&self) -> rusqlite::Result<()> {
+        println!(
+            "{:>5}  {:<20}  {:<18}  {:<16}  {:<10}  {:>7}  kernel",
+            "run", "timestamp", "commit", "host", "microcode", "benches"
+        );
+        for run in self.runs()? {
+            println!(
+                "{:>5}  {:<20}  {:<18}  {:<16}  {:<10}  {:>7}  {}",
+                run.id,
+                run.timestamp,
+                short_commit(&run.git_commit, run.git_dirty),
+                run.hostname,
+                run.microcode,
+                run.benches,
+                run.kernel
+            );
+        }
+        Ok(())
+    }
+
+    pub fn print_trend(&self, name: &str) -> rusqlite::Result<()> {
+        let points = self.trend(name, None)?;
+        if points.is_empty() {
+            println!("no recorded results for {name}");
+            return Ok(());
+        }
+
+        println!(
+            "{:>5}  {:<20}  {:<18}  {:>10}  {:>10}  {:>10}  {:>8}  kernel / microcode",
+            "run", "timestamp", "commit", "mean ns", "p50 ns", "p99 ns", "delta"
+        );
+        let mut previous: Option<f64> = None;
+        for point in &points {
+            let delta = previous
+                .map(|p| format!("{:+.1}%", (point.mean_ns - p) / p * 100.0))
+                .unwrap_or_default();
+            println!(
+                "{:>5}  {:<20}  {:<18}  {:>10.1}  {:>10.1}  {:>10.1}  {:>8}  {} / {} ({})",
+                point.run_id,
+                point.timestamp,
+                short_commit(&point.git_commit, point.git_dirty),
+                point.mean_ns,
+                point.p50_ns,
+                point.p99_ns,
+                delta,
+                point.kernel,
+                point.microcode,
+                point.unit
+            );
+            previous = Some(point.mean_ns);
+        }
+        Ok(())
+    }
+
+    /// Prints the benchmarks matching `filters` (any if empty) averaged over the runs of each
+    /// mitigation setting, in the order the settings first appear, with each setting's change
+    /// against the first.
+    pub fn print_mitigations(
+        &self,
+        filters: &[String],
+        host: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        let mut groups: Vec<Group> = Vec::new();
+        for (run_id, environment) in self.environments(host)? {
+            let setting = environment.mitigations.setting();
+            let index = match groups.iter().position(|group| group.setting == setting) {
+                Some(index) => index,
+                None => {
+                    groups.push(Group {
+                        setting,
+                        label: environment.mitigations.label(),
+                        runs: Vec::new(),
+                        means: BTreeMap::new(),
+                    });
+                    groups.len() - 1
+                }
+            };
+            let group = &mut groups[index];
+            group.runs.push(run_id);
+            for (name, mean) in self.means(run_id)? {
+                if filters.is_empty() || filters.iter().any(|f| name.contains(f.as_str())) {
+                    group.means.entry(name).or_default().push(mean);
+                }
+            }
+        }
+        if groups.is_empty() {
+            println!("no recorded runs");
+            return Ok(());
+        }
+
+        for (i, group) in groups.iter().enumerate() {
+            let runs = group.runs.iter().map(i64::to_string).collect::<Vec<_>>();
+            println!("[{}] {}: runs {}", i + 1, group.label, runs.join(", "));
+        }
+        println!();
+        print!("{:<40}", "benchmark");
+        for i in 1..=groups.len() {
+            print!("  {:>10}", format!("[{i}] ns"));
+            if i > 1 {
+                print!("  {:>9}", "vs [1]");
+            }
+        }
+        println!();
+        let names = groups
+            .iter()
+            .flat_map(|group| group.means.keys())
+            .collect::<BTreeSet<_>>();
+        for name in names {
+            let averages = groups
+                .iter()
+                .map(|group| {
+                    group
+                        .means
+                        .get(name)
+                        .map(|means| means.iter().sum::<f64>() / means.len() as f64)
+                })
+                .collect::<Vec<_>>();
+            print!("{name:<40}");
+            for (i, average) in averages.iter().enumerate() {
+                match average {
+                    Some(mean) => print!("  {mean:>10.1}"),
+                    None => print!("  {:>10}", "-"),
+                }
+                if i > 0 {
+                    match (averages[0], average) {
+                        (Some(first), Some(mean)) => {
+                            print!(
+                                "  {:>9}",
+                                format!("{:+.1}%", (mean - first) / first * 100.0)
+                            )
+                        }
+                        _ => print!("  {:>9}", ""),
+                    }
+                }
+            }
+            println!();
+        }
+        Ok(())
+    }
+}
+
+/// The first 12 hex digits, marked like `git describe --dirty` if there were local changes.
+fn short_commit(commit: &str, dirty: Option<bool>) -> String {
+    let short = &commit[..commit.len().min(12)];
+    match dirty {
+        Some(true) => format!("{short}-dirty"),
+        _ => short.to_string(),
+    }
+}
+
+impl ResultSink for History {
+    fn finish(&mut self, report: &Report) -> io::Result<()> {
+        let run_id = self.record(report).map_err(io::Error::other)?;
+        println!("recorded as run {run_id} in history");
+        Ok(())
+    }
+}
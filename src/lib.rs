@@ -0,0 +1,37 @@
+pub mod aggregate;
+pub mod arch;
+pub mod bench;
+pub mod cachegrind;
+pub mod cold;
+pub mod compare;
+pub mod config;
+pub mod core_to_core;
+#[cfg(target_os = "linux")]
+pub mod counters;
+pub mod daemon;
+pub mod env;
+#[cfg(target_os = "linux")]
+pub mod file_read;
+pub mod history;
+#[cfg(target_arch = "x86_64")]
+pub mod memcpy;
+#[cfg(target_os = "linux")]
+pub mod memory;
+#[cfg(target_os = "linux")]
+pub mod numa;
+pub mod os;
+#[cfg(target_os = "linux")]
+pub mod priority;
+#[cfg(target_os = "linux")]
+pub mod profile;
+pub mod report;
+pub mod score;
+#[cfg(target_os = "linux")]
+pub mod seccomp;
+pub mod stats;
+pub mod stream;
+pub mod suites;
+pub mod timer;
+pub mod watchdog;
+
+pub use inventory;
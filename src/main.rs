@@ -1,48 +1,1269 @@
-use core::arch::x86_64::*;
-use std::hint::black_box;
-
-#[inline(always)]
-fn bench() {
-    unsafe { syscalls::raw_syscall!(syscalls::Sysno::getpid) };
-}
-
-#[inline(always)]
-fn serialized_time() -> u64 {
-    unsafe {
-        _mm_lfence();
-        _mm_mfence();
-        _mm_sfence();
-        __cpuid(0);
-        _mm_lfence();
-        _mm_mfence();
-        _mm_sfence();
-        let result = _rdtsc();
-        _mm_lfence();
-        _mm_mfence();
-        _mm_sfence();
-        __cpuid(0);
-        _mm_lfence();
-        _mm_mfence();
-        _mm_sfence();
-        result
-    }
-}
-
-const NUM_RUNS: u64 = 100_000;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+#[cfg(target_arch = "x86_64")]
+use instruction_benchmark::memcpy::{self, Method};
+use instruction_benchmark::{
+    aggregate,
+    bench::{benches, shuffle, Bench, Bencher, TagFilter, MULTI_THREADED},
+    cachegrind::{self, Cachegrind},
+    cold,
+    compare::{compare, Side},
+    config::{self, Config},
+    core_to_core,
+    daemon::{Daemon, Trigger},
+    env::{self, Environment, Mitigations},
+    history::History,
+    os::{self, ContextSwitches},
+    report::{
+        console::{Console, Sorted},
+        criterion::{self, CombinedReport},
+        markdown::Markdown,
+        prometheus::Pushgateway,
+        BenchResult, ConsoleFormat, Report, ReportFormat, ResultSink,
+    },
+    score::{self, Reference},
+    stream::Stream,
+    timer::TimerKind,
+    watchdog::Watchdog,
+};
+#[cfg(target_os = "linux")]
+use instruction_benchmark::{
+    counters::Counters,
+    file_read::{self, Method as ReadMethod},
+    numa,
+    priority::{self, Load, Policy},
+    profile::Profiler,
+    seccomp,
+};
+
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArgs,
+
+    /// SQLite database every run is recorded into
+    #[arg(long, global = true, default_value = "microbench-history.sqlite")]
+    history: PathBuf,
+
+    /// Benchmark parameters (iterations, cores, suites); missing means the defaults
+    #[arg(long, global = true, default_value = config::DEFAULT_PATH)]
+    config: PathBuf,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List recorded runs
+    History,
+    /// Show how one benchmark evolved across recorded runs
+    Trend { bench: String },
+    /// Compare recorded runs grouped by the kernel's CPU vulnerability mitigation setting
+    Mitigations(MitigationsArgs),
+    /// Build and run two revisions (or binaries) interleaved and diff their results
+    Compare(CompareArgs),
+    /// Keep re-running benchmarks into the history and alert on regressions
+    Daemon(DaemonArgs),
+    /// Merge a `--report json` run with Criterion's estimates into one combined report
+    Merge(MergeArgs),
+    /// Run the benchmarks in several fresh processes and split within- from between-process
+    /// variance
+    Aggregate(AggregateArgs),
+    /// Score a run against a reference machine, overall and per category
+    Score(ScoreArgs),
+    /// Measure the cache line round trip between every pair of cores
+    CoreToCore(CoreToCoreArgs),
+    /// Measure memory latency and bandwidth from every NUMA node to every node (Linux)
+    #[cfg(target_os = "linux")]
+    Numa(NumaArgs),
+    /// Compare rep movsb, an AVX2 loop and copy_nonoverlapping by size and alignment (x86_64)
+    #[cfg(target_arch = "x86_64")]
+    Memcpy(MemcpyArgs),
+    /// Compare buffered, O_DIRECT and mmap reads of a large file (Linux)
+    #[cfg(target_os = "linux")]
+    FileRead(FileReadArgs),
+    /// Time fibonacci under background load by nice value and scheduling policy (Linux)
+    #[cfg(target_os = "linux")]
+    Priority(PriorityArgs),
+}
+
+#[derive(Args)]
+struct MitigationsArgs {
+    /// Only compare benchmarks whose name contains one of these filters
+    #[arg(default_values_t = ["syscall::".to_string()])]
+    filters: Vec<String>,
+
+    /// Only compare runs recorded on this host (default: this one)
+    #[arg(long)]
+    host: Option<String>,
+
+    /// Compare runs from every host
+    #[arg(long, conflicts_with = "host")]
+    all_hosts: bool,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Args)]
+struct NumaArgs {
+    /// Maximum samples per benchmark
+    #[arg(long, default_value_t = 10_000)]
+    samples: u64,
+
+    #[arg(long, value_enum, default_value_t = TimerKind::Cycles)]
+    timer: TimerKind,
+
+    /// Additionally write a report of every measurement into the output directory (repeatable)
+    #[arg(long, value_enum)]
+    report: Vec<ReportFormat>,
+
+    #[arg(long, default_value = "target/microbench")]
+    output_dir: PathBuf,
+}
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Args)]
+struct MemcpyArgs {
+    /// Destination offsets from 64-byte alignment, in bytes
+    #[arg(long, value_delimiter = ',', default_values_t = [0, 1, 32])]
+    offsets: Vec<usize>,
+
+    /// Maximum samples per measurement
+    #[arg(long, default_value_t = 1000)]
+    samples: u64,
+
+    #[arg(long, value_enum, default_value_t = TimerKind::Cycles)]
+    timer: TimerKind,
+
+    /// Additionally write a report of every measurement into the output directory (repeatable)
+    #[arg(long, value_enum)]
+    report: Vec<ReportFormat>,
+
+    #[arg(long, default_value = "target/microbench")]
+    output_dir: PathBuf,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Args)]
+struct FileReadArgs {
+    /// File to read (default: a temporary file of --size-mib, removed afterwards)
+    #[arg(long)]
+    file: Option<PathBuf>,
+
+    /// Size of the temporary file, in MiB
+    #[arg(long, default_value_t = 512)]
+    size_mib: usize,
+
+    /// Drop the file from the page cache before every sample
+    #[arg(long)]
+    drop_cache: bool,
+
+    /// Maximum samples per method
+    #[arg(long, default_value_t = 10)]
+    samples: u64,
+
+    #[arg(long, value_enum, default_value_t = TimerKind::Cycles)]
+    timer: TimerKind,
+
+    /// Additionally write a report of every measurement into the output directory (repeatable)
+    #[arg(long, value_enum)]
+    report: Vec<ReportFormat>,
+
+    #[arg(long, default_value = "target/microbench")]
+    output_dir: PathBuf,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Args)]
+struct PriorityArgs {
+    /// Busy-looping background threads on the measured core
+    #[arg(long, default_value_t = 1)]
+    load_threads: usize,
+
+    /// Nice values of the background load to compare, besides none (negative ones need
+    /// CAP_SYS_NICE)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        allow_negative_numbers = true,
+        default_values_t = [0, 10, 19]
+    )]
+    nice: Vec<i32>,
+
+    /// Scheduling policies of the measured thread (fifo needs CAP_SYS_NICE)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [Policy::Other, Policy::Fifo]
+    )]
+    policies: Vec<Policy>,
+
+    /// Fibonacci input (default: fib_n from the config)
+    #[arg(long)]
+    fib_n: Option<u64>,
+
+    /// Maximum samples per policy and load
+    #[arg(long, default_value_t = 200)]
+    samples: u64,
+
+    #[arg(long, value_enum, default_value_t = TimerKind::Cycles)]
+    timer: TimerKind,
+
+    /// Additionally write a report of every measurement into the output directory (repeatable)
+    #[arg(long, value_enum)]
+    report: Vec<ReportFormat>,
+
+    #[arg(long, default_value = "target/microbench")]
+    output_dir: PathBuf,
+}
+
+#[derive(Args)]
+struct CoreToCoreArgs {
+    /// Cores to measure between (default: every cpu the process may use)
+    #[arg(long, value_delimiter = ',')]
+    cores: Vec<usize>,
+
+    /// Samples per pair
+    #[arg(long, default_value_t = 1000)]
+    samples: u64,
+
+    #[arg(long, value_enum, default_value_t = TimerKind::Cycles)]
+    timer: TimerKind,
+
+    /// Additionally write a report of every pair into the output directory (repeatable)
+    #[arg(long, value_enum)]
+    report: Vec<ReportFormat>,
+
+    /// Where core_to_core.csv and the reports are written
+    #[arg(long, default_value = "target/microbench")]
+    output_dir: PathBuf,
+}
+
+#[derive(Args)]
+struct ScoreArgs {
+    /// Harness results written by `--report json`
+    #[arg(long, default_value = "target/microbench/results.json")]
+    results: PathBuf,
+
+    /// Criterion's output directory; skipped if it does not exist
+    #[arg(long, default_value = "target/criterion")]
+    criterion_dir: PathBuf,
+
+    /// Reference machine profile written by `--save-reference`
+    #[arg(long, default_value = "microbench-reference.json")]
+    reference: PathBuf,
+
+    /// Make these results the reference instead of scoring them
+    #[arg(long)]
+    save_reference: bool,
+
+    /// Where score.json is written
+    #[arg(long, default_value = "target/microbench")]
+    output_dir: PathBuf,
+}
+
+#[derive(Args)]
+struct AggregateArgs {
+    /// Number of processes to launch
+    #[arg(long, default_value_t = 10)]
+    processes: usize,
+
+    #[arg(long, default_value = "target/microbench/aggregate")]
+    work_dir: PathBuf,
+
+    /// Arguments passed through to each process, e.g. `-- syscall::`
+    #[arg(last = true)]
+    run_args: Vec<String>,
+}
+
+#[derive(Args)]
+struct MergeArgs {
+    /// Harness results written by `--report json`
+    #[arg(long, default_value = "target/microbench/results.json")]
+    results: PathBuf,
+
+    /// Criterion's output directory
+    #[arg(long, default_value = "target/criterion")]
+    criterion_dir: PathBuf,
+
+    /// Where combined.json and combined.html are written
+    #[arg(long, default_value = "target/microbench")]
+    output_dir: PathBuf,
+}
+
+#[derive(Args)]
+struct DaemonArgs {
+    /// Seconds between runs
+    #[arg(long, required_unless_present = "watch", conflicts_with = "watch")]
+    interval: Option<u64>,
+
+    /// Run whenever this file is written instead of on a schedule
+    #[arg(long)]
+    watch: Option<PathBuf>,
+
+    /// Number of earlier runs whose median mean is the regression baseline
+    #[arg(long, default_value_t = 5)]
+    window: usize,
+
+    /// Slowdown of the mean, in percent, reported as a regression
+    #[arg(long, default_value_t = 10.0)]
+    threshold: f64,
+
+    /// Shell command run per regression, with MICROBENCH_BENCH, MICROBENCH_BASELINE_NS,
+    /// MICROBENCH_LATEST_NS and MICROBENCH_PERCENT set
+    #[arg(long)]
+    alert_command: Option<String>,
+
+    /// Arguments passed through to each run, e.g. `-- syscall::`
+    #[arg(last = true)]
+    run_args: Vec<String>,
+}
+
+#[derive(Args)]
+struct CompareArgs {
+    /// Git revision to compare against
+    #[arg(
+        long,
+        required_unless_present = "base_bin",
+        conflicts_with = "base_bin"
+    )]
+    base: Option<String>,
+
+    /// Git revision to compare
+    #[arg(
+        long,
+        required_unless_present = "head_bin",
+        conflicts_with = "head_bin"
+    )]
+    head: Option<String>,
+
+    /// Prebuilt benchmark binary to use as the base instead of building a revision
+    #[arg(long)]
+    base_bin: Option<PathBuf>,
+
+    /// Prebuilt benchmark binary to use as the head instead of building a revision
+    #[arg(long)]
+    head_bin: Option<PathBuf>,
+
+    /// How many times each side is run
+    #[arg(long, default_value_t = 6)]
+    rounds: usize,
+
+    #[arg(long, default_value = "target/microbench/compare")]
+    work_dir: PathBuf,
+
+    /// Arguments passed through to each run, e.g. `-- syscall:: --timer monotonic`
+    #[arg(last = true)]
+    run_args: Vec<String>,
+}
+
+impl CompareArgs {
+    fn side(rev: Option<String>, bin: Option<PathBuf>) -> Side {
+        match (rev, bin) {
+            (_, Some(bin)) => Side::Binary(bin),
+            (Some(rev), None) => Side::Revision(rev),
+            (None, None) => unreachable!("clap requires one of them"),
+        }
+    }
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// Only run benchmarks whose name contains one of these filters (e.g. `syscall::`)
+    filters: Vec<String>,
+
+    /// Only run benchmarks with one of these tags (repeatable)
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+
+    /// Skip benchmarks with any of these tags (repeatable)
+    #[arg(long = "skip-tag")]
+    skip_tags: Vec<String>,
+
+    /// List the selected benchmarks and their tags without running them
+    #[arg(long)]
+    list: bool,
+
+    #[arg(long, value_enum, default_value_t = TimerKind::Cycles)]
+    timer: TimerKind,
+
+    /// Console output format
+    #[arg(long, value_enum, default_value_t = ConsoleFormat::Text)]
+    format: ConsoleFormat,
+
+    /// Do not print a latency histogram after each benchmark
+    #[arg(long)]
+    no_histogram: bool,
+
+    /// results.json whose means the Markdown table's deltas are relative to (default: the
+    /// previous run in the history)
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// Additionally write a report of all results into the output directory (repeatable)
+    #[arg(long, value_enum)]
+    report: Vec<ReportFormat>,
+
+    /// Push results to a Prometheus Pushgateway, e.g. `http://localhost:9091`
+    #[arg(long, value_name = "URL")]
+    push_gateway: Option<String>,
+
+    #[arg(long, default_value = "target/microbench")]
+    output_dir: PathBuf,
+
+    /// Record the named benchmark's measurement loop with `perf record` and write a flamegraph
+    /// into the output directory
+    #[cfg(target_os = "linux")]
+    #[arg(long, value_name = "BENCH", conflicts_with = "jobs")]
+    profile: Option<String>,
+
+    /// Count cycles, instructions, branches and branch misses per sample with the hardware
+    /// performance counters (Linux)
+    #[cfg(target_os = "linux")]
+    #[arg(long)]
+    counters: bool,
+
+    /// Measure on a CPU of this NUMA node, with memory allocated from it (Linux)
+    #[arg(long, value_name = "N")]
+    numa_node: Option<usize>,
+
+    /// Re-run this process with address space randomization disabled (Linux)
+    #[arg(long)]
+    no_aslr: bool,
+
+    /// Install this seccomp-bpf filter before running, to measure what filtering adds to every
+    /// syscall (Linux)
+    #[cfg(target_os = "linux")]
+    #[arg(long, value_enum, value_name = "FILTER")]
+    seccomp: Option<seccomp::Filter>,
+
+    /// Count instructions and simulated cache misses per iteration under callgrind instead of
+    /// timing
+    #[arg(long)]
+    cachegrind: bool,
+
+    /// Iterations per callgrind run
+    #[arg(long, default_value_t = 1000)]
+    cachegrind_iterations: u64,
+
+    /// Run up to this many single-threaded benchmarks at once, each on its own isolated core
+    /// without a shared L2 cache (0: as many as there are such cores)
+    #[arg(long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Also time each benchmark's very first call in this many fresh processes, reported
+    /// separately as `<name> [cold]`
+    #[arg(long, default_value_t = 0)]
+    cold_runs: usize,
+
+    /// Time one call of this benchmark and print it (the child side of --cold-runs)
+    #[arg(long, hide = true)]
+    cold_child: Option<String>,
+
+    /// Run only this benchmark's body, untimed (the child side of --cachegrind)
+    #[arg(long, hide = true)]
+    simulate: Option<String>,
+
+    #[arg(long, hide = true, default_value_t = 1000)]
+    simulate_iterations: u64,
+
+    /// Drop the samples that context switches landed in
+    #[arg(long)]
+    discard_preempted: bool,
+
+    /// Stream results live as newline-delimited JSON to `host:port` or `unix:<path>`
+    #[arg(long, value_name = "ADDR")]
+    stream: Option<String>,
+
+    /// Include every sample in the streamed batches, not just their summary
+    #[arg(long, requires = "stream")]
+    stream_samples: bool,
+
+    /// Stop any benchmark still running after this many seconds and report its partial
+    /// statistics; overrides `timeout_secs` in the config
+    #[arg(long, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    /// Do not record this run in the history database
+    #[arg(long)]
+    no_history: bool,
+
+    /// Run the benchmarks in a random order, printing the seed to reproduce it
+    #[arg(long)]
+    shuffle: bool,
+
+    /// Run in the random order drawn from this seed (implies --shuffle)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Run the whole selection this many times, pooling the samples of each benchmark
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    repetitions: u64,
+
+    /// Draw a new random order for every repetition (implies --shuffle)
+    #[arg(long)]
+    reshuffle: bool,
+}
+
+/// One benchmark's measurements pooled over `--repetitions`.
+#[derive(Default)]
+struct Pooled {
+    samples: Vec<u64>,
+    switches: ContextSwitches,
+    discarded: usize,
+    timed_out: bool,
+    /// Hardware and benchmark-counted events over `iterations`, discarded samples included.
+    counts: BTreeMap<String, u64>,
+    iterations: u64,
+}
 
 fn main() {
-    assert!(core_affinity::set_for_current(core_affinity::CoreId {
-        id: 0
-    }));
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::History) => History::open(&cli.history).unwrap().print_runs().unwrap(),
+        Some(Command::Trend { bench }) => History::open(&cli.history)
+            .unwrap()
+            .print_trend(&bench)
+            .unwrap(),
+        Some(Command::Mitigations(args)) => {
+            let host = match (args.host, args.all_hosts) {
+                (_, true) => None,
+                (Some(host), false) => Some(host),
+                (None, false) => Some(env::hostname()),
+            };
+            History::open(&cli.history)
+                .unwrap()
+                .print_mitigations(&args.filters, host.as_deref())
+                .unwrap()
+        }
+        Some(Command::Compare(args)) => compare(
+            &CompareArgs::side(args.base, args.base_bin),
+            &CompareArgs::side(args.head, args.head_bin),
+            args.rounds,
+            &args.run_args,
+            &args.work_dir,
+        )
+        .unwrap(),
+        Some(Command::Daemon(args)) => Daemon {
+            trigger: match (args.interval, args.watch) {
+                (_, Some(path)) => Trigger::Watch(path),
+                (Some(secs), None) => Trigger::Interval(Duration::from_secs(secs)),
+                (None, None) => unreachable!("clap requires one of them"),
+            },
+            history: cli.history,
+            run_args: args.run_args,
+            window: args.window,
+            threshold: args.threshold / 100.0,
+            alert_command: args.alert_command,
+        }
+        .run()
+        .unwrap(),
+        Some(Command::Merge(args)) => merge(&args),
+        Some(Command::Aggregate(args)) => aggregate::print_table(
+            &aggregate::aggregate(
+                &std::env::current_exe().unwrap(),
+                args.processes,
+                &args.run_args,
+                &args.work_dir,
+            )
+            .unwrap(),
+        ),
+        Some(Command::Score(args)) => score(&args),
+        Some(Command::CoreToCore(args)) => core_to_core(&args),
+        #[cfg(target_os = "linux")]
+        Some(Command::Numa(args)) => numa(&args),
+        #[cfg(target_arch = "x86_64")]
+        Some(Command::Memcpy(args)) => memcpy(&args),
+        #[cfg(target_os = "linux")]
+        Some(Command::FileRead(args)) => file_read(&args),
+        #[cfg(target_os = "linux")]
+        Some(Command::Priority(args)) => priority(&args, &Config::load(&cli.config).unwrap()),
+        None => run(cli.run, &cli.config, &cli.history),
+    }
+}
+
+fn run(args: RunArgs, config_path: &Path, history: &Path) {
+    if args.no_aslr && os::aslr_enabled() == Some(true) {
+        panic!("failed to disable ASLR: {}", os::reexec_without_aslr());
+    }
+    let config = &Config::load(config_path).unwrap();
+
+    let tag_filter = TagFilter {
+        include: args.tags.clone(),
+        exclude: args.skip_tags.clone(),
+    };
+    let selected = benches()
+        .into_iter()
+        .filter(|b| {
+            b.matches(&args.filters)
+                && tag_filter.matches(b.tags)
+                && config.suite_enabled(b.suite())
+        })
+        .collect::<Vec<_>>();
+
+    if args.list {
+        for bench in selected {
+            println!("{:<40} {}", bench.name(), bench.tags.join(", "));
+        }
+        return;
+    }
+
+    if let Some(name) = &args.simulate {
+        (bench_named(name).run)(&mut Bencher::simulated(args.simulate_iterations));
+        return;
+    }
+
+    if args.cachegrind {
+        let cachegrind = Cachegrind {
+            binary: std::env::current_exe().unwrap(),
+            iterations: args.cachegrind_iterations,
+            output_dir: args.output_dir.join("cachegrind"),
+        };
+        let counts = selected
+            .iter()
+            .map(|bench| cachegrind.measure(bench.name()).unwrap())
+            .collect::<Vec<_>>();
+        cachegrind::print_table(&counts);
+        cachegrind::write_json(&counts, &args.output_dir.join("cachegrind.json")).unwrap();
+        return;
+    }
+
+    // Connected before pinning so that its writer thread may run on other cores.
+    let stream = args.stream.as_ref().map(|addr| {
+        Stream::connect(addr, args.stream_samples)
+            .unwrap_or_else(|e| panic!("failed to connect to {addr}: {e}"))
+    });
+
+    let watchdog = args
+        .timeout
+        .or(config.timeout_secs)
+        .map(|secs| Watchdog::spawn(Duration::from_secs(secs)));
+
+    let limits = os::cpu_limits();
+    let candidates = match args.numa_node {
+        Some(node) => {
+            let cpus = os::numa_node_cpus(node).expect("failed to read the NUMA node's cpus");
+            os::bind_memory_to_node(node).expect("failed to bind memory to the NUMA node");
+            cpus.into_iter()
+                .filter(|cpu| limits.cpuset.contains(cpu))
+                .collect::<Vec<_>>()
+        }
+        None if !config.cores.is_empty() => config.cores.clone(),
+        None => limits.cpuset.clone(),
+    };
+    let (core, reason) = match (args.numa_node, config.cores.first()) {
+        (None, Some(&core)) => (core, "from config"),
+        _ => os::choose_core(&candidates).expect("no usable cpu"),
+    };
+    let parallel_cores = match args.jobs {
+        1 => Vec::new(),
+        jobs => os::parallel_cores(&candidates, jobs),
+    };
+    assert!(os::pin_to_core(core), "failed to pin to core {core}");
+
+    let timer = args.timer.timer();
+    let overhead = Bencher::overhead(args.timer);
+
+    if let Some(name) = &args.cold_child {
+        let mut bencher = Bencher::new(args.timer, overhead, 1);
+        (bench_named(name).run)(&mut bencher);
+        match (bencher.skipped(), bencher.samples().first()) {
+            (None, Some(sample)) => println!("{sample}"),
+            (reason, _) => println!("{}{}", cold::SKIPPED, reason.unwrap_or("no sample")),
+        }
+        return;
+    }
+
+    println!("core: {core} ({reason})");
+    if !parallel_cores.is_empty() {
+        let cores = parallel_cores
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>();
+        println!("parallel on cores: {}", cores.join(", "));
+        let sequential = selected
+            .iter()
+            .filter(|b| b.multi_threaded())
+            .map(|b| b.name())
+            .collect::<Vec<_>>();
+        if !sequential.is_empty() {
+            println!("sequential ({}): {}", MULTI_THREADED, sequential.join(", "));
+        }
+    }
+    println!(
+        "timer: {} ({} {}/s, overhead {} {})",
+        timer.name(),
+        timer.frequency(),
+        timer.unit(),
+        overhead,
+        timer.unit()
+    );
+
+    if limits.is_limited() {
+        println!(
+            "warning: limited to cpus {} of {}{}; multi-threaded results will not match the host",
+            limits.cpuset_list(),
+            limits.online,
+            limits
+                .quota
+                .map(|q| format!(" with a quota of {q:.2} cpus"))
+                .unwrap_or_default(),
+        );
+    }
 
-    let mut total_difference: u64 = 0;
-    for _ in 0..NUM_RUNS {
-        let start = serialized_time();
-        bench();
-        let end = serialized_time();
-        total_difference += end - start;
+    #[cfg(target_os = "linux")]
+    if let Some(name) = &args.profile {
+        assert!(
+            benches().iter().any(|b| b.name() == name),
+            "no benchmark named {name}"
+        );
+        fs::create_dir_all(&args.output_dir).unwrap();
     }
 
-    println!("elapsed cycles: {}", total_difference);
-    println!("average cycles: {}", total_difference / NUM_RUNS);
+    #[cfg(target_os = "linux")]
+    let counters = args.counters
+        && match Counters::open() {
+            Ok(_) => true,
+            Err(err) => {
+                println!("hardware counters unavailable: {err}");
+                false
+            }
+        };
+
+    let mut sinks: Vec<Box<dyn ResultSink>> = match args.format {
+        ConsoleFormat::Text => vec![Box::new(Console {
+            histogram: !args.no_histogram,
+        })],
+        ConsoleFormat::Md => vec![Box::new(Markdown::new(baseline_means(&args, history)))],
+        ConsoleFormat::Sorted => vec![Box::new(Sorted)],
+    };
+    sinks.extend(args.report.iter().map(|f| f.sink(&args.output_dir)));
+    if let Some(url) = &args.push_gateway {
+        sinks.push(Box::new(Pushgateway::new(url)));
+    }
+    if !args.no_history {
+        sinks.push(Box::new(History::open(history).unwrap()));
+    }
+    let stream_handle = stream.as_ref().map(Stream::handle);
+    if let Some(stream) = stream {
+        sinks.push(Box::new(stream));
+    }
+
+    let seed = (args.shuffle || args.reshuffle || args.seed.is_some())
+        .then(|| args.seed.unwrap_or_else(random_seed));
+    if let Some(seed) = seed {
+        let reshuffle = if args.reshuffle { " --reshuffle" } else { "" };
+        println!("order seed: {seed} (reproduce with --seed {seed}{reshuffle})");
+    }
+
+    let mut cold_args = vec![
+        "--timer".to_string(),
+        args.timer
+            .to_possible_value()
+            .unwrap()
+            .get_name()
+            .to_string(),
+        "--config".to_string(),
+        config_path.display().to_string(),
+    ];
+    if args.no_aslr {
+        cold_args.push("--no-aslr".into());
+    }
+    // The child chooses its core and memory the same way, so it lands where the warm run did.
+    if let Some(node) = args.numa_node {
+        cold_args.extend(["--numa-node".into(), node.to_string()]);
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(filter) = args.seccomp {
+        filter
+            .install()
+            .expect("failed to install the seccomp filter");
+        println!(
+            "seccomp: {} ({} instructions)",
+            filter.label(),
+            filter.program().len()
+        );
+    }
+
+    let new_bencher = |bench: &Bench| {
+        let mut bencher = Bencher::new(args.timer, overhead, config.iterations)
+            .with_discard_preempted(args.discard_preempted);
+        if let Some(stream) = &stream_handle {
+            bencher = bencher.with_stream(stream.batches(bench.name()));
+        }
+        let armed = watchdog.as_ref().map(|w| w.arm(bench.name()));
+        if let Some(armed) = &armed {
+            bencher = bencher.with_deadline(armed.deadline());
+        }
+        #[cfg(target_os = "linux")]
+        if counters {
+            bencher = bencher.with_counters(Counters::open().expect("failed to open counters"));
+        }
+        (bencher, armed)
+    };
+
+    let mitigations = Mitigations::collect();
+    let mut pooled = BTreeMap::<&str, Pooled>::new();
+    let mut results = Vec::new();
+    let mut record = |bench: &'static Bench, bencher: Bencher, last: bool| {
+        if let Some(reason) = bencher.skipped() {
+            if last {
+                println!("{} skipped: {reason}", bench.name());
+            }
+            return;
+        }
+        let pooled = pooled.entry(bench.name()).or_default();
+        pooled.switches += bencher.context_switches();
+        pooled.discarded += bencher.discarded();
+        pooled.timed_out |= bencher.timed_out();
+        for (event, count) in bencher.counts() {
+            *pooled.counts.entry(event).or_default() += count;
+        }
+        pooled.iterations += (bencher.samples().len() + bencher.discarded()) as u64;
+        let (ops, bytes) = (bencher.ops(), bencher.bytes());
+        pooled.samples.extend(bencher.into_samples());
+        if !last {
+            return;
+        }
+
+        let mut result = BenchResult::new(
+            bench.name(),
+            timer.unit(),
+            timer.frequency(),
+            std::mem::take(&mut pooled.samples),
+        );
+        result.context_switches = pooled.switches;
+        result.discarded = pooled.discarded;
+        result.timed_out = pooled.timed_out;
+        result.ops = ops;
+        result.bytes = bytes;
+        if bench.tags.contains(&"syscall") {
+            result.mitigations = Some(mitigations.label());
+        }
+        result.counters = pooled
+            .counts
+            .iter()
+            .map(|(event, &count)| (event.clone(), count as f64 / pooled.iterations as f64))
+            .collect();
+        for sink in &mut sinks {
+            sink.record(&result).unwrap();
+        }
+        results.push(result);
+
+        if args.cold_runs > 0 {
+            let name = format!("{} [cold]", bench.name());
+            let samples = match cold::cold_samples(
+                &std::env::current_exe().unwrap(),
+                bench.name(),
+                args.cold_runs,
+                &cold_args,
+            )
+            .unwrap()
+            {
+                Ok(samples) => samples,
+                Err(reason) => {
+                    println!("{name} skipped: {reason}");
+                    return;
+                }
+            };
+            let result = BenchResult::new(&name, timer.unit(), timer.frequency(), samples);
+            for sink in &mut sinks {
+                sink.record(&result).unwrap();
+            }
+            results.push(result);
+        }
+    };
+
+    let mut order = selected.clone();
+    for repetition in 0..args.repetitions {
+        if let Some(seed) = seed {
+            if repetition == 0 || args.reshuffle {
+                order.clone_from(&selected);
+                shuffle(&mut order, seed.wrapping_add(repetition));
+            }
+        }
+        let last = repetition + 1 == args.repetitions;
+
+        // Side by side on their own cores first, one wave at a time; multi-threaded benchmarks
+        // get the machine to themselves afterwards.
+        let (parallel, sequential): (Vec<_>, Vec<_>) = order
+            .iter()
+            .copied()
+            .partition(|bench| !parallel_cores.is_empty() && !bench.multi_threaded());
+        for wave in parallel.chunks(parallel_cores.len().max(1)) {
+            let benchers = thread::scope(|scope| {
+                let new_bencher = &new_bencher;
+                let threads = wave
+                    .iter()
+                    .zip(&parallel_cores)
+                    .map(|(&bench, &cpu)| {
+                        scope.spawn(move || {
+                            assert!(os::pin_to_core(cpu), "failed to pin to core {cpu}");
+                            let (mut bencher, armed) = new_bencher(bench);
+                            (bench.run)(&mut bencher);
+                            drop(armed);
+                            bencher
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                threads
+                    .into_iter()
+                    .map(|thread| thread.join().unwrap())
+                    .collect::<Vec<_>>()
+            });
+            for (&bench, bencher) in wave.iter().zip(benchers) {
+                record(bench, bencher, last);
+            }
+        }
+
+        for &bench in &sequential {
+            let (mut bencher, armed) = new_bencher(bench);
+
+            #[cfg(target_os = "linux")]
+            let profile_stem = (repetition == 0 && args.profile.as_deref() == Some(bench.name()))
+                .then(|| instruction_benchmark::report::file_stem(bench.name()));
+            #[cfg(target_os = "linux")]
+            if let Some(stem) = &profile_stem {
+                let profiler = Profiler::attach(&args.output_dir.join(format!("{stem}.perf.data")))
+                    .expect("failed to start perf record");
+                bencher = bencher.with_profiler(profiler);
+            }
+
+            (bench.run)(&mut bencher);
+            drop(armed);
+
+            #[cfg(target_os = "linux")]
+            let profiler = bencher.take_profiler();
+
+            record(bench, bencher, last);
+
+            #[cfg(target_os = "linux")]
+            if let (Some(stem), Some(profiler)) = (&profile_stem, profiler) {
+                let flamegraph = args.output_dir.join(format!("{stem}.svg"));
+                profiler.finish(&flamegraph, bench.name()).unwrap();
+                println!("    flamegraph written to {}", flamegraph.display());
+            }
+        }
+    }
+
+    // The stream's writer exits once every sender is gone.
+    drop(stream_handle);
+    results.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut environment = Environment::collect(timer);
+    environment.numa_node = args.numa_node;
+    #[cfg(target_os = "linux")]
+    {
+        environment.seccomp = args.seccomp.map(seccomp::Filter::label);
+    }
+    environment.core = format!("{core} ({reason})");
+    let report = Report {
+        environment,
+        results,
+    };
+    for sink in &mut sinks {
+        sink.finish(&report).unwrap();
+    }
+}
+
+fn merge(args: &MergeArgs) {
+    let harness: Report =
+        serde_json::from_str(&fs::read_to_string(&args.results).unwrap()).unwrap();
+    let criterion = criterion::load(&args.criterion_dir).unwrap();
+    let combined = CombinedReport::new(harness, criterion);
+
+    fs::create_dir_all(&args.output_dir).unwrap();
+    for (file_name, contents) in [
+        ("combined.json", combined.render_json()),
+        ("combined.html", combined.render_html()),
+    ] {
+        let path = args.output_dir.join(file_name);
+        fs::write(&path, contents).unwrap();
+        println!("report written to {}", path.display());
+    }
+}
+
+fn score(args: &ScoreArgs) {
+    let report: Report = serde_json::from_str(&fs::read_to_string(&args.results).unwrap()).unwrap();
+    let criterion = if args.criterion_dir.exists() {
+        criterion::load(&args.criterion_dir).unwrap()
+    } else {
+        Vec::new()
+    };
+
+    if args.save_reference {
+        Reference::new(&report, &criterion)
+            .save(&args.reference)
+            .unwrap();
+        println!("reference written to {}", args.reference.display());
+        return;
+    }
+
+    let reference = Reference::load(&args.reference)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", args.reference.display()));
+    let score = score::score(&report, &criterion, &reference);
+    score::print(&score);
+
+    fs::create_dir_all(&args.output_dir).unwrap();
+    let path = args.output_dir.join("score.json");
+    fs::write(&path, serde_json::to_string_pretty(&score).unwrap()).unwrap();
+    println!("score written to {}", path.display());
+}
+
+fn core_to_core(args: &CoreToCoreArgs) {
+    let mut cores = match args.cores.as_slice() {
+        [] => os::cpu_limits().cpuset,
+        cores => cores.to_vec(),
+    };
+    cores.sort_unstable();
+    cores.dedup();
+    if cores.len() < 2 {
+        println!("core-to-core needs at least two cores, got {cores:?}");
+        return;
+    }
+
+    let timer = args.timer.timer();
+    let mut sinks = args
+        .report
+        .iter()
+        .map(|f| f.sink(&args.output_dir))
+        .collect::<Vec<_>>();
+    let mut results = Vec::new();
+    for (i, &from) in cores.iter().enumerate() {
+        assert!(os::pin_to_core(from), "failed to pin to core {from}");
+        let overhead = Bencher::overhead(args.timer);
+        for (j, &to) in cores.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let mut bencher = Bencher::new(args.timer, overhead, args.samples);
+            core_to_core::ping_pong(&mut bencher, to);
+            let mut result = BenchResult::new(
+                &core_to_core::name(from, to),
+                timer.unit(),
+                timer.frequency(),
+                bencher.samples().to_vec(),
+            );
+            result.context_switches = bencher.context_switches();
+            result.ops = bencher.ops();
+            for sink in &mut sinks {
+                sink.record(&result).unwrap();
+            }
+            results.push(result);
+        }
+    }
+
+    let matrix = core_to_core::matrix(&cores, &results);
+    core_to_core::print_matrix(&cores, &matrix);
+    let path = args.output_dir.join("core_to_core.csv");
+    core_to_core::write_csv(&path, &cores, &matrix).unwrap();
+    println!("matrix written to {}", path.display());
+
+    let report = Report {
+        environment: Environment::collect(timer),
+        results,
+    };
+    for sink in &mut sinks {
+        sink.finish(&report).unwrap();
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn numa(args: &NumaArgs) {
+    let nodes = os::numa_nodes().expect("failed to read the NUMA nodes");
+    if nodes.len() < 2 {
+        println!("only one NUMA node: every access is local");
+    }
+
+    let mut accesses = Vec::new();
+    for &cpu_node in &nodes {
+        for &memory_node in &nodes {
+            accesses.push(
+                numa::measure(cpu_node, memory_node, args.timer, args.samples)
+                    .unwrap_or_else(|e| panic!("node {cpu_node} to {memory_node}: {e}")),
+            );
+        }
+    }
+    numa::print_table(&accesses);
+
+    let results = accesses
+        .into_iter()
+        .flat_map(|access| [access.latency, access.bandwidth])
+        .collect();
+    let report = Report {
+        environment: Environment::collect(args.timer.timer()),
+        results,
+    };
+    for mut sink in args.report.iter().map(|f| f.sink(&args.output_dir)) {
+        sink.finish(&report).unwrap();
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn memcpy(args: &MemcpyArgs) {
+    let (core, _) = os::choose_core(&os::cpu_limits().cpuset).expect("no usable cpu");
+    assert!(os::pin_to_core(core), "failed to pin to core {core}");
+    println!("{}", memcpy::features());
+
+    let overhead = Bencher::overhead(args.timer);
+    let mut results = Vec::new();
+    for &offset in &args.offsets {
+        for len in memcpy::SIZES {
+            for method in Method::ALL {
+                results.extend(memcpy::measure(
+                    method,
+                    len,
+                    offset,
+                    args.timer,
+                    overhead,
+                    args.samples,
+                ));
+            }
+        }
+    }
+    memcpy::print_table(&args.offsets, &results);
+
+    let report = Report {
+        environment: Environment::collect(args.timer.timer()),
+        results,
+    };
+    for mut sink in args.report.iter().map(|f| f.sink(&args.output_dir)) {
+        sink.finish(&report).unwrap();
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn file_read(args: &FileReadArgs) {
+    let (core, _) = os::choose_core(&os::cpu_limits().cpuset).expect("no usable cpu");
+    assert!(os::pin_to_core(core), "failed to pin to core {core}");
+    let temporary = args.file.is_none().then(|| {
+        let path =
+            std::env::temp_dir().join(format!("microbench-file-read-{}", std::process::id()));
+        file_read::create(&path, args.size_mib << 20).expect("failed to write the file to read");
+        path
+    });
+    let path = args.file.as_ref().or(temporary.as_ref()).unwrap();
+    println!(
+        "{} ({} MiB), page cache {}",
+        path.display(),
+        fs::metadata(path).unwrap().len() >> 20,
+        if args.drop_cache {
+            "dropped before every sample"
+        } else {
+            "kept"
+        },
+    );
+
+    let overhead = Bencher::overhead(args.timer);
+    let mut results = Vec::new();
+    for method in ReadMethod::ALL {
+        match file_read::measure(
+            method,
+            path,
+            args.drop_cache,
+            args.timer,
+            overhead,
+            args.samples,
+        ) {
+            Ok(result) => results.push(result),
+            Err(error) => println!("{}: {error}", method.name()),
+        }
+    }
+    if let Some(path) = &temporary {
+        fs::remove_file(path).unwrap();
+    }
+    file_read::print_table(args.drop_cache, &results);
+
+    let report = Report {
+        environment: Environment::collect(args.timer.timer()),
+        results,
+    };
+    for mut sink in args.report.iter().map(|f| f.sink(&args.output_dir)) {
+        sink.finish(&report).unwrap();
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn priority(args: &PriorityArgs, config: &Config) {
+    let (core, _) = os::choose_core(&os::cpu_limits().cpuset).expect("no usable cpu");
+    assert!(os::pin_to_core(core), "failed to pin to core {core}");
+    let fib_n = args.fib_n.unwrap_or(config.fib_n);
+    println!(
+        "fibonacci({fib_n}) on core {core}, {} background thread(s) beside it",
+        args.load_threads
+    );
+
+    let loads = std::iter::once(Load::Idle)
+        .chain(args.nice.iter().map(|&nice| Load::Nice(nice)))
+        .collect::<Vec<_>>();
+    let overhead = Bencher::overhead(args.timer);
+    let mut results = Vec::new();
+    for &policy in &args.policies {
+        for &load in &loads {
+            match priority::measure(
+                policy,
+                load,
+                args.load_threads,
+                fib_n,
+                args.timer,
+                overhead,
+                args.samples,
+            ) {
+                Ok(result) => results.push(result),
+                Err(error) => println!("{}: {error}", priority::name(policy, load)),
+            }
+        }
+    }
+    priority::print_table(&args.policies, &loads, &results);
+
+    let report = Report {
+        environment: Environment::collect(args.timer.timer()),
+        results,
+    };
+    for mut sink in args.report.iter().map(|f| f.sink(&args.output_dir)) {
+        sink.finish(&report).unwrap();
+    }
+}
+
+fn bench_named(name: &str) -> &'static Bench {
+    benches()
+        .into_iter()
+        .find(|b| b.name() == name)
+        .unwrap_or_else(|| panic!("no benchmark named {name}"))
+}
+
+/// Seed for `--shuffle` without `--seed`.
+fn random_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    nanos ^ u64::from(std::process::id()).rotate_left(32)
+}
+
+/// Mean latency in nanoseconds per benchmark from `--baseline`, or else the last recorded run.
+fn baseline_means(args: &RunArgs, history: &Path) -> BTreeMap<String, f64> {
+    if let Some(path) = &args.baseline {
+        let report: Report = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        return report
+            .results
+            .iter()
+            .map(|r| (r.name.clone(), r.to_nanos(r.summary.mean)))
+            .collect();
+    }
+
+    if !history.exists() {
+        return BTreeMap::new();
+    }
+    History::open(history).unwrap().latest_means().unwrap()
 }
@@ -0,0 +1,263 @@
+//! Which copy is fastest at which size: `rep movsb` (fast with ERMSB, and for short copies with
+//! FSRM), an AVX2 loop of unaligned 32-byte loads and stores, and `ptr::copy_nonoverlapping`
+//! (the C library's `memcpy`, which picks between the two itself), from 16 bytes to 256 MiB
+//! and with the destination offset from the source's 64-byte alignment.
+
+use std::{arch::asm, fmt::Write as _, ptr};
+
+use crate::{arch, bench::Bencher, report::BenchResult, timer::TimerKind};
+
+/// Copy sizes, in bytes.
+pub const SIZES: [usize; 13] = [
+    16,
+    64,
+    256,
+    1 << 10,
+    4 << 10,
+    16 << 10,
+    64 << 10,
+    256 << 10,
+    1 << 20,
+    4 << 20,
+    16 << 20,
+    64 << 20,
+    256 << 20,
+];
+/// Bytes copied per sample at least, repeating small copies, so the timer's noise stays small.
+const SAMPLE: usize = 64 << 10;
+/// Bytes copied per measurement, spread over as many samples as fit.
+const BUDGET: u64 = 4 << 30;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    RepMovsb,
+    Avx2,
+    CopyNonoverlapping,
+}
+
+impl Method {
+    pub const ALL: [Method; 3] = [Method::RepMovsb, Method::Avx2, Method::CopyNonoverlapping];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Method::RepMovsb => "rep_movsb",
+            Method::Avx2 => "avx2",
+            Method::CopyNonoverlapping => "copy_nonoverlapping",
+        }
+    }
+
+    /// Why it cannot run here, if it needs a CPU feature.
+    fn unsupported(self) -> Option<&'static str> {
+        match self {
+            Method::Avx2 if !is_x86_feature_detected!("avx2") => Some("no avx2 support"),
+            _ => None,
+        }
+    }
+
+    /// Copies `len` bytes from `src` to `dst`.
+    ///
+    /// # Safety
+    ///
+    /// Both must be valid for `len` bytes and not overlap, and the method supported.
+    #[inline(always)]
+    unsafe fn copy(self, dst: *mut u8, src: *const u8, len: usize) {
+        // Safety: as the caller promises.
+        unsafe {
+            match self {
+                Method::RepMovsb => asm!(
+                    "rep movsb",
+                    inout("rcx") len => _,
+                    inout("rdi") dst => _,
+                    inout("rsi") src => _,
+                    options(nostack, preserves_flags),
+                ),
+                Method::Avx2 => avx2::copy(dst, src, len),
+                Method::CopyNonoverlapping => ptr::copy_nonoverlapping(src, dst, len),
+            }
+        }
+    }
+}
+
+mod avx2 {
+    use std::arch::x86_64::{__m128i, __m256i, _mm256_loadu_si256, _mm256_storeu_si256};
+
+    /// Four vectors per iteration, then the last 32 bytes again so the tail needs no loop;
+    /// copies shorter than a vector take two overlapping 16-byte halves or single bytes.
+    ///
+    /// # Safety
+    ///
+    /// As for `Method::copy`, with avx2 detected.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn copy(dst: *mut u8, src: *const u8, len: usize) {
+        // Safety: every access lies within the first `len` bytes of either buffer.
+        unsafe {
+            if len < 32 {
+                return short(dst, src, len);
+            }
+            let mut i = 0;
+            while i + 128 <= len {
+                let v = [0, 32, 64, 96].map(|at| _mm256_loadu_si256(src.add(i + at).cast()));
+                for (at, v) in [0, 32, 64, 96].into_iter().zip(v) {
+                    _mm256_storeu_si256(dst.add(i + at).cast(), v);
+                }
+                i += 128;
+            }
+            while i + 32 <= len {
+                _mm256_storeu_si256(dst.add(i).cast(), _mm256_loadu_si256(src.add(i).cast()));
+                i += 32;
+            }
+            if i < len {
+                let last = len - 32;
+                let v = _mm256_loadu_si256(src.add(last).cast::<__m256i>());
+                _mm256_storeu_si256(dst.add(last).cast(), v);
+            }
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn short(dst: *mut u8, src: *const u8, len: usize) {
+        use std::arch::x86_64::{_mm_loadu_si128, _mm_storeu_si128};
+
+        // Safety: as for `copy`.
+        unsafe {
+            if len >= 16 {
+                let (low, high) = (
+                    _mm_loadu_si128(src.cast::<__m128i>()),
+                    _mm_loadu_si128(src.add(len - 16).cast()),
+                );
+                _mm_storeu_si128(dst.cast(), low);
+                _mm_storeu_si128(dst.add(len - 16).cast(), high);
+            } else {
+                for i in 0..len {
+                    *dst.add(i) = *src.add(i);
+                }
+            }
+        }
+    }
+}
+
+/// A cache line, for 64-byte aligned buffers.
+#[derive(Clone, Copy)]
+#[repr(C, align(64))]
+struct Line([u8; 64]);
+
+/// Copies of `len` bytes with `method`, the destination `offset` bytes past a 64-byte boundary,
+/// into `memcpy::<method>_<len>_offset_<offset>`; `None` where the method is unsupported.
+pub fn measure(
+    method: Method,
+    len: usize,
+    offset: usize,
+    timer: TimerKind,
+    overhead: u64,
+    samples: u64,
+) -> Option<BenchResult> {
+    if method.unsupported().is_some() {
+        return None;
+    }
+    let lines = len.div_ceil(64) + offset.div_ceil(64);
+    // Non-zero, so both are written, and their pages mapped, before the first sample.
+    let src = vec![Line([0xa5; 64]); lines];
+    let mut dst = vec![Line([0x5a; 64]); lines];
+    let (src, dst) = (src.as_ptr().cast::<u8>(), dst.as_mut_ptr().cast::<u8>());
+    let repeat = (SAMPLE / len).max(1);
+
+    let mut bencher = Bencher::new(timer, overhead, samples);
+    bencher.set_ops(repeat as u64);
+    bencher.set_bytes((repeat * len) as u64);
+    bencher.limit_iterations((BUDGET / (repeat * len) as u64).max(10));
+    bencher.iter(|| {
+        for _ in 0..repeat {
+            // Safety: both buffers hold `offset + len` bytes, and the method is supported.
+            unsafe { method.copy(dst.add(offset), src, len) };
+        }
+    });
+
+    let timer = timer.timer();
+    let mut result = BenchResult::new(
+        &name(method, len, offset),
+        timer.unit(),
+        timer.frequency(),
+        bencher.samples().to_vec(),
+    );
+    result.context_switches = bencher.context_switches();
+    result.ops = bencher.ops();
+    result.bytes = bencher.bytes();
+    Some(result)
+}
+
+pub fn name(method: Method, len: usize, offset: usize) -> String {
+    format!(
+        "memcpy::{}_{}_offset_{offset}",
+        method.name(),
+        size_name(len)
+    )
+}
+
+/// `16`, `4k`, `256m`, as the suites name sizes.
+fn size_name(len: usize) -> String {
+    match len {
+        len if len >= 1 << 20 => format!("{}m", len >> 20),
+        len if len >= 1 << 10 => format!("{}k", len >> 10),
+        len => len.to_string(),
+    }
+}
+
+/// The CPU's `rep movsb` features, for the table's header.
+pub fn features() -> String {
+    let yes_no = |has| if has { "yes" } else { "no" };
+    format!(
+        "ermsb: {}, fsrm: {}, avx2: {}",
+        yes_no(arch::has_ermsb()),
+        yes_no(arch::has_fsrm()),
+        yes_no(is_x86_feature_detected!("avx2")),
+    )
+}
+
+/// Prints the bandwidth of every method by size for each offset, the fastest of each size, and
+/// the sizes at which the fastest changes.
+pub fn print_table(offsets: &[usize], results: &[BenchResult]) {
+    let bandwidth = |method, len, offset| {
+        results
+            .iter()
+            .find(|r| r.name == name(method, len, offset))
+            .and_then(BenchResult::bandwidth)
+    };
+    for &offset in offsets {
+        println!("destination offset {offset}, GB/s");
+        let mut header = format!("{:>6}", "size");
+        for method in Method::ALL {
+            write!(header, " {:>20}", method.name()).unwrap();
+        }
+        println!("{header}  fastest");
+
+        let mut crossovers = Vec::new();
+        let mut previous: Option<Method> = None;
+        for len in SIZES {
+            let mut line = format!("{:>6}", size_name(len));
+            let mut fastest: Option<(Method, f64)> = None;
+            for method in Method::ALL {
+                match bandwidth(method, len, offset) {
+                    Some(gbs) => {
+                        write!(line, " {gbs:>20.2}").unwrap();
+                        if fastest.is_none_or(|(_, best)| gbs > best) {
+                            fastest = Some((method, gbs));
+                        }
+                    }
+                    None => write!(line, " {:>20}", "-").unwrap(),
+                }
+            }
+            if let Some((method, _)) = fastest {
+                write!(line, "  {}", method.name()).unwrap();
+                if previous.is_some_and(|previous| previous != method) {
+                    crossovers.push(format!("{} from {}", method.name(), size_name(len)));
+                }
+                previous = Some(method);
+            }
+            println!("{line}");
+        }
+        if !crossovers.is_empty() {
+            println!("crossovers: {}", crossovers.join(", "));
+        }
+        println!();
+    }
+}
@@ -0,0 +1,109 @@
+//! Page-size controlled allocations, for benchmarks that measure the TLB.
+
+use std::{fs, io, ptr};
+
+pub const SMALL_PAGE: usize = 4 << 10;
+pub const HUGE_PAGE: usize = 2 << 20;
+
+/// What backs a `Mapping`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pages {
+    /// 4 KiB pages, with transparent huge pages turned off for the range.
+    Small,
+    /// 2 MiB pages the kernel assembles on its own (`madvise(MADV_HUGEPAGE)`), if it can.
+    Transparent,
+    /// 2 MiB pages from the reserved hugetlbfs pool (`MAP_HUGETLB`), of which there are none
+    /// unless `vm.nr_hugepages` is set.
+    Huge,
+}
+
+/// Anonymous, private memory, every page faulted in up front and released on drop.
+pub struct Mapping {
+    map: *mut u8,
+    map_len: usize,
+    /// Start of the usable range, `HUGE_PAGE` aligned where transparent huge pages need it.
+    start: *mut u8,
+    len: usize,
+}
+
+impl Mapping {
+    /// Maps `len` bytes (rounded up to whole pages) backed by `pages`.
+    pub fn new(len: usize, pages: Pages) -> io::Result<Self> {
+        if pages == Pages::Transparent && !transparent_huge_pages() {
+            return Err(io::Error::other("transparent huge pages are disabled"));
+        }
+        let len = len.next_multiple_of(HUGE_PAGE);
+        // Transparent huge pages need an aligned range, so map a page more and trim on use.
+        let (map_len, flags) = match pages {
+            Pages::Small => (len, 0),
+            Pages::Transparent => (len + HUGE_PAGE, 0),
+            Pages::Huge => (len, libc::MAP_HUGETLB),
+        };
+        let map = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                map_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+                -1,
+                0,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            let error = io::Error::last_os_error();
+            return Err(match pages {
+                Pages::Huge => io::Error::new(
+                    error.kind(),
+                    format!("no reserved huge pages (vm.nr_hugepages): {error}"),
+                ),
+                _ => error,
+            });
+        }
+        let map = map.cast::<u8>();
+        let start = map.wrapping_add(map.align_offset(HUGE_PAGE).min(map_len - len));
+        let advice = match pages {
+            Pages::Small => Some(libc::MADV_NOHUGEPAGE),
+            Pages::Transparent => Some(libc::MADV_HUGEPAGE),
+            Pages::Huge => None,
+        };
+        let mapping = Self {
+            map,
+            map_len,
+            start,
+            len,
+        };
+        if let Some(advice) = advice {
+            if unsafe { libc::madvise(start.cast(), len, advice) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        for offset in (0..len).step_by(SMALL_PAGE) {
+            unsafe { start.add(offset).write_volatile(0) };
+        }
+        Ok(mapping)
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.start
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.map.cast(), self.map_len) };
+    }
+}
+
+/// Whether the kernel hands out transparent huge pages at least on request.
+pub fn transparent_huge_pages() -> bool {
+    fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled")
+        .is_ok_and(|mode| !mode.contains("[never]"))
+}
@@ -0,0 +1,102 @@
+//! Local versus remote memory: the `memory` suite's pointer chase and the `bandwidth` suite's
+//! read kernel, measured from a CPU of each NUMA node on memory bound to each node.
+
+use std::io;
+
+use crate::{
+    bench::Bencher,
+    os,
+    report::BenchResult,
+    suites::{bandwidth, memory},
+    timer::TimerKind,
+};
+
+/// Far beyond any last-level cache, so every access reaches the node's memory.
+const BYTES: usize = 256 << 20;
+
+/// Measurements from CPUs of `cpu_node` on memory of `memory_node`.
+pub struct Access {
+    pub cpu_node: usize,
+    pub memory_node: usize,
+    /// `numa::cpu<n>_mem<m>_chase`, per load.
+    pub latency: BenchResult,
+    /// `numa::cpu<n>_mem<m>_read`, with its bytes.
+    pub bandwidth: BenchResult,
+}
+
+/// Pins the calling thread to the first CPU of `cpu_node` and measures with its allocations
+/// bound to `memory_node`, taking at most `samples` samples per benchmark. The thread stays
+/// pinned afterwards; its memory policy is reset.
+pub fn measure(
+    cpu_node: usize,
+    memory_node: usize,
+    timer: TimerKind,
+    samples: u64,
+) -> io::Result<Access> {
+    let cpu = *os::numa_node_cpus(cpu_node)?
+        .first()
+        .ok_or_else(|| io::Error::other(format!("NUMA node {cpu_node} has no cpus")))?;
+    if !os::pin_to_core(cpu) {
+        return Err(io::Error::other(format!("failed to pin to core {cpu}")));
+    }
+    os::bind_memory_to_node(memory_node)?;
+
+    let overhead = Bencher::overhead(timer);
+    let run = |kind: &str, bench: &dyn Fn(&mut Bencher)| {
+        let mut bencher = Bencher::new(timer, overhead, samples);
+        bench(&mut bencher);
+        let timer = timer.timer();
+        let mut result = BenchResult::new(
+            &format!("numa::cpu{cpu_node}_mem{memory_node}_{kind}"),
+            timer.unit(),
+            timer.frequency(),
+            bencher.samples().to_vec(),
+        );
+        result.context_switches = bencher.context_switches();
+        result.ops = bencher.ops();
+        result.bytes = bencher.bytes();
+        result
+    };
+    let latency = run("chase", &|b| memory::chase(b, BYTES));
+    let bandwidth = run("read", &|b| bandwidth::run(b, &bandwidth::READ, BYTES, 1));
+    os::unbind_memory()?;
+
+    Ok(Access {
+        cpu_node,
+        memory_node,
+        latency,
+        bandwidth,
+    })
+}
+
+/// Prints every access with its remote/local latency and local/remote bandwidth ratios, both
+/// relative to the same CPU node's local memory.
+pub fn print_table(accesses: &[Access]) {
+    println!(
+        "{:>8} {:>8} {:>12} {:>8} {:>10} {:>8}",
+        "cpu node", "mem node", "latency ns", "ratio", "GB/s", "ratio"
+    );
+    for access in accesses {
+        let local = accesses
+            .iter()
+            .find(|a| a.cpu_node == access.cpu_node && a.memory_node == a.cpu_node);
+        let latency = |a: &Access| a.latency.to_nanos(a.latency.mean_per_op());
+        let bandwidth = |a: &Access| a.bandwidth.bandwidth().unwrap_or(0.0);
+        let (latency_ratio, bandwidth_ratio) = match local {
+            Some(local) => (
+                latency(access) / latency(local),
+                bandwidth(local) / bandwidth(access),
+            ),
+            None => (f64::NAN, f64::NAN),
+        };
+        println!(
+            "{:>8} {:>8} {:>12.1} {:>7.2}x {:>10.2} {:>7.2}x",
+            access.cpu_node,
+            access.memory_node,
+            latency(access),
+            latency_ratio,
+            bandwidth(access),
+            bandwidth_ratio
+        );
+    }
+}
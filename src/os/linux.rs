@@ -0,0 +1,286 @@
+use std::{
+    fs, io,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    ptr,
+    sync::OnceLock,
+};
+
+use super::CpuLimits;
+
+/// Read once: the affinity mask no longer reflects the cgroup after the runner pins itself.
+pub fn cpu_limits() -> CpuLimits {
+    static LIMITS: OnceLock<CpuLimits> = OnceLock::new();
+    LIMITS
+        .get_or_init(|| CpuLimits {
+            online: online_cpus(),
+            cpuset: affinity(),
+            quota: cgroup_quota(),
+        })
+        .clone()
+}
+
+/// CPUs of NUMA node `node`.
+pub fn numa_node_cpus(node: usize) -> io::Result<Vec<usize>> {
+    let list = fs::read_to_string(format!("/sys/devices/system/node/node{node}/cpulist"))?;
+    Ok(parse_cpu_list(&list))
+}
+
+/// NUMA nodes online.
+pub fn numa_nodes() -> io::Result<Vec<usize>> {
+    Ok(parse_cpu_list(&fs::read_to_string(
+        "/sys/devices/system/node/online",
+    )?))
+}
+
+/// Binds this thread's future allocations to NUMA node `node` (`set_mempolicy(MPOL_BIND)`), so
+/// buffers it allocates and first touches afterwards live on that node.
+pub fn bind_memory_to_node(node: usize) -> io::Result<()> {
+    const MPOL_BIND: libc::c_int = 2;
+    const BITS: usize = libc::c_ulong::BITS as usize;
+
+    let mut mask = [0 as libc::c_ulong; 16];
+    if node >= mask.len() * BITS {
+        return Err(io::Error::other(format!("no NUMA node {node}")));
+    }
+    mask[node / BITS] |= 1 << (node % BITS);
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_set_mempolicy,
+            MPOL_BIND,
+            mask.as_ptr(),
+            mask.len() * BITS + 1,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Undoes `bind_memory_to_node`, allocating on the local node again (`MPOL_DEFAULT`).
+pub fn unbind_memory() -> io::Result<()> {
+    const MPOL_DEFAULT: libc::c_int = 0;
+
+    let ret = unsafe { libc::syscall(libc::SYS_set_mempolicy, MPOL_DEFAULT, ptr::null::<u8>(), 0) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Whether addresses are randomized for this process: both the system-wide
+/// `randomize_va_space` and the process's `ADDR_NO_RANDOMIZE` personality can turn it off.
+pub fn aslr_enabled() -> Option<bool> {
+    let system = fs::read_to_string("/proc/sys/kernel/randomize_va_space").ok()?;
+    if system.trim() == "0" {
+        return Some(false);
+    }
+    let persona = unsafe { libc::personality(0xffff_ffff) };
+    if persona == -1 {
+        return None;
+    }
+    Some(persona & libc::ADDR_NO_RANDOMIZE == 0)
+}
+
+/// Re-executes this binary with the same arguments under `ADDR_NO_RANDOMIZE`, so code, heap and
+/// stack land at the same addresses on every run. Only returns on failure.
+pub fn reexec_without_aslr() -> io::Error {
+    let persona = unsafe { libc::personality(0xffff_ffff) };
+    if persona == -1
+        || unsafe { libc::personality((persona | libc::ADDR_NO_RANDOMIZE) as libc::c_ulong) } == -1
+    {
+        return io::Error::last_os_error();
+    }
+    let mut args = std::env::args_os();
+    let arg0 = args.next().unwrap_or_default();
+    std::process::Command::new("/proc/self/exe")
+        .arg0(arg0)
+        .args(args)
+        .exec()
+}
+
+/// Makes a forked child die with `parent`, the process that forked it: the kernel kills it once
+/// the parent exits, however it does, and it exits right away if the parent already has. Only
+/// plain syscalls, for use right after `fork`.
+pub fn die_with_parent(parent: libc::pid_t) {
+    // Safety: plain syscalls on the calling process.
+    unsafe {
+        libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+        if libc::getppid() != parent {
+            libc::_exit(1);
+        }
+    }
+}
+
+/// CPUs removed from the general scheduler with `isolcpus=`.
+pub fn isolated_cpus() -> Vec<usize> {
+    kernel_cpu_list("/sys/devices/system/cpu/isolated", "isolcpus")
+}
+
+/// CPUs running without the periodic tick (`nohz_full=`).
+pub fn nohz_full_cpus() -> Vec<usize> {
+    kernel_cpu_list("/sys/devices/system/cpu/nohz_full", "nohz_full")
+}
+
+/// CPUs sharing `cpu`'s L2 cache, itself included; empty if sysfs does not say.
+pub fn l2_shared_cpus(cpu: usize) -> Vec<usize> {
+    cache_shared_cpus(cpu, 2)
+}
+
+/// CPUs sharing `cpu`'s L3 cache (its core complex on AMD), itself included; empty if sysfs
+/// does not say.
+pub fn l3_shared_cpus(cpu: usize) -> Vec<usize> {
+    cache_shared_cpus(cpu, 3)
+}
+
+/// CPUs in `cpu`'s socket, itself included; empty if sysfs does not say.
+pub fn package_cpus(cpu: usize) -> Vec<usize> {
+    topology_cpus(cpu, "package_cpus_list")
+}
+
+/// The hardware threads of `cpu`'s core, itself included; empty if sysfs does not say.
+pub fn smt_siblings(cpu: usize) -> Vec<usize> {
+    topology_cpus(cpu, "thread_siblings_list")
+}
+
+/// The CPU the calling thread runs on.
+pub fn current_cpu() -> Option<usize> {
+    usize::try_from(unsafe { libc::sched_getcpu() }).ok()
+}
+
+fn topology_cpus(cpu: usize, file: &str) -> Vec<usize> {
+    fs::read_to_string(format!("/sys/devices/system/cpu/cpu{cpu}/topology/{file}"))
+        .map_or_else(|_| Vec::new(), |list| parse_cpu_list(&list))
+}
+
+fn cache_shared_cpus(cpu: usize, level: u32) -> Vec<usize> {
+    let Ok(caches) = fs::read_dir(format!("/sys/devices/system/cpu/cpu{cpu}/cache")) else {
+        return Vec::new();
+    };
+    caches
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|dir| {
+            dir.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("index"))
+        })
+        .find(|dir| {
+            fs::read_to_string(dir.join("level"))
+                .is_ok_and(|found| found.trim() == level.to_string())
+        })
+        .and_then(|dir| fs::read_to_string(dir.join("shared_cpu_list")).ok())
+        .map_or_else(Vec::new, |list| parse_cpu_list(&list))
+}
+
+/// A CPU list from sysfs, or else from the boot parameter `param` on the kernel command line.
+fn kernel_cpu_list(sysfs: &str, param: &str) -> Vec<usize> {
+    if let Ok(list) = fs::read_to_string(sysfs) {
+        return parse_cpu_list(&list);
+    }
+    let cmdline = fs::read_to_string("/proc/cmdline").unwrap_or_default();
+    cmdline
+        .split_whitespace()
+        .filter_map(|arg| arg.strip_prefix(param)?.strip_prefix('='))
+        // isolcpus takes optional flags first, e.g. `isolcpus=domain,managed_irq,2-5`.
+        .flat_map(|value| {
+            value
+                .split(',')
+                .filter(|part| part.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .flat_map(parse_cpu_list)
+        .collect()
+}
+
+/// Parses a sysfs CPU list such as `0-3,8`.
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+            None => range.parse().ok().map(|cpu| cpu..=cpu),
+        })
+        .flatten()
+        .collect()
+}
+
+fn online_cpus() -> usize {
+    unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) }.max(1) as usize
+}
+
+/// CPUs in this thread's affinity mask, which the cgroup cpuset (`--cpuset-cpus`) narrows.
+fn affinity() -> Vec<usize> {
+    let mut set = unsafe { std::mem::zeroed::<libc::cpu_set_t>() };
+    if unsafe { libc::sched_getaffinity(0, std::mem::size_of_val(&set), &mut set) } != 0 {
+        return (0..online_cpus()).collect();
+    }
+    (0..libc::CPU_SETSIZE as usize)
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect()
+}
+
+/// Tightest CFS bandwidth limit, in CPUs, of this process's cgroup and its ancestors.
+fn cgroup_quota() -> Option<f64> {
+    let cgroups = fs::read_to_string("/proc/self/cgroup").ok()?;
+    let mut quota = None::<f64>;
+    let mut tighten = |limit: Option<f64>| {
+        if let Some(limit) = limit {
+            quota = Some(quota.map_or(limit, |q| q.min(limit)));
+        }
+    };
+
+    for line in cgroups.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(_), Some(controllers), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let path = path.trim_start_matches('/');
+
+        if controllers.is_empty() {
+            // cgroup v2: `cpu.max` holds "<quota> <period>" or "max <period>".
+            for dir in ancestors(Path::new("/sys/fs/cgroup"), path) {
+                tighten(
+                    fs::read_to_string(dir.join("cpu.max"))
+                        .ok()
+                        .and_then(|max| {
+                            let (quota, period) = max.trim().split_once(' ')?;
+                            Some(quota.parse::<f64>().ok()? / period.parse::<f64>().ok()?)
+                        }),
+                );
+            }
+        } else if controllers.split(',').any(|c| c == "cpu") {
+            let root = Path::new("/sys/fs/cgroup").join(controllers);
+            for dir in ancestors(&root, path) {
+                let read = |file: &str| {
+                    fs::read_to_string(dir.join(file))
+                        .ok()?
+                        .trim()
+                        .parse::<f64>()
+                        .ok()
+                };
+                // cgroup v1 reports an unlimited quota as -1.
+                tighten(
+                    match (read("cpu.cfs_quota_us"), read("cpu.cfs_period_us")) {
+                        (Some(quota), Some(period)) if quota > 0.0 && period > 0.0 => {
+                            Some(quota / period)
+                        }
+                        _ => None,
+                    },
+                );
+            }
+        }
+    }
+    quota
+}
+
+/// `root/path` and each of its parents up to `root`. Inside a container the cgroup namespace
+/// usually mounts the process's own cgroup at `root`, which this covers as well.
+fn ancestors(root: &Path, path: &str) -> Vec<PathBuf> {
+    root.join(path)
+        .ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .map(Path::to_path_buf)
+        .collect()
+}
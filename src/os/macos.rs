@@ -0,0 +1,42 @@
+use libc::{pthread_set_qos_class_self_np, qos_class_t, thread_policy_t};
+use mach2::{
+    kern_return::KERN_SUCCESS,
+    mach_init::mach_thread_self,
+    mach_time::{mach_absolute_time, mach_timebase_info},
+    thread_policy::{
+        thread_affinity_policy_data_t, thread_policy_set, THREAD_AFFINITY_POLICY,
+        THREAD_AFFINITY_POLICY_COUNT,
+    },
+};
+
+#[inline(always)]
+pub fn absolute_time() -> u64 {
+    unsafe { mach_absolute_time() }
+}
+
+/// Ticks of `absolute_time()` per second.
+pub fn absolute_time_frequency() -> u64 {
+    let mut info = mach_timebase_info { numer: 0, denom: 0 };
+    unsafe { mach_timebase_info(&mut info) };
+    1_000_000_000 * info.denom as u64 / info.numer as u64
+}
+
+/// macOS has no hard pinning. Threads sharing an affinity tag are only hinted to share an L2,
+/// and Apple Silicon ignores the tag entirely, so additionally ask for the user-interactive QoS
+/// class to keep the measurement thread on a performance core.
+pub fn pin_to_core(id: usize) -> bool {
+    let mut policy = thread_affinity_policy_data_t {
+        affinity_tag: id as i32 + 1,
+    };
+    let affinity = unsafe {
+        thread_policy_set(
+            mach_thread_self(),
+            THREAD_AFFINITY_POLICY,
+            &mut policy as *mut _ as thread_policy_t,
+            THREAD_AFFINITY_POLICY_COUNT,
+        )
+    };
+    let qos = unsafe { pthread_set_qos_class_self_np(qos_class_t::QOS_CLASS_USER_INTERACTIVE, 0) };
+
+    affinity == KERN_SUCCESS || qos == 0
+}
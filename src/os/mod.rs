@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_os = "linux")]
+pub mod linux;
+#[cfg(target_os = "macos")]
+pub mod macos;
+#[cfg(windows)]
+pub mod windows;
+
+#[cfg(target_os = "linux")]
+pub use self::linux::{
+    aslr_enabled, bind_memory_to_node, cpu_limits, current_cpu, die_with_parent, isolated_cpus,
+    l2_shared_cpus, l3_shared_cpus, nohz_full_cpus, numa_node_cpus, numa_nodes, package_cpus,
+    reexec_without_aslr, smt_siblings, unbind_memory,
+};
+#[cfg(target_os = "macos")]
+pub use self::macos::pin_to_core;
+
+/// Picks the quietest of `candidates` to measure on: one isolated from the scheduler
+/// (`isolcpus`) and without the periodic tick (`nohz_full`) if there is one, then either of
+/// those, then the first candidate. Returns the core and why it was chosen.
+pub fn choose_core(candidates: &[usize]) -> Option<(usize, &'static str)> {
+    let isolated = isolated_cpus();
+    let tickless = nohz_full_cpus();
+    let find = |pred: &dyn Fn(&usize) -> bool| candidates.iter().copied().find(pred);
+
+    find(&|cpu| isolated.contains(cpu) && tickless.contains(cpu))
+        .map(|cpu| (cpu, "isolated, nohz_full"))
+        .or_else(|| find(&|cpu| isolated.contains(cpu)).map(|cpu| (cpu, "isolated")))
+        .or_else(|| find(&|cpu| tickless.contains(cpu)).map(|cpu| (cpu, "nohz_full")))
+        .or_else(|| candidates.first().map(|&cpu| (cpu, "first allowed cpu")))
+}
+
+/// Up to `count` (all if zero) of `candidates` to run single-threaded benchmarks on side by
+/// side, in `choose_core`'s order: only isolated ones if there are any, and no two sharing an L2
+/// cache as far as the kernel tells.
+pub fn parallel_cores(candidates: &[usize], count: usize) -> Vec<usize> {
+    let isolated = isolated_cpus();
+    let mut pool = candidates.to_vec();
+    if pool.iter().any(|cpu| isolated.contains(cpu)) {
+        pool.retain(|cpu| isolated.contains(cpu));
+    }
+
+    let mut cores = Vec::new();
+    let mut shared = Vec::new();
+    while let Some((cpu, _)) = choose_core(&pool) {
+        pool.retain(|&other| other != cpu);
+        if shared.contains(&cpu) {
+            continue;
+        }
+        cores.push(cpu);
+        shared.extend(l2_shared_cpus(cpu));
+        if cores.len() == count {
+            break;
+        }
+    }
+    cores
+}
+
+/// Context switches of the measuring thread; an involuntary one means it was preempted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextSwitches {
+    pub voluntary: u64,
+    pub involuntary: u64,
+}
+
+impl ContextSwitches {
+    pub fn total(&self) -> u64 {
+        self.voluntary + self.involuntary
+    }
+}
+
+impl std::ops::Sub for ContextSwitches {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            voluntary: self.voluntary.saturating_sub(rhs.voluntary),
+            involuntary: self.involuntary.saturating_sub(rhs.involuntary),
+        }
+    }
+}
+
+impl std::ops::AddAssign for ContextSwitches {
+    fn add_assign(&mut self, rhs: Self) {
+        self.voluntary += rhs.voluntary;
+        self.involuntary += rhs.involuntary;
+    }
+}
+
+/// Switches so far, from `getrusage` (per thread on Linux, per process on macOS).
+#[cfg(unix)]
+pub fn context_switches() -> Option<ContextSwitches> {
+    #[cfg(target_os = "linux")]
+    const WHO: libc::c_int = libc::RUSAGE_THREAD;
+    #[cfg(not(target_os = "linux"))]
+    const WHO: libc::c_int = libc::RUSAGE_SELF;
+
+    let mut usage = unsafe { std::mem::zeroed::<libc::rusage>() };
+    if unsafe { libc::getrusage(WHO, &mut usage) } != 0 {
+        return None;
+    }
+    Some(ContextSwitches {
+        voluntary: usage.ru_nvcsw as u64,
+        involuntary: usage.ru_nivcsw as u64,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn context_switches() -> Option<ContextSwitches> {
+    None
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn pin_to_core(id: usize) -> bool {
+    core_affinity::set_for_current(core_affinity::CoreId { id })
+}
+
+/// CPUs this process may actually use, which in a container can be far fewer than the machine's.
+#[derive(Clone, Debug, Default)]
+pub struct CpuLimits {
+    /// Logical CPUs online on the machine.
+    pub online: usize,
+    /// CPUs the process may be scheduled on (affinity mask, narrowed by a cgroup cpuset).
+    pub cpuset: Vec<usize>,
+    /// CFS bandwidth limit in CPUs, e.g. 1.5 for docker's `--cpus=1.5`.
+    pub quota: Option<f64>,
+}
+
+impl CpuLimits {
+    /// Number of CPUs' worth of time available, at least one.
+    pub fn available(&self) -> usize {
+        let cpus = self.cpuset.len().max(1);
+        self.quota
+            .map_or(cpus, |quota| cpus.min(quota.ceil() as usize))
+            .max(1)
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.quota.is_some() || self.cpuset.len() < self.online
+    }
+
+    /// The cpuset as a list of ranges, e.g. `0-3,8`.
+    pub fn cpuset_list(&self) -> String {
+        let mut ranges = Vec::<(usize, usize)>::new();
+        for &cpu in &self.cpuset {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == cpu => *end = cpu,
+                _ => ranges.push((cpu, cpu)),
+            }
+        }
+        ranges
+            .iter()
+            .map(|&(start, end)| match start == end {
+                true => start.to_string(),
+                false => format!("{start}-{end}"),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn cpu_limits() -> CpuLimits {
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    CpuLimits {
+        online: cpus,
+        cpuset: (0..cpus).collect(),
+        quota: None,
+    }
+}
+
+/// The first CPU this process may use other than `cpu`, for a partner on another core.
+pub fn other_core(cpu: usize) -> Option<usize> {
+    cpu_limits().cpuset.into_iter().find(|&other| other != cpu)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn numa_node_cpus(_node: usize) -> std::io::Result<Vec<usize>> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn bind_memory_to_node(_node: usize) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn aslr_enabled() -> Option<bool> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn reexec_without_aslr() -> std::io::Error {
+    std::io::ErrorKind::Unsupported.into()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn isolated_cpus() -> Vec<usize> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn nohz_full_cpus() -> Vec<usize> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn l2_shared_cpus(_cpu: usize) -> Vec<usize> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn l3_shared_cpus(_cpu: usize) -> Vec<usize> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn package_cpus(_cpu: usize) -> Vec<usize> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn smt_siblings(_cpu: usize) -> Vec<usize> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn current_cpu() -> Option<usize> {
+    None
+}
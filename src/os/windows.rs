@@ -0,0 +1,14 @@
+use windows_sys::Win32::System::Performance::{QueryPerformanceCounter, QueryPerformanceFrequency};
+
+#[inline(always)]
+pub fn performance_counter() -> u64 {
+    let mut value = 0;
+    unsafe { QueryPerformanceCounter(&mut value) };
+    value as u64
+}
+
+pub fn performance_frequency() -> u64 {
+    let mut value = 0;
+    unsafe { QueryPerformanceFrequency(&mut value) };
+    value as u64
+}
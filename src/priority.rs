@@ -0,0 +1,198 @@
+//! How scheduling priority shields a latency-critical thread from background load on its core.
+//! The measuring thread computes `fibonacci(n)`, the Criterion suite's workload, and times each
+//! completion while busy-looping threads pinned to the same core run at a given nice value.
+//! Under `SCHED_OTHER` the fair scheduler splits the core by weight, which falls about 1.25x
+//! per nice step, so even nice 19 load takes a share of every sample. Under `SCHED_FIFO` the
+//! measuring thread preempts the load outright until real-time throttling
+//! (`kernel.sched_rt_runtime_us`, 95% of every second by default) hands the rest of the period
+//! to it, which shows in the tail. Raising the nice value needs no privileges; negative values
+//! and `SCHED_FIFO` need `CAP_SYS_NICE`.
+
+use std::{
+    hint::black_box,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use crate::{bench::Bencher, os, report::BenchResult, timer::TimerKind};
+
+/// The measuring thread's scheduling policy.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum Policy {
+    /// The fair scheduler at nice 0.
+    Other,
+    /// Real-time FIFO at priority 1, above every `SCHED_OTHER` thread.
+    Fifo,
+}
+
+impl Policy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Policy::Other => "other",
+            Policy::Fifo => "fifo",
+        }
+    }
+
+    fn raw(self) -> (libc::c_int, libc::c_int) {
+        match self {
+            Policy::Other => (libc::SCHED_OTHER, 0),
+            Policy::Fifo => (libc::SCHED_FIFO, 1),
+        }
+    }
+}
+
+/// Background load on the measuring thread's core.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Load {
+    Idle,
+    /// Busy loops at this nice value.
+    Nice(i32),
+}
+
+impl Load {
+    pub fn name(self) -> String {
+        match self {
+            Load::Idle => "idle".into(),
+            Load::Nice(nice) => format!("nice_{nice}"),
+        }
+    }
+}
+
+/// The Criterion suite's workload: exponential recursion that stays in registers and L1.
+pub fn fibonacci(n: u64) -> u64 {
+    match black_box(n) {
+        0 | 1 => black_box(1),
+        n => black_box(fibonacci(n - 1)) + black_box(fibonacci(n - 2)),
+    }
+}
+
+fn set_policy(policy: Policy) -> io::Result<()> {
+    let (policy, priority) = policy.raw();
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    // Safety: `param` is a valid `sched_param`; pid 0 is the calling thread.
+    if unsafe { libc::sched_setscheduler(0, policy, &param) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Sets the calling thread's nice value; on Linux it is per thread.
+fn set_nice(nice: i32) -> io::Result<()> {
+    // Safety: plain syscalls on the calling thread.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, libc::gettid() as libc::id_t, nice) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Completion times of `fibonacci(fib_n)` into `priority::<policy>_<load>` on the core the
+/// calling thread is pinned to, with `threads` busy loops of `load` beside it; an error where
+/// the policy or nice value is not permitted.
+pub fn measure(
+    policy: Policy,
+    load: Load,
+    threads: usize,
+    fib_n: u64,
+    timer: TimerKind,
+    overhead: u64,
+    samples: u64,
+) -> io::Result<BenchResult> {
+    let core = os::current_cpu().ok_or_else(|| io::Error::other("current cpu unknown"))?;
+    let stop = &AtomicBool::new(false);
+    let mut bencher = Bencher::new(timer, overhead, samples);
+    thread::scope(|scope| {
+        if let Load::Nice(nice) = load {
+            let (ready_tx, ready_rx) = mpsc::channel();
+            for _ in 0..threads {
+                let ready_tx = ready_tx.clone();
+                scope.spawn(move || {
+                    let ready = if os::pin_to_core(core) {
+                        set_nice(nice)
+                    } else {
+                        Err(io::Error::other(format!("failed to pin to core {core}")))
+                    };
+                    let ok = ready.is_ok();
+                    ready_tx.send(ready).unwrap();
+                    while ok && !stop.load(Ordering::Relaxed) {
+                        std::hint::spin_loop();
+                    }
+                });
+            }
+            let ready = (0..threads).try_for_each(|_| ready_rx.recv().unwrap());
+            if ready.is_err() {
+                stop.store(true, Ordering::Relaxed);
+            }
+            ready?;
+        }
+        let result = set_policy(policy).map(|()| {
+            bencher.iter(|| fibonacci(fib_n));
+            set_policy(Policy::Other).unwrap();
+        });
+        stop.store(true, Ordering::Relaxed);
+        result
+    })?;
+
+    let timer = timer.timer();
+    let mut result = BenchResult::new(
+        &name(policy, load),
+        timer.unit(),
+        timer.frequency(),
+        bencher.samples().to_vec(),
+    );
+    result.context_switches = bencher.context_switches();
+    Ok(result)
+}
+
+pub fn name(policy: Policy, load: Load) -> String {
+    format!("priority::{}_{}", policy.name(), load.name())
+}
+
+/// Prints the completion-time percentiles of every policy and load, in milliseconds, and the
+/// median's slowdown over the same policy without load; `-` for those that failed.
+pub fn print_table(policies: &[Policy], loads: &[Load], results: &[BenchResult]) {
+    println!(
+        "{:>7} {:>10} {:>9} {:>9} {:>9} {:>9} {:>9} {:>10}",
+        "policy", "load", "p50 ms", "p90 ms", "p99 ms", "max ms", "slowdown", "switches"
+    );
+    let find = |policy, load| results.iter().find(|r| r.name == name(policy, load));
+    for &policy in policies {
+        let idle = find(policy, Load::Idle).map(|r| r.summary.p50 as f64);
+        for &load in loads {
+            let Some(result) = find(policy, load) else {
+                println!(
+                    "{:>7} {:>10} {:>9} {:>9} {:>9} {:>9} {:>9} {:>10}",
+                    policy.name(),
+                    load.name(),
+                    "-",
+                    "-",
+                    "-",
+                    "-",
+                    "-",
+                    "-"
+                );
+                continue;
+            };
+            let ms = |ticks: u64| result.to_nanos(ticks as f64) / 1e6;
+            let slowdown = idle.map_or("-".into(), |idle| {
+                format!("{:.2}x", result.summary.p50 as f64 / idle)
+            });
+            println!(
+                "{:>7} {:>10} {:>9.3} {:>9.3} {:>9.3} {:>9.3} {:>9} {:>10}",
+                policy.name(),
+                load.name(),
+                ms(result.summary.p50),
+                ms(result.summary.p90),
+                ms(result.summary.p99),
+                ms(result.summary.max),
+                slowdown,
+                result.context_switches.total(),
+            );
+        }
+    }
+}
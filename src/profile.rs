@@ -0,0 +1,238 @@
+use std::{
+    collections::BTreeMap,
+    ffi::CString,
+    fmt::Write as _,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Write},
+    os::unix::{ffi::OsStrExt, fs::OpenOptionsExt},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use crate::report::escape;
+
+const SAMPLE_FREQUENCY: &str = "4999";
+const FRAME_HEIGHT: usize = 16;
+const CHART_WIDTH: f64 = 1200.0;
+
+/// A `perf record` child attached to this process. Sampling starts disabled and is switched on
+/// only around the measurement loop through perf's control FIFO.
+pub struct Profiler {
+    child: Child,
+    control: File,
+    ack: File,
+    fifo_dir: PathBuf,
+    data: PathBuf,
+}
+
+impl Profiler {
+    pub fn attach(data: &Path) -> io::Result<Self> {
+        let fifo_dir = std::env::temp_dir().join(format!("microbench-perf-{}", std::process::id()));
+        fs::create_dir_all(&fifo_dir)?;
+        let control_path = fifo_dir.join("control");
+        let ack_path = fifo_dir.join("ack");
+        mkfifo(&control_path)?;
+        mkfifo(&ack_path)?;
+
+        let mut child = Command::new("perf")
+            .arg("record")
+            .args(["-F", SAMPLE_FREQUENCY, "-g", "--delay=-1"])
+            .arg(format!(
+                "--control=fifo:{},{}",
+                control_path.display(),
+                ack_path.display()
+            ))
+            .arg("-o")
+            .arg(data)
+            .args(["-p", &std::process::id().to_string()])
+            .stdout(Stdio::null())
+            .spawn()?;
+
+        // A non-blocking write open of a FIFO fails until perf has opened the read end, which it
+        // only does once it is ready to record. Poll so a perf that exits early is not waited on.
+        let control = loop {
+            match OpenOptions::new()
+                .write(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(&control_path)
+            {
+                Ok(control) => break control,
+                Err(err) if err.raw_os_error() == Some(libc::ENXIO) => {
+                    if let Some(status) = child.try_wait()? {
+                        return Err(io::Error::other(format!("perf record exited: {status}")));
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(err) => return Err(err),
+            }
+        };
+        let ack = File::open(&ack_path)?;
+
+        Ok(Self {
+            child,
+            control,
+            ack,
+            fifo_dir,
+            data: data.to_path_buf(),
+        })
+    }
+
+    pub fn enable(&mut self) {
+        self.command("enable");
+    }
+
+    pub fn disable(&mut self) {
+        self.command("disable");
+    }
+
+    fn command(&mut self, command: &str) {
+        writeln!(self.control, "{command}").unwrap();
+        let mut ack = [0; 5];
+        self.ack.read_exact(&mut ack).unwrap();
+    }
+
+    /// Stops perf and renders the recorded stacks as a flamegraph SVG.
+    pub fn finish(mut self, flamegraph: &Path, title: &str) -> io::Result<()> {
+        unsafe { libc::kill(self.child.id() as i32, libc::SIGINT) };
+        self.child.wait()?;
+        let _ = fs::remove_dir_all(&self.fifo_dir);
+
+        let mut script = Command::new("perf")
+            .arg("script")
+            .arg("-i")
+            .arg(&self.data)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stacks = collapse(BufReader::new(script.stdout.take().unwrap()))?;
+        script.wait()?;
+
+        fs::write(flamegraph, render(&stacks, title))
+    }
+}
+
+fn mkfifo(path: &Path) -> io::Result<()> {
+    let _ = fs::remove_file(path);
+    let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    if unsafe { libc::mkfifo(path.as_ptr(), 0o600) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Folds `perf script` output into root-first `a;b;c` stacks with sample counts.
+fn collapse(script: impl BufRead) -> io::Result<BTreeMap<String, u64>> {
+    let mut stacks = BTreeMap::new();
+    let mut frames = Vec::new();
+
+    for line in script.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            if !frames.is_empty() {
+                frames.reverse();
+                *stacks.entry(frames.join(";")).or_default() += 1;
+                frames.clear();
+            }
+        } else if line.starts_with(char::is_whitespace) {
+            // "\t    7f3a12345678 symbol+0x1c (/path/to/dso)"
+            let symbol = line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or("[unknown]")
+                .split('+')
+                .next()
+                .unwrap();
+            frames.push(symbol.to_string());
+        }
+    }
+
+    Ok(stacks)
+}
+
+#[derive(Default)]
+struct Frame {
+    samples: u64,
+    children: BTreeMap<String, Frame>,
+}
+
+fn render(stacks: &BTreeMap<String, u64>, title: &str) -> String {
+    let mut root = Frame::default();
+    for (stack, &count) in stacks {
+        root.samples += count;
+        let mut frame = &mut root;
+        for name in stack.split(';') {
+            frame = frame.children.entry(name.to_string()).or_default();
+            frame.samples += count;
+        }
+    }
+
+    let depth = stacks
+        .keys()
+        .map(|s| s.split(';').count())
+        .max()
+        .unwrap_or(0);
+    let height = (depth + 2) * FRAME_HEIGHT;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg width=\"{CHART_WIDTH}\" height=\"{height}\" xmlns=\"http://www.w3.org/2000/svg\" \
+         font-family=\"monospace\" font-size=\"11\">"
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        "<text x=\"4\" y=\"12\">{} ({} samples)</text>",
+        escape(title),
+        root.samples
+    )
+    .unwrap();
+    if root.samples > 0 {
+        render_children(&mut svg, &root, 0.0, height - FRAME_HEIGHT, root.samples);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn render_children(svg: &mut String, parent: &Frame, x: f64, y: usize, total: u64) {
+    let mut x = x;
+    for (name, frame) in &parent.children {
+        let width = frame.samples as f64 / total as f64 * CHART_WIDTH;
+        let y = y - FRAME_HEIGHT;
+        // Stable warm colour per symbol, as in the classic flamegraph.pl palette.
+        let hash = name
+            .bytes()
+            .fold(0u32, |h, b| h.wrapping_mul(31).wrapping_add(b as u32));
+        writeln!(
+            svg,
+            "<g><title>{} ({} samples, {:.2}%)</title>\
+             <rect x=\"{x:.2}\" y=\"{y}\" width=\"{width:.2}\" height=\"{}\" \
+             fill=\"rgb(230,{},{})\"/>",
+            escape(name),
+            frame.samples,
+            frame.samples as f64 * 100.0 / total as f64,
+            FRAME_HEIGHT - 1,
+            100 + hash % 120,
+            hash / 120 % 60,
+        )
+        .unwrap();
+        let chars = (width / 7.0) as usize;
+        if chars > 3 {
+            let label = name.chars().take(chars - 2).collect::<String>();
+            writeln!(
+                svg,
+                "<text x=\"{:.2}\" y=\"{}\">{}</text>",
+                x + 2.0,
+                y + FRAME_HEIGHT - 4,
+                escape(&label)
+            )
+            .unwrap();
+        }
+        svg.push_str("</g>\n");
+
+        render_children(svg, frame, x, y, total);
+        x += width;
+    }
+}
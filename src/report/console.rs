@@ -0,0 +1,127 @@
+use std::io;
+
+use super::{BenchResult, Report, ResultSink};
+use crate::stats::log_histogram;
+
+const HISTOGRAM_BUCKETS: usize = 16;
+const BAR_WIDTH: usize = 40;
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+pub struct Console {
+    pub histogram: bool,
+}
+
+impl ResultSink for Console {
+    fn record(&mut self, result: &BenchResult) -> io::Result<()> {
+        let total_difference = result.samples.iter().sum::<u64>();
+        println!("{}", result.name);
+        println!("    elapsed {}: {}", result.unit, total_difference);
+        println!(
+            "    average {}: {}",
+            result.unit,
+            total_difference / result.samples.len().max(1) as u64
+        );
+        if result.ops > 1 {
+            println!(
+                "    per operation ({} per sample): {:.2} {}",
+                result.ops,
+                result.mean_per_op(),
+                result.unit
+            );
+        }
+        if let Some(bandwidth) = result.bandwidth() {
+            println!("    bandwidth: {bandwidth:.2} GB/s");
+        }
+        if !result.counters.is_empty() {
+            let (per, divisor) = match result.ops {
+                1 => ("sample", 1.0),
+                ops => ("operation", ops as f64),
+            };
+            let counts = result
+                .counters
+                .iter()
+                .map(|(event, count)| format!("{event} {:.2}", count / divisor))
+                .collect::<Vec<_>>();
+            println!("    counters per {per}: {}", counts.join(", "));
+            if let (Some(cycles), Some(instructions)) = (
+                result.counters.get("cycles"),
+                result.counters.get("instructions"),
+            ) {
+                println!("    instructions per cycle: {:.2}", instructions / cycles);
+            }
+        }
+        let switches = result.context_switches;
+        if switches.total() > 0 {
+            println!(
+                "    context switches: {} voluntary, {} involuntary ({} samples discarded)",
+                switches.voluntary, switches.involuntary, result.discarded
+            );
+        }
+        if result.timed_out {
+            println!(
+                "    timed out: partial statistics over {} samples",
+                result.samples.len()
+            );
+        }
+        if self.histogram {
+            print!("{}", histogram(result));
+        }
+        Ok(())
+    }
+}
+
+/// A bpftrace-style histogram over log-scaled buckets, so bimodal distributions stand out.
+pub fn histogram(result: &BenchResult) -> String {
+    let buckets = log_histogram(&result.samples, HISTOGRAM_BUCKETS);
+    let tallest = buckets.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    let label_width = buckets
+        .iter()
+        .map(|b| format!("[{}, {})", b.start, b.end).len())
+        .max()
+        .unwrap_or(0);
+
+    let mut text = String::new();
+    for bucket in &buckets {
+        let eighths = bucket.count * BAR_WIDTH * 8 / tallest;
+        let (full, partial) = (eighths / 8, eighths % 8);
+        let mut bar = "█".repeat(full);
+        if partial != 0 {
+            bar.push(EIGHTHS[partial]);
+        }
+        text.push_str(&format!(
+            "    {:<label_width$} {:>8} |{:<BAR_WIDTH$}|\n",
+            format!("[{}, {})", bucket.start, bucket.end),
+            bucket.count,
+            bar,
+        ));
+    }
+    text
+}
+
+/// Prints a table of every result by mean per operation once the run is done, e.g. to rank the
+/// `syscall` suite's calls.
+pub struct Sorted;
+
+impl ResultSink for Sorted {
+    fn finish(&mut self, report: &Report) -> io::Result<()> {
+        let mut results = report.results.iter().collect::<Vec<_>>();
+        results.sort_by(|a, b| a.mean_per_op().total_cmp(&b.mean_per_op()));
+        let width = results.iter().map(|r| r.name.len()).max().unwrap_or(0);
+        let unit = results.first().map_or("cycles", |r| r.unit.as_str());
+        println!(
+            "{:<width$} {:>14} {:>12}",
+            "benchmark",
+            format!("{unit} per op"),
+            "ns per op"
+        );
+        for result in results {
+            let mean = result.mean_per_op();
+            println!(
+                "{:<width$} {mean:>14.1} {:>12.1}",
+                result.name,
+                result.to_nanos(mean)
+            );
+        }
+        Ok(())
+    }
+}
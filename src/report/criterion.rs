@@ -0,0 +1,173 @@
+use std::{
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{escape, BenchResult, Report};
+use crate::env::Environment;
+
+/// One Criterion benchmark's latest estimates, in nanoseconds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CriterionEstimate {
+    pub name: String,
+    pub mean: f64,
+    pub mean_lower: f64,
+    pub mean_upper: f64,
+    pub median: f64,
+    pub std_dev: f64,
+}
+
+/// Both measurement methodologies in one artifact: the harness's per-iteration timings and
+/// Criterion's bootstrapped estimates.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CombinedReport {
+    pub environment: Environment,
+    pub harness: Vec<BenchResult>,
+    pub criterion: Vec<CriterionEstimate>,
+}
+
+#[derive(Deserialize)]
+struct BenchmarkJson {
+    full_id: String,
+}
+
+#[derive(Deserialize)]
+struct EstimatesJson {
+    mean: Estimate,
+    median: Estimate,
+    std_dev: Estimate,
+}
+
+#[derive(Deserialize)]
+struct Estimate {
+    point_estimate: f64,
+    confidence_interval: Interval,
+}
+
+#[derive(Deserialize)]
+struct Interval {
+    lower_bound: f64,
+    upper_bound: f64,
+}
+
+/// Reads every `new/estimates.json` below Criterion's output directory (`target/criterion`).
+pub fn load(criterion_dir: &Path) -> io::Result<Vec<CriterionEstimate>> {
+    let mut dirs = Vec::new();
+    find_latest(criterion_dir, &mut dirs)?;
+
+    let mut estimates = dirs
+        .iter()
+        .map(|dir| {
+            let read = |file: &str| fs::read_to_string(dir.join(file));
+            let id: BenchmarkJson =
+                serde_json::from_str(&read("benchmark.json")?).map_err(io::Error::other)?;
+            let estimates: EstimatesJson =
+                serde_json::from_str(&read("estimates.json")?).map_err(io::Error::other)?;
+            Ok(CriterionEstimate {
+                name: id.full_id,
+                mean: estimates.mean.point_estimate,
+                mean_lower: estimates.mean.confidence_interval.lower_bound,
+                mean_upper: estimates.mean.confidence_interval.upper_bound,
+                median: estimates.median.point_estimate,
+                std_dev: estimates.std_dev.point_estimate,
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    estimates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(estimates)
+}
+
+fn find_latest(dir: &Path, found: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if path.ends_with("new") && path.join("estimates.json").exists() {
+            found.push(path);
+        } else if !path.ends_with("report") && !path.ends_with("base") {
+            find_latest(&path, found)?;
+        }
+    }
+    Ok(())
+}
+
+impl CombinedReport {
+    pub fn new(harness: Report, criterion: Vec<CriterionEstimate>) -> Self {
+        Self {
+            environment: harness.environment,
+            harness: harness.results,
+            criterion,
+        }
+    }
+
+    pub fn render_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn render_html(&self) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<title>Combined microbenchmark report</title>\n");
+        writeln!(
+            html,
+            "<style>\n{}</style>\n</head>\n<body>",
+            super::html::STYLE
+        )
+        .unwrap();
+        html.push_str("<h1>Combined microbenchmark report</h1>\n");
+
+        html.push_str("<h2>Environment</h2>\n<table>\n");
+        for (label, value) in self.environment.fields() {
+            writeln!(
+                html,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape(label),
+                escape(&value)
+            )
+            .unwrap();
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Harness (per-iteration timings)</h2>\n<table>\n");
+        html.push_str("<tr><th>benchmark</th><th>mean ns</th><th>p50 ns</th><th>p99 ns</th>");
+        html.push_str("<th>stddev ns</th><th>samples</th></tr>\n");
+        for result in &self.harness {
+            let summary = &result.summary;
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td><td>{}</td></tr>",
+                escape(&result.name),
+                result.to_nanos(summary.mean),
+                result.to_nanos(summary.p50 as f64),
+                result.to_nanos(summary.p99 as f64),
+                result.to_nanos(summary.stddev),
+                summary.count,
+            )
+            .unwrap();
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Criterion (bootstrapped estimates)</h2>\n<table>\n");
+        html.push_str("<tr><th>benchmark</th><th>mean ns</th><th>95% interval ns</th>");
+        html.push_str("<th>median ns</th><th>stddev ns</th></tr>\n");
+        for estimate in &self.criterion {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{:.1}</td><td>{:.1} – {:.1}</td><td>{:.1}</td><td>{:.1}</td></tr>",
+                escape(&estimate.name),
+                estimate.mean,
+                estimate.mean_lower,
+                estimate.mean_upper,
+                estimate.median,
+                estimate.std_dev,
+            )
+            .unwrap();
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
@@ -0,0 +1,42 @@
+use std::fmt::Write;
+
+use super::Report;
+
+/// One row per result, in the result's unit and in nanoseconds, for spreadsheets and plotting.
+pub fn render(report: &Report) -> String {
+    let mut text = String::from(
+        "benchmark,unit,samples,ops,mean,stddev,min,p50,p90,p99,p999,max,mean_ns,mean_per_op_ns\n",
+    );
+    for result in &report.results {
+        let s = &result.summary;
+        writeln!(
+            text,
+            "{},{},{},{},{:.2},{:.2},{},{},{},{},{},{},{:.2},{:.2}",
+            field(&result.name),
+            field(&result.unit),
+            s.count,
+            result.ops,
+            s.mean,
+            s.stddev,
+            s.min,
+            s.p50,
+            s.p90,
+            s.p99,
+            s.p999,
+            s.max,
+            result.to_nanos(s.mean),
+            result.to_nanos(result.mean_per_op()),
+        )
+        .unwrap();
+    }
+    text
+}
+
+/// Quotes `value` if it holds a separator or a quote.
+pub fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
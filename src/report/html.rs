@@ -0,0 +1,120 @@
+use std::fmt::Write;
+
+use super::{escape, BenchResult, Report};
+use crate::stats::log_histogram;
+
+const HISTOGRAM_BUCKETS: usize = 40;
+const CHART_WIDTH: usize = 800;
+const CHART_HEIGHT: usize = 200;
+
+pub(crate) const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td, th { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: right; }
+th { background: #f0f0f0; }
+td:first-child, th:first-child { text-align: left; }
+svg rect { fill: #4a7ebb; }
+svg text { font-size: 10px; fill: #555; }
+";
+
+pub fn render(report: &Report) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Microbenchmark report</title>\n");
+    writeln!(html, "<style>\n{STYLE}</style>\n</head>\n<body>").unwrap();
+    html.push_str("<h1>Microbenchmark report</h1>\n");
+
+    html.push_str("<h2>Environment</h2>\n<table>\n");
+    for (label, value) in report.environment.fields() {
+        writeln!(
+            html,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            escape(label),
+            escape(&value)
+        )
+        .unwrap();
+    }
+    html.push_str("</table>\n");
+
+    for result in &report.results {
+        render_result(&mut html, result);
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_result(html: &mut String, result: &BenchResult) {
+    let summary = &result.summary;
+    writeln!(html, "<h2>{}</h2>", escape(&result.name)).unwrap();
+    writeln!(
+        html,
+        "<p>{} samples, mean {:.1} {unit} ({:.1} ns), stddev {:.1} {unit}</p>",
+        summary.count,
+        summary.mean,
+        result.to_nanos(summary.mean),
+        summary.stddev,
+        unit = result.unit,
+    )
+    .unwrap();
+
+    html.push_str("<table>\n<tr><th></th>");
+    for (label, _) in summary.percentiles() {
+        write!(html, "<th>{label}</th>").unwrap();
+    }
+    write!(html, "</tr>\n<tr><td>{}</td>", result.unit).unwrap();
+    for (_, value) in summary.percentiles() {
+        write!(html, "<td>{value}</td>").unwrap();
+    }
+    html.push_str("</tr>\n<tr><td>ns</td>");
+    for (_, value) in summary.percentiles() {
+        write!(html, "<td>{:.1}</td>", result.to_nanos(value as f64)).unwrap();
+    }
+    html.push_str("</tr>\n</table>\n");
+
+    render_histogram(html, result);
+}
+
+fn render_histogram(html: &mut String, result: &BenchResult) {
+    let histogram = log_histogram(&result.samples, HISTOGRAM_BUCKETS);
+    let Some(tallest) = histogram.iter().map(|b| b.count).max() else {
+        return;
+    };
+
+    let bar_width = CHART_WIDTH as f64 / histogram.len() as f64;
+    writeln!(
+        html,
+        "<svg width=\"{CHART_WIDTH}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">",
+        CHART_HEIGHT + 20
+    )
+    .unwrap();
+    for (i, bucket) in histogram.iter().enumerate() {
+        let height = bucket.count as f64 / tallest as f64 * CHART_HEIGHT as f64;
+        writeln!(
+            html,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{height:.1}\">\
+             <title>[{}, {}) {}: {}</title></rect>",
+            i as f64 * bar_width,
+            CHART_HEIGHT as f64 - height,
+            (bar_width - 1.0).max(1.0),
+            bucket.start,
+            bucket.end,
+            result.unit,
+            bucket.count,
+        )
+        .unwrap();
+    }
+    if let (Some(first), Some(last)) = (histogram.first(), histogram.last()) {
+        writeln!(
+            html,
+            "<text x=\"0\" y=\"{y}\">{}</text>\
+             <text x=\"{CHART_WIDTH}\" y=\"{y}\" text-anchor=\"end\">{} {}</text>",
+            first.start,
+            last.end,
+            result.unit,
+            y = CHART_HEIGHT + 15,
+        )
+        .unwrap();
+    }
+    html.push_str("</svg>\n");
+}
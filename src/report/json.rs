@@ -0,0 +1,5 @@
+use super::Report;
+
+pub fn render(report: &Report) -> String {
+    serde_json::to_string_pretty(report).unwrap()
+}
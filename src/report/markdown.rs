@@ -0,0 +1,61 @@
+use std::{collections::BTreeMap, io};
+
+use super::{Report, ResultSink};
+
+/// Prints a GitHub-flavored Markdown table, ready to paste into PRs and issues.
+pub struct Markdown {
+    /// Baseline mean latency in nanoseconds, keyed by benchmark name.
+    baseline: BTreeMap<String, f64>,
+}
+
+impl Markdown {
+    pub fn new(baseline: BTreeMap<String, f64>) -> Self {
+        Self { baseline }
+    }
+}
+
+impl ResultSink for Markdown {
+    fn finish(&mut self, report: &Report) -> io::Result<()> {
+        println!("{}", render(report, &self.baseline));
+        Ok(())
+    }
+}
+
+pub fn render(report: &Report, baseline: &BTreeMap<String, f64>) -> String {
+    let unit = report.results.first().map_or("cycles", |r| r.unit.as_str());
+    let per_op = report.results.iter().any(|r| r.ops > 1);
+    let bandwidth = report.results.iter().any(|r| r.bytes > 0);
+    let mut table = format!(
+        "| benchmark | mean ({unit}) | p50 ({unit}) | p99 ({unit}) | mean (ns) |{}{} Δ vs baseline |\n\
+         |:--|--:|--:|--:|--:|{}{}--:|\n",
+        if per_op { format!(" per op ({unit}) |") } else { String::new() },
+        if bandwidth { " GB/s |" } else { "" },
+        if per_op { "--:|" } else { "" },
+        if bandwidth { "--:|" } else { "" },
+    );
+
+    for result in &report.results {
+        let summary = &result.summary;
+        let mean_ns = result.to_nanos(summary.mean);
+        let delta = baseline
+            .get(&result.name)
+            .map(|base| format!("{:+.1}%", (mean_ns - base) / base * 100.0))
+            .unwrap_or_else(|| "–".to_string());
+        let per_op = if per_op {
+            format!(" {:.2} |", result.mean_per_op())
+        } else {
+            String::new()
+        };
+        let bandwidth = match (bandwidth, result.bandwidth()) {
+            (false, _) => String::new(),
+            (true, Some(gbps)) => format!(" {gbps:.2} |"),
+            (true, None) => " – |".to_string(),
+        };
+        table.push_str(&format!(
+            "| `{}` | {:.1} | {} | {} | {:.1} |{per_op}{bandwidth} {} |\n",
+            result.name, summary.mean, summary.p50, summary.p99, mean_ns, delta
+        ));
+    }
+
+    table
+}
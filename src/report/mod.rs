@@ -0,0 +1,180 @@
+pub mod console;
+pub mod criterion;
+pub mod csv;
+pub mod html;
+pub mod json;
+pub mod markdown;
+pub mod plot;
+pub mod prometheus;
+pub mod samples;
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{env::Environment, os::ContextSwitches, stats::Summary};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub name: String,
+    pub unit: String,
+    /// Timer ticks per second, for converting to nanoseconds.
+    pub frequency: u64,
+    #[serde(skip)]
+    pub samples: Vec<u64>,
+    pub summary: Summary,
+    #[serde(default)]
+    pub context_switches: ContextSwitches,
+    /// Samples dropped because a context switch landed in them.
+    #[serde(default)]
+    pub discarded: usize,
+    /// Stopped by the watchdog, so the summary covers only the samples taken until then.
+    #[serde(default)]
+    pub timed_out: bool,
+    /// Operations per sample; see `Bencher::set_ops`.
+    #[serde(default = "one")]
+    pub ops: u64,
+    /// Bytes moved per sample, or 0; see `Bencher::set_bytes`.
+    #[serde(default)]
+    pub bytes: u64,
+    /// Hardware events per sample from `--counters`, and any the benchmark counts itself, by
+    /// name.
+    #[serde(default)]
+    pub counters: BTreeMap<String, f64>,
+    /// The kernel's mitigations for results tagged `syscall`, which they tax; see
+    /// `Mitigations::label`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mitigations: Option<String>,
+}
+
+fn one() -> u64 {
+    1
+}
+
+impl BenchResult {
+    pub fn new(name: &str, unit: &str, frequency: u64, samples: Vec<u64>) -> Self {
+        Self {
+            name: name.to_string(),
+            unit: unit.to_string(),
+            frequency,
+            summary: Summary::new(&samples),
+            samples,
+            context_switches: ContextSwitches::default(),
+            discarded: 0,
+            timed_out: false,
+            ops: 1,
+            bytes: 0,
+            counters: BTreeMap::new(),
+            mitigations: None,
+        }
+    }
+
+    pub fn to_nanos(&self, ticks: f64) -> f64 {
+        ticks * 1e9 / self.frequency as f64
+    }
+
+    /// Mean per operation, in the result's unit.
+    pub fn mean_per_op(&self) -> f64 {
+        self.summary.mean / self.ops as f64
+    }
+
+    /// Mean bandwidth in GB/s, for results that set their bytes.
+    pub fn bandwidth(&self) -> Option<f64> {
+        (self.bytes > 0).then(|| self.bytes as f64 / self.to_nanos(self.summary.mean))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Report {
+    pub environment: Environment,
+    pub results: Vec<BenchResult>,
+}
+
+/// A destination for benchmark results.
+pub trait ResultSink {
+    /// Called as soon as each benchmark finishes.
+    fn record(&mut self, _result: &BenchResult) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called once after every selected benchmark has run.
+    fn finish(&mut self, _report: &Report) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// How results are printed to the terminal.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum ConsoleFormat {
+    Text,
+    /// GitHub-flavored Markdown table
+    Md,
+    /// One line per benchmark once all have run, cheapest per operation first
+    Sorted,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// One row per benchmark
+    Csv,
+    Html,
+    Json,
+    /// Prometheus text exposition format, e.g. for node_exporter's textfile collector
+    Prometheus,
+    /// Every raw sample as little-endian u64 files under `samples/`
+    Samples,
+    /// A latency histogram and CDF per benchmark as `plots/<name>.svg`
+    Svg,
+}
+
+impl ReportFormat {
+    pub fn sink(self, output_dir: &Path) -> Box<dyn ResultSink> {
+        let (file_name, render): (_, fn(&Report) -> String) = match self {
+            ReportFormat::Csv => ("results.csv", csv::render),
+            ReportFormat::Html => ("report.html", html::render),
+            ReportFormat::Json => ("results.json", json::render),
+            ReportFormat::Prometheus => ("microbench.prom", prometheus::render),
+            ReportFormat::Samples => {
+                return Box::new(samples::SampleDump::new(output_dir.join("samples")))
+            }
+            ReportFormat::Svg => return Box::new(plot::Plots::new(output_dir.join("plots"))),
+        };
+        Box::new(FileReport {
+            path: output_dir.join(file_name),
+            render,
+        })
+    }
+}
+
+/// Renders the whole report into a single file once all benchmarks have run.
+pub struct FileReport {
+    path: PathBuf,
+    render: fn(&Report) -> String,
+}
+
+impl ResultSink for FileReport {
+    fn finish(&mut self, report: &Report) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, (self.render)(report))?;
+        println!("report written to {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// File name stem for per-benchmark artifacts, e.g. `syscall.getpid`.
+pub fn file_stem(name: &str) -> String {
+    name.replace("::", ".")
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,182 @@
+use std::{fs, io, path::PathBuf};
+
+use plotters::{coord::Shift, prelude::*};
+
+use super::{file_stem, BenchResult, Report, ResultSink};
+use crate::stats::log_histogram;
+
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 450;
+const HISTOGRAM_BUCKETS: usize = 60;
+/// Points on the CDF line; more are indistinguishable at this width.
+const CDF_POINTS: usize = 1000;
+
+/// Writes one `<stem>.svg` per benchmark with its latency histogram (log-scaled buckets) next to
+/// its cumulative distribution, both in nanoseconds, and `memory_latency.svg` with the latency
+/// per load of the `memory::chase_<size>` benchmarks against their working set.
+pub struct Plots {
+    dir: PathBuf,
+}
+
+impl Plots {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl ResultSink for Plots {
+    fn record(&mut self, result: &BenchResult) -> io::Result<()> {
+        if result.samples.is_empty() {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("{}.svg", file_stem(&result.name)));
+        let root = SVGBackend::new(&path, (WIDTH, HEIGHT)).into_drawing_area();
+        draw(&root, result).map_err(io::Error::other)?;
+        root.present().map_err(io::Error::other)?;
+        println!("plot written to {}", path.display());
+        Ok(())
+    }
+
+    fn finish(&mut self, report: &Report) -> io::Result<()> {
+        let mut points = report
+            .results
+            .iter()
+            .filter_map(|r| Some((working_set(&r.name)?, r.to_nanos(r.mean_per_op()))))
+            .collect::<Vec<_>>();
+        if points.len() < 2 {
+            return Ok(());
+        }
+        points.sort_by_key(|&(bytes, _)| bytes);
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join("memory_latency.svg");
+        let root = SVGBackend::new(&path, (WIDTH, HEIGHT)).into_drawing_area();
+        draw_memory_latency(&root, &points).map_err(io::Error::other)?;
+        root.present().map_err(io::Error::other)?;
+        println!("plot written to {}", path.display());
+        Ok(())
+    }
+}
+
+/// Working set of a `memory::chase_<n><k|m|g>` benchmark in bytes.
+fn working_set(name: &str) -> Option<u64> {
+    let size = name.strip_prefix("memory::chase_")?;
+    let (count, unit) = size.split_at(size.len().checked_sub(1)?);
+    let shift = match unit {
+        "k" => 10,
+        "m" => 20,
+        "g" => 30,
+        _ => return None,
+    };
+    Some(count.parse::<u64>().ok()? << shift)
+}
+
+fn draw_memory_latency(root: &DrawingArea<SVGBackend, Shift>, points: &[(u64, f64)]) -> DrawResult {
+    root.fill(&WHITE)?;
+    // Sizes are powers of two, so a log2 axis puts every one on a tick.
+    let log2 = |bytes: u64| bytes.ilog2();
+    let x = log2(points[0].0)..log2(points[points.len() - 1].0) + 1;
+    let slowest = points.iter().map(|&(_, ns)| ns).fold(0.1, f64::max);
+    let fastest = points.iter().map(|&(_, ns)| ns).fold(slowest, f64::min);
+
+    let mut chart = ChartBuilder::on(root)
+        .caption("memory latency by working set", ("sans-serif", 22))
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x, (fastest.max(0.01) / 1.5..slowest * 1.5).log_scale())?;
+    chart
+        .configure_mesh()
+        .x_desc("working set")
+        .y_desc("latency per load (ns)")
+        .x_label_formatter(&|&shift| match shift {
+            30.. => format!("{}G", 1u64 << (shift - 30)),
+            20.. => format!("{}M", 1u64 << (shift - 20)),
+            10.. => format!("{}K", 1u64 << (shift - 10)),
+            _ => format!("{}", 1u64 << shift),
+        })
+        .y_label_formatter(&|ns| format!("{ns:.1}"))
+        .draw()?;
+    let points = points.iter().map(|&(bytes, ns)| (log2(bytes), ns));
+    chart.draw_series(LineSeries::new(points.clone(), RED.stroke_width(2)))?;
+    chart.draw_series(points.map(|point| Circle::new(point, 4, RED.filled())))?;
+    Ok(())
+}
+
+type DrawResult =
+    Result<(), DrawingAreaErrorKind<<SVGBackend<'static> as DrawingBackend>::ErrorType>>;
+
+fn draw(root: &DrawingArea<SVGBackend, Shift>, result: &BenchResult) -> DrawResult {
+    root.fill(&WHITE)?;
+    let root = root.titled(&result.name, ("sans-serif", 22))?;
+    let (left, right) = root.split_horizontally(WIDTH / 2);
+    draw_histogram(&left, result)?;
+    draw_cdf(&right, result)
+}
+
+fn draw_histogram(area: &DrawingArea<SVGBackend, Shift>, result: &BenchResult) -> DrawResult {
+    let histogram = log_histogram(&result.samples, HISTOGRAM_BUCKETS);
+    let (Some(first), Some(last)) = (histogram.first(), histogram.last()) else {
+        return Ok(());
+    };
+    let tallest = histogram.iter().map(|b| b.count).max().unwrap_or(1);
+    let x = nanos_range(result, first.start, last.end);
+
+    let mut chart = ChartBuilder::on(area)
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x.clone().log_scale(), 0usize..tallest + tallest / 20 + 1)?;
+    chart
+        .configure_mesh()
+        .x_desc("latency (ns)")
+        .y_desc("samples")
+        .x_label_formatter(&|ns| format!("{ns:.0}"))
+        .draw()?;
+    chart.draw_series(histogram.iter().map(|bucket| {
+        Rectangle::new(
+            [
+                (result.to_nanos(bucket.start as f64).max(x.start), 0),
+                (result.to_nanos(bucket.end as f64), bucket.count),
+            ],
+            BLUE.mix(0.6).filled(),
+        )
+    }))?;
+    Ok(())
+}
+
+fn draw_cdf(area: &DrawingArea<SVGBackend, Shift>, result: &BenchResult) -> DrawResult {
+    let mut sorted = result.samples.clone();
+    sorted.sort_unstable();
+    let x = nanos_range(result, sorted[0], sorted[sorted.len() - 1] + 1);
+
+    let mut chart = ChartBuilder::on(area)
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x.clone().log_scale(), 0.0..1.0)?;
+    chart
+        .configure_mesh()
+        .x_desc("latency (ns)")
+        .y_desc("cumulative fraction")
+        .x_label_formatter(&|ns| format!("{ns:.0}"))
+        .draw()?;
+
+    let step = sorted.len().div_ceil(CDF_POINTS);
+    let points = (0..sorted.len())
+        .step_by(step)
+        .chain([sorted.len() - 1])
+        .map(|i| {
+            let ns = result.to_nanos(sorted[i] as f64).max(x.start);
+            (ns, (i + 1) as f64 / sorted.len() as f64)
+        });
+    chart.draw_series(LineSeries::new(points, RED.stroke_width(2)))?;
+    Ok(())
+}
+
+/// `[start, end)` ticks in nanoseconds, kept positive for the log axis.
+fn nanos_range(result: &BenchResult, start: u64, end: u64) -> std::ops::Range<f64> {
+    let start = result.to_nanos(start as f64).max(0.1);
+    start..result.to_nanos(end as f64).max(start * 2.0)
+}
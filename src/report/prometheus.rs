@@ -0,0 +1,129 @@
+use std::{
+    fmt::Write as _,
+    io::{self, Read, Write},
+    net::TcpStream,
+};
+
+use super::{Report, ResultSink};
+
+pub fn render(report: &Report) -> String {
+    let mut text = String::new();
+
+    text.push_str("# HELP microbench_latency_nanoseconds Per-iteration latency percentiles.\n");
+    text.push_str("# TYPE microbench_latency_nanoseconds gauge\n");
+    for result in &report.results {
+        for (percentile, value) in result.summary.percentiles() {
+            writeln!(
+                text,
+                "microbench_latency_nanoseconds{{benchmark=\"{}\",percentile=\"{percentile}\"}} {}",
+                label(&result.name),
+                result.to_nanos(value as f64)
+            )
+            .unwrap();
+        }
+    }
+
+    text.push_str(
+        "# HELP microbench_latency_ticks Per-iteration latency percentiles in timer units.\n",
+    );
+    text.push_str("# TYPE microbench_latency_ticks gauge\n");
+    for result in &report.results {
+        for (percentile, value) in result.summary.percentiles() {
+            writeln!(
+                text,
+                "microbench_latency_ticks{{benchmark=\"{}\",percentile=\"{percentile}\",unit=\"{}\"}} {value}",
+                label(&result.name),
+                label(&result.unit)
+            )
+            .unwrap();
+        }
+    }
+
+    text.push_str("# HELP microbench_mean_nanoseconds Mean per-iteration latency.\n");
+    text.push_str("# TYPE microbench_mean_nanoseconds gauge\n");
+    for result in &report.results {
+        writeln!(
+            text,
+            "microbench_mean_nanoseconds{{benchmark=\"{}\"}} {}",
+            label(&result.name),
+            result.to_nanos(result.summary.mean)
+        )
+        .unwrap();
+    }
+
+    text.push_str(
+        "# HELP microbench_samples Number of samples behind each benchmark's statistics.\n",
+    );
+    text.push_str("# TYPE microbench_samples gauge\n");
+    for result in &report.results {
+        writeln!(
+            text,
+            "microbench_samples{{benchmark=\"{}\"}} {}",
+            label(&result.name),
+            result.summary.count
+        )
+        .unwrap();
+    }
+
+    text
+}
+
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Pushes the exposition to a Prometheus Pushgateway, grouped by job and hostname.
+pub struct Pushgateway {
+    url: String,
+}
+
+impl Pushgateway {
+    /// `url` is the gateway's base address, e.g. `http://pushgateway:9091`.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+impl ResultSink for Pushgateway {
+    fn finish(&mut self, report: &Report) -> io::Result<()> {
+        let address = self.url.strip_prefix("http://").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "only http:// gateways are supported",
+            )
+        })?;
+        let (host, prefix) = address.split_once('/').unwrap_or((address, ""));
+        let path = format!(
+            "/{prefix}{}metrics/job/microbench/instance/{}",
+            if prefix.is_empty() { "" } else { "/" },
+            report.environment.hostname
+        );
+        let body = render(report);
+
+        let mut stream = TcpStream::connect(host)?;
+        write!(
+            stream,
+            "PUT {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: text/plain; version=0.0.4\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        let status = response.split_whitespace().nth(1).unwrap_or("");
+        if !status.starts_with('2') {
+            let line = response.lines().next().unwrap_or("no response");
+            return Err(io::Error::other(format!(
+                "pushgateway rejected metrics: {line}"
+            )));
+        }
+
+        println!("metrics pushed to {}", self.url);
+        Ok(())
+    }
+}
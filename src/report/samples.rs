@@ -0,0 +1,91 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{file_stem, BenchResult, Report, ResultSink};
+use crate::env::Environment;
+
+/// One benchmark's raw samples in `index.json`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SampleFile {
+    pub name: String,
+    /// Relative to the index, e.g. `syscall.getpid.u64`.
+    pub file: String,
+    pub unit: String,
+    pub frequency: u64,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SampleIndex {
+    pub environment: Environment,
+    pub benches: Vec<SampleFile>,
+}
+
+/// Writes every raw sample as a headerless stream of little-endian u64 (`<stem>.u64`, loadable
+/// with e.g. `numpy.fromfile(path, "<u8")`), described by an `index.json` next to them.
+pub struct SampleDump {
+    dir: PathBuf,
+    benches: Vec<SampleFile>,
+}
+
+impl SampleDump {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            benches: Vec::new(),
+        }
+    }
+}
+
+impl ResultSink for SampleDump {
+    fn record(&mut self, result: &BenchResult) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let file = format!("{}.u64", file_stem(&result.name));
+        let mut out = BufWriter::new(File::create(self.dir.join(&file))?);
+        for sample in &result.samples {
+            out.write_all(&sample.to_le_bytes())?;
+        }
+        out.flush()?;
+
+        self.benches.push(SampleFile {
+            name: result.name.clone(),
+            file,
+            unit: result.unit.clone(),
+            frequency: result.frequency,
+            count: result.samples.len(),
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self, report: &Report) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let index = SampleIndex {
+            environment: report.environment.clone(),
+            benches: self.benches.clone(),
+        };
+        let path = self.dir.join("index.json");
+        fs::write(&path, serde_json::to_string_pretty(&index).unwrap())?;
+        println!("samples written to {}", self.dir.display());
+        Ok(())
+    }
+}
+
+pub fn read_index(dir: &Path) -> io::Result<SampleIndex> {
+    let json = fs::read_to_string(dir.join("index.json"))?;
+    serde_json::from_str(&json).map_err(io::Error::other)
+}
+
+pub fn read_samples(path: &Path) -> io::Result<Vec<u64>> {
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+    let (chunks, _) = bytes.as_chunks::<8>();
+    Ok(chunks
+        .iter()
+        .map(|&chunk| u64::from_le_bytes(chunk))
+        .collect())
+}
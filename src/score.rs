@@ -0,0 +1,144 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bench::benches,
+    report::{criterion::CriterionEstimate, Report},
+};
+
+/// What the reference machine scores, overall and in every category.
+pub const REFERENCE_SCORE: f64 = 1000.0;
+
+/// Categories a benchmark can count towards, by tag.
+pub const CATEGORIES: [&str; 4] = ["syscall", "memory", "scheduling", "async"];
+
+/// Criterion benchmarks carry no tags outside `benches/`, so they are categorized by name.
+const CRITERION_CATEGORIES: [(&str, &str); 3] = [
+    ("tokio", "async"),
+    ("thread", "scheduling"),
+    ("syscall", "syscall"),
+];
+
+/// Mean latencies of a reference machine, in nanoseconds, that other machines are scored
+/// against.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Reference {
+    /// E.g. the CPU model, for the printout.
+    pub machine: String,
+    pub means: BTreeMap<String, f64>,
+}
+
+impl Reference {
+    pub fn new(report: &Report, criterion: &[CriterionEstimate]) -> Self {
+        Self {
+            machine: report.environment.cpu_model.clone(),
+            means: means(report, criterion),
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        serde_json::from_str(&fs::read_to_string(path)?).map_err(io::Error::other)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap())
+    }
+}
+
+/// A machine relative to the reference: `REFERENCE_SCORE` times the geometric mean of the
+/// per-benchmark speedups (reference mean / mean), so higher is faster and a benchmark twice as
+/// fast counts as much as one twice as slow.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Score {
+    pub reference: String,
+    pub overall: Option<f64>,
+    pub categories: BTreeMap<String, f64>,
+    /// Speedup per benchmark measured on both machines.
+    pub speedups: BTreeMap<String, f64>,
+    /// Benchmarks without a reference mean, left out of the score.
+    pub unscored: Vec<String>,
+}
+
+pub fn score(report: &Report, criterion: &[CriterionEstimate], reference: &Reference) -> Score {
+    let mut speedups = BTreeMap::new();
+    let mut unscored = Vec::new();
+    for (name, mean) in means(report, criterion) {
+        match reference.means.get(&name) {
+            Some(&reference) if reference > 0.0 && mean > 0.0 => {
+                speedups.insert(name, reference / mean);
+            }
+            _ => unscored.push(name),
+        }
+    }
+
+    let categories = CATEGORIES
+        .iter()
+        .filter_map(|&category| {
+            let members = speedups
+                .iter()
+                .filter(|(name, _)| categories(name).contains(&category))
+                .map(|(_, &speedup)| speedup);
+            Some((category.to_string(), scale(members)?))
+        })
+        .collect();
+
+    Score {
+        reference: reference.machine.clone(),
+        overall: scale(speedups.values().copied()),
+        categories,
+        speedups,
+        unscored,
+    }
+}
+
+/// Mean latency per benchmark in nanoseconds, harness and Criterion alike.
+fn means(report: &Report, criterion: &[CriterionEstimate]) -> BTreeMap<String, f64> {
+    report
+        .results
+        .iter()
+        .map(|r| (r.name.clone(), r.to_nanos(r.summary.mean)))
+        .chain(criterion.iter().map(|e| (e.name.clone(), e.mean)))
+        .collect()
+}
+
+fn categories(name: &str) -> Vec<&'static str> {
+    if let Some(bench) = benches().into_iter().find(|b| b.name() == name) {
+        return CATEGORIES
+            .into_iter()
+            .filter(|category| bench.tags.contains(category))
+            .collect();
+    }
+    CRITERION_CATEGORIES
+        .iter()
+        .filter(|(keyword, _)| name.contains(keyword))
+        .map(|&(_, category)| category)
+        .collect()
+}
+
+/// `REFERENCE_SCORE` times the geometric mean of `speedups`, if there are any.
+fn scale(speedups: impl Iterator<Item = f64>) -> Option<f64> {
+    let (count, log_sum) = speedups.fold((0, 0.0), |(n, sum), s| (n + 1, sum + s.ln()));
+    (count > 0).then(|| REFERENCE_SCORE * (log_sum / count as f64).exp())
+}
+
+pub fn print(score: &Score) {
+    println!("relative to {} (= {REFERENCE_SCORE})", score.reference);
+    match score.overall {
+        Some(overall) => println!(
+            "{:<12}  {overall:>8.0}  ({} benchmarks)",
+            "overall",
+            score.speedups.len()
+        ),
+        None => println!("no benchmark has a reference mean"),
+    }
+    for category in CATEGORIES {
+        match score.categories.get(category) {
+            Some(value) => println!("{category:<12}  {value:>8.0}"),
+            None => println!("{category:<12}  {:>8}", "-"),
+        }
+    }
+    if !score.unscored.is_empty() {
+        println!("not in the reference: {}", score.unscored.join(", "));
+    }
+}
@@ -0,0 +1,193 @@
+//! Seccomp-bpf filters installed before a run (`--seccomp`), so the `syscall` suite measures
+//! what filtering adds to every syscall. The allowlists compare the syscall number against each
+//! entry in turn, as a straightforward filter does; calls missing from the list fall through to
+//! be allowed anyway, after every comparison a real allowlist would make before failing them,
+//! so the runner keeps working whatever it calls. Since Linux 5.11 the kernel caches the result
+//! of filters that never look at syscall arguments or the instruction pointer and skips running
+//! them, so those only show the cost of seccomp's entry hook; the `*-args` variants load the
+//! first argument before the allowlist, as filters that restrict arguments do, which keeps the
+//! kernel running them on every call.
+
+use std::io;
+
+use syscalls::Sysno;
+
+/// A filter for `--seccomp`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum Filter {
+    /// One instruction allowing everything.
+    AllowAll,
+    /// An architecture check, then the 21 syscalls a sandboxed worker needs.
+    SmallAllowlist,
+    /// An architecture check, then every syscall the kernel has, in number order.
+    LargeAllowlist,
+    /// `small-allowlist` after loading the first argument.
+    SmallAllowlistArgs,
+    /// `large-allowlist` after loading the first argument.
+    LargeAllowlistArgs,
+}
+
+impl Filter {
+    /// The BPF program.
+    pub fn program(self) -> Vec<libc::sock_filter> {
+        let allowed = match self {
+            Filter::AllowAll => return vec![ret(libc::SECCOMP_RET_ALLOW)],
+            Filter::SmallAllowlist | Filter::SmallAllowlistArgs => SMALL_ALLOWLIST.to_vec(),
+            Filter::LargeAllowlist | Filter::LargeAllowlistArgs => Sysno::iter().collect(),
+        };
+        let mut program = Vec::new();
+        if let Some(arch) = AUDIT_ARCH {
+            program.extend([
+                load(ARCH_OFFSET),
+                jump_if_equal(arch, 1, 0),
+                ret(libc::SECCOMP_RET_KILL_PROCESS),
+            ]);
+        }
+        if self.reads_args() {
+            program.push(load(ARG0_OFFSET));
+        }
+        program.push(load(NR_OFFSET));
+        for sysno in allowed {
+            program.extend([
+                jump_if_equal(sysno.id() as u32, 0, 1),
+                ret(libc::SECCOMP_RET_ALLOW),
+            ]);
+        }
+        program.push(ret(libc::SECCOMP_RET_ALLOW));
+        program
+    }
+
+    fn reads_args(self) -> bool {
+        matches!(
+            self,
+            Filter::SmallAllowlistArgs | Filter::LargeAllowlistArgs
+        )
+    }
+
+    /// Whether the kernel caches the filter's verdict instead of running it: since Linux 5.11,
+    /// for filters that read only the syscall number and architecture.
+    pub fn cached(self) -> bool {
+        !self.reads_args() && kernel_version() >= (5, 11)
+    }
+
+    /// The filter's name, marked where the kernel caches it, for the output and fingerprint.
+    pub fn label(self) -> String {
+        let name = clap::ValueEnum::to_possible_value(&self)
+            .unwrap()
+            .get_name()
+            .to_string();
+        if self.cached() {
+            format!("{name} (cached by the kernel)")
+        } else {
+            name
+        }
+    }
+
+    /// Installs the filter on every thread of the process and on its future children; there
+    /// is no removing it.
+    pub fn install(self) -> io::Result<()> {
+        let mut program = self.program();
+        let fprog = libc::sock_fprog {
+            len: program.len() as u16,
+            filter: program.as_mut_ptr(),
+        };
+        // Safety: `fprog` points to the program, which the kernel copies.
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_SET_MODE_FILTER,
+                libc::SECCOMP_FILTER_FLAG_TSYNC,
+                &raw const fprog,
+            ) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+const SMALL_ALLOWLIST: [Sysno; 21] = [
+    Sysno::read,
+    Sysno::write,
+    Sysno::close,
+    Sysno::fstat,
+    Sysno::lseek,
+    Sysno::mmap,
+    Sysno::mprotect,
+    Sysno::munmap,
+    Sysno::brk,
+    Sysno::rt_sigaction,
+    Sysno::rt_sigprocmask,
+    Sysno::rt_sigreturn,
+    Sysno::pread64,
+    Sysno::pwrite64,
+    Sysno::futex,
+    Sysno::clock_gettime,
+    Sysno::clock_nanosleep,
+    Sysno::sched_yield,
+    Sysno::getrandom,
+    Sysno::exit,
+    Sysno::exit_group,
+];
+
+/// Offsets of the fields of `seccomp_data`.
+const NR_OFFSET: u32 = 0;
+const ARCH_OFFSET: u32 = 4;
+/// The low word of `args[0]` on little-endian machines.
+const ARG0_OFFSET: u32 = 16;
+
+/// The `AUDIT_ARCH_*` value of the native syscall ABI, which filters check first so that calls
+/// through another ABI cannot pass for allowed ones.
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: Option<u32> = Some(0xc000_003e);
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: Option<u32> = Some(0xc000_00b7);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const AUDIT_ARCH: Option<u32> = None;
+
+/// The running kernel's major and minor version, `(0, 0)` if unknown.
+fn kernel_version() -> (u32, u32) {
+    // Safety: zero is a valid `utsname`.
+    let mut uts = unsafe { std::mem::zeroed::<libc::utsname>() };
+    // Safety: fills `uts`.
+    if unsafe { libc::uname(&mut uts) } != 0 {
+        return (0, 0);
+    }
+    // Safety: `release` is null-terminated.
+    let release = unsafe { std::ffi::CStr::from_ptr(uts.release.as_ptr()) }.to_string_lossy();
+    let mut numbers = release
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|n| n.parse().unwrap_or(0));
+    (numbers.next().unwrap_or(0), numbers.next().unwrap_or(0))
+}
+
+fn load(offset: u32) -> libc::sock_filter {
+    libc::sock_filter {
+        code: (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+        jt: 0,
+        jf: 0,
+        k: offset,
+    }
+}
+
+fn jump_if_equal(value: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter {
+        code: (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
+        jt,
+        jf,
+        k: value,
+    }
+}
+
+fn ret(action: u32) -> libc::sock_filter {
+    libc::sock_filter {
+        code: (libc::BPF_RET | libc::BPF_K) as u16,
+        jt: 0,
+        jf: 0,
+        k: action,
+    }
+}
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub stddev: f64,
+    pub min: u64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub max: u64,
+}
+
+impl Summary {
+    pub fn new(samples: &[u64]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let count = sorted.len();
+        let mean = mean(&sorted);
+        let variance = if count > 1 {
+            sorted
+                .iter()
+                .map(|&x| (x as f64 - mean).powi(2))
+                .sum::<f64>()
+                / (count - 1) as f64
+        } else {
+            0.0
+        };
+
+        Self {
+            count,
+            mean,
+            stddev: variance.sqrt(),
+            min: sorted.first().copied().unwrap_or(0),
+            p50: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+            p999: percentile(&sorted, 99.9),
+            max: sorted.last().copied().unwrap_or(0),
+        }
+    }
+
+    /// Label/value pairs in display order.
+    pub fn percentiles(&self) -> [(&'static str, u64); 6] {
+        [
+            ("min", self.min),
+            ("p50", self.p50),
+            ("p90", self.p90),
+            ("p99", self.p99),
+            ("p99.9", self.p999),
+            ("max", self.max),
+        ]
+    }
+}
+
+pub fn mean(samples: &[u64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().map(|&x| x as f64).sum::<f64>() / samples.len() as f64
+}
+
+/// Nearest-rank percentile of an already sorted slice.
+pub fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bucket {
+    pub start: u64,
+    pub end: u64,
+    pub count: usize,
+}
+
+/// Splits `[min, max]` into up to `buckets` logarithmically sized, half-open buckets. Narrow
+/// ranges yield fewer buckets since boundaries are whole numbers.
+pub fn log_histogram(samples: &[u64], buckets: usize) -> Vec<Bucket> {
+    let (Some(&min), Some(&max)) = (samples.iter().min(), samples.iter().max()) else {
+        return Vec::new();
+    };
+
+    let low = min.max(1) as f64;
+    let high = (max + 1) as f64;
+    let ratio = (high / low).powf(1.0 / buckets.max(1) as f64);
+
+    let mut bounds = vec![min];
+    for i in 1..buckets {
+        let bound = (low * ratio.powi(i as i32)).ceil() as u64;
+        if bound > *bounds.last().unwrap() && bound <= max {
+            bounds.push(bound);
+        }
+    }
+    bounds.push(max + 1);
+
+    let mut histogram = bounds
+        .windows(2)
+        .map(|w| Bucket {
+            start: w[0],
+            end: w[1],
+            count: 0,
+        })
+        .collect::<Vec<_>>();
+
+    for &sample in samples {
+        let index = bounds[1..].partition_point(|&end| end <= sample);
+        histogram[index].count += 1;
+    }
+
+    histogram
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TTest {
+    pub t: f64,
+    pub df: f64,
+    /// Two-sided p-value.
+    pub p: f64,
+}
+
+/// Welch's unequal-variance t-test between two small sets of measurements.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<TTest> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+
+    let (mean_a, var_a) = mean_variance(a);
+    let (mean_b, var_b) = mean_variance(b);
+    let (se_a, se_b) = (var_a / a.len() as f64, var_b / b.len() as f64);
+    let se = se_a + se_b;
+    if se == 0.0 {
+        let p = if mean_a == mean_b { 1.0 } else { 0.0 };
+        return Some(TTest {
+            t: 0.0,
+            df: (a.len() + b.len() - 2) as f64,
+            p,
+        });
+    }
+
+    let t = (mean_a - mean_b) / se.sqrt();
+    let df =
+        se.powi(2) / (se_a.powi(2) / (a.len() - 1) as f64 + se_b.powi(2) / (b.len() - 1) as f64);
+    let p = regularized_incomplete_beta(df / 2.0, 0.5, df / (df + t * t));
+
+    Some(TTest { t, df, p })
+}
+
+/// One-way random-effects decomposition of repeated runs (one `Summary` per process) into
+/// the spread within a process and the extra spread between processes.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VarianceComponents {
+    pub grand_mean: f64,
+    /// Pooled within-process variance.
+    pub within: f64,
+    /// Variance of the true per-process means, with the sampling noise of each process mean
+    /// (`within / n`) removed; zero when the process means agree within that noise.
+    pub between: f64,
+}
+
+pub fn variance_components(groups: &[Summary]) -> Option<VarianceComponents> {
+    if groups.len() < 2 || groups.iter().any(|g| g.count < 2) {
+        return None;
+    }
+
+    let total = groups.iter().map(|g| g.count).sum::<usize>() as f64;
+    let grand_mean = groups.iter().map(|g| g.mean * g.count as f64).sum::<f64>() / total;
+    let within = groups
+        .iter()
+        .map(|g| g.stddev.powi(2) * (g.count - 1) as f64)
+        .sum::<f64>()
+        / (total - groups.len() as f64);
+    let means = groups.iter().map(|g| g.mean).collect::<Vec<_>>();
+    let (_, means_variance) = mean_variance(&means);
+    let mean_count = total / groups.len() as f64;
+
+    Some(VarianceComponents {
+        grand_mean,
+        within,
+        between: (means_variance - within / mean_count).max(0.0),
+    })
+}
+
+fn mean_variance(values: &[f64]) -> (f64, f64) {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance =
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    (mean, variance)
+}
+
+/// I_x(a, b) via Lentz's continued fraction (Numerical Recipes 6.4).
+fn regularized_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - regularized_incomplete_beta(b, a, 1.0 - x);
+    }
+
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    let front = ln_front.exp() / a;
+
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut f = d;
+
+    for m in 1..200 {
+        let m = m as f64;
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            f *= c * d;
+        }
+        if (c * d - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+
+    front * f
+}
+
+/// Lanczos approximation (g = 7, n = 9).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, &c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
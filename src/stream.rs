@@ -0,0 +1,148 @@
+use std::{
+    io::{self, BufWriter, Write},
+    net::TcpStream,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+};
+
+use serde_json::json;
+
+use crate::{
+    report::{BenchResult, Report, ResultSink},
+    stats::Summary,
+};
+
+enum Message {
+    Batch {
+        bench: String,
+        index: usize,
+        samples: Vec<u64>,
+    },
+    Result(serde_json::Value),
+}
+
+/// Streams results as newline-delimited JSON to a socket while benchmarks run: a `batch` line
+/// per block of iterations, a `result` line per finished benchmark. Formatting and writing
+/// happen on a background thread so the measuring thread only hands over its samples.
+pub struct Stream {
+    tx: Option<Sender<Message>>,
+    writer: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl Stream {
+    /// Connects to `addr`, either `host:port` or `unix:<path>`. Call before pinning, so that
+    /// the writer thread is not confined to the measurement core. With `raw`, batch lines
+    /// carry every sample instead of only a summary.
+    pub fn connect(addr: &str, raw: bool) -> io::Result<Self> {
+        let socket: Box<dyn Write + Send> = match addr.strip_prefix("unix:") {
+            #[cfg(unix)]
+            Some(path) => Box::new(std::os::unix::net::UnixStream::connect(path)?),
+            #[cfg(not(unix))]
+            Some(_) => return Err(io::ErrorKind::Unsupported.into()),
+            None => Box::new(TcpStream::connect(addr)?),
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let writer = thread::Builder::new()
+            .name("stream".into())
+            .spawn(move || write_lines(rx, BufWriter::new(socket), raw))?;
+        Ok(Self {
+            tx: Some(tx),
+            writer: Some(writer),
+        })
+    }
+
+    /// A handle that stays usable after the stream itself is handed over as a `ResultSink`.
+    pub fn handle(&self) -> StreamHandle {
+        StreamHandle(self.tx.clone().expect("stream already finished"))
+    }
+}
+
+#[derive(Clone)]
+pub struct StreamHandle(Sender<Message>);
+
+impl StreamHandle {
+    /// For a `Bencher` to send the batches of `bench` through.
+    pub fn batches(&self, bench: &str) -> Batches {
+        Batches {
+            tx: self.0.clone(),
+            bench: bench.to_string(),
+            index: 0,
+        }
+    }
+}
+
+pub struct Batches {
+    tx: Sender<Message>,
+    bench: String,
+    index: usize,
+}
+
+impl Batches {
+    pub fn send(&mut self, samples: &[u64]) {
+        let _ = self.tx.send(Message::Batch {
+            bench: self.bench.clone(),
+            index: self.index,
+            samples: samples.to_vec(),
+        });
+        self.index += 1;
+    }
+}
+
+fn write_lines(rx: Receiver<Message>, mut out: impl Write, raw: bool) -> io::Result<()> {
+    for message in rx {
+        let line = match message {
+            Message::Batch {
+                bench,
+                index,
+                samples,
+            } => {
+                let summary = Summary::new(&samples);
+                let mut line = json!({
+                    "type": "batch",
+                    "bench": bench,
+                    "batch": index,
+                    "count": summary.count,
+                    "mean": summary.mean,
+                    "min": summary.min,
+                    "p50": summary.p50,
+                    "p99": summary.p99,
+                    "max": summary.max,
+                });
+                if raw {
+                    line["samples"] = json!(samples);
+                }
+                line
+            }
+            Message::Result(line) => line,
+        };
+        serde_json::to_writer(&mut out, &line)?;
+        out.write_all(b"\n")?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+impl ResultSink for Stream {
+    fn record(&mut self, result: &BenchResult) -> io::Result<()> {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(Message::Result(json!({
+                "type": "result",
+                "bench": result.name,
+                "unit": result.unit,
+                "frequency": result.frequency,
+                "summary": result.summary,
+            })));
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _report: &Report) -> io::Result<()> {
+        drop(self.tx.take());
+        match self.writer.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) => Err(io::Error::other("stream writer panicked")),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! AES-128 encryption, reported per byte: with the AES-NI instructions (x86_64, one `aesenc`
+//! per round, skipped without them) and in software with the classic lookup tables, which
+//! take a load per byte and round. `*_ecb` encrypts independent blocks, which overlap;
+//! `*_cbc` chains every block on the previous ciphertext, so only one is in flight at a time.
+
+use std::array;
+
+use crate::{bench::Bencher, register_bench};
+
+/// Bytes encrypted per sample.
+const BYTES: usize = 4096;
+const BLOCKS: usize = BYTES / 16;
+/// Blocks the AES-NI ECB loop keeps in flight, enough to cover `aesenc`'s latency.
+#[cfg(target_arch = "x86_64")]
+const INTERLEAVE: usize = 8;
+const KEY: [u8; 16] = *b"microbenchmarks!";
+
+fn software_ecb(b: &mut Bencher) {
+    b.set_ops(BYTES as u64);
+    b.set_bytes(BYTES as u64);
+    let keys = expand_key(&KEY);
+    let mut blocks = vec![[0; 16]; BLOCKS];
+    b.iter(|| {
+        for block in &mut blocks {
+            *block = encrypt(&keys, *block);
+        }
+        blocks[0]
+    });
+}
+
+fn software_cbc(b: &mut Bencher) {
+    b.set_ops(BYTES as u64);
+    b.set_bytes(BYTES as u64);
+    let keys = expand_key(&KEY);
+    let mut blocks = vec![[0; 16]; BLOCKS];
+    b.iter(|| {
+        let mut previous = [0; 16];
+        for block in &mut blocks {
+            let input: [u8; 16] = array::from_fn(|i| block[i] ^ previous[i]);
+            *block = encrypt(&keys, input);
+            previous = *block;
+        }
+        previous
+    });
+}
+
+register_bench!(software_ecb, tags = ["aes", "crypto"]);
+register_bench!(software_cbc, tags = ["aes", "crypto"]);
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// The round keys in registers.
+#[cfg(target_arch = "x86_64")]
+fn aesni_keys() -> [__m128i; ROUNDS + 1] {
+    expand_key(&KEY).map(|words| {
+        let bytes: [u8; 16] = array::from_fn(|i| words[i / 4].to_be_bytes()[i % 4]);
+        // Safety: `bytes` is 16 bytes long.
+        unsafe { _mm_loadu_si128(bytes.as_ptr().cast()) }
+    })
+}
+
+#[cfg(target_arch = "x86_64")]
+fn aesni_ecb(b: &mut Bencher) {
+    if !std::is_x86_feature_detected!("aes") {
+        return b.skip("no aes support");
+    }
+    b.set_ops(BYTES as u64);
+    b.set_bytes(BYTES as u64);
+    let keys = aesni_keys();
+    let mut blocks = vec![[0; 16]; BLOCKS];
+    // Safety: the feature was detected above.
+    b.iter(|| unsafe { ecb(&keys, &mut blocks) });
+
+    #[target_feature(enable = "aes")]
+    fn ecb(keys: &[__m128i; ROUNDS + 1], blocks: &mut [[u8; 16]]) {
+        for chunk in blocks.as_chunks_mut::<INTERLEAVE>().0 {
+            // Safety: every block is 16 bytes long.
+            let mut s: [__m128i; INTERLEAVE] = array::from_fn(|i| {
+                _mm_xor_si128(
+                    unsafe { _mm_loadu_si128(chunk[i].as_ptr().cast()) },
+                    keys[0],
+                )
+            });
+            for &key in &keys[1..ROUNDS] {
+                for x in &mut s {
+                    *x = _mm_aesenc_si128(*x, key);
+                }
+            }
+            for (block, x) in chunk.iter_mut().zip(s) {
+                let x = _mm_aesenclast_si128(x, keys[ROUNDS]);
+                // Safety: as above.
+                unsafe { _mm_storeu_si128(block.as_mut_ptr().cast(), x) };
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn aesni_cbc(b: &mut Bencher) {
+    if !std::is_x86_feature_detected!("aes") {
+        return b.skip("no aes support");
+    }
+    b.set_ops(BYTES as u64);
+    b.set_bytes(BYTES as u64);
+    let keys = aesni_keys();
+    let mut blocks = vec![[0; 16]; BLOCKS];
+    // Safety: the feature was detected above.
+    b.iter(|| unsafe { cbc(&keys, &mut blocks) });
+
+    #[target_feature(enable = "aes")]
+    fn cbc(keys: &[__m128i; ROUNDS + 1], blocks: &mut [[u8; 16]]) -> __m128i {
+        let mut previous = _mm_setzero_si128();
+        for block in blocks {
+            // Safety: every block is 16 bytes long.
+            let input = unsafe { _mm_loadu_si128(block.as_ptr().cast()) };
+            let mut x = _mm_xor_si128(_mm_xor_si128(input, previous), keys[0]);
+            for &key in &keys[1..ROUNDS] {
+                x = _mm_aesenc_si128(x, key);
+            }
+            previous = _mm_aesenclast_si128(x, keys[ROUNDS]);
+            // Safety: as above.
+            unsafe { _mm_storeu_si128(block.as_mut_ptr().cast(), previous) };
+        }
+        previous
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+register_bench!(aesni_ecb, tags = ["aes", "crypto"]);
+#[cfg(target_arch = "x86_64")]
+register_bench!(aesni_cbc, tags = ["aes", "crypto"]);
+
+/// The S-box, generated by walking the multiplicative group with generator 3: the inverse of
+/// `3^i` is `3^-i`, followed by the affine transform.
+const SBOX: [u8; 256] = {
+    let mut sbox = [0x63; 256];
+    let (mut p, mut q) = (1u8, 1u8);
+    loop {
+        p ^= (p << 1) ^ if p & 0x80 != 0 { 0x1b } else { 0 };
+        q ^= q << 1;
+        q ^= q << 2;
+        q ^= q << 4;
+        if q & 0x80 != 0 {
+            q ^= 0x09;
+        }
+        sbox[p as usize] =
+            q ^ q.rotate_left(1) ^ q.rotate_left(2) ^ q.rotate_left(3) ^ q.rotate_left(4) ^ 0x63;
+        if p == 1 {
+            break sbox;
+        }
+    }
+};
+
+/// SubBytes and MixColumns of one byte per column position, `T[i]` being `T[0]` rotated right
+/// by `8 * i` bits.
+const T: [[u32; 256]; 4] = {
+    let mut t = [[0; 256]; 4];
+    let mut x = 0;
+    while x < 256 {
+        let s = SBOX[x];
+        let s2 = (s << 1) ^ if s & 0x80 != 0 { 0x1b } else { 0 };
+        let word = u32::from_be_bytes([s2, s, s, s2 ^ s]);
+        let mut i = 0;
+        while i < 4 {
+            t[i][x] = word.rotate_right(8 * i as u32);
+            i += 1;
+        }
+        x += 1;
+    }
+    t
+};
+
+const ROUNDS: usize = 10;
+/// Round keys as big-endian column words.
+type RoundKeys = [[u32; 4]; ROUNDS + 1];
+
+fn expand_key(key: &[u8; 16]) -> RoundKeys {
+    let mut words = [0u32; 4 * (ROUNDS + 1)];
+    for (i, word) in key.as_chunks::<4>().0.iter().enumerate() {
+        words[i] = u32::from_be_bytes(*word);
+    }
+    let mut rcon = 1u8;
+    for i in 4..words.len() {
+        let mut word = words[i - 1];
+        if i % 4 == 0 {
+            word = u32::from_be_bytes(word.rotate_left(8).to_be_bytes().map(|b| SBOX[b as usize]))
+                ^ (u32::from(rcon) << 24);
+            rcon = (rcon << 1) ^ if rcon & 0x80 != 0 { 0x1b } else { 0 };
+        }
+        words[i] = words[i - 4] ^ word;
+    }
+    array::from_fn(|round| array::from_fn(|c| words[4 * round + c]))
+}
+
+/// One block through the lookup tables: a table lookup per byte and round, the last round
+/// through the S-box alone.
+fn encrypt(keys: &RoundKeys, block: [u8; 16]) -> [u8; 16] {
+    let mut s: [u32; 4] =
+        array::from_fn(|c| u32::from_be_bytes(block[4 * c..4 * c + 4].try_into().unwrap()));
+    for (c, k) in s.iter_mut().zip(keys[0]) {
+        *c ^= k;
+    }
+    for key in &keys[1..ROUNDS] {
+        s = array::from_fn(|c| {
+            T[0][(s[c] >> 24) as usize]
+                ^ T[1][(s[(c + 1) % 4] >> 16) as usize & 0xff]
+                ^ T[2][(s[(c + 2) % 4] >> 8) as usize & 0xff]
+                ^ T[3][s[(c + 3) % 4] as usize & 0xff]
+                ^ key[c]
+        });
+    }
+    let mut out = [0; 16];
+    for c in 0..4 {
+        let bytes = [
+            SBOX[(s[c] >> 24) as usize],
+            SBOX[(s[(c + 1) % 4] >> 16) as usize & 0xff],
+            SBOX[(s[(c + 2) % 4] >> 8) as usize & 0xff],
+            SBOX[s[(c + 3) % 4] as usize & 0xff],
+        ];
+        let word = u32::from_be_bytes(bytes) ^ keys[ROUNDS][c];
+        out[4 * c..4 * c + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
@@ -0,0 +1,189 @@
+//! Throughput of loads and stores by alignment: `<load|store>_<bits>_<place>` repeats one
+//! access of `bits` at a `place` in a page-aligned buffer. The places are:
+//!
+//! - `aligned`: the start of a cache line.
+//! - `unaligned`: one byte past it. A 64-byte access there already straddles two lines.
+//! - `split_line`: across the boundary between two cache lines, half on either side.
+//! - `split_page`: across the boundary between two pages, half on either side.
+//!
+//! 64-bit accesses are plain `u64` reads and writes. On x86_64 the 128-, 256- and 512-bit ones
+//! are SSE2, AVX2 and AVX-512 `loadu`/`storeu`, skipped on CPUs without them.
+
+use std::ptr;
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+const LINE: usize = 64;
+const PAGE: usize = 4096;
+/// Accesses per sample, reported per access.
+const OPS: usize = 1024;
+/// Independent `xor` chains that loads fold into, so that they do not limit the loads.
+const LANES: usize = 4;
+
+/// Where an access starts.
+#[derive(Clone, Copy)]
+enum Place {
+    Aligned,
+    Unaligned,
+    SplitLine,
+    SplitPage,
+}
+
+impl Place {
+    /// Offset into a `Buffer` of an access `width` bytes wide.
+    fn offset(self, width: usize) -> usize {
+        match self {
+            Place::Aligned => LINE,
+            Place::Unaligned => LINE + 1,
+            Place::SplitLine => 2 * LINE - width / 2,
+            Place::SplitPage => PAGE - width / 2,
+        }
+    }
+}
+
+#[repr(C, align(4096))]
+struct Buffer([u8; 2 * PAGE]);
+
+/// The exposed address of `place` in a fresh buffer, which must outlive its use.
+fn address(buffer: &mut Buffer, place: Place, width: usize) -> usize {
+    buffer.0[place.offset(width)..]
+        .as_mut_ptr()
+        .expose_provenance()
+}
+
+/// A module of `loads` and `stores` for one access width. Every access takes its address
+/// through `opaque`, so none can be hoisted or merged, and loads fold into `LANES` of `$ty` with
+/// `xor`.
+macro_rules! width {
+    ($module:ident: $ty:ty, $(requires = $feature:tt,)? zero = $zero:expr,
+     load = |$p:ident| $load:expr, store = |$q:ident, $x:ident| $store:expr,
+     xor = |$a:ident, $b:ident| $xor:expr) => {
+        mod $module {
+            use super::*;
+
+            pub fn loads(b: &mut Bencher, place: Place) {
+                $(if !std::is_x86_feature_detected!($feature) {
+                    return b.skip(concat!("no ", $feature, " support"));
+                })?
+                b.set_ops(OPS as u64);
+                let mut buffer = Box::new(Buffer([0; 2 * PAGE]));
+                let address = address(&mut buffer, place, size_of::<$ty>());
+                // Safety: the required feature, if any, was detected above, and every access
+                // lies within `buffer`.
+                b.iter(|| unsafe { loads(address) });
+
+                $(#[target_feature(enable = $feature)])?
+                unsafe fn loads(address: usize) -> [$ty; LANES] {
+                    let mut lanes: [$ty; LANES] = [$zero; LANES];
+                    for _ in 0..OPS / LANES {
+                        for lane in &mut lanes {
+                            let $p = ptr::with_exposed_provenance::<$ty>(opaque(address));
+                            let ($a, $b) = (*lane, unsafe { $load });
+                            *lane = $xor;
+                        }
+                    }
+                    lanes
+                }
+            }
+
+            pub fn stores(b: &mut Bencher, place: Place) {
+                $(if !std::is_x86_feature_detected!($feature) {
+                    return b.skip(concat!("no ", $feature, " support"));
+                })?
+                b.set_ops(OPS as u64);
+                let mut buffer = Box::new(Buffer([0; 2 * PAGE]));
+                let address = address(&mut buffer, place, size_of::<$ty>());
+                // Safety: as above.
+                b.iter(|| unsafe { stores(address) });
+
+                $(#[target_feature(enable = $feature)])?
+                unsafe fn stores(address: usize) {
+                    let $x: $ty = $zero;
+                    for _ in 0..OPS {
+                        let $q = ptr::with_exposed_provenance_mut::<$ty>(opaque(address));
+                        unsafe { $store };
+                    }
+                }
+            }
+        }
+    };
+}
+
+width!(scalar: u64, zero = 0,
+    load = |p| p.read_unaligned(),
+    store = |p, x| p.write_unaligned(x),
+    xor = |a, b| a ^ b);
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+#[cfg(target_arch = "x86_64")]
+width!(sse2: __m128i, zero = _mm_setzero_si128(),
+    load = |p| _mm_loadu_si128(p),
+    store = |p, x| _mm_storeu_si128(p, x),
+    xor = |a, b| _mm_xor_si128(a, b));
+
+#[cfg(target_arch = "x86_64")]
+width!(avx2: __m256i, requires = "avx2", zero = _mm256_setzero_si256(),
+    load = |p| _mm256_loadu_si256(p),
+    store = |p, x| _mm256_storeu_si256(p, x),
+    xor = |a, b| _mm256_xor_si256(a, b));
+
+#[cfg(target_arch = "x86_64")]
+width!(avx512: __m512i, requires = "avx512f", zero = _mm512_setzero_si512(),
+    load = |p| _mm512_loadu_si512(p.cast()),
+    store = |p, x| _mm512_storeu_si512(p.cast(), x),
+    xor = |a, b| _mm512_xor_si512(a, b));
+
+/// `$name` runs `$run` at `$place`.
+macro_rules! alignment_benches {
+    ($($name:ident: $run:path, $place:ident, tags = [$($tag:literal),*];)*) => {$(
+        fn $name(b: &mut Bencher) {
+            $run(b, Place::$place);
+        }
+
+        register_bench!($name, tags = ["memory", "alignment" $(, $tag)*]);
+    )*};
+}
+
+alignment_benches! {
+    load_64_aligned: scalar::loads, Aligned, tags = [];
+    load_64_unaligned: scalar::loads, Unaligned, tags = [];
+    load_64_split_line: scalar::loads, SplitLine, tags = [];
+    load_64_split_page: scalar::loads, SplitPage, tags = [];
+    store_64_aligned: scalar::stores, Aligned, tags = [];
+    store_64_unaligned: scalar::stores, Unaligned, tags = [];
+    store_64_split_line: scalar::stores, SplitLine, tags = [];
+    store_64_split_page: scalar::stores, SplitPage, tags = [];
+}
+
+#[cfg(target_arch = "x86_64")]
+alignment_benches! {
+    load_128_aligned: sse2::loads, Aligned, tags = ["sse2"];
+    load_128_unaligned: sse2::loads, Unaligned, tags = ["sse2"];
+    load_128_split_line: sse2::loads, SplitLine, tags = ["sse2"];
+    load_128_split_page: sse2::loads, SplitPage, tags = ["sse2"];
+    store_128_aligned: sse2::stores, Aligned, tags = ["sse2"];
+    store_128_unaligned: sse2::stores, Unaligned, tags = ["sse2"];
+    store_128_split_line: sse2::stores, SplitLine, tags = ["sse2"];
+    store_128_split_page: sse2::stores, SplitPage, tags = ["sse2"];
+    load_256_aligned: avx2::loads, Aligned, tags = ["avx2"];
+    load_256_unaligned: avx2::loads, Unaligned, tags = ["avx2"];
+    load_256_split_line: avx2::loads, SplitLine, tags = ["avx2"];
+    load_256_split_page: avx2::loads, SplitPage, tags = ["avx2"];
+    store_256_aligned: avx2::stores, Aligned, tags = ["avx2"];
+    store_256_unaligned: avx2::stores, Unaligned, tags = ["avx2"];
+    store_256_split_line: avx2::stores, SplitLine, tags = ["avx2"];
+    store_256_split_page: avx2::stores, SplitPage, tags = ["avx2"];
+    load_512_aligned: avx512::loads, Aligned, tags = ["avx512f"];
+    load_512_unaligned: avx512::loads, Unaligned, tags = ["avx512f"];
+    load_512_split_line: avx512::loads, SplitLine, tags = ["avx512f"];
+    load_512_split_page: avx512::loads, SplitPage, tags = ["avx512f"];
+    store_512_aligned: avx512::stores, Aligned, tags = ["avx512f"];
+    store_512_unaligned: avx512::stores, Unaligned, tags = ["avx512f"];
+    store_512_split_line: avx512::stores, SplitLine, tags = ["avx512f"];
+    store_512_split_page: avx512::stores, SplitPage, tags = ["avx512f"];
+}
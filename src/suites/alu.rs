@@ -0,0 +1,75 @@
+//! Integer ALU latency and throughput, to check against vendor instruction tables. Each sample
+//! is `OPS` operations, reported per operation as well; the loop around them costs an add and a
+//! predicted branch per lane round, and division adds Rust's predicted divide-by-zero check.
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+const OPS: usize = 1024;
+
+/// Times `$op` on `$ty` twice: as a single dependent chain in which every operation waits for
+/// the previous result (`$latency`), and as `$lanes` interleaved chains the core can overlap
+/// (`$throughput`). `$x` is the chained value and `$y` a constant operand; divisions keep the
+/// quotient from collapsing by dividing a constant by the chain, which converges on its root.
+macro_rules! alu_benches {
+    ($($latency:ident, $throughput:ident: $ty:ty, lanes = $lanes:literal,
+       x = $x0:expr, y = $y0:expr, |$x:ident, $y:ident| $op:expr;)*) => {$(
+        fn $latency(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            let $y: $ty = opaque($y0);
+            b.iter(|| {
+                let mut $x: $ty = opaque($x0);
+                for _ in 0..OPS {
+                    $x = opaque($op);
+                }
+                $x
+            });
+        }
+
+        fn $throughput(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            let $y: $ty = opaque($y0);
+            b.iter(|| {
+                let x: $ty = $x0;
+                let mut lanes = [x; $lanes].map(opaque);
+                for _ in 0..OPS / $lanes {
+                    for lane in &mut lanes {
+                        let $x = *lane;
+                        *lane = opaque($op);
+                    }
+                }
+                lanes
+            });
+        }
+
+        register_bench!($latency, tags = ["alu", "latency"]);
+        register_bench!($throughput, tags = ["alu", "throughput"]);
+    )*};
+}
+
+alu_benches! {
+    add_u32_latency, add_u32_throughput: u32, lanes = 8, x = 1, y = 3, |x, y| x.wrapping_add(y);
+    add_u64_latency, add_u64_throughput: u64, lanes = 8, x = 1, y = 3, |x, y| x.wrapping_add(y);
+    add_u128_latency, add_u128_throughput: u128, lanes = 4, x = 1, y = 3, |x, y| x.wrapping_add(y);
+    sub_u32_latency, sub_u32_throughput: u32, lanes = 8, x = 1, y = 3, |x, y| x.wrapping_sub(y);
+    sub_u64_latency, sub_u64_throughput: u64, lanes = 8, x = 1, y = 3, |x, y| x.wrapping_sub(y);
+    sub_u128_latency, sub_u128_throughput: u128, lanes = 4, x = 1, y = 3, |x, y| x.wrapping_sub(y);
+    mul_u32_latency, mul_u32_throughput: u32, lanes = 8, x = 3, y = 0x9e37_79b9,
+        |x, y| x.wrapping_mul(y);
+    mul_u64_latency, mul_u64_throughput: u64, lanes = 8, x = 3, y = 0x9e37_79b9_7f4a_7c15,
+        |x, y| x.wrapping_mul(y);
+    mul_u128_latency, mul_u128_throughput: u128, lanes = 4, x = 3,
+        y = 0x9e37_79b9_7f4a_7c15_f39c_c060_5ced_c835, |x, y| x.wrapping_mul(y);
+    div_u32_latency, div_u32_throughput: u32, lanes = 8, x = 1 << 16, y = u32::MAX, |x, y| y / x;
+    div_u64_latency, div_u64_throughput: u64, lanes = 8, x = 1 << 32, y = u64::MAX, |x, y| y / x;
+    div_u128_latency, div_u128_throughput: u128, lanes = 4, x = 1 << 64, y = u128::MAX,
+        |x, y| y / x;
+    idiv_i32_latency, idiv_i32_throughput: i32, lanes = 8, x = 46_341, y = i32::MIN + 1,
+        |x, y| y / x;
+    idiv_i64_latency, idiv_i64_throughput: i64, lanes = 8, x = 3_037_000_500, y = i64::MIN + 1,
+        |x, y| y / x;
+    idiv_i128_latency, idiv_i128_throughput: i128, lanes = 4, x = 1 << 63, y = i128::MIN + 1,
+        |x, y| y / x;
+}
@@ -0,0 +1,183 @@
+//! Atomic read-modify-write cost by contention: `AtomicU64::fetch_add` and a single
+//! `compare_exchange` (success or not, its expected value refreshed from the last result) on a
+//! counter that nothing else touches (`*_uncontended`), or that a second thread hammers with the
+//! same operation from another core in the same L3 domain (`*_same_l3`, a core complex on AMD)
+//! or in another socket (`*_cross_socket`). The measuring thread stays where the runner pinned
+//! it; the other one is pinned to the first core placed as required that shares no L2 with it,
+//! and the benchmark is skipped where there is none or the topology is unknown. Reported per
+//! operation.
+//!
+//! `<op>_<ordering>` runs loads, stores and `fetch_add`s on an uncontended counter in otherwise
+//! identical loops under each ordering the operation accepts, which shows the ones that are free:
+//! on x86 only the `SeqCst` store (an `xchg`) costs more than a relaxed one, and every RMW is a
+//! locked instruction whatever its ordering; on AArch64 acquire and release pick other
+//! instructions.
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread,
+};
+
+use super::partner::spawn_pinned;
+use crate::{
+    bench::{opaque, Bencher},
+    os, register_bench,
+};
+
+const OPS: usize = 256;
+
+/// A counter alone on its cache line (and the adjacent-line prefetcher's pair).
+#[repr(align(128))]
+struct Counter(AtomicU64);
+
+/// Where the contending thread runs.
+#[derive(Clone, Copy)]
+enum Placement {
+    Uncontended,
+    SameL3,
+    CrossSocket,
+}
+
+impl Placement {
+    /// The core for the contending thread, `Ok(None)` for none, or why there is none.
+    fn partner(self) -> Result<Option<usize>, &'static str> {
+        if let Placement::Uncontended = self {
+            return Ok(None);
+        }
+        let cpu = os::current_cpu().ok_or("current cpu unknown")?;
+        let (candidates, missing) = match self {
+            Placement::SameL3 => (
+                os::l3_shared_cpus(cpu),
+                "no other core sharing the L3 cache",
+            ),
+            _ => {
+                let package = os::package_cpus(cpu);
+                if package.is_empty() {
+                    return Err("socket topology unknown");
+                }
+                let others = (0..os::cpu_limits().online)
+                    .filter(|other| !package.contains(other))
+                    .collect::<Vec<_>>();
+                (others, "no other socket")
+            }
+        };
+        let l2 = os::l2_shared_cpus(cpu);
+        candidates
+            .into_iter()
+            .find(|&other| other != cpu && !l2.contains(&other))
+            .map(Some)
+            .ok_or(missing)
+    }
+}
+
+/// Runs `op` `OPS` times per sample on a counter the partner of `placement`, if any, runs `op`
+/// on as well until the benchmark ends.
+fn contended(b: &mut Bencher, placement: Placement, op: fn(&AtomicU64, &mut u64)) {
+    let partner = match placement.partner() {
+        Ok(partner) => partner,
+        Err(reason) => return b.skip(reason),
+    };
+    b.set_ops(OPS as u64);
+    let counter = &Counter(AtomicU64::new(0));
+    let stop = &AtomicBool::new(false);
+    thread::scope(|scope| {
+        if let Some(cpu) = partner {
+            let spawned = spawn_pinned(scope, cpu, move || {
+                let mut expected = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    op(&counter.0, &mut expected);
+                }
+            });
+            if let Err(reason) = spawned {
+                return b.skip(reason);
+            }
+        }
+        b.iter(|| {
+            let mut expected = 0;
+            for _ in 0..OPS {
+                op(&counter.0, &mut expected);
+            }
+            expected
+        });
+        stop.store(true, Ordering::Relaxed);
+    });
+}
+
+fn fetch_add(counter: &AtomicU64, last: &mut u64) {
+    *last = counter.fetch_add(1, Ordering::Relaxed);
+}
+
+fn compare_exchange(counter: &AtomicU64, expected: &mut u64) {
+    *expected = match counter.compare_exchange(
+        *expected,
+        *expected + 1,
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+    ) {
+        Ok(previous) => previous + 1,
+        Err(current) => current,
+    };
+}
+
+/// `$name` runs `$op` with `$placement`.
+macro_rules! atomic_benches {
+    ($($name:ident: $op:ident, $placement:ident, tags = [$($tag:literal),*];)*) => {$(
+        fn $name(b: &mut Bencher) {
+            contended(b, Placement::$placement, $op);
+        }
+
+        register_bench!($name, tags = ["atomic" $(, $tag)*]);
+    )*};
+}
+
+atomic_benches! {
+    fetch_add_uncontended: fetch_add, Uncontended, tags = [];
+    fetch_add_same_l3: fetch_add, SameL3, tags = ["multi-threaded"];
+    fetch_add_cross_socket: fetch_add, CrossSocket, tags = ["multi-threaded"];
+    compare_exchange_uncontended: compare_exchange, Uncontended, tags = [];
+    compare_exchange_same_l3: compare_exchange, SameL3, tags = ["multi-threaded"];
+    compare_exchange_cross_socket: compare_exchange, CrossSocket, tags = ["multi-threaded"];
+}
+
+/// `$name` runs `OPS` of `$op` on an uncontended counter with `Ordering::$ordering`.
+macro_rules! ordering_benches {
+    ($($name:ident: $ordering:ident, |$counter:ident, $o:ident, $i:ident| $op:expr;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            let counter = Counter(AtomicU64::new(0));
+            b.iter(|| {
+                let ($counter, $o) = (&counter.0, Ordering::$ordering);
+                let mut sum = 0u64;
+                for $i in 0..OPS as u64 {
+                    sum = sum.wrapping_add(opaque($op));
+                }
+                sum
+            });
+        }
+
+        register_bench!($name, tags = ["atomic", "ordering"]);
+    )*};
+}
+
+ordering_benches! {
+    load_relaxed: Relaxed, |c, o, _i| c.load(o);
+    load_acquire: Acquire, |c, o, _i| c.load(o);
+    load_seq_cst: SeqCst, |c, o, _i| c.load(o);
+    store_relaxed: Relaxed, |c, o, i| {
+        c.store(i, o);
+        i
+    };
+    store_release: Release, |c, o, i| {
+        c.store(i, o);
+        i
+    };
+    store_seq_cst: SeqCst, |c, o, i| {
+        c.store(i, o);
+        i
+    };
+    fetch_add_relaxed: Relaxed, |c, o, _i| c.fetch_add(1, o);
+    fetch_add_acquire: Acquire, |c, o, _i| c.fetch_add(1, o);
+    fetch_add_release: Release, |c, o, _i| c.fetch_add(1, o);
+    fetch_add_acq_rel: AcqRel, |c, o, _i| c.fetch_add(1, o);
+    fetch_add_seq_cst: SeqCst, |c, o, _i| c.fetch_add(1, o);
+}
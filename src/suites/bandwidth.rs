@@ -0,0 +1,208 @@
+//! STREAM-style sequential bandwidth: read, write, copy and triad (`a = b + s * c`) over
+//! buffers from 32 KiB, within L1, to 256 MiB, far out of cache, plus the largest size split
+//! across threads. Copy counts the bytes read and written, triad all three arrays. On x86_64
+//! `write_nt_*` writes with non-temporal stores, which bypass the cache and skip the read for
+//! ownership a normal store makes first.
+
+use std::{hint::black_box, thread};
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+/// One cache line of doubles; aligned for the 32-byte non-temporal stores.
+#[derive(Clone, Copy)]
+#[repr(C, align(64))]
+struct Line([f64; 8]);
+
+/// Bytes moved per sample budgeted to each benchmark, so the large sizes take tens of samples
+/// rather than the default count.
+const BUDGET: u64 = 16 << 30;
+/// The triad's scalar.
+const SCALAR: f64 = 3.0;
+
+pub(crate) struct Kernel {
+    /// Arrays of the buffer size it walks, all of whose bytes count.
+    arrays: usize,
+    run: fn(&mut [Vec<Line>]) -> f64,
+    /// Why it cannot run here, if it needs a CPU feature.
+    unsupported: fn() -> Option<&'static str>,
+}
+
+pub(crate) const READ: Kernel = Kernel {
+    arrays: 1,
+    run: read,
+    unsupported: || None,
+};
+const WRITE: Kernel = Kernel {
+    arrays: 1,
+    run: write,
+    unsupported: || None,
+};
+const COPY: Kernel = Kernel {
+    arrays: 2,
+    run: copy,
+    unsupported: || None,
+};
+const TRIAD: Kernel = Kernel {
+    arrays: 3,
+    run: triad,
+    unsupported: || None,
+};
+#[cfg(target_arch = "x86_64")]
+const WRITE_NT: Kernel = Kernel {
+    arrays: 1,
+    run: nt::write,
+    unsupported: || (!is_x86_feature_detected!("avx")).then_some("no avx support"),
+};
+
+/// Sums with one accumulator per lane, so the adds vectorize instead of forming one chain.
+fn read(arrays: &mut [Vec<Line>]) -> f64 {
+    let mut sums = [0.0; 8];
+    for line in &arrays[0] {
+        for (sum, x) in sums.iter_mut().zip(line.0) {
+            *sum += x;
+        }
+    }
+    sums.iter().sum()
+}
+
+fn write(arrays: &mut [Vec<Line>]) -> f64 {
+    let x = opaque(1.0);
+    arrays[0].fill(Line([x; 8]));
+    x
+}
+
+fn copy(arrays: &mut [Vec<Line>]) -> f64 {
+    let [a, b] = arrays else { unreachable!() };
+    a.copy_from_slice(b);
+    a[0].0[0]
+}
+
+fn triad(arrays: &mut [Vec<Line>]) -> f64 {
+    let [a, b, c] = arrays else { unreachable!() };
+    let s = opaque(SCALAR);
+    for ((a, b), c) in a.iter_mut().zip(b.iter()).zip(c.iter()) {
+        for i in 0..8 {
+            a.0[i] = b.0[i] + s * c.0[i];
+        }
+    }
+    a[0].0[0]
+}
+
+#[cfg(target_arch = "x86_64")]
+mod nt {
+    use std::arch::x86_64::{__m256i, _mm256_set1_epi64x, _mm256_stream_si256, _mm_sfence};
+
+    use super::Line;
+
+    pub fn write(arrays: &mut [Vec<Line>]) -> f64 {
+        // Safety: `WRITE_NT` only runs where avx was detected.
+        unsafe { stream(&mut arrays[0]) };
+        0.0
+    }
+
+    #[target_feature(enable = "avx")]
+    unsafe fn stream(lines: &mut [Line]) {
+        let x = _mm256_set1_epi64x(crate::bench::opaque(1.0f64).to_bits() as i64);
+        for line in lines {
+            let line = (line as *mut Line).cast::<__m256i>();
+            // Safety: a line is 64-byte aligned and holds two vectors.
+            unsafe {
+                _mm256_stream_si256(line, x);
+                _mm256_stream_si256(line.add(1), x);
+            }
+        }
+        // Orders the weakly-ordered stores before the sample ends.
+        _mm_sfence();
+    }
+}
+
+/// Runs `kernel` over `bytes`-sized arrays, split evenly between `threads` threads, each with
+/// arrays of its own. Threads are spawned per sample, which only the largest size amortizes.
+pub(crate) fn run(b: &mut Bencher, kernel: &Kernel, bytes: usize, threads: usize) {
+    if let Some(reason) = (kernel.unsupported)() {
+        return b.skip(reason);
+    }
+    let moved = (bytes * kernel.arrays) as u64;
+    b.set_bytes(moved);
+    b.limit_iterations((BUDGET / moved).max(10));
+    let lines = bytes / threads / size_of::<Line>();
+    let mut sets = (0..threads)
+        .map(|_| vec![vec![Line([1.0; 8]); lines]; kernel.arrays])
+        .collect::<Vec<_>>();
+    b.iter_custom(|timer| {
+        let start = timer.now();
+        match sets.as_mut_slice() {
+            [arrays] => {
+                black_box((kernel.run)(arrays));
+            }
+            sets => thread::scope(|scope| {
+                for arrays in sets {
+                    scope.spawn(|| black_box((kernel.run)(arrays)));
+                }
+            }),
+        }
+        timer.now() - start
+    });
+}
+
+/// `<kernel>_<size>` and `<kernel>_<size>_threads_<n>`: `$kernel` over `$bytes` on `$threads`.
+macro_rules! bandwidth_benches {
+    ($($name:ident: $kernel:expr, $bytes:expr, threads = $threads:literal,
+       tags = [$($tag:literal),*];)*) => {$(
+        fn $name(b: &mut Bencher) {
+            run(b, &$kernel, $bytes, $threads);
+        }
+
+        register_bench!($name, tags = ["memory", "bandwidth" $(, $tag)*]);
+    )*};
+}
+
+bandwidth_benches! {
+    read_32k: READ, 32 << 10, threads = 1, tags = [];
+    read_256k: READ, 256 << 10, threads = 1, tags = [];
+    read_4m: READ, 4 << 20, threads = 1, tags = [];
+    read_32m: READ, 32 << 20, threads = 1, tags = [];
+    read_256m: READ, 256 << 20, threads = 1, tags = ["slow"];
+    read_256m_threads_2: READ, 256 << 20, threads = 2, tags = ["slow", "multi-threaded"];
+    read_256m_threads_4: READ, 256 << 20, threads = 4, tags = ["slow", "multi-threaded"];
+    read_256m_threads_8: READ, 256 << 20, threads = 8, tags = ["slow", "multi-threaded"];
+    write_32k: WRITE, 32 << 10, threads = 1, tags = [];
+    write_256k: WRITE, 256 << 10, threads = 1, tags = [];
+    write_4m: WRITE, 4 << 20, threads = 1, tags = [];
+    write_32m: WRITE, 32 << 20, threads = 1, tags = [];
+    write_256m: WRITE, 256 << 20, threads = 1, tags = ["slow"];
+    write_256m_threads_2: WRITE, 256 << 20, threads = 2, tags = ["slow", "multi-threaded"];
+    write_256m_threads_4: WRITE, 256 << 20, threads = 4, tags = ["slow", "multi-threaded"];
+    write_256m_threads_8: WRITE, 256 << 20, threads = 8, tags = ["slow", "multi-threaded"];
+    copy_32k: COPY, 32 << 10, threads = 1, tags = [];
+    copy_256k: COPY, 256 << 10, threads = 1, tags = [];
+    copy_4m: COPY, 4 << 20, threads = 1, tags = [];
+    copy_32m: COPY, 32 << 20, threads = 1, tags = [];
+    copy_256m: COPY, 256 << 20, threads = 1, tags = ["slow"];
+    copy_256m_threads_2: COPY, 256 << 20, threads = 2, tags = ["slow", "multi-threaded"];
+    copy_256m_threads_4: COPY, 256 << 20, threads = 4, tags = ["slow", "multi-threaded"];
+    copy_256m_threads_8: COPY, 256 << 20, threads = 8, tags = ["slow", "multi-threaded"];
+    triad_32k: TRIAD, 32 << 10, threads = 1, tags = [];
+    triad_256k: TRIAD, 256 << 10, threads = 1, tags = [];
+    triad_4m: TRIAD, 4 << 20, threads = 1, tags = [];
+    triad_32m: TRIAD, 32 << 20, threads = 1, tags = [];
+    triad_256m: TRIAD, 256 << 20, threads = 1, tags = ["slow"];
+    triad_256m_threads_2: TRIAD, 256 << 20, threads = 2, tags = ["slow", "multi-threaded"];
+    triad_256m_threads_4: TRIAD, 256 << 20, threads = 4, tags = ["slow", "multi-threaded"];
+    triad_256m_threads_8: TRIAD, 256 << 20, threads = 8, tags = ["slow", "multi-threaded"];
+}
+
+#[cfg(target_arch = "x86_64")]
+bandwidth_benches! {
+    write_nt_32k: WRITE_NT, 32 << 10, threads = 1, tags = ["avx"];
+    write_nt_256k: WRITE_NT, 256 << 10, threads = 1, tags = ["avx"];
+    write_nt_4m: WRITE_NT, 4 << 20, threads = 1, tags = ["avx"];
+    write_nt_32m: WRITE_NT, 32 << 20, threads = 1, tags = ["avx"];
+    write_nt_256m: WRITE_NT, 256 << 20, threads = 1, tags = ["avx", "slow"];
+    write_nt_256m_threads_2: WRITE_NT, 256 << 20, threads = 2, tags = ["avx", "slow", "multi-threaded"];
+    write_nt_256m_threads_4: WRITE_NT, 256 << 20, threads = 4, tags = ["avx", "slow", "multi-threaded"];
+    write_nt_256m_threads_8: WRITE_NT, 256 << 20, threads = 8, tags = ["avx", "slow", "multi-threaded"];
+}
@@ -0,0 +1,163 @@
+//! Bit manipulation latency and throughput, laid out like the `alu` suite: population count,
+//! leading and trailing zero counts, and bit deposit/extract, each as the x86_64 instruction
+//! (`popcnt`, `lzcnt`, `tzcnt` from BMI1, `pdep` and `pext` from BMI2) and as the portable
+//! fallback a program without it would run. `pdep` and `pext` are microcoded on AMD before Zen 3,
+//! taking a cycle or more per set mask bit, so there the fallback can come close.
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+const OPS: usize = 1024;
+const LANES: usize = 8;
+/// The deposit/extract mask, half of its bits set.
+const MASK: u64 = 0x5555_5555_5555_5555;
+
+/// Times `$op` on `u64` as one dependent chain (`$latency`) and as `LANES` interleaved chains
+/// (`$throughput`), like `fp_benches!`, skipping the instruction where `requires` is missing.
+macro_rules! bits_benches {
+    ($($latency:ident, $throughput:ident: $(requires = $feature:tt,)? x = $x0:expr,
+       |$x:ident, $y:ident| $op:expr;)*) => {$(
+        fn $latency(b: &mut Bencher) {
+            $(if !std::is_x86_feature_detected!($feature) {
+                return b.skip(concat!("no ", $feature, " support"));
+            })?
+            b.set_ops(OPS as u64);
+            let $y = opaque(MASK);
+            // Safety: the required feature, if any, was detected above.
+            b.iter(|| unsafe { chain($y) });
+
+            $(#[target_feature(enable = $feature)])?
+            unsafe fn chain($y: u64) -> u64 {
+                let mut $x: u64 = opaque($x0);
+                for _ in 0..OPS {
+                    $x = opaque($op);
+                }
+                $x
+            }
+        }
+
+        fn $throughput(b: &mut Bencher) {
+            $(if !std::is_x86_feature_detected!($feature) {
+                return b.skip(concat!("no ", $feature, " support"));
+            })?
+            b.set_ops(OPS as u64);
+            let $y = opaque(MASK);
+            // Safety: as above.
+            b.iter(|| unsafe { lanes($y) });
+
+            $(#[target_feature(enable = $feature)])?
+            unsafe fn lanes($y: u64) -> [u64; LANES] {
+                let x: u64 = $x0;
+                let mut lanes = [x; LANES].map(opaque);
+                for _ in 0..OPS / LANES {
+                    for lane in &mut lanes {
+                        let $x = *lane;
+                        *lane = opaque($op);
+                    }
+                }
+                lanes
+            }
+        }
+
+        register_bench!($latency, tags = ["bits", "latency" $(, $feature)?]);
+        register_bench!($throughput, tags = ["bits", "throughput" $(, $feature)?]);
+    )*};
+}
+
+bits_benches! {
+    popcnt_software_latency, popcnt_software_throughput: x = u64::MAX, |x, _y| popcount(x);
+    lzcnt_software_latency, lzcnt_software_throughput: x = 1, |x, _y| leading_zeros(x);
+    tzcnt_software_latency, tzcnt_software_throughput: x = 1 << 63, |x, _y| trailing_zeros(x);
+    pdep_software_latency, pdep_software_throughput: x = u64::MAX, |x, y| deposit(x, y);
+    pext_software_latency, pext_software_throughput: x = u64::MAX, |x, y| extract(x, y);
+}
+
+// With the feature enabled the standard methods compile to the single instruction.
+#[cfg(target_arch = "x86_64")]
+bits_benches! {
+    popcnt_latency, popcnt_throughput: requires = "popcnt", x = u64::MAX,
+        |x, _y| x.count_ones() as u64;
+    lzcnt_latency, lzcnt_throughput: requires = "lzcnt", x = 1, |x, _y| x.leading_zeros() as u64;
+    tzcnt_latency, tzcnt_throughput: requires = "bmi1", x = 1 << 63,
+        |x, _y| x.trailing_zeros() as u64;
+    pdep_latency, pdep_throughput: requires = "bmi2", x = u64::MAX,
+        |x, y| std::arch::x86_64::_pdep_u64(x, y);
+    pext_latency, pext_throughput: requires = "bmi2", x = u64::MAX,
+        |x, y| std::arch::x86_64::_pext_u64(x, y);
+}
+
+/// Sums bit counts in ever wider fields (the SWAR population count).
+#[inline(always)]
+fn popcount(x: u64) -> u64 {
+    let x = x - ((x >> 1) & 0x5555_5555_5555_5555);
+    let x = (x & 0x3333_3333_3333_3333) + ((x >> 2) & 0x3333_3333_3333_3333);
+    let x = (x + (x >> 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x.wrapping_mul(0x0101_0101_0101_0101) >> 56
+}
+
+/// Binary search for the highest set bit, 64 for zero.
+#[inline(always)]
+fn leading_zeros(mut x: u64) -> u64 {
+    if x == 0 {
+        return 64;
+    }
+    let mut n = 0;
+    for shift in [32, 16, 8, 4, 2, 1] {
+        if x >> (64 - shift) == 0 {
+            n += shift;
+            x <<= shift;
+        }
+    }
+    n
+}
+
+/// Looks up the isolated lowest set bit by its de Bruijn product, 64 for zero. LLVM recognizes
+/// the table and emits the target's own count instead (`bsf` on x86_64 without BMI1), so this
+/// measures what such code actually runs.
+#[inline(always)]
+fn trailing_zeros(x: u64) -> u64 {
+    const DE_BRUIJN: u64 = 0x03f7_9d71_b4cb_0a89;
+    const INDEX: [u8; 64] = {
+        let mut index = [0; 64];
+        let mut bit = 0;
+        while bit < 64 {
+            index[((DE_BRUIJN << bit) >> 58) as usize] = bit as u8;
+            bit += 1;
+        }
+        index
+    };
+    if x == 0 {
+        return 64;
+    }
+    INDEX[(x.isolate_lowest_one().wrapping_mul(DE_BRUIJN) >> 58) as usize] as u64
+}
+
+/// Scatters the low bits of `x` to the set bits of `mask`, lowest first, like `pdep`.
+#[inline(always)]
+fn deposit(x: u64, mut mask: u64) -> u64 {
+    let mut result = 0;
+    let mut bit = 0;
+    while mask != 0 {
+        let lowest = mask.isolate_lowest_one();
+        result |= lowest * ((x >> bit) & 1);
+        mask ^= lowest;
+        bit += 1;
+    }
+    result
+}
+
+/// Gathers the bits of `x` under the set bits of `mask` into the low bits, like `pext`.
+#[inline(always)]
+fn extract(x: u64, mut mask: u64) -> u64 {
+    let mut result = 0;
+    let mut bit = 0;
+    while mask != 0 {
+        let lowest = mask.isolate_lowest_one();
+        result |= u64::from(x & lowest != 0) << bit;
+        mask ^= lowest;
+        bit += 1;
+    }
+    result
+}
@@ -0,0 +1,130 @@
+//! Bounds checks in tight loops over two 256 KiB arrays of `u32`s. `dot_<form>` is a dot
+//! product of two slices whose lengths the compiler cannot relate, so indexing both has to
+//! check the second every iteration unless the compiler versions the loop on the lengths, which
+//! LLVM usually does. Reslicing the second to the first's length (`dot_reslice`) or asserting
+//! the lengths up front (`dot_assert`) make that explicit; iterators, `as_chunks` and
+//! `get_unchecked` avoid the question. `gather_<form>` sums the first array at indices read from
+//! the second, which nothing proves in bounds: indexing checks every load, masking the index to
+//! the power-of-two length makes the check redundant, and `get_unchecked` trusts the indices.
+//! Reported per element.
+
+use std::hint::black_box;
+
+use crate::{
+    bench::{random_u64s, Bencher},
+    register_bench,
+};
+
+const SEED: u64 = 0x5eed;
+/// Elements per array, a power of two for `gather_mask`.
+const ELEMENTS: usize = 64 << 10;
+
+mod dot {
+    pub fn index(a: &[u32], b: &[u32]) -> u32 {
+        let mut sum = 0u32;
+        for i in 0..a.len() {
+            sum = sum.wrapping_add(a[i].wrapping_mul(b[i]));
+        }
+        sum
+    }
+
+    pub fn reslice(a: &[u32], b: &[u32]) -> u32 {
+        let b = &b[..a.len()];
+        let mut sum = 0u32;
+        for i in 0..a.len() {
+            sum = sum.wrapping_add(a[i].wrapping_mul(b[i]));
+        }
+        sum
+    }
+
+    pub fn assert(a: &[u32], b: &[u32]) -> u32 {
+        assert!(b.len() >= a.len());
+        let mut sum = 0u32;
+        for i in 0..a.len() {
+            sum = sum.wrapping_add(a[i].wrapping_mul(b[i]));
+        }
+        sum
+    }
+
+    pub fn iter(a: &[u32], b: &[u32]) -> u32 {
+        a.iter()
+            .zip(b)
+            .fold(0, |sum, (&x, &y)| sum.wrapping_add(x.wrapping_mul(y)))
+    }
+
+    pub fn chunks(a: &[u32], b: &[u32]) -> u32 {
+        let mut sums = [0u32; 8];
+        let (a_chunks, a_rest) = a.as_chunks::<8>();
+        let (b_chunks, _) = b.as_chunks::<8>();
+        for (x, y) in a_chunks.iter().zip(b_chunks) {
+            for i in 0..8 {
+                sums[i] = sums[i].wrapping_add(x[i].wrapping_mul(y[i]));
+            }
+        }
+        let rest = iter(a_rest, &b[a.len() - a_rest.len()..]);
+        sums.into_iter().fold(rest, u32::wrapping_add)
+    }
+
+    pub fn unchecked(a: &[u32], b: &[u32]) -> u32 {
+        assert!(b.len() >= a.len());
+        let mut sum = 0u32;
+        for i in 0..a.len() {
+            // Safety: `i` is below both lengths.
+            let (x, y) = unsafe { (*a.get_unchecked(i), *b.get_unchecked(i)) };
+            sum = sum.wrapping_add(x.wrapping_mul(y));
+        }
+        sum
+    }
+}
+
+mod gather {
+    pub fn index(data: &[u32], indices: &[u32]) -> u32 {
+        indices
+            .iter()
+            .fold(0, |sum, &i| sum.wrapping_add(data[i as usize]))
+    }
+
+    pub fn mask(data: &[u32], indices: &[u32]) -> u32 {
+        assert!(data.len().is_power_of_two());
+        let mask = data.len() - 1;
+        indices
+            .iter()
+            .fold(0, |sum, &i| sum.wrapping_add(data[i as usize & mask]))
+    }
+
+    pub fn unchecked(data: &[u32], indices: &[u32]) -> u32 {
+        indices.iter().fold(0, |sum, &i| {
+            // Safety: every index was drawn below `ELEMENTS`, the length of `data`.
+            sum.wrapping_add(unsafe { *data.get_unchecked(i as usize) })
+        })
+    }
+}
+
+/// `$name` runs `$kernel` on the two arrays, the second holding random indices into the first.
+macro_rules! bounds_benches {
+    ($($name:ident: $kernel:path;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            b.set_ops(ELEMENTS as u64);
+            let data = (0..ELEMENTS as u32).collect::<Vec<_>>();
+            let indices = random_u64s(SEED, ELEMENTS)
+                .into_iter()
+                .map(|r| (r % ELEMENTS as u64) as u32)
+                .collect::<Vec<_>>();
+            b.iter(|| $kernel(black_box(&data), black_box(&indices)));
+        }
+
+        register_bench!($name, tags = ["bounds"]);
+    )*};
+}
+
+bounds_benches! {
+    dot_index: dot::index;
+    dot_reslice: dot::reslice;
+    dot_assert: dot::assert;
+    dot_iter: dot::iter;
+    dot_chunks: dot::chunks;
+    dot_unchecked: dot::unchecked;
+    gather_index: gather::index;
+    gather_mask: gather::mask;
+    gather_unchecked: gather::unchecked;
+}
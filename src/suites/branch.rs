@@ -0,0 +1,172 @@
+//! Conditional branch cost by predictability: the same loop over data whose branch outcomes
+//! are sorted, repeat a random pattern of some length, or are random. Each sample runs
+//! `LEN` branches, reported per branch; with `--counters` the branch misses per branch show how
+//! much of the difference is misprediction. Predictors learn even random sequences of a few
+//! thousand branches when they recur, so random outcomes come from `RANDOM_LEN` values that
+//! every sample walks a new window of.
+//!
+//! `select_<how>_<input>` picks one of two values by the same condition, on sorted or random
+//! outcomes: with a branch, with a conditional move (`select_unpredictable`), or with a mask
+//! built from the condition. The branchless forms cost the same on either input, and win once
+//! enough branches miss.
+
+use crate::{
+    bench::{opaque, shuffle, Bencher},
+    register_bench,
+};
+
+const LEN: usize = 4096;
+const RANDOM_LEN: usize = 1 << 20;
+const SEED: u64 = 0x5eed;
+
+/// Counts the values at or above the midpoint in the next `LEN` of `data`. The increment goes
+/// through `opaque`, which the compiler may not speculate, so the branch is neither turned into
+/// a conditional move nor vectorized away.
+fn branches(b: &mut Bencher, data: Vec<u8>) {
+    b.set_ops(LEN as u64);
+    let mut windows = data.as_chunks::<LEN>().0.iter().cycle();
+    b.iter(|| {
+        let mut taken = 0u64;
+        for &x in windows.next().unwrap() {
+            if x >= 128 {
+                taken = opaque(taken + 1);
+            }
+        }
+        taken
+    });
+}
+
+/// Sums `select(x >= 128)` over the next `LEN` of `data`.
+fn selects(b: &mut Bencher, data: Vec<u8>, select: impl Fn(bool) -> u64) {
+    b.set_ops(LEN as u64);
+    let mut windows = data.as_chunks::<LEN>().0.iter().cycle();
+    b.iter(|| {
+        let mut sum = 0u64;
+        for &x in windows.next().unwrap() {
+            sum = sum.wrapping_add(select(x >= 128));
+        }
+        sum
+    });
+}
+
+/// A branch: the compiler may not speculate `opaque`, so it cannot select instead.
+fn select_branch(b: &mut Bencher, data: Vec<u8>) {
+    let (x, y) = (opaque(1u64), opaque(3u64));
+    selects(b, data, |taken| if taken { opaque(x) } else { y });
+}
+
+fn select_cmov(b: &mut Bencher, data: Vec<u8>) {
+    let (x, y) = (opaque(1u64), opaque(3u64));
+    selects(b, data, |taken| {
+        std::hint::select_unpredictable(taken, x, y)
+    });
+}
+
+/// `y ^ ((x ^ y) & mask)`, the mask all ones when taken and zero otherwise.
+fn select_mask(b: &mut Bencher, data: Vec<u8>) {
+    let (x, y) = (opaque(1u64), opaque(3u64));
+    selects(b, data, |taken| {
+        y ^ ((x ^ y) & u64::from(taken).wrapping_neg())
+    });
+}
+
+/// `len` values of which `taken` pass the branch, in random order.
+fn random(len: usize, taken: usize) -> Vec<u8> {
+    let mut data = (0..len)
+        .map(|i| if i < taken { 255 } else { 0 })
+        .collect::<Vec<u8>>();
+    shuffle(&mut data, SEED);
+    data
+}
+
+/// A random pattern of `period` outcomes, half of them taken, repeated to `LEN`.
+fn pattern(period: usize) -> Vec<u8> {
+    random(period, period / 2).repeat(LEN / period)
+}
+
+fn sorted_data() -> Vec<u8> {
+    let mut data = random(LEN, LEN / 2);
+    data.sort_unstable();
+    data
+}
+
+fn sorted(b: &mut Bencher) {
+    branches(b, sorted_data());
+}
+
+fn shuffled(b: &mut Bencher) {
+    branches(b, random(RANDOM_LEN, RANDOM_LEN / 2));
+}
+
+fn biased_90(b: &mut Bencher) {
+    branches(b, random(RANDOM_LEN, RANDOM_LEN * 9 / 10));
+}
+
+fn biased_99(b: &mut Bencher) {
+    branches(b, random(RANDOM_LEN, RANDOM_LEN * 99 / 100));
+}
+
+fn pattern_2(b: &mut Bencher) {
+    branches(b, pattern(2));
+}
+
+fn pattern_8(b: &mut Bencher) {
+    branches(b, pattern(8));
+}
+
+fn pattern_32(b: &mut Bencher) {
+    branches(b, pattern(32));
+}
+
+fn pattern_128(b: &mut Bencher) {
+    branches(b, pattern(128));
+}
+
+fn pattern_512(b: &mut Bencher) {
+    branches(b, pattern(512));
+}
+
+fn pattern_2048(b: &mut Bencher) {
+    branches(b, pattern(2048));
+}
+
+fn select_branch_sorted(b: &mut Bencher) {
+    select_branch(b, sorted_data());
+}
+
+fn select_branch_shuffled(b: &mut Bencher) {
+    select_branch(b, random(RANDOM_LEN, RANDOM_LEN / 2));
+}
+
+fn select_cmov_sorted(b: &mut Bencher) {
+    select_cmov(b, sorted_data());
+}
+
+fn select_cmov_shuffled(b: &mut Bencher) {
+    select_cmov(b, random(RANDOM_LEN, RANDOM_LEN / 2));
+}
+
+fn select_mask_sorted(b: &mut Bencher) {
+    select_mask(b, sorted_data());
+}
+
+fn select_mask_shuffled(b: &mut Bencher) {
+    select_mask(b, random(RANDOM_LEN, RANDOM_LEN / 2));
+}
+
+register_bench!(sorted, tags = ["branch"]);
+register_bench!(shuffled, tags = ["branch"]);
+register_bench!(biased_90, tags = ["branch"]);
+register_bench!(biased_99, tags = ["branch"]);
+register_bench!(pattern_2, tags = ["branch"]);
+register_bench!(pattern_8, tags = ["branch"]);
+register_bench!(pattern_32, tags = ["branch"]);
+register_bench!(pattern_128, tags = ["branch"]);
+register_bench!(pattern_512, tags = ["branch"]);
+register_bench!(pattern_2048, tags = ["branch"]);
+register_bench!(select_branch_sorted, tags = ["branch", "select"]);
+register_bench!(select_branch_shuffled, tags = ["branch", "select"]);
+register_bench!(select_cmov_sorted, tags = ["branch", "select"]);
+register_bench!(select_cmov_shuffled, tags = ["branch", "select"]);
+register_bench!(select_mask_sorted, tags = ["branch", "select"]);
+register_bench!(select_mask_shuffled, tags = ["branch", "select"]);
@@ -0,0 +1,83 @@
+//! Flushing a line from every cache level and reloading it, each timed alone with the
+//! serialized counter. `reload_flushed` minus `reload_cached` is one miss to memory; if the
+//! timer cannot tell the two apart, it is too coarse for single misses.
+
+use core::arch::{
+    asm,
+    x86_64::{_mm_clflush, _mm_mfence},
+};
+use std::ptr;
+
+use crate::{arch::has_clflushopt, bench::Bencher, register_bench};
+
+/// One cache line.
+#[repr(C, align(64))]
+struct Line([u64; 8]);
+
+#[inline(always)]
+fn load(line: &Line) -> u64 {
+    unsafe { ptr::read_volatile(&line.0[0]) }
+}
+
+/// Flushes a cached line; timed until the fence that waits for the write-back.
+fn clflush(b: &mut Bencher) {
+    let line = Box::new(Line([0; 8]));
+    b.iter_custom(|timer| {
+        load(&line);
+        unsafe { _mm_mfence() };
+        let start = timer.now();
+        unsafe {
+            _mm_clflush(line.0.as_ptr().cast());
+            _mm_mfence();
+        }
+        timer.now() - start
+    });
+}
+
+/// Like `clflush`, with the weakly-ordered variant that only fences order.
+fn clflushopt(b: &mut Bencher) {
+    if !has_clflushopt() {
+        return b.skip("clflushopt not supported");
+    }
+    let line = Box::new(Line([0; 8]));
+    b.iter_custom(|timer| {
+        load(&line);
+        unsafe { _mm_mfence() };
+        let start = timer.now();
+        unsafe {
+            asm!("clflushopt [{}]", in(reg) line.0.as_ptr(), options(nostack, preserves_flags));
+            _mm_mfence();
+        }
+        timer.now() - start
+    });
+}
+
+/// An L1 hit.
+fn reload_cached(b: &mut Bencher) {
+    let line = Box::new(Line([0; 8]));
+    b.iter_custom(|timer| {
+        load(&line);
+        let start = timer.now();
+        load(&line);
+        timer.now() - start
+    });
+}
+
+/// A miss to memory: the line was flushed just before.
+fn reload_flushed(b: &mut Bencher) {
+    let line = Box::new(Line([0; 8]));
+    b.iter_custom(|timer| {
+        unsafe {
+            _mm_clflush(line.0.as_ptr().cast());
+            _mm_mfence();
+        }
+        let start = timer.now();
+        load(&line);
+        timer.now() - start
+    });
+}
+
+register_bench!(clflush, tags = ["memory", "cache", "latency"]);
+register_bench!(clflushopt, tags = ["memory", "cache", "latency"]);
+register_bench!(reload_cached, tags = ["memory", "cache", "latency"]);
+register_bench!(reload_flushed, tags = ["memory", "cache", "latency"]);
@@ -0,0 +1,123 @@
+//! Call overhead without the arithmetic of a benchmark like `fibonacci`: near call/ret pairs,
+//! calls passing more and more arguments (past the six that System V and the four that Windows
+//! pass in registers), and recursion deep enough to overflow the return stack buffer, whose
+//! returns then mispredict. Every sample makes `LEN` calls, reported per call.
+
+use crate::{
+    bench::{opaque, random_u64s, Bencher},
+    register_bench,
+};
+
+const LEN: usize = 1024;
+const SEED: u64 = 0x5eed;
+/// Random recursion paths, one per recursion.
+const PATHS: usize = 4096;
+
+#[inline(never)]
+fn leaf(x: u64) -> u64 {
+    opaque(x)
+}
+
+fn call_ret(b: &mut Bencher) {
+    b.set_ops(LEN as u64);
+    b.iter(|| {
+        let mut x = opaque(1u64);
+        for _ in 0..LEN {
+            x = leaf(x);
+        }
+        x
+    });
+}
+
+/// `$name` calls a function of as many `u64` arguments as `$arg`s in a dependent chain. Every
+/// argument is hidden so none can be dropped or folded, and the callee sums them.
+macro_rules! args_benches {
+    ($($name:ident: $($arg:ident)+;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            #[inline(never)]
+            #[allow(clippy::too_many_arguments)]
+            fn callee($($arg: u64),+) -> u64 {
+                opaque(0u64 $(.wrapping_add($arg))+)
+            }
+
+            b.set_ops(LEN as u64);
+            b.iter(|| {
+                let mut x = opaque(1u64);
+                for _ in 0..LEN {
+                    x = callee($({ let $arg = opaque(x); $arg }),+);
+                }
+                x
+            });
+        }
+
+        register_bench!($name, tags = ["call"]);
+    )*};
+}
+
+args_benches! {
+    args_1: a;
+    args_2: a b;
+    args_4: a b c d;
+    args_6: a b c d e f;
+    args_8: a b c d e f g h;
+    args_12: a b c d e f g h i j k l;
+}
+
+/// Recurses `depth` calls deep before returning, each level calling `even` or `odd` by the next
+/// bit of `path`. Every `ret` thus goes back to one of two call sites at random, which only the
+/// return stack buffer predicts, as far as it is deep. The branch choosing the call site is as
+/// random and costs the same at every depth, so compare depths with each other. The `opaque`
+/// after the inner calls keeps them from being turned into a loop.
+#[inline(never)]
+fn even(depth: usize, path: u64, x: u64) -> u64 {
+    match depth {
+        0 => opaque(x),
+        _ if path & 1 == 0 => opaque(even(depth - 1, path.rotate_right(1), x)).wrapping_add(1),
+        _ => opaque(odd(depth - 1, path.rotate_right(1), x)).wrapping_add(1),
+    }
+}
+
+/// `even`, but distinct so the two are not merged.
+#[inline(never)]
+fn odd(depth: usize, path: u64, x: u64) -> u64 {
+    match depth {
+        0 => opaque(x),
+        _ if path & 1 == 0 => opaque(even(depth - 1, path.rotate_right(1), x)).wrapping_add(2),
+        _ => opaque(odd(depth - 1, path.rotate_right(1), x)).wrapping_add(2),
+    }
+}
+
+/// `LEN / depth` recursions `depth` deep, each down a new path.
+fn recursion(b: &mut Bencher, depth: usize) {
+    b.set_ops(LEN as u64);
+    let paths = random_u64s(SEED, PATHS);
+    let mut paths = paths.iter().cycle();
+    b.iter(|| {
+        let mut x = opaque(1u64);
+        for _ in 0..LEN / depth {
+            x = even(opaque(depth - 1), *paths.next().unwrap(), x);
+        }
+        x
+    });
+}
+
+macro_rules! recursion_benches {
+    ($($name:ident: $depth:literal;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            recursion(b, $depth);
+        }
+
+        register_bench!($name, tags = ["call"]);
+    )*};
+}
+
+recursion_benches! {
+    recursion_4: 4;
+    recursion_16: 16;
+    recursion_32: 32;
+    recursion_64: 64;
+    recursion_256: 256;
+    recursion_1024: 1024;
+}
+
+register_bench!(call_ret, tags = ["call"]);
@@ -0,0 +1,183 @@
+//! Context switch cost: a ping-pong between the measuring thread and a partner thread
+//! (`thread_*`) or forked process (`process_*`), over a pair of pipes or a futex word, with the
+//! partner pinned to the measuring core (`*_same_core`), where every round trip is two switches
+//! between the sides, or to another (`*_cross_core`), where each side sleeps and is woken by the
+//! other. Reported per one-way switch, half a round trip: the sample's time over twice its round
+//! trips.
+
+use std::{process, ptr, thread};
+
+use super::{
+    futex::{Turn, MEASURER, PARTNER, STOP},
+    partner::{spawn_pinned, Placement},
+};
+use crate::{bench::Bencher, os, register_bench};
+
+/// Round trips per sample, each two switches.
+const ROUND_TRIPS: u64 = 10;
+/// Untimed round trips first, so the partner is up and waiting.
+const WARMUP: u64 = 1000;
+/// Samples at most; round trips take microseconds.
+const ITERATIONS: u64 = 10_000;
+
+/// Both ends of a ping-pong: `round_trip` from the measuring side, `serve` on the partner until
+/// `stop`. Only calls that are safe after `fork`, no allocation.
+trait Channel: Sync {
+    fn round_trip(&self);
+    fn serve(&self);
+    fn stop(&self);
+}
+
+/// A pipe each way, carrying one byte: 1 to ping, 0 to stop.
+struct Pipes {
+    to_partner: [libc::c_int; 2],
+    to_measurer: [libc::c_int; 2],
+}
+
+impl Pipes {
+    fn new() -> Self {
+        let mut pipes = Self {
+            to_partner: [0; 2],
+            to_measurer: [0; 2],
+        };
+        // Safety: `pipe` fills two descriptors.
+        unsafe {
+            assert_eq!(libc::pipe(pipes.to_partner.as_mut_ptr()), 0);
+            assert_eq!(libc::pipe(pipes.to_measurer.as_mut_ptr()), 0);
+        }
+        pipes
+    }
+
+    fn send(fd: libc::c_int, byte: u8) {
+        // Safety: writes one byte from `byte`.
+        assert_eq!(
+            unsafe { libc::write(fd, (&byte as *const u8).cast(), 1) },
+            1
+        );
+    }
+
+    fn receive(fd: libc::c_int) -> u8 {
+        let mut byte = 0u8;
+        // Safety: reads one byte into `byte`.
+        assert_eq!(
+            unsafe { libc::read(fd, (&mut byte as *mut u8).cast(), 1) },
+            1
+        );
+        byte
+    }
+}
+
+impl Channel for Pipes {
+    fn round_trip(&self) {
+        Self::send(self.to_partner[1], 1);
+        Self::receive(self.to_measurer[0]);
+    }
+
+    fn serve(&self) {
+        while Self::receive(self.to_partner[0]) != 0 {
+            Self::send(self.to_measurer[1], 1);
+        }
+    }
+
+    fn stop(&self) {
+        Self::send(self.to_partner[1], 0);
+    }
+}
+
+impl Drop for Pipes {
+    fn drop(&mut self) {
+        for fd in self.to_partner.into_iter().chain(self.to_measurer) {
+            // Safety: the descriptor is ours and closed once.
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+impl Channel for Turn {
+    fn round_trip(&self) {
+        self.pass(PARTNER);
+        self.wait_while(PARTNER);
+    }
+
+    fn serve(&self) {
+        while self.wait_while(MEASURER) != STOP {
+            self.pass(MEASURER);
+        }
+    }
+
+    fn stop(&self) {
+        self.pass(STOP);
+    }
+}
+
+/// Times round trips over `channel` to a partner thread pinned to `cpu`.
+fn thread_ping_pong(b: &mut Bencher, channel: &impl Channel, cpu: usize) {
+    thread::scope(|scope| match spawn_pinned(scope, cpu, || channel.serve()) {
+        Ok(_) => measure(b, channel),
+        Err(reason) => b.skip(reason),
+    });
+}
+
+/// Times round trips over `channel` to a forked partner pinned to `cpu`.
+fn process_ping_pong(b: &mut Bencher, channel: &impl Channel, cpu: usize) {
+    let parent = process::id() as libc::pid_t;
+    // Safety: the child only pins itself, serves the channel with plain syscalls and exits,
+    // none of which allocates or takes a lock another thread may have held at the fork. It dies
+    // with the parent if that exits before stopping it.
+    match unsafe { libc::fork() } {
+        -1 => panic!("fork failed: {}", std::io::Error::last_os_error()),
+        0 => unsafe {
+            os::die_with_parent(parent);
+            let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+            libc::CPU_SET(cpu, &mut set);
+            libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set);
+            channel.serve();
+            libc::_exit(0);
+        },
+        child => {
+            measure(b, channel);
+            // Safety: reaps the child, which exits once stopped.
+            unsafe { libc::waitpid(child, ptr::null_mut(), 0) };
+        }
+    }
+}
+
+fn measure(b: &mut Bencher, channel: &impl Channel) {
+    b.set_ops(2 * ROUND_TRIPS);
+    b.limit_iterations(ITERATIONS);
+    for _ in 0..WARMUP {
+        channel.round_trip();
+    }
+    b.iter(|| {
+        for _ in 0..ROUND_TRIPS {
+            channel.round_trip();
+        }
+    });
+    channel.stop();
+}
+
+/// `$name` ping-pongs over `$channel` with a `$partner` placed per `$placement`.
+macro_rules! context_switch_benches {
+    ($($name:ident: $partner:ident, $channel:expr, $placement:ident;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            let cpu = match Placement::$placement.partner() {
+                Ok(cpu) => cpu,
+                Err(reason) => return b.skip(reason),
+            };
+            $partner(b, &$channel, cpu);
+        }
+
+        register_bench!($name, tags = ["scheduling", "context-switch", "multi-threaded"]);
+    )*};
+}
+
+context_switch_benches! {
+    thread_pipe_same_core: thread_ping_pong, Pipes::new(), SameCore;
+    thread_pipe_cross_core: thread_ping_pong, Pipes::new(), CrossCore;
+    thread_futex_same_core: thread_ping_pong, Turn::new(true), SameCore;
+    thread_futex_cross_core: thread_ping_pong, Turn::new(true), CrossCore;
+    process_pipe_same_core: process_ping_pong, Pipes::new(), SameCore;
+    process_pipe_cross_core: process_ping_pong, Pipes::new(), CrossCore;
+    process_futex_same_core: process_ping_pong, Turn::new(false), SameCore;
+    process_futex_cross_core: process_ping_pong, Turn::new(false), CrossCore;
+}
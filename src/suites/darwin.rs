@@ -0,0 +1,12 @@
+use crate::{bench::Bencher, register_bench};
+
+fn getpid(b: &mut Bencher) {
+    b.iter(|| unsafe { libc::getpid() });
+}
+
+fn mach_absolute_time(b: &mut Bencher) {
+    b.iter(crate::os::macos::absolute_time);
+}
+
+register_bench!(getpid, tags = ["syscall"]);
+register_bench!(mach_absolute_time, tags = ["timer"]);
@@ -0,0 +1,74 @@
+//! The subnormal penalty: `f64` multiply and add throughput, laid out like the `fp` suite's
+//! `*_throughput` benchmarks, on normal operands and on subnormal ones (below
+//! `f64::MIN_POSITIVE`), which many cores hand to a microcode assist costing a hundred cycles or
+//! more. `*_ftz_daz` runs the same with the MXCSR's flush-to-zero (subnormal results become zero)
+//! and denormals-are-zero (subnormal inputs read as zero) bits set, which removes the assists.
+
+use std::arch::asm;
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+const OPS: usize = 1024;
+const LANES: usize = 8;
+/// A quarter of the smallest normal `f64`, and so subnormal.
+const SUBNORMAL: f64 = f64::MIN_POSITIVE / 4.0;
+const FTZ: u32 = 1 << 15;
+const DAZ: u32 = 1 << 6;
+
+fn mxcsr() -> u32 {
+    let mut csr = 0u32;
+    // Safety: `stmxcsr` stores the four bytes of `csr`.
+    unsafe { asm!("stmxcsr [{}]", in(reg) &mut csr, options(nostack)) };
+    csr
+}
+
+fn set_mxcsr(csr: u32) {
+    // Safety: `ldmxcsr` loads the four bytes of `csr`, which is either a saved value or one with
+    // only FTZ and DAZ added, restored right after the benchmark.
+    unsafe { asm!("ldmxcsr [{}]", in(reg) &csr, options(nostack)) };
+}
+
+/// `$name` runs `LANES` chains of `$op` from `$x`, with `$y` constant, which keeps every lane
+/// at its starting value (or zero once flushed); with `ftz_daz` it sets both bits for the
+/// benchmark.
+macro_rules! denormal_benches {
+    ($($name:ident: x = $x0:expr, y = $y0:expr, ftz_daz = $ftz_daz:literal,
+       |$x:ident, $y:ident| $op:expr;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            let $y: f64 = opaque($y0);
+            let saved = mxcsr();
+            if $ftz_daz {
+                set_mxcsr(saved | FTZ | DAZ);
+            }
+            b.iter(|| {
+                let x: f64 = $x0;
+                let mut lanes = [x; LANES].map(opaque);
+                for _ in 0..OPS / LANES {
+                    for lane in &mut lanes {
+                        let $x = *lane;
+                        *lane = opaque($op);
+                    }
+                }
+                lanes
+            });
+            set_mxcsr(saved);
+        }
+
+        register_bench!($name, tags = ["fp", "denormal", "throughput"]);
+    )*};
+}
+
+denormal_benches! {
+    mul_normal: x = 1.0, y = 1.0, ftz_daz = false, |x, y| x * y;
+    mul_subnormal: x = SUBNORMAL, y = 1.0, ftz_daz = false, |x, y| x * y;
+    mul_normal_ftz_daz: x = 1.0, y = 1.0, ftz_daz = true, |x, y| x * y;
+    mul_subnormal_ftz_daz: x = SUBNORMAL, y = 1.0, ftz_daz = true, |x, y| x * y;
+    add_normal: x = 1.0, y = 0.0, ftz_daz = false, |x, y| x + y;
+    add_subnormal: x = SUBNORMAL, y = 0.0, ftz_daz = false, |x, y| x + y;
+    add_normal_ftz_daz: x = 1.0, y = 0.0, ftz_daz = true, |x, y| x + y;
+    add_subnormal_ftz_daz: x = SUBNORMAL, y = 0.0, ftz_daz = true, |x, y| x + y;
+}
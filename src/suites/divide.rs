@@ -0,0 +1,95 @@
+//! `u64` division by 7 three ways, laid out like the `alu` suite: by the literal (`constant_*`),
+//! which the compiler strength-reduces to a multiply, shifts and adds; by a divisor only known
+//! at run time (`runtime_*`), a `div` instruction; and through a reciprocal precomputed for that
+//! divisor at run time (`reciprocal_*`), the same multiply as the compiler's, as libdivide does
+//! it. `reciprocal_new` is the cost of the precomputation, which pays off after about
+//! `reciprocal_new / (runtime - reciprocal)` divisions.
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+const OPS: usize = 1024;
+const LANES: usize = 8;
+const DIVISOR: u64 = 7;
+/// Added to every quotient so the dividend stays large (the chains converge on 7/6 of it); some
+/// cores divide small numbers faster. The add costs a cycle in every variant alike.
+const OFFSET: u64 = 1 << 60;
+
+/// Division by a fixed divisor through a multiply by its rounded-up reciprocal (Granlund and
+/// Montgomery's method, with the one-bit correction that makes it exact for every `u64`).
+#[derive(Clone, Copy)]
+struct Reciprocal {
+    magic: u64,
+    pre_shift: u32,
+    shift: u32,
+}
+
+impl Reciprocal {
+    fn new(divisor: u64) -> Self {
+        let bits = 64 - (divisor - 1).leading_zeros();
+        let magic = ((((1u128 << bits) - divisor as u128) << 64) / divisor as u128) as u64 + 1;
+        Self {
+            magic,
+            pre_shift: bits.min(1),
+            shift: bits.saturating_sub(1),
+        }
+    }
+
+    #[inline(always)]
+    fn divide(self, n: u64) -> u64 {
+        let high = ((self.magic as u128 * n as u128) >> 64) as u64;
+        (high + ((n - high) >> self.pre_shift)) >> self.shift
+    }
+}
+
+/// Times `$op` as one dependent chain (`$latency`) and as `LANES` interleaved chains
+/// (`$throughput`), like `alu_benches!`, `$d` being what `$setup`, if any, makes of the
+/// divisor.
+macro_rules! divide_benches {
+    ($($latency:ident, $throughput:ident: $($d:ident = $setup:expr,)? |$x:ident| $op:expr;)*) => {$(
+        fn $latency(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            $(let $d = $setup;)?
+            b.iter(|| {
+                let mut $x = opaque(OFFSET);
+                for _ in 0..OPS {
+                    $x = opaque($op.wrapping_add(OFFSET));
+                }
+                $x
+            });
+        }
+
+        fn $throughput(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            $(let $d = $setup;)?
+            b.iter(|| {
+                let mut lanes = [OFFSET; LANES].map(opaque);
+                for _ in 0..OPS / LANES {
+                    for lane in &mut lanes {
+                        let $x = *lane;
+                        *lane = opaque($op.wrapping_add(OFFSET));
+                    }
+                }
+                lanes
+            });
+        }
+
+        register_bench!($latency, tags = ["alu", "divide", "latency"]);
+        register_bench!($throughput, tags = ["alu", "divide", "throughput"]);
+    )*};
+}
+
+divide_benches! {
+    constant_latency, constant_throughput: |x| x / DIVISOR;
+    runtime_latency, runtime_throughput: d = opaque(DIVISOR), |x| x / d;
+    reciprocal_latency, reciprocal_throughput: d = Reciprocal::new(opaque(DIVISOR)),
+        |x| d.divide(x);
+}
+
+fn reciprocal_new(b: &mut Bencher) {
+    b.iter(|| Reciprocal::new(opaque(DIVISOR)).magic);
+}
+
+register_bench!(reciprocal_new, tags = ["alu", "divide"]);
@@ -0,0 +1,108 @@
+//! AVX-512 frequency licenses: cores that drop their clock to run heavy 512-bit instructions
+//! and keep it down for a while after the last one, slowing unrelated scalar code. The clock is
+//! read off a dependent chain of adds, which takes one core cycle per add on every x86 core, so
+//! its duration in timer ticks is inversely proportional to the effective frequency.
+
+use std::arch::x86_64::*;
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+    timer::Timer,
+};
+
+/// Adds per frequency probe, about a microsecond.
+const PROBE_OPS: usize = 2048;
+/// FMA rounds per burst chunk, each over `LANES` independent registers.
+const CHUNK: usize = 512;
+const LANES: usize = 8;
+/// Length of the burst before a recovery is timed, long enough to be granted the license.
+const BURST_MICROS: u64 = 1000;
+/// A recovery taking longer than this is reported as this.
+const RECOVERY_LIMIT_MICROS: u64 = 20_000;
+/// A probe within this factor of the unthrottled one counts as back at full speed.
+const RECOVERED: f64 = 1.05;
+/// Recovery samples take about `BURST_MICROS` plus the recovery each.
+const RECOVERY_ITERATIONS: u64 = 200;
+
+/// Runs the chain of `PROBE_OPS` dependent adds and returns its duration in `timer` ticks.
+#[inline(always)]
+fn probe(timer: &dyn Timer) -> u64 {
+    let start = timer.now();
+    let mut x = opaque(0u64);
+    for _ in 0..PROBE_OPS {
+        x = opaque(x.wrapping_add(1));
+    }
+    let end = timer.now();
+    opaque(x);
+    end - start
+}
+
+/// The fastest of many probes, taken as the unthrottled clock.
+fn unthrottled(timer: &dyn Timer) -> u64 {
+    (0..1000).map(|_| probe(timer)).min().unwrap_or(0)
+}
+
+/// A chunk of 512-bit FMAs, `x * 0.5 + 0.5`, keeping both FMA ports busy.
+#[target_feature(enable = "avx512f")]
+unsafe fn fma_chunk(lanes: &mut [__m512; LANES]) {
+    let half = _mm512_set1_ps(opaque(0.5));
+    for _ in 0..CHUNK {
+        for lane in lanes.iter_mut() {
+            *lane = _mm512_fmadd_ps(*lane, half, half);
+        }
+    }
+}
+
+/// The scalar add chain measured between chunks of 512-bit FMAs, i.e. while the license is in
+/// effect: compare its time per add with `alu::add_u64_latency`.
+fn avx512_burst_scalar_add(b: &mut Bencher) {
+    if !is_x86_feature_detected!("avx512f") {
+        return b.skip("no avx512f support");
+    }
+    b.set_ops(PROBE_OPS as u64);
+    // Safety (here and below): avx512f was detected above.
+    let mut lanes = [unsafe { _mm512_set1_ps(1.0) }; LANES];
+    b.iter_custom(|timer| {
+        unsafe { fma_chunk(&mut lanes) };
+        probe(timer)
+    });
+}
+
+/// Time from the end of a burst of 512-bit FMAs until scalar code runs at full speed again,
+/// probing the clock back to back.
+fn avx512_recovery(b: &mut Bencher) {
+    if !is_x86_feature_detected!("avx512f") {
+        return b.skip("no avx512f support");
+    }
+    b.limit_iterations(RECOVERY_ITERATIONS);
+    // Safety (here and below): avx512f was detected above.
+    let mut lanes = [unsafe { _mm512_set1_ps(1.0) }; LANES];
+    let mut threshold = None;
+    b.iter_custom(|timer| {
+        let threshold =
+            *threshold.get_or_insert_with(|| (unthrottled(timer) as f64 * RECOVERED) as u64);
+        let micros = |us: u64| us * timer.frequency() / 1_000_000;
+
+        let start = timer.now();
+        while timer.now() - start < micros(BURST_MICROS) {
+            unsafe { fma_chunk(&mut lanes) };
+        }
+        let end = timer.now();
+        while probe(timer) > threshold {
+            if timer.now() - end >= micros(RECOVERY_LIMIT_MICROS) {
+                break;
+            }
+        }
+        timer.now() - end
+    });
+}
+
+register_bench!(
+    avx512_burst_scalar_add,
+    tags = ["simd", "avx512f", "frequency"]
+);
+register_bench!(
+    avx512_recovery,
+    tags = ["simd", "avx512f", "frequency", "slow"]
+);
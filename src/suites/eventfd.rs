@@ -0,0 +1,152 @@
+//! eventfd wakeups on Linux, the primitive async runtimes wake their threads with: from the
+//! measuring thread's `write` to a thread blocked on the eventfd running again, pinned to the
+//! same core (`*_same_core`), where the write switches to it, or another one
+//! (`*_cross_core`), where it is woken by an interrupt and takes both cores' timers to agree. It
+//! blocks in `read` (`blocking_*`) or, as an event loop does, in `epoll_wait` on an epoll set
+//! holding the eventfd before reading it (`epoll_*`). It answers on a second eventfd, so every
+//! sample finds it blocked again. Reported per wakeup.
+
+use std::{
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    ptr,
+    sync::atomic::{AtomicPtr, AtomicU64, Ordering},
+    thread,
+};
+
+use super::partner::{spawn_pinned, Placement};
+use crate::{bench::Bencher, register_bench, timer::Timer};
+
+/// Samples at most; every one takes two wakeups.
+const ITERATIONS: u64 = 10_000;
+
+/// How the woken thread waits.
+#[derive(Clone, Copy, PartialEq)]
+enum Wait {
+    Blocking,
+    Epoll,
+}
+
+/// Timestamps of one wakeup.
+struct Probe<'a> {
+    timer: &'a dyn Timer,
+    woken: AtomicU64,
+}
+
+fn eventfd() -> OwnedFd {
+    // Safety: a plain descriptor-creating call, checked below.
+    let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+    assert!(fd >= 0, "eventfd failed: {}", io::Error::last_os_error());
+    // Safety: `fd` was just opened and is owned by nothing else.
+    unsafe { OwnedFd::from_raw_fd(fd) }
+}
+
+fn signal(fd: &OwnedFd) {
+    let one = 1u64;
+    // Safety: writes the eight bytes of `one`.
+    assert_eq!(
+        unsafe { libc::write(fd.as_raw_fd(), (&one as *const u64).cast(), 8) },
+        8
+    );
+}
+
+fn consume(fd: &OwnedFd) {
+    let mut count = 0u64;
+    // Safety: reads eight bytes into `count`.
+    assert_eq!(
+        unsafe { libc::read(fd.as_raw_fd(), (&mut count as *mut u64).cast(), 8) },
+        8
+    );
+}
+
+/// An epoll set holding `fd`, for reading.
+fn epoll(fd: &OwnedFd) -> OwnedFd {
+    // Safety: a plain descriptor-creating call, checked below.
+    let epoll = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+    assert!(
+        epoll >= 0,
+        "epoll_create1 failed: {}",
+        io::Error::last_os_error()
+    );
+    // Safety: `epoll` was just opened and is owned by nothing else.
+    let epoll = unsafe { OwnedFd::from_raw_fd(epoll) };
+    let mut event = libc::epoll_event {
+        events: libc::EPOLLIN as u32,
+        u64: 0,
+    };
+    // Safety: both descriptors are open, and `event` is valid.
+    let added = unsafe {
+        libc::epoll_ctl(
+            epoll.as_raw_fd(),
+            libc::EPOLL_CTL_ADD,
+            fd.as_raw_fd(),
+            &mut event,
+        )
+    };
+    assert_eq!(added, 0, "epoll_ctl failed");
+    epoll
+}
+
+fn wakeup(b: &mut Bencher, wait: Wait, placement: Placement) {
+    let partner = match placement.partner() {
+        Ok(partner) => partner,
+        Err(reason) => return b.skip(reason),
+    };
+    b.limit_iterations(ITERATIONS);
+    let (wake, answer) = (&eventfd(), &eventfd());
+    let request = &AtomicPtr::<Probe>::new(ptr::null_mut());
+    thread::scope(|scope| {
+        let spawned = spawn_pinned(scope, partner, move || {
+            let epoll = (wait == Wait::Epoll).then(|| epoll(wake));
+            loop {
+                if let Some(epoll) = &epoll {
+                    let mut event = libc::epoll_event { events: 0, u64: 0 };
+                    // Safety: room for one event, waiting as long as it takes.
+                    unsafe { libc::epoll_wait(epoll.as_raw_fd(), &mut event, 1, -1) };
+                }
+                consume(wake);
+                // Safety: the measuring thread keeps the probe alive until answered, and
+                // publishes none to stop.
+                let Some(probe) = (unsafe { request.load(Ordering::Acquire).as_ref() }) else {
+                    break;
+                };
+                probe.woken.store(probe.timer.now(), Ordering::Relaxed);
+                signal(answer);
+            }
+        });
+        if let Err(reason) = spawned {
+            return b.skip(reason);
+        }
+        b.iter_custom(|timer| {
+            let probe = Probe {
+                timer,
+                woken: AtomicU64::new(0),
+            };
+            request.store(ptr::from_ref(&probe).cast_mut().cast(), Ordering::Release);
+            let sent = timer.now();
+            signal(wake);
+            consume(answer);
+            probe.woken.load(Ordering::Relaxed).saturating_sub(sent)
+        });
+        request.store(ptr::null_mut(), Ordering::Release);
+        signal(wake);
+    });
+}
+
+/// `$name` wakes a thread that waits per `$wait`, placed per `$placement`.
+macro_rules! eventfd_benches {
+    ($($name:ident: $wait:ident, $placement:ident;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            wakeup(b, Wait::$wait, Placement::$placement);
+        }
+
+        register_bench!($name, tags = ["eventfd", "scheduling", "multi-threaded"]);
+    )*};
+}
+
+eventfd_benches! {
+    blocking_same_core: Blocking, SameCore;
+    blocking_cross_core: Blocking, CrossCore;
+    epoll_same_core: Epoll, SameCore;
+    epoll_cross_core: Epoll, CrossCore;
+}
@@ -0,0 +1,69 @@
+//! Store-to-load forwarding: a store immediately followed by a load from the same place, whose
+//! result is the next store's data. The round trip is one dependency chain, run in an asm loop
+//! so the compiler cannot keep the value in a register. Where the load reads only bytes of one
+//! earlier store, the store buffer forwards them in a few cycles; where it needs more (a wider
+//! load, two stores, a partial overlap), it stalls until the stores reach the cache.
+
+use core::arch::asm;
+
+use crate::{bench::Bencher, register_bench};
+
+/// Store/load round trips per sample, reported per round trip.
+const OPS: usize = 1024;
+
+#[repr(C, align(64))]
+struct Slot([u8; 64]);
+
+/// `$name` stores with `$store` and loads with `$load`, both given `{x}` (the chained value)
+/// and `{buf}` (a cache line), and with `xmm = v` a scratch `{v}` vector register.
+macro_rules! forwarding_benches {
+    ($($name:ident: $(xmm = $v:ident,)? $store:literal, $load:literal;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            let mut slot = Slot([0; 64]);
+            let buf = slot.0.as_mut_ptr();
+            b.iter(|| {
+                let mut x = 0u64;
+                // Safety: every access lies within `slot`.
+                unsafe {
+                    asm!(
+                        "2:",
+                        $store,
+                        $load,
+                        "dec {n}",
+                        "jnz 2b",
+                        buf = in(reg) buf,
+                        x = inout(reg) x,
+                        n = inout(reg) OPS => _,
+                        $($v = out(xmm_reg) _,)?
+                        options(nostack),
+                    )
+                };
+                x
+            });
+        }
+
+        register_bench!($name, tags = ["memory", "forwarding"]);
+    )*};
+}
+
+forwarding_benches! {
+    // Forwarded: the load reads bytes of one store.
+    same_8: "mov byte ptr [{buf}], {x:l}", "movzx {x:e}, byte ptr [{buf}]";
+    same_16: "mov word ptr [{buf}], {x:x}", "movzx {x:e}, word ptr [{buf}]";
+    same_32: "mov dword ptr [{buf}], {x:e}", "mov {x:e}, dword ptr [{buf}]";
+    same_64: "mov qword ptr [{buf}], {x}", "mov {x}, qword ptr [{buf}]";
+    narrow_low: "mov qword ptr [{buf}], {x}", "mov {x:e}, dword ptr [{buf}]";
+    narrow_high: "mov qword ptr [{buf}], {x}", "mov {x:e}, dword ptr [{buf} + 4]";
+    narrow_byte: "mov qword ptr [{buf}], {x}", "movzx {x:e}, byte ptr [{buf} + 5]";
+    // Stalled: the load needs bytes the store does not have.
+    wide: "mov dword ptr [{buf}], {x:e}", "mov {x}, qword ptr [{buf}]";
+    partial: "mov qword ptr [{buf}], {x}", "mov {x}, qword ptr [{buf} + 4]";
+    two_stores: "mov dword ptr [{buf}], {x:e}\nmov dword ptr [{buf} + 4], {x:e}",
+        "mov {x}, qword ptr [{buf}]";
+    // From a vector store to a general-purpose load, including the `movq` to the vector.
+    vector_low: xmm = v, "movq {v}, {x}\nmovdqu xmmword ptr [{buf}], {v}",
+        "mov {x}, qword ptr [{buf}]";
+    vector_high: xmm = v, "movq {v}, {x}\nmovdqu xmmword ptr [{buf}], {v}",
+        "mov {x}, qword ptr [{buf} + 8]";
+}
@@ -0,0 +1,111 @@
+//! Scalar floating-point latency and throughput, laid out like the `alu` suite so both read off
+//! the same report. Each sample is `OPS` operations, reported per operation as well.
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+const OPS: usize = 1024;
+
+/// Times `$op` on `$ty` as one dependent chain (`$latency`) and as `$lanes` interleaved chains
+/// (`$throughput`), like `alu_benches!`. An operation needing a CPU feature names it in
+/// `requires`: the benchmark is skipped where it is missing and compiled for it otherwise, so
+/// its intrinsics inline into the loop. `sqrt` chains converge on 1.0 and divisions alternate
+/// between `$x` and `$y / $x`, so no chain reaches infinity, zero or a denormal.
+macro_rules! fp_benches {
+    ($($latency:ident, $throughput:ident: $ty:ty, lanes = $lanes:literal,
+       $(requires = $feature:tt,)? x = $x0:expr, y = $y0:expr,
+       |$x:ident, $y:ident| $op:expr;)*) => {$(
+        fn $latency(b: &mut Bencher) {
+            $(if !std::is_x86_feature_detected!($feature) {
+                return b.skip(concat!("no ", $feature, " support"));
+            })?
+            b.set_ops(OPS as u64);
+            let $y: $ty = opaque($y0);
+            // Safety: the required feature, if any, was detected above.
+            b.iter(|| unsafe { chain($y) });
+
+            $(#[target_feature(enable = $feature)])?
+            unsafe fn chain($y: $ty) -> $ty {
+                let mut $x: $ty = opaque($x0);
+                for _ in 0..OPS {
+                    $x = opaque($op);
+                }
+                $x
+            }
+        }
+
+        fn $throughput(b: &mut Bencher) {
+            $(if !std::is_x86_feature_detected!($feature) {
+                return b.skip(concat!("no ", $feature, " support"));
+            })?
+            b.set_ops(OPS as u64);
+            let $y: $ty = opaque($y0);
+            // Safety: as above.
+            b.iter(|| unsafe { lanes($y) });
+
+            $(#[target_feature(enable = $feature)])?
+            unsafe fn lanes($y: $ty) -> [$ty; $lanes] {
+                let x: $ty = $x0;
+                let mut lanes = [x; $lanes].map(opaque);
+                for _ in 0..OPS / $lanes {
+                    for lane in &mut lanes {
+                        let $x = *lane;
+                        *lane = opaque($op);
+                    }
+                }
+                lanes
+            }
+        }
+
+        register_bench!($latency, tags = ["fp", "latency"]);
+        register_bench!($throughput, tags = ["fp", "throughput"]);
+    )*};
+}
+
+fp_benches! {
+    add_f32_latency, add_f32_throughput: f32, lanes = 8, x = 1.0, y = 1.0, |x, y| x + y;
+    add_f64_latency, add_f64_throughput: f64, lanes = 8, x = 1.0, y = 1.0, |x, y| x + y;
+    mul_f32_latency, mul_f32_throughput: f32, lanes = 8, x = 1.0, y = 0.999, |x, y| x * y;
+    mul_f64_latency, mul_f64_throughput: f64, lanes = 8, x = 1.0, y = 0.999, |x, y| x * y;
+    div_f32_latency, div_f32_throughput: f32, lanes = 8, x = 1.5, y = 2.0, |x, y| y / x;
+    div_f64_latency, div_f64_throughput: f64, lanes = 8, x = 1.5, y = 2.0, |x, y| y / x;
+    sqrt_f32_latency, sqrt_f32_throughput: f32, lanes = 8, x = 2.0, y = 0.0, |x, _y| x.sqrt();
+    sqrt_f64_latency, sqrt_f64_throughput: f64, lanes = 8, x = 2.0, y = 0.0, |x, _y| x.sqrt();
+}
+
+// `x * y + 0.5` in one rounding, through `vfmadd`; the chains converge on 1.0.
+#[cfg(target_arch = "x86_64")]
+fp_benches! {
+    fma_f32_latency, fma_f32_throughput: f32, lanes = 8, requires = "fma", x = 1.0, y = 0.5,
+        |x, y| fma::f32(x, y, 0.5);
+    fma_f64_latency, fma_f64_throughput: f64, lanes = 8, requires = "fma", x = 1.0, y = 0.5,
+        |x, y| fma::f64(x, y, 0.5);
+}
+
+// FMA is part of the aarch64 baseline, where `mul_add` compiles to a single `fmadd`.
+#[cfg(target_arch = "aarch64")]
+fp_benches! {
+    fma_f32_latency, fma_f32_throughput: f32, lanes = 8, x = 1.0, y = 0.5,
+        |x, y| x.mul_add(y, 0.5);
+    fma_f64_latency, fma_f64_throughput: f64, lanes = 8, x = 1.0, y = 0.5,
+        |x, y| x.mul_add(y, 0.5);
+}
+
+#[cfg(target_arch = "x86_64")]
+mod fma {
+    use std::arch::x86_64::*;
+
+    #[inline]
+    #[target_feature(enable = "fma")]
+    pub fn f32(a: f32, b: f32, c: f32) -> f32 {
+        _mm_cvtss_f32(_mm_fmadd_ss(_mm_set_ss(a), _mm_set_ss(b), _mm_set_ss(c)))
+    }
+
+    #[inline]
+    #[target_feature(enable = "fma")]
+    pub fn f64(a: f64, b: f64, c: f64) -> f64 {
+        _mm_cvtsd_f64(_mm_fmadd_sd(_mm_set_sd(a), _mm_set_sd(b), _mm_set_sd(c)))
+    }
+}
@@ -0,0 +1,148 @@
+//! Durability on Linux, the cost that dominates a write-ahead log: appending a 512-byte record
+//! to a file (`*_write`), then making it durable with `fsync` (`*_fsync`), `fdatasync`, which
+//! skips metadata such as the modification time but not the size (`*_fdatasync`), or
+//! `sync_file_range` on the record (`*_sync_file_range`), which writes and waits for the data
+//! only, no metadata and no cache flush, so it is not durable on its own; or appending to a file
+//! opened with `O_DSYNC` (`*_o_dsync`) or `O_SYNC` (`*_o_sync`), which make every write
+//! `fdatasync` or `fsync` itself. `tmpfs_*` writes to `/dev/shm`, where every sync is free, and
+//! `disk_*` to the current directory, on whatever device holds it. Reported per record.
+
+use std::{
+    ffi::CString,
+    fs::{self, File},
+    io::Write,
+    os::{fd::AsRawFd, unix::fs::OpenOptionsExt},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{bench::Bencher, register_bench};
+
+const RECORD: usize = 512;
+/// Samples at most; a sync to a disk can take milliseconds.
+const ITERATIONS: u64 = 1000;
+
+/// Where the files live, shared with the `metadata` suite.
+#[derive(Clone, Copy)]
+pub(super) enum Location {
+    Tmpfs,
+    Disk,
+}
+
+impl Location {
+    /// The directory, or why it does not fit.
+    pub(super) fn directory(self) -> Result<&'static Path, &'static str> {
+        let (path, tmpfs) = match self {
+            Location::Tmpfs => (Path::new("/dev/shm"), true),
+            Location::Disk => (Path::new("."), false),
+        };
+        let name = CString::new(path.as_os_str().as_encoded_bytes()).unwrap();
+        // Safety: zero is a valid `statfs`.
+        let mut stat = unsafe { std::mem::zeroed::<libc::statfs>() };
+        // Safety: `name` is a C string, and the call fills `stat`.
+        if unsafe { libc::statfs(name.as_ptr(), &mut stat) } != 0 {
+            return Err("directory not found");
+        }
+        match (stat.f_type == libc::TMPFS_MAGIC, tmpfs) {
+            (true, true) | (false, false) => Ok(path),
+            (false, true) => Err("/dev/shm is not tmpfs"),
+            (true, false) => Err("the current directory is on tmpfs"),
+        }
+    }
+}
+
+/// How each record is made durable.
+#[derive(Clone, Copy)]
+enum Durability {
+    None,
+    Fsync,
+    Fdatasync,
+    SyncFileRange,
+    /// By the file's open flags.
+    Open(libc::c_int),
+}
+
+/// A file appended to, removed on drop.
+struct Log {
+    file: File,
+    path: PathBuf,
+}
+
+impl Drop for Log {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn append(b: &mut Bencher, location: Location, durability: Durability) {
+    let directory = match location.directory() {
+        Ok(directory) => directory,
+        Err(reason) => return b.skip(reason),
+    };
+    let path = directory.join(format!("microbench-fsync-{}", process::id()));
+    let flags = match durability {
+        Durability::Open(flags) => flags,
+        _ => 0,
+    };
+    let file = match File::options()
+        .append(true)
+        .create_new(true)
+        .custom_flags(flags)
+        .open(&path)
+    {
+        Ok(file) => file,
+        Err(error) => return b.skip(format!("failed to create {}: {error}", path.display())),
+    };
+    let mut log = Log { file, path };
+    b.limit_iterations(ITERATIONS);
+    let record = [0xa5u8; RECORD];
+    let mut offset = 0;
+    b.iter(|| {
+        log.file.write_all(&record).unwrap();
+        let fd = log.file.as_raw_fd();
+        // Safety: plain calls on an open descriptor.
+        let synced = unsafe {
+            match durability {
+                Durability::None | Durability::Open(_) => 0,
+                Durability::Fsync => libc::fsync(fd),
+                Durability::Fdatasync => libc::fdatasync(fd),
+                Durability::SyncFileRange => libc::sync_file_range(
+                    fd,
+                    offset,
+                    RECORD as libc::off64_t,
+                    libc::SYNC_FILE_RANGE_WAIT_BEFORE
+                        | libc::SYNC_FILE_RANGE_WRITE
+                        | libc::SYNC_FILE_RANGE_WAIT_AFTER,
+                ),
+            }
+        };
+        assert_eq!(synced, 0, "sync failed");
+        offset += RECORD as libc::off64_t;
+    });
+}
+
+/// `$name` appends records in `$location`, made durable per `$durability`.
+macro_rules! fsync_benches {
+    ($($name:ident: $location:ident, $durability:expr;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            append(b, Location::$location, $durability);
+        }
+
+        register_bench!($name, tags = ["fsync", "io", "syscall"]);
+    )*};
+}
+
+fsync_benches! {
+    tmpfs_write: Tmpfs, Durability::None;
+    tmpfs_fsync: Tmpfs, Durability::Fsync;
+    tmpfs_fdatasync: Tmpfs, Durability::Fdatasync;
+    tmpfs_sync_file_range: Tmpfs, Durability::SyncFileRange;
+    tmpfs_o_dsync: Tmpfs, Durability::Open(libc::O_DSYNC);
+    tmpfs_o_sync: Tmpfs, Durability::Open(libc::O_SYNC);
+    disk_write: Disk, Durability::None;
+    disk_fsync: Disk, Durability::Fsync;
+    disk_fdatasync: Disk, Durability::Fdatasync;
+    disk_sync_file_range: Disk, Durability::SyncFileRange;
+    disk_o_dsync: Disk, Durability::Open(libc::O_DSYNC);
+    disk_o_sync: Disk, Durability::Open(libc::O_SYNC);
+}
@@ -0,0 +1,171 @@
+//! Raw futex cost on Linux, the floor under every mutex and condition variable: `FUTEX_WAKE`
+//! with nobody waiting (`wake_no_waiters`) and `FUTEX_WAIT` on a word that has already changed
+//! (`wait_changed`), both a bare syscall that returns at once; a lock and unlock of a three-state
+//! futex mutex nobody else wants (`lock_uncontended`), which stays in user space, one atomic
+//! each way. Reported per operation. Wait/wake round trips with a partner are
+//! `context_switch`'s `*_futex_*`, on the `Turn` protocol kept here.
+
+use std::{
+    ptr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::{bench::Bencher, register_bench};
+
+const OPS: usize = 256;
+/// Operations on a word only this process uses, which skip the shared-futex bookkeeping.
+const WAIT: libc::c_int = libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG;
+const WAKE: libc::c_int = libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG;
+
+/// Runs futex operation `op` on `word`, returning the raw result. A wait may return early,
+/// which callers retry.
+pub(super) fn futex(word: &AtomicU32, op: libc::c_int, value: u32) -> libc::c_long {
+    // Safety: `word` is a valid aligned word for as long as the call lasts, and no timeout is
+    // passed.
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            word.as_ptr(),
+            op,
+            value,
+            ptr::null::<libc::timespec>(),
+        )
+    }
+}
+
+fn wake_no_waiters(b: &mut Bencher) {
+    b.set_ops(OPS as u64);
+    let word = AtomicU32::new(0);
+    b.iter(|| {
+        for _ in 0..OPS {
+            futex(&word, WAKE, 1);
+        }
+    });
+}
+
+fn wait_changed(b: &mut Bencher) {
+    b.set_ops(OPS as u64);
+    let word = AtomicU32::new(0);
+    b.iter(|| {
+        for _ in 0..OPS {
+            futex(&word, WAIT, 1);
+        }
+    });
+}
+
+register_bench!(wake_no_waiters, tags = ["futex", "syscall"]);
+register_bench!(wait_changed, tags = ["futex", "syscall"]);
+
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+const CONTENDED: u32 = 2;
+
+/// Drepper's futex mutex: the syscalls only happen once a waiter has marked it contended.
+struct Lock(AtomicU32);
+
+impl Lock {
+    fn lock(&self) {
+        if self
+            .0
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            return;
+        }
+        while self.0.swap(CONTENDED, Ordering::Acquire) != UNLOCKED {
+            futex(&self.0, WAIT, CONTENDED);
+        }
+    }
+
+    fn unlock(&self) {
+        if self.0.swap(UNLOCKED, Ordering::Release) == CONTENDED {
+            futex(&self.0, WAKE, 1);
+        }
+    }
+}
+
+fn lock_uncontended(b: &mut Bencher) {
+    b.set_ops(OPS as u64);
+    let lock = Lock(AtomicU32::new(UNLOCKED));
+    b.iter(|| {
+        for _ in 0..OPS {
+            lock.lock();
+            lock.unlock();
+        }
+    });
+}
+
+register_bench!(lock_uncontended, tags = ["futex"]);
+
+pub(super) const MEASURER: u32 = 0;
+pub(super) const PARTNER: u32 = 1;
+pub(super) const STOP: u32 = 2;
+
+/// A word saying whose turn it is, each side sleeping in `FUTEX_WAIT` until it changes, the
+/// protocol of `context_switch`'s `*_futex_*`. It lives in a shared anonymous mapping so a
+/// forked partner sees the same word; threads use the cheaper private futex operations.
+pub(super) struct Turn {
+    word: *const AtomicU32,
+    private: bool,
+}
+
+// Safety: `word` points to an atomic in a mapping that outlives every user.
+unsafe impl Sync for Turn {}
+
+impl Turn {
+    pub(super) fn new(private: bool) -> Self {
+        // Safety: a fresh anonymous mapping, checked below.
+        let page = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                4096,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert_ne!(page, libc::MAP_FAILED, "mmap failed");
+        Self {
+            word: page.cast(),
+            private,
+        }
+    }
+
+    fn word(&self) -> &AtomicU32 {
+        // Safety: the mapping is zeroed, page aligned and lives as long as `self`.
+        unsafe { &*self.word }
+    }
+
+    fn futex(&self, op: libc::c_int, value: u32) {
+        let op = if self.private {
+            op | libc::FUTEX_PRIVATE_FLAG
+        } else {
+            op
+        };
+        futex(self.word(), op, value);
+    }
+
+    /// Hands the turn to `next` and wakes the other side.
+    pub(super) fn pass(&self, next: u32) {
+        self.word().store(next, Ordering::Release);
+        self.futex(libc::FUTEX_WAKE, 1);
+    }
+
+    /// Sleeps while the turn is `current`, returning the new one.
+    pub(super) fn wait_while(&self, current: u32) -> u32 {
+        loop {
+            match self.word().load(Ordering::Acquire) {
+                turn if turn == current => self.futex(libc::FUTEX_WAIT, current),
+                turn => return turn,
+            }
+        }
+    }
+}
+
+impl Drop for Turn {
+    fn drop(&mut self) {
+        // Safety: unmaps the page mapped in `new`.
+        unsafe { libc::munmap(self.word as *mut libc::c_void, 4096) };
+    }
+}
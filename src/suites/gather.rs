@@ -0,0 +1,207 @@
+//! Gathers and scatters of `u32`s against the scalar loops they replace, whose profitability
+//! varies more between microarchitectures than any other vector instruction's: AVX2
+//! `vpgatherdd` (8 lanes) and AVX-512 `vpgatherdd`/`vpscatterdd` (16), skipped on CPUs without
+//! them. Indices run through the array in order, a cache line apart (wrapping around, so they
+//! touch 256 KiB at most), or at random, over arrays that fit the L1, the L2 or neither.
+//! Reported per element.
+
+use std::arch::x86_64::*;
+
+use crate::{
+    bench::{random_u64s, Bencher},
+    register_bench,
+};
+
+const SEED: u64 = 0x5eed;
+/// Elements gathered or scattered per sample.
+const INDICES: usize = 4096;
+
+/// How the indices walk the array.
+#[derive(Clone, Copy)]
+enum Pattern {
+    Sequential,
+    /// Sixteen elements, a cache line, apart.
+    Strided,
+    Random,
+}
+
+impl Pattern {
+    fn indices(self, elements: usize) -> Vec<u32> {
+        match self {
+            Pattern::Sequential => (0..INDICES).map(|i| (i % elements) as u32).collect(),
+            Pattern::Strided => (0..INDICES)
+                .map(|i| (i * 16 % elements + i * 16 / elements) as u32)
+                .collect(),
+            Pattern::Random => random_u64s(SEED, INDICES)
+                .into_iter()
+                .map(|r| (r % elements as u64) as u32)
+                .collect(),
+        }
+    }
+}
+
+struct Kernel {
+    run: fn(&mut [u32], &[u32]) -> u32,
+    /// Why it cannot run here, if it needs a CPU feature.
+    unsupported: fn() -> Option<&'static str>,
+}
+
+const SCALAR_GATHER: Kernel = Kernel {
+    run: scalar_gather,
+    unsupported: || None,
+};
+const AVX2_GATHER: Kernel = Kernel {
+    run: avx2_gather,
+    unsupported: || (!is_x86_feature_detected!("avx2")).then_some("no avx2 support"),
+};
+const AVX512_GATHER: Kernel = Kernel {
+    run: avx512_gather,
+    unsupported: || (!is_x86_feature_detected!("avx512f")).then_some("no avx512f support"),
+};
+const SCALAR_SCATTER: Kernel = Kernel {
+    run: scalar_scatter,
+    unsupported: || None,
+};
+const AVX512_SCATTER: Kernel = Kernel {
+    run: avx512_scatter,
+    unsupported: || (!is_x86_feature_detected!("avx512f")).then_some("no avx512f support"),
+};
+
+fn scalar_gather(data: &mut [u32], indices: &[u32]) -> u32 {
+    indices
+        .iter()
+        .fold(0, |sum, &i| sum.wrapping_add(data[i as usize]))
+}
+
+fn avx2_gather(data: &mut [u32], indices: &[u32]) -> u32 {
+    #[target_feature(enable = "avx2")]
+    fn gather(data: &[u32], indices: &[u32]) -> u32 {
+        let mut sum = _mm256_setzero_si256();
+        for chunk in indices.as_chunks::<8>().0 {
+            // Safety: every index is within `data`, and the chunk holds eight of them.
+            let gathered = unsafe {
+                let offsets = _mm256_loadu_si256(chunk.as_ptr().cast());
+                _mm256_i32gather_epi32::<4>(data.as_ptr().cast(), offsets)
+            };
+            sum = _mm256_add_epi32(sum, gathered);
+        }
+        // Safety: a vector is eight `u32`s.
+        let lanes: [u32; 8] = unsafe { std::mem::transmute(sum) };
+        lanes.into_iter().fold(0, u32::wrapping_add)
+    }
+
+    // Safety: `AVX2_GATHER` only runs where avx2 was detected.
+    unsafe { gather(data, indices) }
+}
+
+fn avx512_gather(data: &mut [u32], indices: &[u32]) -> u32 {
+    #[target_feature(enable = "avx512f")]
+    fn gather(data: &[u32], indices: &[u32]) -> u32 {
+        let mut sum = _mm512_setzero_si512();
+        for chunk in indices.as_chunks::<16>().0 {
+            // Safety: every index is within `data`, and the chunk holds sixteen of them.
+            let gathered = unsafe {
+                let offsets = _mm512_loadu_si512(chunk.as_ptr().cast());
+                _mm512_i32gather_epi32::<4>(offsets, data.as_ptr().cast())
+            };
+            sum = _mm512_add_epi32(sum, gathered);
+        }
+        _mm512_reduce_add_epi32(sum) as u32
+    }
+
+    // Safety: `AVX512_GATHER` only runs where avx512f was detected.
+    unsafe { gather(data, indices) }
+}
+
+fn scalar_scatter(data: &mut [u32], indices: &[u32]) -> u32 {
+    for (value, &i) in indices.iter().enumerate() {
+        data[i as usize] = value as u32;
+    }
+    data[0]
+}
+
+fn avx512_scatter(data: &mut [u32], indices: &[u32]) -> u32 {
+    #[target_feature(enable = "avx512f")]
+    fn scatter(data: &mut [u32], indices: &[u32]) {
+        let step = _mm512_set1_epi32(16);
+        let mut values = _mm512_setr_epi32(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+        for chunk in indices.as_chunks::<16>().0 {
+            // Safety: every index is within `data`, and the chunk holds sixteen of them.
+            unsafe {
+                let offsets = _mm512_loadu_si512(chunk.as_ptr().cast());
+                _mm512_i32scatter_epi32::<4>(data.as_mut_ptr().cast(), offsets, values);
+            }
+            values = _mm512_add_epi32(values, step);
+        }
+    }
+
+    // Safety: `AVX512_SCATTER` only runs where avx512f was detected.
+    unsafe { scatter(data, indices) };
+    data[0]
+}
+
+/// `$name` runs `$kernel` over `$bytes` of `u32`s with indices following `$pattern`.
+macro_rules! gather_benches {
+    ($($name:ident: $kernel:ident, $pattern:ident, $bytes:expr;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            if let Some(reason) = ($kernel.unsupported)() {
+                return b.skip(reason);
+            }
+            b.set_ops(INDICES as u64);
+            let elements = $bytes / size_of::<u32>();
+            let mut data = (0..elements as u32).collect::<Vec<_>>();
+            let indices = Pattern::$pattern.indices(elements);
+            b.iter(|| ($kernel.run)(&mut data, &indices));
+        }
+
+        register_bench!($name, tags = ["simd", "gather"]);
+    )*};
+}
+
+gather_benches! {
+    scalar_sequential_16k: SCALAR_GATHER, Sequential, 16 << 10;
+    scalar_strided_16k: SCALAR_GATHER, Strided, 16 << 10;
+    scalar_random_16k: SCALAR_GATHER, Random, 16 << 10;
+    scalar_sequential_1m: SCALAR_GATHER, Sequential, 1 << 20;
+    scalar_strided_1m: SCALAR_GATHER, Strided, 1 << 20;
+    scalar_random_1m: SCALAR_GATHER, Random, 1 << 20;
+    scalar_sequential_64m: SCALAR_GATHER, Sequential, 64 << 20;
+    scalar_strided_64m: SCALAR_GATHER, Strided, 64 << 20;
+    scalar_random_64m: SCALAR_GATHER, Random, 64 << 20;
+    avx2_sequential_16k: AVX2_GATHER, Sequential, 16 << 10;
+    avx2_strided_16k: AVX2_GATHER, Strided, 16 << 10;
+    avx2_random_16k: AVX2_GATHER, Random, 16 << 10;
+    avx2_sequential_1m: AVX2_GATHER, Sequential, 1 << 20;
+    avx2_strided_1m: AVX2_GATHER, Strided, 1 << 20;
+    avx2_random_1m: AVX2_GATHER, Random, 1 << 20;
+    avx2_sequential_64m: AVX2_GATHER, Sequential, 64 << 20;
+    avx2_strided_64m: AVX2_GATHER, Strided, 64 << 20;
+    avx2_random_64m: AVX2_GATHER, Random, 64 << 20;
+    avx512_sequential_16k: AVX512_GATHER, Sequential, 16 << 10;
+    avx512_strided_16k: AVX512_GATHER, Strided, 16 << 10;
+    avx512_random_16k: AVX512_GATHER, Random, 16 << 10;
+    avx512_sequential_1m: AVX512_GATHER, Sequential, 1 << 20;
+    avx512_strided_1m: AVX512_GATHER, Strided, 1 << 20;
+    avx512_random_1m: AVX512_GATHER, Random, 1 << 20;
+    avx512_sequential_64m: AVX512_GATHER, Sequential, 64 << 20;
+    avx512_strided_64m: AVX512_GATHER, Strided, 64 << 20;
+    avx512_random_64m: AVX512_GATHER, Random, 64 << 20;
+    scatter_scalar_sequential_16k: SCALAR_SCATTER, Sequential, 16 << 10;
+    scatter_scalar_strided_16k: SCALAR_SCATTER, Strided, 16 << 10;
+    scatter_scalar_random_16k: SCALAR_SCATTER, Random, 16 << 10;
+    scatter_scalar_sequential_1m: SCALAR_SCATTER, Sequential, 1 << 20;
+    scatter_scalar_strided_1m: SCALAR_SCATTER, Strided, 1 << 20;
+    scatter_scalar_random_1m: SCALAR_SCATTER, Random, 1 << 20;
+    scatter_scalar_sequential_64m: SCALAR_SCATTER, Sequential, 64 << 20;
+    scatter_scalar_strided_64m: SCALAR_SCATTER, Strided, 64 << 20;
+    scatter_scalar_random_64m: SCALAR_SCATTER, Random, 64 << 20;
+    scatter_avx512_sequential_16k: AVX512_SCATTER, Sequential, 16 << 10;
+    scatter_avx512_strided_16k: AVX512_SCATTER, Strided, 16 << 10;
+    scatter_avx512_random_16k: AVX512_SCATTER, Random, 16 << 10;
+    scatter_avx512_sequential_1m: AVX512_SCATTER, Sequential, 1 << 20;
+    scatter_avx512_strided_1m: AVX512_SCATTER, Strided, 1 << 20;
+    scatter_avx512_random_1m: AVX512_SCATTER, Random, 1 << 20;
+    scatter_avx512_sequential_64m: AVX512_SCATTER, Sequential, 64 << 20;
+    scatter_avx512_strided_64m: AVX512_SCATTER, Strided, 64 << 20;
+    scatter_avx512_random_64m: AVX512_SCATTER, Random, 64 << 20;
+}
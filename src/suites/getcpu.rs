@@ -0,0 +1,100 @@
+//! Asking which CPU the thread runs on, on Linux: `sched_getcpu`, which glibc serves from the
+//! vDSO, itself reading `rdpid` or, without it, a segment limit with `lsl`; the `getcpu`
+//! syscall (`getcpu_syscall`); and on x86_64 `rdtscp`, whose `TSC_AUX` output the kernel loads
+//! with `(node << 12) | cpu` on every CPU (`rdtscp_aux`), skipped where it does not hold the
+//! CPU `sched_getcpu` reports, as under some hypervisors.
+//!
+//! `cpu_changes_<pinned|unpinned>` check the answer against the pinning: each sample calls
+//! `sched_getcpu` `CALLS` times and counts the calls reporting another CPU than the one before
+//! as `cpu-changes`, per sample of `CALLS` calls. Pinned, as the runner is, there must be none;
+//! unpinned, allowed on every CPU of the cpuset until pinned back, they show how often the
+//! scheduler moves a busy thread. The unpinned one is skipped on one core.
+
+use syscalls::{raw_syscall, Sysno};
+
+use super::migration::{cpu_set, set_affinity};
+use crate::{bench::Bencher, os, register_bench};
+
+/// `sched_getcpu` calls per sample of the change counts.
+const CALLS: u64 = 1000;
+/// Samples at most of the change counts.
+const ITERATIONS: u64 = 10_000;
+
+fn sched_getcpu(b: &mut Bencher) {
+    // Safety: no arguments.
+    b.iter(|| unsafe { libc::sched_getcpu() });
+}
+
+fn getcpu_syscall(b: &mut Bencher) {
+    let mut cpu = 0u32;
+    // Safety: writes the CPU to `cpu`; the node and cache pointers may be null.
+    b.iter(|| unsafe { raw_syscall!(Sysno::getcpu, &mut cpu as *mut u32, 0, 0) });
+}
+
+#[cfg(target_arch = "x86_64")]
+fn rdtscp_aux(b: &mut Bencher) {
+    use core::arch::x86_64::__rdtscp;
+
+    if !crate::arch::has_rdtscp() {
+        return b.skip("rdtscp not supported");
+    }
+    let mut aux = 0;
+    // Safety: the CPU has `rdtscp`.
+    unsafe { __rdtscp(&mut aux) };
+    if os::current_cpu() != Some(aux as usize & 0xfff) {
+        return b.skip("TSC_AUX does not hold the cpu number");
+    }
+    // Safety: as above.
+    b.iter(|| unsafe {
+        __rdtscp(&mut aux);
+        aux & 0xfff
+    });
+}
+
+/// Counts the changes of the reported CPU over `CALLS` calls a sample, with the thread allowed
+/// on every CPU of the cpuset if not `pinned`.
+fn cpu_changes(b: &mut Bencher, pinned: bool) {
+    let Some(cpu) = os::current_cpu() else {
+        return b.skip("current cpu unknown");
+    };
+    let cpuset = os::cpu_limits().cpuset;
+    if !pinned {
+        if cpuset.len() < 2 {
+            return b.skip("one core");
+        }
+        set_affinity(&cpu_set(&cpuset));
+    }
+    b.limit_iterations(ITERATIONS);
+    b.set_ops(CALLS);
+    let mut changes = 0;
+    b.iter(|| {
+        // Safety: no arguments.
+        let mut last = unsafe { libc::sched_getcpu() };
+        for _ in 1..CALLS {
+            // Safety: as above.
+            let current = unsafe { libc::sched_getcpu() };
+            changes += u64::from(current != last);
+            last = current;
+        }
+    });
+    b.count("cpu-changes", changes);
+    assert!(os::pin_to_core(cpu), "failed to pin back to core {cpu}");
+}
+
+fn cpu_changes_pinned(b: &mut Bencher) {
+    cpu_changes(b, true);
+}
+
+fn cpu_changes_unpinned(b: &mut Bencher) {
+    cpu_changes(b, false);
+}
+
+register_bench!(sched_getcpu, tags = ["getcpu", "vdso"]);
+register_bench!(getcpu_syscall, tags = ["getcpu", "syscall"]);
+#[cfg(target_arch = "x86_64")]
+register_bench!(rdtscp_aux, tags = ["getcpu", "instruction"]);
+register_bench!(cpu_changes_pinned, tags = ["getcpu"]);
+register_bench!(
+    cpu_changes_unpinned,
+    tags = ["getcpu", "scheduling", "multi-threaded"]
+);
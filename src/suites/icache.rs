@@ -0,0 +1,60 @@
+//! Instruction cache and ITLB capacity: straight-line code from 4 KiB to 4 MiB, four-byte adds
+//! on four registers so dependencies allow four per cycle, run front to back every sample
+//! (repeatedly for the small footprints). Once the footprint outgrows the L1i (and the µop
+//! cache), then the L2, then what the ITLB covers, the front end waits on fetches and the time
+//! per instruction steps up. Reported per instruction.
+
+use core::arch::asm;
+
+use crate::{bench::Bencher, register_bench};
+
+/// Bytes of one group of four adds.
+const GROUP: usize = 16;
+/// Bytes run per sample at least, repeating small footprints, so the timer's noise stays small.
+const SAMPLE: usize = 256 << 10;
+
+/// `$name` runs `$bytes` of adds, generated by the assembler in a function of their own so the
+/// code exists once.
+macro_rules! icache_benches {
+    ($($name:ident: $bytes:expr;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            #[inline(never)]
+            fn run() {
+                // Safety: the adds only touch the clobbered registers.
+                unsafe {
+                    asm!(
+                        ".rept {groups}",
+                        "add rax, 1",
+                        "add rcx, 1",
+                        "add rdx, 1",
+                        "add rsi, 1",
+                        ".endr",
+                        groups = const $bytes / GROUP,
+                        out("rax") _, out("rcx") _, out("rdx") _, out("rsi") _,
+                        options(nomem, nostack),
+                    )
+                };
+            }
+
+            let repeat = (SAMPLE / $bytes).max(1);
+            b.set_ops((repeat * $bytes / 4) as u64);
+            b.iter(|| {
+                for _ in 0..repeat {
+                    run();
+                }
+            });
+        }
+
+        register_bench!($name, tags = ["icache", "frontend"]);
+    )*};
+}
+
+icache_benches! {
+    footprint_4k: 4 << 10;
+    footprint_16k: 16 << 10;
+    footprint_32k: 32 << 10;
+    footprint_64k: 64 << 10;
+    footprint_256k: 256 << 10;
+    footprint_1m: 1 << 20;
+    footprint_4m: 4 << 20;
+}
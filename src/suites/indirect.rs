@@ -0,0 +1,121 @@
+//! Indirect branch cost: calls through an array of function pointers and a `match` the compiler
+//! lowers to a jump table, over 1 to 64 targets picked in a cycle or at random, against a
+//! direct call. Each sample makes `LEN` dependent calls, reported per call. Branch target
+//! buffers predict single targets and short cycles; random picks among many targets miss, and
+//! a binary built with retpolines (or a kernel's mitigations) pays for every indirect call.
+
+use std::hint::black_box;
+
+use crate::{
+    bench::{opaque, shuffle, Bencher},
+    register_bench,
+};
+
+const LEN: usize = 1024;
+const RANDOM_LEN: usize = 1 << 20;
+const SEED: u64 = 0x5eed;
+const TARGET_COUNT: usize = 64;
+
+/// A distinct function per `I`, so the compiler can merge none of them, and none the identity
+/// it could call through.
+#[inline(never)]
+fn target<const I: u64>(x: u64) -> u64 {
+    x.wrapping_mul(2 * I + 3) ^ (I + 1)
+}
+
+macro_rules! targets {
+    ($($i:literal)*) => {
+        const TARGETS: [fn(u64) -> u64; TARGET_COUNT] = [$(target::<$i>),*];
+
+        #[inline(always)]
+        fn jump_table(index: u8, x: u64) -> u64 {
+            match index {
+                $($i => target::<$i>(x),)*
+                _ => x,
+            }
+        }
+    };
+}
+
+targets!(0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61 62 63);
+
+/// Calls through `TARGETS[index]` for the indices in the next `LEN` of `indices`.
+fn fn_pointer(b: &mut Bencher, indices: Vec<u8>) {
+    b.set_ops(LEN as u64);
+    let targets = black_box(TARGETS);
+    let mut windows = indices.as_chunks::<LEN>().0.iter().cycle();
+    b.iter(|| {
+        let mut x = opaque(1u64);
+        for &index in windows.next().unwrap() {
+            x = targets[index as usize % TARGET_COUNT](x);
+        }
+        x
+    });
+}
+
+/// Like `fn_pointer`, dispatching through `jump_table`.
+fn match_table(b: &mut Bencher, indices: Vec<u8>) {
+    b.set_ops(LEN as u64);
+    let mut windows = indices.as_chunks::<LEN>().0.iter().cycle();
+    b.iter(|| {
+        let mut x = opaque(1u64);
+        for &index in windows.next().unwrap() {
+            x = jump_table(index, x);
+        }
+        x
+    });
+}
+
+/// Targets `0..targets` in turn.
+fn cycle(targets: usize) -> Vec<u8> {
+    (0..LEN).map(|i| (i % targets) as u8).collect()
+}
+
+/// Targets `0..targets` in random order, each equally often, new for every sample.
+fn random(targets: usize) -> Vec<u8> {
+    let mut indices = (0..RANDOM_LEN)
+        .map(|i| (i % targets) as u8)
+        .collect::<Vec<_>>();
+    shuffle(&mut indices, SEED);
+    indices
+}
+
+fn direct_call(b: &mut Bencher) {
+    b.set_ops(LEN as u64);
+    b.iter(|| {
+        let mut x = opaque(1u64);
+        for _ in 0..LEN {
+            x = target::<1>(x);
+        }
+        x
+    });
+}
+
+macro_rules! indirect_benches {
+    ($($name:ident: $dispatch:ident($indices:expr);)*) => {$(
+        fn $name(b: &mut Bencher) {
+            $dispatch(b, $indices);
+        }
+
+        register_bench!($name, tags = ["indirect"]);
+    )*};
+}
+
+register_bench!(direct_call, tags = ["indirect"]);
+
+indirect_benches! {
+    fn_pointer_1: fn_pointer(cycle(1));
+    fn_pointer_cycle_4: fn_pointer(cycle(4));
+    fn_pointer_cycle_16: fn_pointer(cycle(16));
+    fn_pointer_cycle_64: fn_pointer(cycle(64));
+    fn_pointer_random_4: fn_pointer(random(4));
+    fn_pointer_random_16: fn_pointer(random(16));
+    fn_pointer_random_64: fn_pointer(random(64));
+    jump_table_1: match_table(cycle(1));
+    jump_table_cycle_4: match_table(cycle(4));
+    jump_table_cycle_16: match_table(cycle(16));
+    jump_table_cycle_64: match_table(cycle(64));
+    jump_table_random_4: match_table(random(4));
+    jump_table_random_16: match_table(random(16));
+    jump_table_random_64: match_table(random(64));
+}
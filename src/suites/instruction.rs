@@ -0,0 +1,150 @@
+use core::arch::x86_64::{
+    __cpuid, __rdtscp, _mm_lfence, _mm_mfence, _mm_pause, _mm_sfence, _rdtsc,
+};
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread,
+};
+
+use crate::{arch::has_rdtscp, bench::Bencher, register_bench};
+
+/// Operations per sample of the fenced stores and locked adds, reported per operation.
+const FENCE_OPS: usize = 256;
+
+fn rdtsc(b: &mut Bencher) {
+    b.iter(|| unsafe { _rdtsc() });
+}
+
+/// Waits for earlier instructions to retire, unlike `rdtsc`.
+fn rdtscp(b: &mut Bencher) {
+    if !has_rdtscp() {
+        return b.skip("rdtscp not supported");
+    }
+    let mut aux = 0;
+    b.iter(|| unsafe { __rdtscp(&mut aux) });
+}
+
+/// Around 10 cycles before Skylake, around 140 since.
+fn pause(b: &mut Bencher) {
+    b.iter(_mm_pause);
+}
+
+fn lfence(b: &mut Bencher) {
+    b.iter(|| unsafe { _mm_lfence() });
+}
+
+fn mfence(b: &mut Bencher) {
+    b.iter(|| unsafe { _mm_mfence() });
+}
+
+fn sfence(b: &mut Bencher) {
+    b.iter(|| unsafe { _mm_sfence() });
+}
+
+/// A counter alone on its cache line (and the adjacent-line prefetcher's pair).
+#[repr(align(128))]
+struct Line(AtomicU64);
+
+/// Runs `op` on a line that `threads - 1` other threads increment as fast as they can. A fence
+/// waits for the stores before it, and a store to a line other cores keep taking away waits
+/// for the line, so the cost depends on the contention as much as on the fence.
+fn contended(b: &mut Bencher, threads: usize, op: fn(&AtomicU64)) {
+    b.set_ops(FENCE_OPS as u64);
+    let line = Line(AtomicU64::new(0));
+    let stop = AtomicBool::new(false);
+    thread::scope(|scope| {
+        for _ in 1..threads {
+            scope.spawn(|| {
+                while !stop.load(Ordering::Relaxed) {
+                    line.0.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+        b.iter(|| {
+            for _ in 0..FENCE_OPS {
+                op(&line.0);
+            }
+        });
+        stop.store(true, Ordering::Relaxed);
+    });
+}
+
+fn store_mfence(line: &AtomicU64) {
+    line.store(1, Ordering::Relaxed);
+    unsafe { _mm_mfence() };
+}
+
+fn store_sfence(line: &AtomicU64) {
+    line.store(1, Ordering::Relaxed);
+    unsafe { _mm_sfence() };
+}
+
+/// A `lock add`, which is a full fence as well.
+fn locked_add(line: &AtomicU64) {
+    line.fetch_add(1, Ordering::Relaxed);
+}
+
+/// `$name` runs `$op` under `contended` with `$threads` threads.
+macro_rules! contended_benches {
+    ($($name:ident: $op:ident, threads = $threads:literal, tags = [$($tag:literal),*];)*) => {$(
+        fn $name(b: &mut Bencher) {
+            contended(b, $threads, $op);
+        }
+
+        register_bench!($name, tags = ["instruction", "fence" $(, $tag)*]);
+    )*};
+}
+
+contended_benches! {
+    mfence_store: store_mfence, threads = 1, tags = [];
+    mfence_store_threads_2: store_mfence, threads = 2, tags = ["multi-threaded"];
+    mfence_store_threads_4: store_mfence, threads = 4, tags = ["multi-threaded"];
+    mfence_store_threads_8: store_mfence, threads = 8, tags = ["multi-threaded"];
+    sfence_store: store_sfence, threads = 1, tags = [];
+    sfence_store_threads_2: store_sfence, threads = 2, tags = ["multi-threaded"];
+    sfence_store_threads_4: store_sfence, threads = 4, tags = ["multi-threaded"];
+    sfence_store_threads_8: store_sfence, threads = 8, tags = ["multi-threaded"];
+    lock_add: locked_add, threads = 1, tags = [];
+    lock_add_threads_2: locked_add, threads = 2, tags = ["multi-threaded"];
+    lock_add_threads_4: locked_add, threads = 4, tags = ["multi-threaded"];
+    lock_add_threads_8: locked_add, threads = 8, tags = ["multi-threaded"];
+}
+
+// cpuid always exits to the hypervisor under virtualization, whatever the leaf.
+
+/// Maximum leaf and vendor string.
+fn cpuid_0(b: &mut Bencher) {
+    b.iter(|| __cpuid(0));
+}
+
+/// Family, model and feature flags.
+fn cpuid_1(b: &mut Bencher) {
+    b.iter(|| __cpuid(1));
+}
+
+/// Structured extended feature flags.
+fn cpuid_7(b: &mut Bencher) {
+    b.iter(|| __cpuid(7));
+}
+
+/// Extended topology.
+fn cpuid_b(b: &mut Bencher) {
+    b.iter(|| __cpuid(0xb));
+}
+
+/// Extended feature flags.
+fn cpuid_80000001(b: &mut Bencher) {
+    b.iter(|| __cpuid(0x8000_0001));
+}
+
+register_bench!(rdtsc, tags = ["instruction", "timer"]);
+register_bench!(rdtscp, tags = ["instruction", "timer"]);
+register_bench!(pause, tags = ["instruction"]);
+register_bench!(lfence, tags = ["instruction", "fence"]);
+register_bench!(mfence, tags = ["instruction", "fence"]);
+register_bench!(sfence, tags = ["instruction", "fence"]);
+register_bench!(cpuid_0, tags = ["instruction", "cpuid"]);
+register_bench!(cpuid_1, tags = ["instruction", "cpuid"]);
+register_bench!(cpuid_7, tags = ["instruction", "cpuid"]);
+register_bench!(cpuid_b, tags = ["instruction", "cpuid"]);
+register_bench!(cpuid_80000001, tags = ["instruction", "cpuid"]);
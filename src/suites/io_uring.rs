@@ -0,0 +1,537 @@
+//! The three ways to drive IO on Linux, per operation: plain blocking syscalls (`blocking_*`),
+//! non-blocking descriptors behind `epoll_wait` like an event loop (`epoll_*`), and io_uring
+//! (`uring_*_<batch>`), submitting `<batch>` operations per `io_uring_enter` to show what
+//! batching saves; the inverse of each result is the ops/sec one thread can reach. io_uring is
+//! set up with plain syscalls, no liburing, and skipped where the kernel refuses it (before 5.6,
+//! or with `kernel.io_uring_disabled`).
+//!
+//! `*_message` writes 64 bytes to a Unix stream socket pair and reads them from the other end,
+//! reported per message. `*_accept` accepts a loopback TCP connection and receives the 64 bytes
+//! its client sent before the timing started, then closes it, reported per connection.
+
+use std::{
+    io::Write,
+    net::{TcpListener, TcpStream},
+    os::{
+        fd::{AsRawFd, RawFd},
+        unix::net::UnixStream,
+    },
+    ptr,
+};
+
+use uring::{Ring, Sqe};
+
+use crate::{bench::Bencher, register_bench};
+
+const MESSAGE: usize = 64;
+/// Connections accepted per benchmark at most, spread over its samples.
+const CONNECTIONS: u64 = 20_000;
+
+/// A connected pair of sockets, non-blocking unless `blocking`.
+fn socket_pair(blocking: bool) -> (UnixStream, UnixStream) {
+    let (writer, reader) = UnixStream::pair().unwrap();
+    writer.set_nonblocking(!blocking).unwrap();
+    reader.set_nonblocking(!blocking).unwrap();
+    (writer, reader)
+}
+
+/// A listener on a loopback port, non-blocking unless `blocking`.
+fn listener(blocking: bool) -> TcpListener {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(!blocking).unwrap();
+    listener
+}
+
+/// A connection to `listener` with its message sent, reset on close so no port lingers in
+/// `TIME_WAIT`.
+fn client(listener: &TcpListener) -> TcpStream {
+    let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let linger = libc::linger {
+        l_onoff: 1,
+        l_linger: 0,
+    };
+    // Safety: `linger` is a `linger`, as `SO_LINGER` expects.
+    let set = unsafe {
+        libc::setsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_LINGER,
+            (&raw const linger).cast(),
+            size_of::<libc::linger>() as libc::socklen_t,
+        )
+    };
+    assert_eq!(set, 0, "setsockopt failed");
+    stream.write_all(&[0xa5; MESSAGE]).unwrap();
+    stream
+}
+
+/// Times `accept` on `batch` connections to `listener` made before each sample.
+fn accept(b: &mut Bencher, listener: &TcpListener, batch: usize, mut accept: impl FnMut()) {
+    b.set_ops(batch as u64);
+    b.limit_iterations(CONNECTIONS / batch as u64);
+    b.iter_custom(|timer| {
+        let clients = (0..batch).map(|_| client(listener)).collect::<Vec<_>>();
+        let start = timer.now();
+        accept();
+        let end = timer.now();
+        drop(clients);
+        end - start
+    });
+}
+
+/// An epoll set watching `fd` for input.
+fn epoll(fd: RawFd) -> RawFd {
+    // Safety: a plain call.
+    let epoll = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+    assert!(epoll >= 0, "epoll_create1 failed");
+    epoll_add(epoll, fd);
+    epoll
+}
+
+/// Adds `fd` to `epoll` for input, tagged with itself.
+fn epoll_add(epoll: RawFd, fd: RawFd) {
+    let mut event = libc::epoll_event {
+        events: libc::EPOLLIN as u32,
+        u64: fd as u64,
+    };
+    // Safety: `event` is a valid `epoll_event`.
+    let added = unsafe { libc::epoll_ctl(epoll, libc::EPOLL_CTL_ADD, fd, &mut event) };
+    assert_eq!(added, 0, "epoll_ctl failed");
+}
+
+/// Waits for a descriptor in `epoll` to be ready, and returns it.
+fn epoll_wait(epoll: RawFd) -> RawFd {
+    let mut event = libc::epoll_event { events: 0, u64: 0 };
+    // Safety: the call fills at most the one event.
+    let ready = unsafe { libc::epoll_wait(epoll, &mut event, 1, -1) };
+    assert_eq!(ready, 1, "epoll_wait failed");
+    event.u64 as RawFd
+}
+
+/// Reads one message from `fd`.
+fn read(fd: RawFd, buffer: &mut [u8; MESSAGE]) {
+    // Safety: the call writes at most `MESSAGE` bytes to `buffer`.
+    let read = unsafe { libc::read(fd, buffer.as_mut_ptr().cast(), MESSAGE) };
+    assert_eq!(read, MESSAGE as isize, "read failed");
+}
+
+fn blocking_message(b: &mut Bencher) {
+    let (mut writer, reader) = socket_pair(true);
+    let mut buffer = [0; MESSAGE];
+    b.iter(|| {
+        writer.write_all(&[0xa5; MESSAGE]).unwrap();
+        read(reader.as_raw_fd(), &mut buffer);
+        buffer[0]
+    });
+}
+
+fn epoll_message(b: &mut Bencher) {
+    let (mut writer, reader) = socket_pair(false);
+    let epoll = epoll(reader.as_raw_fd());
+    let mut buffer = [0; MESSAGE];
+    b.iter(|| {
+        writer.write_all(&[0xa5; MESSAGE]).unwrap();
+        read(epoll_wait(epoll), &mut buffer);
+        buffer[0]
+    });
+    // Safety: nothing uses the set any more.
+    unsafe { libc::close(epoll) };
+}
+
+/// Submits `batch` writes, each followed by the read of its message, per `io_uring_enter`.
+fn uring_message(b: &mut Bencher, batch: usize) {
+    let mut ring = match Ring::new(2 * batch as u32) {
+        Ok(ring) => ring,
+        Err(error) => return b.skip(format!("no io_uring: {error}")),
+    };
+    let (writer, reader) = socket_pair(false);
+    let message = [0xa5; MESSAGE];
+    let mut buffers = vec![[0u8; MESSAGE]; batch];
+    b.set_ops(batch as u64);
+    b.iter(|| {
+        for buffer in &mut buffers {
+            ring.push(Sqe::write(writer.as_raw_fd(), &message));
+            ring.push(Sqe::read(reader.as_raw_fd(), buffer));
+        }
+        ring.submit(2 * batch as u32).unwrap();
+        ring.complete(|cqe| assert_eq!(cqe.res, MESSAGE as i32, "io_uring IO failed"));
+        buffers[0][0]
+    });
+}
+
+fn blocking_accept(b: &mut Bencher) {
+    let listener = &listener(true);
+    let mut buffer = [0; MESSAGE];
+    accept(b, listener, 1, || {
+        let (stream, _) = listener.accept().unwrap();
+        read(stream.as_raw_fd(), &mut buffer);
+    });
+}
+
+fn epoll_accept(b: &mut Bencher) {
+    let listener = &listener(false);
+    let epoll = epoll(listener.as_raw_fd());
+    let mut buffer = [0; MESSAGE];
+    accept(b, listener, 1, || {
+        assert_eq!(epoll_wait(epoll), listener.as_raw_fd());
+        // Safety: the new descriptor is closed right after its read.
+        unsafe {
+            let stream = libc::accept4(
+                listener.as_raw_fd(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+            );
+            assert!(stream >= 0, "accept4 failed");
+            epoll_add(epoll, stream);
+            read(epoll_wait(epoll), &mut buffer);
+            libc::close(stream);
+        }
+    });
+    // Safety: nothing uses the set any more.
+    unsafe { libc::close(epoll) };
+}
+
+/// Submits `batch` accepts per `io_uring_enter`, then the `batch` receives.
+fn uring_accept(b: &mut Bencher, batch: usize) {
+    let mut ring = match Ring::new(batch as u32) {
+        Ok(ring) => ring,
+        Err(error) => return b.skip(format!("no io_uring: {error}")),
+    };
+    let listener = &listener(true);
+    let mut streams = Vec::with_capacity(batch);
+    let mut buffers = vec![[0u8; MESSAGE]; batch];
+    accept(b, listener, batch, || {
+        for _ in 0..batch {
+            ring.push(Sqe::accept(listener.as_raw_fd()));
+        }
+        ring.submit(batch as u32).unwrap();
+        ring.complete(|cqe| {
+            assert!(cqe.res >= 0, "io_uring accept failed");
+            streams.push(cqe.res);
+        });
+        for (&stream, buffer) in streams.iter().zip(&mut buffers) {
+            ring.push(Sqe::recv(stream, buffer));
+        }
+        ring.submit(batch as u32).unwrap();
+        ring.complete(|cqe| assert_eq!(cqe.res, MESSAGE as i32, "io_uring recv failed"));
+        for stream in streams.drain(..) {
+            // Safety: the descriptor came from the accept above.
+            unsafe { libc::close(stream) };
+        }
+    });
+}
+
+register_bench!(blocking_message, tags = ["io-uring", "io", "syscall"]);
+register_bench!(epoll_message, tags = ["io-uring", "io", "syscall"]);
+register_bench!(blocking_accept, tags = ["io-uring", "io", "network"]);
+register_bench!(epoll_accept, tags = ["io-uring", "io", "network"]);
+
+/// `$name` runs `$run` with `$batch` operations per submission.
+macro_rules! uring_benches {
+    ($($name:ident: $run:ident, $batch:expr, tags = [$($tag:literal),*];)*) => {$(
+        fn $name(b: &mut Bencher) {
+            $run(b, $batch);
+        }
+
+        register_bench!($name, tags = ["io-uring", "io" $(, $tag)*]);
+    )*};
+}
+
+uring_benches! {
+    uring_message_1: uring_message, 1, tags = ["syscall"];
+    uring_message_8: uring_message, 8, tags = ["syscall"];
+    uring_message_32: uring_message, 32, tags = ["syscall"];
+    uring_accept_1: uring_accept, 1, tags = ["network"];
+    uring_accept_8: uring_accept, 8, tags = ["network"];
+    uring_accept_32: uring_accept, 32, tags = ["network"];
+}
+
+/// Just enough io_uring: the layouts of `<linux/io_uring.h>` and a ring that is filled,
+/// submitted and drained in turn.
+mod uring {
+    use std::{
+        io,
+        os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        ptr,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    const OP_ACCEPT: u8 = 13;
+    const OP_READ: u8 = 22;
+    const OP_WRITE: u8 = 23;
+    const OP_RECV: u8 = 27;
+    const OFF_SQ_RING: i64 = 0;
+    const OFF_CQ_RING: i64 = 0x800_0000;
+    const OFF_SQES: i64 = 0x1000_0000;
+    const ENTER_GETEVENTS: u32 = 1;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct SqOffsets {
+        head: u32,
+        tail: u32,
+        ring_mask: u32,
+        ring_entries: u32,
+        flags: u32,
+        dropped: u32,
+        array: u32,
+        resv1: u32,
+        user_addr: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct CqOffsets {
+        head: u32,
+        tail: u32,
+        ring_mask: u32,
+        ring_entries: u32,
+        overflow: u32,
+        cqes: u32,
+        flags: u32,
+        resv1: u32,
+        user_addr: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Params {
+        sq_entries: u32,
+        cq_entries: u32,
+        flags: u32,
+        sq_thread_cpu: u32,
+        sq_thread_idle: u32,
+        features: u32,
+        wq_fd: u32,
+        resv: [u32; 3],
+        sq_off: SqOffsets,
+        cq_off: CqOffsets,
+    }
+
+    /// A submission queue entry.
+    #[repr(C)]
+    #[derive(Default, Clone, Copy)]
+    pub struct Sqe {
+        opcode: u8,
+        flags: u8,
+        ioprio: u16,
+        fd: i32,
+        off: u64,
+        addr: u64,
+        len: u32,
+        op_flags: u32,
+        user_data: u64,
+        buf_index: u16,
+        personality: u16,
+        splice_fd_in: i32,
+        addr3: u64,
+        pad: u64,
+    }
+
+    impl Sqe {
+        fn io(opcode: u8, fd: RawFd, addr: *const u8, len: usize) -> Self {
+            Self {
+                opcode,
+                fd,
+                addr: addr as u64,
+                len: len as u32,
+                ..Self::default()
+            }
+        }
+
+        /// Writes `buffer`, which must live until the write completes.
+        pub fn write(fd: RawFd, buffer: &[u8]) -> Self {
+            Self::io(OP_WRITE, fd, buffer.as_ptr(), buffer.len())
+        }
+
+        /// Reads into `buffer`, which must live until the read completes.
+        pub fn read(fd: RawFd, buffer: &mut [u8]) -> Self {
+            Self::io(OP_READ, fd, buffer.as_mut_ptr(), buffer.len())
+        }
+
+        /// Receives into `buffer`, which must live until the receive completes.
+        pub fn recv(fd: RawFd, buffer: &mut [u8]) -> Self {
+            Self::io(OP_RECV, fd, buffer.as_mut_ptr(), buffer.len())
+        }
+
+        /// Accepts a connection, its descriptor the result.
+        pub fn accept(fd: RawFd) -> Self {
+            Self {
+                op_flags: libc::SOCK_CLOEXEC as u32,
+                ..Self::io(OP_ACCEPT, fd, ptr::null(), 0)
+            }
+        }
+    }
+
+    /// A completion queue entry.
+    #[repr(C)]
+    pub struct Cqe {
+        pub user_data: u64,
+        pub res: i32,
+        pub flags: u32,
+    }
+
+    /// A shared mapping of part of the ring, unmapped on drop.
+    struct Region {
+        ptr: *mut u8,
+        len: usize,
+    }
+
+    impl Region {
+        fn new(fd: &OwnedFd, len: usize, offset: i64) -> io::Result<Self> {
+            // Safety: a new mapping of the ring, which the kernel sized.
+            let ptr = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED | libc::MAP_POPULATE,
+                    fd.as_raw_fd(),
+                    offset,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self {
+                ptr: ptr.cast(),
+                len,
+            })
+        }
+
+        fn at<T>(&self, offset: u32) -> *mut T {
+            // Safety: the kernel's offsets are within the region.
+            unsafe { self.ptr.add(offset as usize).cast() }
+        }
+    }
+
+    impl Drop for Region {
+        fn drop(&mut self) {
+            // Safety: the region is a whole mapping, and nothing points into it any more.
+            unsafe { libc::munmap(self.ptr.cast(), self.len) };
+        }
+    }
+
+    /// A ring used by one thread, with no submission queue polling.
+    pub struct Ring {
+        fd: OwnedFd,
+        sq_tail: *const AtomicU32,
+        sq_mask: u32,
+        sq_array: *mut u32,
+        sqes: *mut Sqe,
+        cq_head: *const AtomicU32,
+        cq_tail: *const AtomicU32,
+        cq_mask: u32,
+        cqes: *const Cqe,
+        /// The submission queue's tail as pushed, ahead of the shared one until `submit`.
+        tail: u32,
+        entries: u32,
+        pending: u32,
+        _regions: [Region; 3],
+    }
+
+    impl Ring {
+        /// A ring of at least `entries` submissions.
+        pub fn new(entries: u32) -> io::Result<Self> {
+            let mut params = Params::default();
+            // Safety: the call fills `params`.
+            let fd = unsafe {
+                libc::syscall(libc::SYS_io_uring_setup, entries, &raw mut params) as RawFd
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // Safety: the descriptor is new and ours.
+            let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+            let (sq_off, cq_off) = (&params.sq_off, &params.cq_off);
+            let sq = Region::new(
+                &fd,
+                sq_off.array as usize + params.sq_entries as usize * size_of::<u32>(),
+                OFF_SQ_RING,
+            )?;
+            let cq = Region::new(
+                &fd,
+                cq_off.cqes as usize + params.cq_entries as usize * size_of::<Cqe>(),
+                OFF_CQ_RING,
+            )?;
+            let sqes = Region::new(&fd, params.sq_entries as usize * size_of::<Sqe>(), OFF_SQES)?;
+            // Safety: the ring is new, so nothing else writes its words.
+            let (tail, sq_mask, cq_mask) = unsafe {
+                (
+                    *sq.at::<u32>(sq_off.tail),
+                    *sq.at::<u32>(sq_off.ring_mask),
+                    *cq.at::<u32>(cq_off.ring_mask),
+                )
+            };
+            Ok(Self {
+                sq_tail: sq.at(sq_off.tail),
+                sq_mask,
+                sq_array: sq.at(sq_off.array),
+                sqes: sqes.at(0),
+                cq_head: cq.at(cq_off.head),
+                cq_tail: cq.at(cq_off.tail),
+                cq_mask,
+                cqes: cq.at(cq_off.cqes),
+                tail,
+                entries: params.sq_entries,
+                pending: 0,
+                fd,
+                _regions: [sq, cq, sqes],
+            })
+        }
+
+        /// Queues `sqe` for the next `submit`; the buffers it points to must outlive it.
+        pub fn push(&mut self, sqe: Sqe) {
+            assert!(self.pending < self.entries, "submission queue full");
+            let index = self.tail & self.sq_mask;
+            // Safety: `index` is within the queue, whose slot the kernel is done with.
+            unsafe {
+                self.sqes.add(index as usize).write(sqe);
+                self.sq_array.add(index as usize).write(index);
+            }
+            self.tail = self.tail.wrapping_add(1);
+            self.pending += 1;
+        }
+
+        /// Submits the queued entries and waits for `wait` completions.
+        pub fn submit(&mut self, wait: u32) -> io::Result<()> {
+            // Safety: the tail is a shared word of the ring.
+            unsafe { (*self.sq_tail).store(self.tail, Ordering::Release) };
+            // Safety: no signal mask is passed.
+            let submitted = unsafe {
+                libc::syscall(
+                    libc::SYS_io_uring_enter,
+                    self.fd.as_raw_fd(),
+                    self.pending,
+                    wait,
+                    ENTER_GETEVENTS,
+                    ptr::null::<libc::sigset_t>(),
+                    0,
+                )
+            };
+            if submitted < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            assert_eq!(submitted, self.pending as libc::c_long, "short submission");
+            self.pending = 0;
+            Ok(())
+        }
+
+        /// Passes every completion waiting in the queue to `f`.
+        pub fn complete(&mut self, mut f: impl FnMut(&Cqe)) {
+            // Safety: the head and tail are shared words of the ring, and the entries between
+            // them are the kernel's completions.
+            unsafe {
+                let head = (*self.cq_head).load(Ordering::Relaxed);
+                let tail = (*self.cq_tail).load(Ordering::Acquire);
+                let mut next = head;
+                while next != tail {
+                    f(&*self.cqes.add((next & self.cq_mask) as usize));
+                    next = next.wrapping_add(1);
+                }
+                (*self.cq_head).store(tail, Ordering::Release);
+            }
+        }
+    }
+}
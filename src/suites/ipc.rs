@@ -0,0 +1,322 @@
+//! Local IPC, the start of the networking benchmarks: messages of 64 bytes to 64 KiB between
+//! the measuring thread and a thread pinned to the same core (`*_same_core`) or another one
+//! (`*_cross_core`), over a pair of pipes (`pipe_*`), a Unix stream socket pair (`unix_stream_*`),
+//! a Unix datagram socket pair, one message per datagram (`unix_datagram_*`), a TCP
+//! connection over loopback with Nagle's algorithm off (`tcp_*`), and a POSIX message queue each
+//! way, holding one message of the benchmark's size (`mqueue_*`, round trips of the small sizes
+//! only, as queues take at most `fs.mqueue.msgsize_max` bytes, 8 KiB by default).
+//! `*_latency_*` sends a message and waits for the same size back, reported per round trip;
+//! `*_throughput_*` streams 256 KiB of messages one way and waits for a one-byte
+//! acknowledgement, reported as bandwidth.
+
+use std::{
+    ffi::CString,
+    io::{self, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    os::unix::net::{UnixDatagram, UnixStream},
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+use super::partner::{spawn_pinned, Placement};
+use crate::{bench::Bencher, register_bench};
+
+/// Bytes streamed per `*_throughput_*` sample.
+const STREAMED: usize = 256 << 10;
+/// Samples at most of `*_latency_*`, whose round trips take microseconds.
+const ITERATIONS: u64 = 10_000;
+/// Samples at most of `*_throughput_*`, whose streams of small messages take milliseconds.
+const STREAM_ITERATIONS: u64 = 1000;
+
+/// One end of a connection, sending and receiving whole messages.
+trait Link: Send {
+    fn send(&mut self, message: &[u8]);
+    fn receive(&mut self, message: &mut [u8]);
+}
+
+/// A byte stream, read and written through separate handles.
+struct Stream<R, W>(R, W);
+
+impl<R: Read + Send, W: Write + Send> Link for Stream<R, W> {
+    fn send(&mut self, message: &[u8]) {
+        self.1.write_all(message).unwrap();
+    }
+
+    fn receive(&mut self, message: &mut [u8]) {
+        self.0.read_exact(message).unwrap();
+    }
+}
+
+/// Two POSIX message queues, sending on one and receiving from the other.
+struct MessageQueue {
+    send: libc::mqd_t,
+    receive: libc::mqd_t,
+}
+
+impl MessageQueue {
+    /// Both ends of a pair of queues for messages of `size` bytes, unlinked once opened.
+    fn pair(size: usize) -> io::Result<(Self, Self)> {
+        // Safety: zero is a valid `mq_attr`.
+        let mut attr = unsafe { std::mem::zeroed::<libc::mq_attr>() };
+        attr.mq_maxmsg = 1;
+        attr.mq_msgsize = size as _;
+        let open = |direction| -> io::Result<[libc::mqd_t; 2]> {
+            let name =
+                CString::new(format!("/microbench-{}-{direction}", std::process::id())).unwrap();
+            let flags = libc::O_RDWR | libc::O_CREAT | libc::O_EXCL;
+            // Safety: `name` is a C string and `attr` a valid `mq_attr`; the second open takes
+            // neither mode nor attributes.
+            let ends = unsafe {
+                let created = libc::mq_open(name.as_ptr(), flags, 0o600 as libc::mode_t, &attr);
+                let opened = libc::mq_open(name.as_ptr(), libc::O_RDWR);
+                libc::mq_unlink(name.as_ptr());
+                [created, opened]
+            };
+            if !ends.contains(&-1) {
+                return Ok(ends);
+            }
+            let error = io::Error::last_os_error();
+            close(&ends);
+            Err(error)
+        };
+        let [out_here, out_there] = open("out")?;
+        let [back_here, back_there] =
+            open("back").inspect_err(|_| close(&[out_here, out_there]))?;
+        Ok((
+            Self {
+                send: out_here,
+                receive: back_here,
+            },
+            Self {
+                send: back_there,
+                receive: out_there,
+            },
+        ))
+    }
+}
+
+/// Closes the open ones of `queues`.
+fn close(queues: &[libc::mqd_t]) {
+    for &queue in queues.iter().filter(|&&queue| queue != -1) {
+        // Safety: the descriptor is ours and closed once.
+        unsafe { libc::mq_close(queue) };
+    }
+}
+
+impl Link for MessageQueue {
+    fn send(&mut self, message: &[u8]) {
+        // Safety: sends the bytes of `message`.
+        let sent = unsafe { libc::mq_send(self.send, message.as_ptr().cast(), message.len(), 0) };
+        assert_eq!(sent, 0, "mq_send failed");
+    }
+
+    fn receive(&mut self, message: &mut [u8]) {
+        // Safety: receives into `message`, as large as the queue's messages.
+        let received = unsafe {
+            libc::mq_receive(
+                self.receive,
+                message.as_mut_ptr().cast(),
+                message.len(),
+                ptr::null_mut(),
+            )
+        };
+        assert_eq!(received, message.len() as isize, "mq_receive failed");
+    }
+}
+
+impl Drop for MessageQueue {
+    fn drop(&mut self) {
+        close(&[self.send, self.receive]);
+    }
+}
+
+impl Link for UnixDatagram {
+    fn send(&mut self, message: &[u8]) {
+        assert_eq!(UnixDatagram::send(self, message).unwrap(), message.len());
+    }
+
+    fn receive(&mut self, message: &mut [u8]) {
+        assert_eq!(self.recv(message).unwrap(), message.len());
+    }
+}
+
+/// What the two ends are connected by.
+#[derive(Clone, Copy)]
+enum Transport {
+    Pipe,
+    UnixStream,
+    UnixDatagram,
+    Tcp,
+    MessageQueue,
+}
+
+impl Transport {
+    /// Both ends of a new connection for messages of `size` bytes.
+    fn connect(self, size: usize) -> io::Result<(Box<dyn Link>, Box<dyn Link>)> {
+        Ok(match self {
+            Transport::Pipe => {
+                let (there_read, here_write) = io::pipe()?;
+                let (here_read, there_write) = io::pipe()?;
+                (
+                    Box::new(Stream(here_read, here_write)),
+                    Box::new(Stream(there_read, there_write)),
+                )
+            }
+            Transport::UnixStream => {
+                let (here, there) = UnixStream::pair()?;
+                (
+                    Box::new(Stream(here.try_clone()?, here)),
+                    Box::new(Stream(there.try_clone()?, there)),
+                )
+            }
+            Transport::UnixDatagram => {
+                let (here, there) = UnixDatagram::pair()?;
+                (Box::new(here), Box::new(there))
+            }
+            Transport::Tcp => {
+                let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+                let here = TcpStream::connect(listener.local_addr()?)?;
+                let (there, _) = listener.accept()?;
+                here.set_nodelay(true)?;
+                there.set_nodelay(true)?;
+                (
+                    Box::new(Stream(here.try_clone()?, here)),
+                    Box::new(Stream(there.try_clone()?, there)),
+                )
+            }
+            Transport::MessageQueue => {
+                let (here, there) = MessageQueue::pair(size)?;
+                (Box::new(here), Box::new(there))
+            }
+        })
+    }
+}
+
+/// What one sample exchanges.
+#[derive(Clone, Copy)]
+enum Mode {
+    Latency,
+    Throughput,
+}
+
+/// Times `mode` exchanges of `size`-byte messages over `transport` with a thread placed per
+/// `placement`.
+fn exchange(b: &mut Bencher, transport: Transport, mode: Mode, size: usize, placement: Placement) {
+    let partner = match placement.partner() {
+        Ok(partner) => partner,
+        Err(reason) => return b.skip(reason),
+    };
+    let (mut here, mut there) = match transport.connect(size) {
+        Ok(ends) => ends,
+        Err(error) => return b.skip(format!("failed to connect: {error}")),
+    };
+    // Messages per sample, and the size of the answer to them.
+    let (messages, answer) = match mode {
+        Mode::Latency => {
+            b.limit_iterations(ITERATIONS);
+            (1, size)
+        }
+        Mode::Throughput => {
+            let messages = (STREAMED / size).max(1);
+            b.set_bytes((messages * size) as u64);
+            b.limit_iterations(STREAM_ITERATIONS);
+            (messages, 1)
+        }
+    };
+    let stop = &AtomicBool::new(false);
+    thread::scope(|scope| {
+        let spawned = spawn_pinned(scope, partner, move || {
+            let mut message = vec![0u8; size];
+            loop {
+                for _ in 0..messages {
+                    there.receive(&mut message);
+                }
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                there.send(&message[..answer]);
+            }
+        });
+        if let Err(reason) = spawned {
+            return b.skip(reason);
+        }
+        let mut message = vec![0xa5u8; size];
+        b.iter(|| {
+            for _ in 0..messages {
+                here.send(&message);
+            }
+            here.receive(&mut message[..answer]);
+        });
+        stop.store(true, Ordering::Relaxed);
+        for _ in 0..messages {
+            here.send(&message);
+        }
+    });
+}
+
+/// `$name` runs `exchange` with `$transport`, `$mode`, `$size` and `$placement`.
+macro_rules! ipc_benches {
+    ($($name:ident: $transport:ident, $mode:ident, $size:expr, $placement:ident;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            exchange(b, Transport::$transport, Mode::$mode, $size, Placement::$placement);
+        }
+
+        register_bench!($name, tags = ["ipc", "network", "multi-threaded"]);
+    )*};
+}
+
+ipc_benches! {
+    pipe_latency_64_same_core: Pipe, Latency, 64, SameCore;
+    pipe_latency_4k_same_core: Pipe, Latency, 4 << 10, SameCore;
+    pipe_latency_64k_same_core: Pipe, Latency, 64 << 10, SameCore;
+    pipe_latency_64_cross_core: Pipe, Latency, 64, CrossCore;
+    pipe_latency_4k_cross_core: Pipe, Latency, 4 << 10, CrossCore;
+    pipe_latency_64k_cross_core: Pipe, Latency, 64 << 10, CrossCore;
+    pipe_throughput_64_same_core: Pipe, Throughput, 64, SameCore;
+    pipe_throughput_4k_same_core: Pipe, Throughput, 4 << 10, SameCore;
+    pipe_throughput_64k_same_core: Pipe, Throughput, 64 << 10, SameCore;
+    pipe_throughput_64_cross_core: Pipe, Throughput, 64, CrossCore;
+    pipe_throughput_4k_cross_core: Pipe, Throughput, 4 << 10, CrossCore;
+    pipe_throughput_64k_cross_core: Pipe, Throughput, 64 << 10, CrossCore;
+    unix_stream_latency_64_same_core: UnixStream, Latency, 64, SameCore;
+    unix_stream_latency_4k_same_core: UnixStream, Latency, 4 << 10, SameCore;
+    unix_stream_latency_64k_same_core: UnixStream, Latency, 64 << 10, SameCore;
+    unix_stream_latency_64_cross_core: UnixStream, Latency, 64, CrossCore;
+    unix_stream_latency_4k_cross_core: UnixStream, Latency, 4 << 10, CrossCore;
+    unix_stream_latency_64k_cross_core: UnixStream, Latency, 64 << 10, CrossCore;
+    unix_stream_throughput_64_same_core: UnixStream, Throughput, 64, SameCore;
+    unix_stream_throughput_4k_same_core: UnixStream, Throughput, 4 << 10, SameCore;
+    unix_stream_throughput_64k_same_core: UnixStream, Throughput, 64 << 10, SameCore;
+    unix_stream_throughput_64_cross_core: UnixStream, Throughput, 64, CrossCore;
+    unix_stream_throughput_4k_cross_core: UnixStream, Throughput, 4 << 10, CrossCore;
+    unix_stream_throughput_64k_cross_core: UnixStream, Throughput, 64 << 10, CrossCore;
+    unix_datagram_latency_64_same_core: UnixDatagram, Latency, 64, SameCore;
+    unix_datagram_latency_4k_same_core: UnixDatagram, Latency, 4 << 10, SameCore;
+    unix_datagram_latency_64k_same_core: UnixDatagram, Latency, 64 << 10, SameCore;
+    unix_datagram_latency_64_cross_core: UnixDatagram, Latency, 64, CrossCore;
+    unix_datagram_latency_4k_cross_core: UnixDatagram, Latency, 4 << 10, CrossCore;
+    unix_datagram_latency_64k_cross_core: UnixDatagram, Latency, 64 << 10, CrossCore;
+    unix_datagram_throughput_64_same_core: UnixDatagram, Throughput, 64, SameCore;
+    unix_datagram_throughput_4k_same_core: UnixDatagram, Throughput, 4 << 10, SameCore;
+    unix_datagram_throughput_64k_same_core: UnixDatagram, Throughput, 64 << 10, SameCore;
+    unix_datagram_throughput_64_cross_core: UnixDatagram, Throughput, 64, CrossCore;
+    unix_datagram_throughput_4k_cross_core: UnixDatagram, Throughput, 4 << 10, CrossCore;
+    unix_datagram_throughput_64k_cross_core: UnixDatagram, Throughput, 64 << 10, CrossCore;
+    tcp_latency_64_same_core: Tcp, Latency, 64, SameCore;
+    tcp_latency_4k_same_core: Tcp, Latency, 4 << 10, SameCore;
+    tcp_latency_64k_same_core: Tcp, Latency, 64 << 10, SameCore;
+    tcp_latency_64_cross_core: Tcp, Latency, 64, CrossCore;
+    tcp_latency_4k_cross_core: Tcp, Latency, 4 << 10, CrossCore;
+    tcp_latency_64k_cross_core: Tcp, Latency, 64 << 10, CrossCore;
+    tcp_throughput_64_same_core: Tcp, Throughput, 64, SameCore;
+    tcp_throughput_4k_same_core: Tcp, Throughput, 4 << 10, SameCore;
+    tcp_throughput_64k_same_core: Tcp, Throughput, 64 << 10, SameCore;
+    tcp_throughput_64_cross_core: Tcp, Throughput, 64, CrossCore;
+    tcp_throughput_4k_cross_core: Tcp, Throughput, 4 << 10, CrossCore;
+    tcp_throughput_64k_cross_core: Tcp, Throughput, 64 << 10, CrossCore;
+    mqueue_latency_64_same_core: MessageQueue, Latency, 64, SameCore;
+    mqueue_latency_4k_same_core: MessageQueue, Latency, 4 << 10, SameCore;
+    mqueue_latency_64_cross_core: MessageQueue, Latency, 64, CrossCore;
+    mqueue_latency_4k_cross_core: MessageQueue, Latency, 4 << 10, CrossCore;
+}
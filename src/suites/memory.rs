@@ -0,0 +1,80 @@
+//! Load-to-use latency by working-set size, from a random pointer chase: every load's address
+//! is the value of the previous one, so they cannot overlap, and a random order defeats the
+//! prefetchers. Sizes from 4 KiB to 1 GiB trace the L1, L2, L3 and DRAM latencies (with TLB
+//! misses from the larger sizes on); `--report svg` plots them as one curve.
+
+use std::ptr;
+
+use crate::{
+    bench::{shuffle, Bencher},
+    register_bench,
+};
+
+pub(crate) const LINE: usize = 64;
+/// Dependent loads per sample, reported per load.
+const HOPS: usize = 1024;
+/// Samples per size; the chase carries on across samples, so this many hops visit 655 MB of
+/// lines, about 60% of the 1 GiB chain's.
+const ITERATIONS: u64 = 10_000;
+const SEED: u64 = 0x5eed;
+
+/// A random cycle through every cache line of a `bytes` buffer, each line's first word holding
+/// the address of the next.
+pub(crate) fn chain(bytes: usize) -> Vec<usize> {
+    let stride = LINE / size_of::<usize>();
+    let mut order = (0..bytes / LINE).collect::<Vec<_>>();
+    shuffle(&mut order, SEED);
+    let mut buffer = vec![0usize; bytes / size_of::<usize>()];
+    let base = buffer.as_ptr().expose_provenance();
+    for (i, &line) in order.iter().enumerate() {
+        buffer[line * stride] = base + order[(i + 1) % order.len()] * LINE;
+    }
+    buffer
+}
+
+pub(crate) fn chase(b: &mut Bencher, bytes: usize) {
+    b.set_ops(HOPS as u64);
+    b.limit_iterations(ITERATIONS);
+    let buffer = chain(bytes);
+    let mut address = buffer.as_ptr().expose_provenance();
+    b.iter(|| {
+        for _ in 0..HOPS {
+            // Safety: every link is the address of a line in `buffer`.
+            address = unsafe { *ptr::with_exposed_provenance::<usize>(address) };
+        }
+        address
+    });
+}
+
+/// `chase_<size>` over `$bytes`; the name's suffix is what the latency plot reads the size from.
+macro_rules! chase_benches {
+    ($($name:ident: $bytes:expr, tags = [$($tag:literal),*];)*) => {$(
+        fn $name(b: &mut Bencher) {
+            chase(b, $bytes);
+        }
+
+        register_bench!($name, tags = ["memory", "latency" $(, $tag)*]);
+    )*};
+}
+
+chase_benches! {
+    chase_4k: 4 << 10, tags = [];
+    chase_8k: 8 << 10, tags = [];
+    chase_16k: 16 << 10, tags = [];
+    chase_32k: 32 << 10, tags = [];
+    chase_64k: 64 << 10, tags = [];
+    chase_128k: 128 << 10, tags = [];
+    chase_256k: 256 << 10, tags = [];
+    chase_512k: 512 << 10, tags = [];
+    chase_1m: 1 << 20, tags = [];
+    chase_2m: 2 << 20, tags = [];
+    chase_4m: 4 << 20, tags = [];
+    chase_8m: 8 << 20, tags = [];
+    chase_16m: 16 << 20, tags = [];
+    chase_32m: 32 << 20, tags = [];
+    chase_64m: 64 << 20, tags = [];
+    chase_128m: 128 << 20, tags = [];
+    chase_256m: 256 << 20, tags = ["slow"];
+    chase_512m: 512 << 20, tags = ["slow"];
+    chase_1g: 1 << 30, tags = ["slow"];
+}
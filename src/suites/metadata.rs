@@ -0,0 +1,177 @@
+//! File system metadata syscalls on Linux, on one regular file and one symlink to it:
+//! `openat` and `close` (`*_open_close`), `statx` by path (`*_statx`), `fstat` on an open
+//! descriptor (`*_fstat`), `faccessat` (`*_faccessat`) and `readlinkat` (`*_readlink`). Paths
+//! are resolved relative to a directory descriptor, so every lookup is one component deep.
+//! `tmpfs_*` works in `/dev/shm` and `disk_*` in the current directory, like the `fsync` suite.
+
+use std::{
+    ffi::CStr,
+    fs::{self, File},
+    io,
+    os::{fd::AsRawFd, unix::fs::symlink},
+    path::PathBuf,
+    process,
+};
+
+use super::fsync::Location;
+use crate::{bench::Bencher, register_bench};
+
+const FILE: &CStr = c"file";
+const LINK: &CStr = c"link";
+
+/// A directory holding `FILE` and `LINK`, removed on drop.
+struct Fixture {
+    directory: File,
+    path: PathBuf,
+}
+
+impl Fixture {
+    fn new(location: Location) -> Result<Self, String> {
+        let parent = location.directory()?;
+        let path = parent.join(format!("microbench-metadata-{}", process::id()));
+        let create = || {
+            fs::create_dir(&path)?;
+            fs::write(path.join("file"), [0xa5; 4096])?;
+            symlink("file", path.join("link"))?;
+            File::open(&path)
+        };
+        match create() {
+            Ok(directory) => Ok(Self { directory, path }),
+            Err(error) => {
+                let _ = fs::remove_dir_all(&path);
+                Err(format!("failed to create {}: {error}", path.display()))
+            }
+        }
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Runs `call` on the fixture's directory descriptor, skipped if a first call fails, as where
+/// the kernel or a seccomp filter does not offer it.
+fn metadata(b: &mut Bencher, location: Location, call: fn(libc::c_int) -> libc::c_long) {
+    let fixture = match Fixture::new(location) {
+        Ok(fixture) => fixture,
+        Err(reason) => return b.skip(reason),
+    };
+    let directory = fixture.directory.as_raw_fd();
+    if call(directory) < 0 {
+        return b.skip(format!("call failed: {}", io::Error::last_os_error()));
+    }
+    b.iter(|| {
+        let result = call(directory);
+        assert!(result >= 0, "call failed");
+        result
+    });
+}
+
+fn open_close(directory: libc::c_int) -> libc::c_long {
+    // Safety: `FILE` is a C string, and the descriptor is closed right away.
+    unsafe {
+        let fd = libc::openat(directory, FILE.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return fd as libc::c_long;
+        }
+        libc::close(fd) as libc::c_long
+    }
+}
+
+fn statx(directory: libc::c_int) -> libc::c_long {
+    // Safety: zero is a valid `statx`, and the call fills it.
+    unsafe {
+        let mut stat = std::mem::zeroed::<libc::statx>();
+        let result = libc::statx(
+            directory,
+            FILE.as_ptr(),
+            0,
+            libc::STATX_BASIC_STATS,
+            &mut stat,
+        );
+        if result == 0 {
+            assert_eq!(stat.stx_size, 4096);
+        }
+        result as libc::c_long
+    }
+}
+
+fn faccessat(directory: libc::c_int) -> libc::c_long {
+    // Safety: `FILE` is a C string.
+    unsafe { libc::faccessat(directory, FILE.as_ptr(), libc::R_OK, 0) as libc::c_long }
+}
+
+fn readlink(directory: libc::c_int) -> libc::c_long {
+    let mut target = [0u8; 64];
+    // Safety: the call writes at most `target.len()` bytes.
+    let len = unsafe {
+        libc::readlinkat(
+            directory,
+            LINK.as_ptr(),
+            target.as_mut_ptr().cast(),
+            target.len(),
+        )
+    };
+    if len >= 0 {
+        assert_eq!(&target[..len as usize], b"file");
+    }
+    len as libc::c_long
+}
+
+/// `fstat` needs the file open, which the other calls do not.
+fn fstat(b: &mut Bencher, location: Location) {
+    let fixture = match Fixture::new(location) {
+        Ok(fixture) => fixture,
+        Err(reason) => return b.skip(reason),
+    };
+    let file = File::open(fixture.path.join("file")).unwrap();
+    let fd = file.as_raw_fd();
+    // Safety: zero is a valid `stat`, and the call fills it.
+    if unsafe { libc::fstat(fd, &mut std::mem::zeroed()) } != 0 {
+        return b.skip(format!("fstat failed: {}", io::Error::last_os_error()));
+    }
+    b.iter(|| {
+        // Safety: zero is a valid `stat`, and the call fills it.
+        let stat = unsafe {
+            let mut stat = std::mem::zeroed::<libc::stat>();
+            assert_eq!(libc::fstat(fd, &mut stat), 0, "fstat failed");
+            stat
+        };
+        stat.st_size
+    });
+}
+
+/// `$name` runs `$call` in `$location`.
+macro_rules! metadata_benches {
+    ($($name:ident: $location:ident, $call:ident;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            metadata(b, Location::$location, $call);
+        }
+
+        register_bench!($name, tags = ["metadata", "io", "syscall"]);
+    )*};
+}
+
+metadata_benches! {
+    tmpfs_open_close: Tmpfs, open_close;
+    tmpfs_statx: Tmpfs, statx;
+    tmpfs_faccessat: Tmpfs, faccessat;
+    tmpfs_readlink: Tmpfs, readlink;
+    disk_open_close: Disk, open_close;
+    disk_statx: Disk, statx;
+    disk_faccessat: Disk, faccessat;
+    disk_readlink: Disk, readlink;
+}
+
+fn tmpfs_fstat(b: &mut Bencher) {
+    fstat(b, Location::Tmpfs);
+}
+
+fn disk_fstat(b: &mut Bencher) {
+    fstat(b, Location::Disk);
+}
+
+register_bench!(tmpfs_fstat, tags = ["metadata", "io", "syscall"]);
+register_bench!(disk_fstat, tags = ["metadata", "io", "syscall"]);
@@ -0,0 +1,141 @@
+//! What moving between cores costs on Linux. `setaffinity_same` is `sched_setaffinity` to the
+//! core the thread already runs on, the syscall alone; `setaffinity_migrate` alternates between
+//! that core and another, each call returning on the new one, so it includes the migration.
+//!
+//! `chase_<stay|migrate>_<size>` is the penalty after it: each sample sets the affinity, to the
+//! same core (`stay`) or the other (`migrate`), then times one pass of a random pointer chase
+//! through every line of a working set, writing to each as it goes so the core left behind keeps
+//! no valid copy. After a migration the pass starts on cold private caches and TLB; reported per
+//! line. The other core is the first allowed one sharing no L2 with the runner's, or any other;
+//! the migrating benchmarks are skipped on one core.
+
+use std::{mem, ptr};
+
+use super::memory::{chain, LINE};
+use crate::{bench::Bencher, os, register_bench};
+
+/// Samples at most; a migration takes microseconds.
+const ITERATIONS: u64 = 2000;
+
+/// A mask with `cpus` set.
+pub(super) fn cpu_set(cpus: &[usize]) -> libc::cpu_set_t {
+    // Safety: an empty set is all zeroes.
+    let mut set = unsafe { mem::zeroed::<libc::cpu_set_t>() };
+    for &cpu in cpus {
+        // Safety: `cpu` is below the set's size for every CPU Linux numbers.
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    set
+}
+
+pub(super) fn set_affinity(set: &libc::cpu_set_t) {
+    // Safety: `set` is a whole `cpu_set_t`.
+    let result = unsafe { libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), set) };
+    assert_eq!(result, 0, "sched_setaffinity failed");
+}
+
+/// The runner's core and the one to migrate to, the same if not `migrate`, or why there is
+/// none.
+fn cores(migrate: bool) -> Result<(usize, usize), &'static str> {
+    let cpu = os::current_cpu().ok_or("current cpu unknown")?;
+    if !migrate {
+        return Ok((cpu, cpu));
+    }
+    let others = os::cpu_limits()
+        .cpuset
+        .into_iter()
+        .filter(|&other| other != cpu)
+        .collect::<Vec<_>>();
+    let l2 = os::l2_shared_cpus(cpu);
+    let other = others
+        .iter()
+        .find(|other| !l2.contains(other))
+        .or(others.first())
+        .ok_or("no other core")?;
+    Ok((cpu, *other))
+}
+
+/// Runs `f` with the masks of the runner's core and the other (see `cores`), then pins back to
+/// the first.
+fn with_cores(b: &mut Bencher, migrate: bool, f: impl FnOnce(&mut Bencher, [libc::cpu_set_t; 2])) {
+    let (cpu, other) = match cores(migrate) {
+        Ok(cores) => cores,
+        Err(reason) => return b.skip(reason),
+    };
+    b.limit_iterations(ITERATIONS);
+    f(b, [cpu_set(&[cpu]), cpu_set(&[other])]);
+    assert!(os::pin_to_core(cpu), "failed to pin back to core {cpu}");
+}
+
+fn setaffinity_same(b: &mut Bencher) {
+    with_cores(b, false, |b, [home, _]| b.iter(|| set_affinity(&home)));
+}
+
+fn setaffinity_migrate(b: &mut Bencher) {
+    with_cores(b, true, |b, sets| {
+        let mut next = 1;
+        b.iter(|| {
+            set_affinity(&sets[next]);
+            next ^= 1;
+        });
+    });
+}
+
+/// Times a pass through a `bytes` working set after moving to the other core each sample if
+/// `migrate`, or staying put.
+fn chase(b: &mut Bencher, bytes: usize, migrate: bool) {
+    with_cores(b, migrate, |b, sets| {
+        let lines = bytes / LINE;
+        b.set_ops(lines as u64);
+        let mut buffer = chain(bytes);
+        // The chase writes through the links, so they need the buffer's mutable provenance.
+        let mut address = buffer.as_mut_ptr().expose_provenance();
+        let mut next = 0;
+        b.iter_custom(|timer| {
+            next ^= 1;
+            set_affinity(&sets[next]);
+            let start = timer.now();
+            for _ in 0..lines {
+                let line = ptr::with_exposed_provenance_mut::<usize>(address);
+                // Safety: every link is the address of a line in `buffer`, which has a second
+                // word.
+                unsafe {
+                    *line.add(1) += 1;
+                    address = *line;
+                }
+            }
+            let end = timer.now();
+            end - start
+        });
+    });
+}
+
+/// `$name` runs a chase over `$bytes`, moving to the other core before each sample if
+/// `$migrate`.
+macro_rules! migration_benches {
+    ($($name:ident: $bytes:expr, migrate = $migrate:literal;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            chase(b, $bytes, $migrate);
+        }
+
+        register_bench!($name, tags = ["scheduling", "migration", "multi-threaded"]);
+    )*};
+}
+
+migration_benches! {
+    chase_stay_32k: 32 << 10, migrate = false;
+    chase_stay_256k: 256 << 10, migrate = false;
+    chase_stay_2m: 2 << 20, migrate = false;
+    chase_migrate_32k: 32 << 10, migrate = true;
+    chase_migrate_256k: 256 << 10, migrate = true;
+    chase_migrate_2m: 2 << 20, migrate = true;
+}
+
+register_bench!(
+    setaffinity_same,
+    tags = ["scheduling", "migration", "syscall", "multi-threaded"]
+);
+register_bench!(
+    setaffinity_migrate,
+    tags = ["scheduling", "migration", "multi-threaded"]
+);
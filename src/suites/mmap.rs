@@ -0,0 +1,151 @@
+//! Mapping churn on Linux, by size from 4 KiB to 1 GiB: an anonymous `mmap` and `munmap` pair
+//! that never touches the memory (`map_<size>`), which only creates and removes the area; the
+//! same with `MAP_POPULATE` (`populate_<size>`), which also faults in, zeroes and frees every
+//! page (2 MiB at a time where transparent huge pages are on), and is shown as bandwidth too;
+//! and `mremap` growing a populated mapping to twice its size (`mremap_grow_<size>`), which
+//! extends it in place where the address space after it is free and otherwise moves its page
+//! tables, never copying the pages. Reported per call or pair.
+//!
+//! `brk_<size>` grows the heap by `sbrk` and shrinks it back, the path malloc takes for its main
+//! arena, against `map_<size>`; `brk_touch_<size>` and `map_touch_<size>` also write a byte to
+//! every page in between, so both take the same page faults. The heap top is malloc's too, so
+//! nothing may allocate while it is grown: those benchmarks are tagged multi-threaded, which
+//! keeps `--jobs` from running anything beside them.
+
+use std::{hint::black_box, ptr};
+
+use crate::{bench::Bencher, memory::SMALL_PAGE, register_bench};
+
+/// Bytes populated per benchmark, spread over as many samples as fit.
+const BUDGET: u64 = 16 << 30;
+
+/// An anonymous private mapping of `len` bytes with extra `flags`.
+fn map(len: usize, flags: libc::c_int) -> *mut libc::c_void {
+    // Safety: a fresh anonymous mapping, checked below.
+    let map = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+    assert_ne!(
+        map,
+        libc::MAP_FAILED,
+        "mmap failed: {}",
+        std::io::Error::last_os_error()
+    );
+    map
+}
+
+fn unmap(map: *mut libc::c_void, len: usize) {
+    // Safety: `map` is a whole mapping of `len` bytes nothing refers to any more.
+    assert_eq!(unsafe { libc::munmap(map, len) }, 0, "munmap failed");
+}
+
+fn map_unmap(b: &mut Bencher, len: usize) {
+    b.iter(|| unmap(black_box(map(len, 0)), len));
+}
+
+/// Writes a byte to every page from `start` on.
+fn touch(start: *mut libc::c_void, len: usize) {
+    for offset in (0..len).step_by(SMALL_PAGE) {
+        // Safety: the caller owns `len` writable bytes from `start`.
+        unsafe { start.cast::<u8>().add(offset).write_volatile(1) };
+    }
+}
+
+fn map_touch(b: &mut Bencher, len: usize) {
+    b.limit_iterations((BUDGET / len as u64).max(10));
+    b.iter(|| {
+        let map = map(len, 0);
+        touch(map, len);
+        unmap(map, len);
+    });
+}
+
+/// Grows the heap by `len` bytes, touching them if `touch_pages`, and shrinks it back.
+fn grow_heap(len: usize, touch_pages: bool) {
+    // Safety: the runner allocates nothing until the heap is shrunk back below, so malloc never
+    // sees the bytes in between.
+    let start = unsafe { libc::sbrk(len as libc::intptr_t) };
+    assert_ne!(start as isize, -1, "sbrk failed");
+    if touch_pages {
+        touch(start, len);
+    }
+    // Safety: as above.
+    unsafe { libc::sbrk(-(len as libc::intptr_t)) };
+}
+
+fn brk(b: &mut Bencher, len: usize) {
+    b.iter(|| grow_heap(len, false));
+}
+
+fn brk_touch(b: &mut Bencher, len: usize) {
+    b.limit_iterations((BUDGET / len as u64).max(10));
+    b.iter(|| grow_heap(len, true));
+}
+
+fn populate(b: &mut Bencher, len: usize) {
+    b.set_bytes(len as u64);
+    b.limit_iterations((BUDGET / len as u64).max(10));
+    b.iter(|| unmap(black_box(map(len, libc::MAP_POPULATE)), len));
+}
+
+fn mremap_grow(b: &mut Bencher, len: usize) {
+    b.limit_iterations((BUDGET / len as u64).max(10));
+    b.iter_custom(|timer| {
+        let old = map(len, libc::MAP_POPULATE);
+        let start = timer.now();
+        // Safety: `old` is a whole mapping of `len` bytes, which the kernel may move.
+        let new = unsafe { libc::mremap(old, len, 2 * len, libc::MREMAP_MAYMOVE) };
+        let end = timer.now();
+        assert_ne!(new, libc::MAP_FAILED, "mremap failed");
+        unmap(new, 2 * len);
+        end - start
+    });
+}
+
+/// `$name` runs `$run` on a mapping of `$len` bytes, with any extra `tags`.
+macro_rules! mmap_benches {
+    ($($name:ident: $run:ident, $len:expr $(, tags = [$($tag:literal),*])?;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            $run(b, $len);
+        }
+
+        register_bench!($name, tags = ["mmap", "syscall" $($(, $tag)*)?]);
+    )*};
+}
+
+mmap_benches! {
+    map_4k: map_unmap, 4 << 10;
+    map_64k: map_unmap, 64 << 10;
+    map_2m: map_unmap, 2 << 20;
+    map_64m: map_unmap, 64 << 20;
+    map_1g: map_unmap, 1 << 30, tags = ["slow"];
+    populate_4k: populate, 4 << 10;
+    populate_64k: populate, 64 << 10;
+    populate_2m: populate, 2 << 20;
+    populate_64m: populate, 64 << 20;
+    populate_1g: populate, 1 << 30, tags = ["slow"];
+    mremap_grow_4k: mremap_grow, 4 << 10;
+    mremap_grow_64k: mremap_grow, 64 << 10;
+    mremap_grow_2m: mremap_grow, 2 << 20;
+    mremap_grow_64m: mremap_grow, 64 << 20;
+    mremap_grow_1g: mremap_grow, 1 << 30, tags = ["slow"];
+    map_touch_4k: map_touch, 4 << 10;
+    map_touch_64k: map_touch, 64 << 10;
+    map_touch_2m: map_touch, 2 << 20;
+    map_touch_64m: map_touch, 64 << 20;
+    brk_4k: brk, 4 << 10, tags = ["brk", "multi-threaded"];
+    brk_64k: brk, 64 << 10, tags = ["brk", "multi-threaded"];
+    brk_2m: brk, 2 << 20, tags = ["brk", "multi-threaded"];
+    brk_64m: brk, 64 << 20, tags = ["brk", "multi-threaded"];
+    brk_touch_4k: brk_touch, 4 << 10, tags = ["brk", "multi-threaded"];
+    brk_touch_64k: brk_touch, 64 << 10, tags = ["brk", "multi-threaded"];
+    brk_touch_2m: brk_touch, 2 << 20, tags = ["brk", "multi-threaded"];
+    brk_touch_64m: brk_touch, 64 << 20, tags = ["brk", "multi-threaded"];
+}
@@ -0,0 +1,86 @@
+mod aes;
+mod alignment;
+mod alu;
+mod atomic;
+pub(crate) mod bandwidth;
+mod bits;
+mod bounds;
+mod branch;
+#[cfg(target_arch = "x86_64")]
+mod cache;
+mod call;
+#[cfg(target_os = "linux")]
+mod context_switch;
+#[cfg(target_os = "macos")]
+mod darwin;
+#[cfg(target_arch = "x86_64")]
+mod denormal;
+mod divide;
+#[cfg(target_arch = "x86_64")]
+mod downclock;
+#[cfg(target_os = "linux")]
+mod eventfd;
+#[cfg(target_arch = "x86_64")]
+mod forwarding;
+mod fp;
+#[cfg(target_os = "linux")]
+mod fsync;
+#[cfg(target_os = "linux")]
+mod futex;
+#[cfg(target_arch = "x86_64")]
+mod gather;
+#[cfg(target_os = "linux")]
+mod getcpu;
+#[cfg(target_arch = "x86_64")]
+mod icache;
+mod indirect;
+#[cfg(target_arch = "x86_64")]
+mod instruction;
+#[cfg(target_os = "linux")]
+mod io_uring;
+#[cfg(target_os = "linux")]
+mod ipc;
+pub(crate) mod memory;
+#[cfg(target_os = "linux")]
+mod metadata;
+#[cfg(target_os = "linux")]
+mod migration;
+#[cfg(target_os = "linux")]
+mod mmap;
+mod overflow;
+#[cfg(target_os = "linux")]
+mod page_fault;
+mod partner;
+#[cfg(target_arch = "x86_64")]
+mod ports;
+#[cfg(target_arch = "x86_64")]
+mod prefetch;
+#[cfg(target_os = "linux")]
+mod process;
+mod random;
+mod sha;
+#[cfg(target_os = "linux")]
+mod shm;
+#[cfg(target_os = "linux")]
+mod signal;
+#[cfg(target_arch = "x86_64")]
+mod simd;
+#[cfg(target_os = "linux")]
+mod sleep;
+#[cfg(target_arch = "x86_64")]
+mod speculation;
+mod spin;
+#[cfg(target_os = "linux")]
+mod syscall;
+#[cfg(target_os = "linux")]
+mod thread;
+#[cfg(target_os = "linux")]
+mod tlb;
+#[cfg(target_arch = "x86_64")]
+mod uop_cache;
+#[cfg(target_os = "linux")]
+mod vectored;
+#[cfg(windows)]
+mod win32;
+#[cfg(target_arch = "x86_64")]
+mod x87;
@@ -0,0 +1,82 @@
+//! What overflow checks cost in a hot loop: `u64` additions that never overflow, done with
+//! `wrapping_add`, `overflowing_add` (the flags collected and checked once), `checked_add`
+//! (stopping at the first overflow) and `strict_add`, which panics like `+` in a debug build.
+//! `<op>_sum` reduces an array, which only the wrapping form lets the compiler vectorize;
+//! `<op>_chain` runs a dependent chain through `opaque`, like `alu::add_u64_latency`, which
+//! leaves just the check on the critical path. Reported per addition.
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+const OPS: usize = 1024;
+
+/// `$sum` adds up `OPS` small numbers and `$chain` adds one `OPS` times, both with `$op`, which
+/// gives `None` on overflow.
+macro_rules! overflow_benches {
+    ($($sum:ident, $chain:ident: |$x:ident, $y:ident| $op:expr;)*) => {$(
+        fn $sum(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            let data = (0..OPS as u64).collect::<Vec<_>>();
+            b.iter(|| {
+                let mut $x = 0u64;
+                for &$y in &data {
+                    $x = $op?;
+                }
+                Some($x)
+            });
+        }
+
+        fn $chain(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            b.iter(|| {
+                let mut $x = opaque(0u64);
+                for _ in 0..OPS {
+                    let $y = 1;
+                    $x = opaque($op?);
+                }
+                Some($x)
+            });
+        }
+
+        register_bench!($sum, tags = ["alu", "overflow"]);
+        register_bench!($chain, tags = ["alu", "overflow", "latency"]);
+    )*};
+}
+
+overflow_benches! {
+    wrapping_sum, wrapping_chain: |x, y| Some(x.wrapping_add(y));
+    checked_sum, checked_chain: |x, y| x.checked_add(y);
+    strict_sum, strict_chain: |x, y| Some(x.strict_add(y));
+}
+
+/// The same loops with `overflowing_add`, the overflow flags or-ed together and checked once
+/// at the end.
+fn overflowing_sum(b: &mut Bencher) {
+    b.set_ops(OPS as u64);
+    let data = (0..OPS as u64).collect::<Vec<_>>();
+    b.iter(|| {
+        let (mut sum, mut overflowed) = (0u64, false);
+        for &y in &data {
+            let (next, overflow) = sum.overflowing_add(y);
+            (sum, overflowed) = (next, overflowed | overflow);
+        }
+        (!overflowed).then_some(sum)
+    });
+}
+
+fn overflowing_chain(b: &mut Bencher) {
+    b.set_ops(OPS as u64);
+    b.iter(|| {
+        let (mut x, mut overflowed) = (opaque(0u64), false);
+        for _ in 0..OPS {
+            let (next, overflow) = x.overflowing_add(1);
+            (x, overflowed) = (opaque(next), overflowed | overflow);
+        }
+        (!overflowed).then_some(x)
+    });
+}
+
+register_bench!(overflowing_sum, tags = ["alu", "overflow"]);
+register_bench!(overflowing_chain, tags = ["alu", "overflow", "latency"]);
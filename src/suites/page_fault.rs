@@ -0,0 +1,134 @@
+//! Page fault cost on Linux, per 4 KiB page touched for the first time, transparent huge pages
+//! off: reads of fresh anonymous memory (`anonymous_read`), which map the shared zero page,
+//! and writes (`anonymous_write`), which allocate and zero a page each; writes to memory mapped
+//! with `MAP_POPULATE` (`populated_write`), which fault nothing and leave the TLB misses alone;
+//! and reads of a file mapping whose pages are in the page cache (`file_cached_read`), where
+//! each fault maps up to 16 of them around the one touched, or were dropped from it before the
+//! sample (`file_uncached_read`, readahead off, so every page is read from the disk on its own
+//! unless the file system keeps files in memory, like tmpfs).
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    os::fd::AsRawFd,
+    process, ptr,
+};
+
+use crate::{bench::Bencher, memory::SMALL_PAGE, register_bench};
+
+/// Pages touched per sample, reported per page.
+const PAGES: usize = 1024;
+const LEN: usize = PAGES * SMALL_PAGE;
+const ITERATIONS: u64 = 1000;
+/// Samples for `file_uncached_read`, which waits for the disk on every page.
+const UNCACHED_ITERATIONS: u64 = 20;
+
+/// What backs the pages touched.
+#[derive(Clone, Copy, PartialEq)]
+enum Source {
+    Anonymous,
+    Populated,
+    FileCached,
+    FileUncached,
+}
+
+/// A file of `LEN` bytes, unlinked once open.
+fn file() -> File {
+    let path = std::env::temp_dir().join(format!("microbench-page-fault-{}", process::id()));
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .expect("failed to create the file to map");
+    fs::remove_file(&path).unwrap();
+    file.write_all(&vec![0xa5; LEN]).unwrap();
+    file.sync_all().unwrap();
+    file
+}
+
+/// Maps `LEN` bytes from `source`, `file` for the file-backed ones.
+fn map(source: Source, file: Option<&File>) -> *mut u8 {
+    let (flags, fd) = match (source, file) {
+        (Source::Populated, _) => (libc::MAP_ANONYMOUS | libc::MAP_POPULATE, -1),
+        (_, Some(file)) => (0, file.as_raw_fd()),
+        _ => (libc::MAP_ANONYMOUS, -1),
+    };
+    // Safety: a fresh mapping of `LEN` bytes, of a file at least that long if any, checked below.
+    let map = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            LEN,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | flags,
+            fd,
+            0,
+        )
+    };
+    assert_ne!(
+        map,
+        libc::MAP_FAILED,
+        "mmap failed: {}",
+        std::io::Error::last_os_error()
+    );
+    let advice = match source {
+        Source::FileUncached => libc::MADV_RANDOM,
+        _ => libc::MADV_NOHUGEPAGE,
+    };
+    // Safety: advises the whole mapping just made. `MAP_POPULATE` has already faulted, but its
+    // pages were never huge ones unless transparent huge pages are always on.
+    unsafe { libc::madvise(map, LEN, advice) };
+    map.cast()
+}
+
+/// Times touching every page of `source`, reading or writing one byte each.
+fn touch(b: &mut Bencher, source: Source, write: bool) {
+    b.set_ops(PAGES as u64);
+    let file = matches!(source, Source::FileCached | Source::FileUncached).then(file);
+    b.limit_iterations(match source {
+        Source::FileUncached => UNCACHED_ITERATIONS,
+        _ => ITERATIONS,
+    });
+    b.iter_custom(|timer| {
+        if let (Source::FileUncached, Some(file)) = (source, &file) {
+            // Safety: drops the file's clean pages from the page cache, which nothing maps now.
+            unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+        }
+        let map = map(source, file.as_ref());
+        let start = timer.now();
+        for page in 0..PAGES {
+            // Safety: every page is within the mapping.
+            unsafe {
+                let byte = map.add(page * SMALL_PAGE);
+                if write {
+                    byte.write_volatile(1);
+                } else {
+                    byte.read_volatile();
+                }
+            }
+        }
+        let end = timer.now();
+        // Safety: unmaps the mapping made above, which nothing refers to any more.
+        unsafe { libc::munmap(map.cast(), LEN) };
+        end - start
+    });
+}
+
+/// `$name` touches the pages of `$source`, writing if `$write`.
+macro_rules! page_fault_benches {
+    ($($name:ident: $source:ident, write = $write:literal;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            touch(b, Source::$source, $write);
+        }
+
+        register_bench!($name, tags = ["page-fault", "mmap"]);
+    )*};
+}
+
+page_fault_benches! {
+    anonymous_read: Anonymous, write = false;
+    anonymous_write: Anonymous, write = true;
+    populated_write: Populated, write = true;
+    file_cached_read: FileCached, write = false;
+    file_uncached_read: FileUncached, write = false;
+}
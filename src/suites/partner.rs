@@ -0,0 +1,52 @@
+//! The partner thread the cross-core benchmarks measure against: where it runs, and spawning it
+//! pinned there before the measurement starts.
+
+use std::{
+    sync::mpsc,
+    thread::{Scope, ScopedJoinHandle},
+};
+
+use crate::os;
+
+/// Where the partner runs.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy)]
+pub(super) enum Placement {
+    SameCore,
+    CrossCore,
+}
+
+#[cfg(target_os = "linux")]
+impl Placement {
+    /// The partner's core: the measuring thread's, or the first other one allowed; or why there
+    /// is none.
+    pub(super) fn partner(self) -> Result<usize, &'static str> {
+        let cpu = os::current_cpu().ok_or("current cpu unknown")?;
+        match self {
+            Placement::SameCore => Ok(cpu),
+            Placement::CrossCore => os::other_core(cpu).ok_or("no other core"),
+        }
+    }
+}
+
+/// Spawns a thread on `scope` that pins itself to `cpu` and then runs `f`, returning once it is
+/// pinned; the reason to skip the benchmark if it could not be.
+pub(super) fn spawn_pinned<'scope>(
+    scope: &'scope Scope<'scope, '_>,
+    cpu: usize,
+    f: impl FnOnce() + Send + 'scope,
+) -> Result<ScopedJoinHandle<'scope, ()>, String> {
+    let (pinned_tx, pinned_rx) = mpsc::channel();
+    let handle = scope.spawn(move || {
+        let pinned = os::pin_to_core(cpu);
+        pinned_tx.send(pinned).unwrap();
+        if pinned {
+            f();
+        }
+    });
+    if pinned_rx.recv().unwrap() {
+        Ok(handle)
+    } else {
+        Err(format!("failed to pin to core {cpu}"))
+    }
+}
@@ -0,0 +1,90 @@
+//! Execution-port pressure in the style of uarch-bench: unrolled asm loops of one instruction
+//! mix each, eight independent instructions per group so no dependency chain limits them, which
+//! leaves only the ports that can execute them. Pure loads, stores, adds, multiplies, shifts,
+//! `lea`s and vector adds each saturate their own ports; `load_alu` and `load_store` show
+//! whether two kinds overlap. Reported per instruction, the reciprocal of the throughput in
+//! reference cycles; `--counters` gives the IPC in core cycles.
+
+use core::arch::asm;
+
+use crate::{bench::Bencher, register_bench};
+
+/// Instructions per group, groups per loop iteration (the `.rept` count) and loop iterations
+/// per sample.
+const GROUP: usize = 8;
+const UNROLL: usize = 16;
+const ITERATIONS: usize = 64;
+
+#[repr(C, align(64))]
+struct Lines([u64; 16]);
+
+/// `$name` runs its eight instructions `UNROLL` times per loop iteration. They may use the two
+/// cache lines `r11` points to and the scratch registers `rax`, `rcx`, `rdx`, `rsi`, `rdi`,
+/// `r8` to `r10` and `xmm0` to `xmm7`.
+macro_rules! port_benches {
+    ($($name:ident: $($instruction:literal)*;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            b.set_ops((GROUP * UNROLL * ITERATIONS) as u64);
+            let mut lines = Lines([0; 16]);
+            let buf = lines.0.as_mut_ptr();
+            b.iter(|| {
+                // Safety: every access lies within `lines`, and the scratch registers are
+                // declared clobbered.
+                unsafe {
+                    asm!(
+                        "2:",
+                        ".rept {unroll}",
+                        $($instruction,)*
+                        ".endr",
+                        "dec {n}",
+                        "jnz 2b",
+                        in("r11") buf,
+                        n = inout(reg) ITERATIONS => _,
+                        unroll = const UNROLL,
+                        out("rax") _, out("rcx") _, out("rdx") _, out("rsi") _, out("rdi") _,
+                        out("r8") _, out("r9") _, out("r10") _,
+                        out("xmm0") _, out("xmm1") _, out("xmm2") _, out("xmm3") _,
+                        out("xmm4") _, out("xmm5") _, out("xmm6") _, out("xmm7") _,
+                        options(nostack),
+                    )
+                };
+            });
+        }
+
+        register_bench!($name, tags = ["ports", "throughput"]);
+    )*};
+}
+
+port_benches! {
+    load:
+        "mov rax, [r11]" "mov rcx, [r11 + 8]" "mov rdx, [r11 + 16]" "mov rsi, [r11 + 24]"
+        "mov rdi, [r11 + 32]" "mov r8, [r11 + 40]" "mov r9, [r11 + 48]"
+        "mov r10, [r11 + 56]";
+    store:
+        "mov [r11 + 64], rax" "mov [r11 + 72], rcx" "mov [r11 + 80], rdx"
+        "mov [r11 + 88], rsi" "mov [r11 + 96], rdi" "mov [r11 + 104], r8"
+        "mov [r11 + 112], r9" "mov [r11 + 120], r10";
+    alu:
+        "add rax, 1" "add rcx, 1" "add rdx, 1" "add rsi, 1" "add rdi, 1" "add r8, 1" "add r9, 1"
+        "add r10, 1";
+    mul:
+        "imul rax, rax" "imul rcx, rcx" "imul rdx, rdx" "imul rsi, rsi" "imul rdi, rdi"
+        "imul r8, r8" "imul r9, r9" "imul r10, r10";
+    shift:
+        "shl rax, 1" "shl rcx, 1" "shl rdx, 1" "shl rsi, 1" "shl rdi, 1" "shl r8, 1" "shl r9, 1"
+        "shl r10, 1";
+    lea:
+        "lea rax, [rax + rax]" "lea rcx, [rcx + rcx]" "lea rdx, [rdx + rdx]"
+        "lea rsi, [rsi + rsi]" "lea rdi, [rdi + rdi]" "lea r8, [r8 + r8]" "lea r9, [r9 + r9]"
+        "lea r10, [r10 + r10]";
+    vector_add:
+        "paddd xmm0, xmm0" "paddd xmm1, xmm1" "paddd xmm2, xmm2" "paddd xmm3, xmm3"
+        "paddd xmm4, xmm4" "paddd xmm5, xmm5" "paddd xmm6, xmm6" "paddd xmm7, xmm7";
+    load_alu:
+        "mov rax, [r11]" "add rcx, 1" "mov rdx, [r11 + 8]" "add rsi, 1"
+        "mov rdi, [r11 + 16]" "add r8, 1" "mov r9, [r11 + 24]" "add r10, 1";
+    load_store:
+        "mov rax, [r11]" "mov [r11 + 64], rcx" "mov rdx, [r11 + 8]" "mov [r11 + 72], rsi"
+        "mov rdi, [r11 + 16]" "mov [r11 + 80], r8" "mov r9, [r11 + 24]"
+        "mov [r11 + 88], r10";
+}
@@ -0,0 +1,117 @@
+//! Software prefetching (`_mm_prefetch`) ahead of two access patterns the hardware prefetchers
+//! cannot follow, against the same pattern without it:
+//!
+//! - `strided_*` reads one word per 4 KiB page plus a line, a stride too long for them.
+//! - `chase_*` follows a random chain of lines. Each line also holds a jump pointer to the
+//!   line `distance` hops ahead, which is what gets prefetched.
+//!
+//! Each runs with every hint (`t0`, `t1`, `t2`, `nta`) at distances of 4, 16 and 64 accesses,
+//! over a buffer well beyond the last-level cache.
+
+use core::arch::x86_64::{_mm_prefetch, _MM_HINT_NTA, _MM_HINT_T0, _MM_HINT_T1, _MM_HINT_T2};
+
+use crate::{
+    bench::{shuffle, Bencher},
+    register_bench,
+};
+
+const BYTES: usize = 128 << 20;
+const LINE_WORDS: usize = 64 / size_of::<u64>();
+/// A page and a line, in words.
+const STRIDE_WORDS: usize = (4096 + 64) / size_of::<u64>();
+/// Accesses per sample, reported per access.
+const OPS: usize = 1024;
+const ITERATIONS: u64 = 10_000;
+const SEED: u64 = 0x5eed;
+
+fn strided(b: &mut Bencher, distance: usize, prefetch: impl Fn(*const i8)) {
+    b.set_ops(OPS as u64);
+    b.limit_iterations(ITERATIONS);
+    let data = vec![1u64; BYTES / size_of::<u64>()];
+    let steps = data.len() / STRIDE_WORDS;
+    let mut step = 0;
+    b.iter(|| {
+        let mut sum = 0u64;
+        for _ in 0..OPS {
+            let ahead = (step + distance) % steps;
+            prefetch(data[ahead * STRIDE_WORDS..].as_ptr().cast());
+            sum = sum.wrapping_add(data[step * STRIDE_WORDS]);
+            step += 1;
+            if step == steps {
+                step = 0;
+            }
+        }
+        sum
+    });
+}
+
+fn chase(b: &mut Bencher, distance: usize, prefetch: impl Fn(*const i8)) {
+    b.set_ops(OPS as u64);
+    b.limit_iterations(ITERATIONS);
+    // Word 0 of a line is the index of the next line, word 1 that of the line `distance` on.
+    let mut lines = vec![[0u64; LINE_WORDS]; BYTES / (LINE_WORDS * size_of::<u64>())];
+    let mut order = (0..lines.len()).collect::<Vec<_>>();
+    shuffle(&mut order, SEED);
+    for (i, &line) in order.iter().enumerate() {
+        lines[line][0] = order[(i + 1) % order.len()] as u64;
+        lines[line][1] = order[(i + distance) % order.len()] as u64;
+    }
+    let mut current = order[0];
+    b.iter(|| {
+        for _ in 0..OPS {
+            let line = &lines[current];
+            prefetch(lines[line[1] as usize].as_ptr().cast());
+            current = line[0] as usize;
+        }
+        current
+    });
+}
+
+fn strided_none(b: &mut Bencher) {
+    strided(b, 0, |_| {});
+}
+
+fn chase_none(b: &mut Bencher) {
+    chase(b, 0, |_| {});
+}
+
+register_bench!(strided_none, tags = ["memory", "prefetch"]);
+register_bench!(chase_none, tags = ["memory", "prefetch"]);
+
+/// `<pattern>_<hint>_<distance>`.
+macro_rules! prefetch_benches {
+    ($($name:ident: $pattern:ident, $hint:ident, $distance:literal;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            $pattern(b, $distance, |p| unsafe { _mm_prefetch::<$hint>(p) });
+        }
+
+        register_bench!($name, tags = ["memory", "prefetch"]);
+    )*};
+}
+
+prefetch_benches! {
+    strided_t0_4: strided, _MM_HINT_T0, 4;
+    strided_t0_16: strided, _MM_HINT_T0, 16;
+    strided_t0_64: strided, _MM_HINT_T0, 64;
+    strided_t1_4: strided, _MM_HINT_T1, 4;
+    strided_t1_16: strided, _MM_HINT_T1, 16;
+    strided_t1_64: strided, _MM_HINT_T1, 64;
+    strided_t2_4: strided, _MM_HINT_T2, 4;
+    strided_t2_16: strided, _MM_HINT_T2, 16;
+    strided_t2_64: strided, _MM_HINT_T2, 64;
+    strided_nta_4: strided, _MM_HINT_NTA, 4;
+    strided_nta_16: strided, _MM_HINT_NTA, 16;
+    strided_nta_64: strided, _MM_HINT_NTA, 64;
+    chase_t0_4: chase, _MM_HINT_T0, 4;
+    chase_t0_16: chase, _MM_HINT_T0, 16;
+    chase_t0_64: chase, _MM_HINT_T0, 64;
+    chase_t1_4: chase, _MM_HINT_T1, 4;
+    chase_t1_16: chase, _MM_HINT_T1, 16;
+    chase_t1_64: chase, _MM_HINT_T1, 64;
+    chase_t2_4: chase, _MM_HINT_T2, 4;
+    chase_t2_16: chase, _MM_HINT_T2, 16;
+    chase_t2_64: chase, _MM_HINT_T2, 64;
+    chase_nta_4: chase, _MM_HINT_NTA, 4;
+    chase_nta_16: chase, _MM_HINT_NTA, 16;
+    chase_nta_64: chase, _MM_HINT_NTA, 64;
+}
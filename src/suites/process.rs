@@ -0,0 +1,191 @@
+//! Process creation on Linux, each from the call to the parent reaping the child: `fork` with
+//! the child calling `_exit` at once (`fork_*`); a vfork-style `clone(CLONE_VM | CLONE_VFORK)`
+//! whose child runs `/bin/true` (`vfork_exec_*`), the parent sleeping until the exec; glibc's
+//! `posix_spawn` of the same (`posix_spawn_*`), which does that internally; and a raw `clone3`
+//! syscall with fork semantics (`clone3_*`), which skips glibc's fork handlers. `*_small` runs
+//! from the runner's own address space and `*_large` after mapping and touching 1 GiB more in
+//! 4 KiB pages, whose page tables `fork` and `clone3` copy while the others share them.
+//! The `thread` suite's `std_spawn`, a `std::thread` spawned and joined, is the baseline.
+//!
+//! `command_*` launches `true` (found through `PATH`) with `std::process::Command` for Rust's
+//! overhead on top: `status()` (`command_status_*`), spawning with all three streams piped and
+//! waiting (`command_piped_*`), and with an empty `pre_exec` hook (`command_pre_exec_*`), which
+//! keeps the standard library from using `posix_spawn`, so it forks and execs instead.
+
+use std::{
+    ffi::CStr,
+    io,
+    os::unix::process::CommandExt,
+    path::Path,
+    process::{Command, Stdio},
+    ptr,
+};
+
+use crate::{
+    bench::Bencher,
+    memory::{Mapping, Pages},
+    register_bench,
+};
+
+/// Samples at most; the largest forks take milliseconds.
+const ITERATIONS: u64 = 500;
+/// Memory mapped on top of the runner's own for `*_large`.
+const LARGE: usize = 1 << 30;
+const TRUE: &CStr = c"/bin/true";
+/// Stack for the vfork child, which only calls `execve`.
+const CHILD_STACK: usize = 64 << 10;
+
+/// Waits for `pid`, asserting it exited with 0.
+fn reap(pid: libc::pid_t) {
+    assert!(pid > 0, "spawn failed: {}", io::Error::last_os_error());
+    let mut status = 0;
+    // Safety: `pid` is our child.
+    assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+    assert!(
+        libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0,
+        "child failed with status {status:#x}"
+    );
+}
+
+fn fork() {
+    // Safety: the child only calls `_exit`, which is async-signal-safe.
+    match unsafe { libc::fork() } {
+        0 => unsafe { libc::_exit(0) },
+        pid => reap(pid),
+    }
+}
+
+/// `argv` and `envp` for `/bin/true`: its name, and no environment.
+fn true_argv() -> [*const libc::c_char; 2] {
+    [TRUE.as_ptr(), ptr::null()]
+}
+
+extern "C" fn exec_true(_: *mut libc::c_void) -> libc::c_int {
+    let argv = true_argv();
+    let envp = [ptr::null()];
+    // Safety: the arguments are null-terminated arrays of C strings; only `execve` and `_exit`
+    // run in the child, which shares the parent's memory until either.
+    unsafe {
+        libc::execve(TRUE.as_ptr(), argv.as_ptr(), envp.as_ptr());
+        libc::_exit(127)
+    }
+}
+
+fn vfork_exec(stack: &mut [u8]) {
+    let top = stack.as_mut_ptr_range().end;
+    let top = top.wrapping_sub(top.addr() % 16);
+    // Safety: the child runs `exec_true` on its own stack, and the parent sleeps until it
+    // execs or exits.
+    let pid = unsafe {
+        libc::clone(
+            exec_true,
+            top.cast(),
+            libc::CLONE_VM | libc::CLONE_VFORK | libc::SIGCHLD,
+            ptr::null_mut(),
+        )
+    };
+    reap(pid);
+}
+
+fn posix_spawn() {
+    let argv = true_argv();
+    let envp = [ptr::null_mut()];
+    let mut pid = 0;
+    // Safety: the arguments are null-terminated arrays of C strings, and no file actions or
+    // attributes are passed.
+    let error = unsafe {
+        libc::posix_spawn(
+            &mut pid,
+            TRUE.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            argv.as_ptr().cast(),
+            envp.as_ptr(),
+        )
+    };
+    assert_eq!(error, 0, "posix_spawn failed");
+    reap(pid);
+}
+
+fn command_status() {
+    assert!(Command::new("true").status().unwrap().success());
+}
+
+fn command_piped() {
+    let child = Command::new("true")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    assert!(child.wait_with_output().unwrap().status.success());
+}
+
+fn command_pre_exec() {
+    let mut command = Command::new("true");
+    // Safety: the hook does nothing, which is async-signal-safe.
+    unsafe { command.pre_exec(|| Ok(())) };
+    assert!(command.status().unwrap().success());
+}
+
+fn clone3() {
+    // Safety: zero is a valid value for every field.
+    let mut args = unsafe { std::mem::zeroed::<libc::clone_args>() };
+    args.exit_signal = libc::SIGCHLD as u64;
+    // Safety: without `CLONE_VM` the child gets a copy of the address space, like after `fork`,
+    // and only makes the `exit_group` syscall.
+    match unsafe { libc::syscall(libc::SYS_clone3, &mut args, size_of::<libc::clone_args>()) } {
+        0 => unsafe {
+            libc::syscall(libc::SYS_exit_group, 0);
+            unreachable!()
+        },
+        pid => reap(pid as libc::pid_t),
+    }
+}
+
+/// Times `spawn`, after mapping `LARGE` bytes more if `large`.
+fn spawn(b: &mut Bencher, large: bool, exec: bool, mut spawn: impl FnMut()) {
+    if exec && !Path::new(TRUE.to_str().unwrap()).exists() {
+        return b.skip("no /bin/true");
+    }
+    let _mapping = match large.then(|| Mapping::new(LARGE, Pages::Small)).transpose() {
+        Ok(mapping) => mapping,
+        Err(error) => return b.skip(format!("failed to map {LARGE} bytes: {error}")),
+    };
+    b.limit_iterations(ITERATIONS);
+    b.iter(&mut spawn);
+}
+
+/// `$name` spawns through `$spawn`, with a large address space if `$large`.
+macro_rules! process_benches {
+    ($($name:ident: large = $large:literal, exec = $exec:literal, $spawn:expr;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            spawn(b, $large, $exec, $spawn);
+        }
+
+        register_bench!($name, tags = ["process", "syscall", "multi-threaded"]);
+    )*};
+}
+
+process_benches! {
+    fork_small: large = false, exec = false, fork;
+    fork_large: large = true, exec = false, fork;
+    vfork_exec_small: large = false, exec = true, {
+        let mut stack = vec![0u8; CHILD_STACK];
+        move || vfork_exec(&mut stack)
+    };
+    vfork_exec_large: large = true, exec = true, {
+        let mut stack = vec![0u8; CHILD_STACK];
+        move || vfork_exec(&mut stack)
+    };
+    posix_spawn_small: large = false, exec = true, posix_spawn;
+    posix_spawn_large: large = true, exec = true, posix_spawn;
+    clone3_small: large = false, exec = false, clone3;
+    clone3_large: large = true, exec = false, clone3;
+    command_status_small: large = false, exec = true, command_status;
+    command_status_large: large = true, exec = true, command_status;
+    command_piped_small: large = false, exec = true, command_piped;
+    command_piped_large: large = true, exec = true, command_piped;
+    command_pre_exec_small: large = false, exec = true, command_pre_exec;
+    command_pre_exec_large: large = true, exec = true, command_pre_exec;
+}
@@ -0,0 +1,280 @@
+//! Random numbers, per value drawn: the x86 hardware generator (`rdrand`, a DRBG reseeded from
+//! the on-chip entropy source, and `rdseed`, the conditioned entropy itself) against the
+//! operating system's generator (`getrandom`) and ChaCha in userspace.
+//!
+//! Either instruction reports with the carry flag whether it had a value, and callers retry
+//! until it does. `rdseed` runs dry far sooner, so `<insn>_64_threads_<n>` measures the retry
+//! loop on one thread while `n - 1` others draw as fast as they can, and `<insn>_64_once` a
+//! single attempt without it.
+//!
+//! `fill_<source>_<size>` fills buffers of 8 bytes to 1 MiB, reported per byte (cycles per byte
+//! with the default timer) and as bandwidth: with `getrandom` (`fill_getrandom_*`), with
+//! `GRND_INSECURE` on Linux, which never blocks for entropy (`fill_insecure_*`, skipped before
+//! 5.6), with reads from `/dev/urandom` through an 8 KiB `BufReader` (`fill_urandom_*`), from
+//! rand's thread-local generator (`fill_thread_rng_*`), a ChaCha12 reseeded from the operating
+//! system every 64 KiB, and from a thread-local ChaCha12 seeded once by `getrandom`, the same
+//! generator without the reseeding (`fill_chacha12_*`).
+
+use std::cell::RefCell;
+
+use getrandom::fill;
+use rand_chacha::{
+    rand_core::{RngCore, SeedableRng},
+    ChaCha12Rng, ChaCha20Rng, ChaCha8Rng,
+};
+
+use crate::{bench::Bencher, register_bench};
+
+/// Draws per sample, reported per draw.
+const OPS: usize = 1024;
+const SEED: u64 = 0x5eed;
+/// Samples for the benchmarks that take thousands of cycles per draw.
+const SLOW_ITERATIONS: u64 = 10_000;
+/// Bytes filled per `fill_*` benchmark, spread over as many samples as fit.
+const FILL_BUDGET: u64 = 1 << 30;
+
+fn getrandom_8(b: &mut Bencher) {
+    b.set_ops(OPS as u64);
+    b.limit_iterations(SLOW_ITERATIONS);
+    let mut buf = [0u8; 8];
+    b.iter(|| {
+        for _ in 0..OPS {
+            fill(&mut buf).unwrap();
+        }
+        buf
+    });
+}
+
+/// One call for the whole sample, the syscall amortized over `OPS` values.
+fn getrandom_8k(b: &mut Bencher) {
+    b.set_ops(OPS as u64);
+    let mut buf = [0u8; 8 * OPS];
+    b.iter(|| {
+        fill(&mut buf).unwrap();
+        buf[0]
+    });
+}
+
+fn chacha<R: RngCore + SeedableRng>(b: &mut Bencher) {
+    b.set_ops(OPS as u64);
+    let mut rng = R::seed_from_u64(SEED);
+    b.iter(|| {
+        let mut x = 0;
+        for _ in 0..OPS {
+            x ^= rng.next_u64();
+        }
+        x
+    });
+}
+
+fn chacha8(b: &mut Bencher) {
+    chacha::<ChaCha8Rng>(b);
+}
+
+fn chacha20(b: &mut Bencher) {
+    chacha::<ChaCha20Rng>(b);
+}
+
+register_bench!(getrandom_8, tags = ["random", "syscall"]);
+register_bench!(getrandom_8k, tags = ["random", "syscall"]);
+register_bench!(chacha8, tags = ["random"]);
+register_bench!(chacha20, tags = ["random"]);
+
+/// Fills a buffer of `bytes` with `source` per sample, the samples limited so the largest
+/// finish in seconds.
+fn fill_with(b: &mut Bencher, bytes: usize, mut source: impl FnMut(&mut [u8])) {
+    b.set_ops(bytes as u64);
+    b.set_bytes(bytes as u64);
+    b.limit_iterations((FILL_BUDGET / bytes as u64).clamp(100, SLOW_ITERATIONS));
+    let mut buf = vec![0; bytes];
+    b.iter(|| {
+        source(&mut buf);
+        buf[0]
+    });
+}
+
+fn fill_getrandom(b: &mut Bencher, bytes: usize) {
+    fill_with(b, bytes, |buf| fill(buf).unwrap());
+}
+
+/// `getrandom` with `flags`, retried until `buf` is full.
+#[cfg(target_os = "linux")]
+fn getrandom_flags(buf: &mut [u8], flags: libc::c_uint) -> std::io::Result<()> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let rest = &mut buf[filled..];
+        // Safety: the call writes at most `rest.len()` bytes to `rest`.
+        let n = unsafe { libc::getrandom(rest.as_mut_ptr().cast(), rest.len(), flags) };
+        if n < 0 {
+            let error = std::io::Error::last_os_error();
+            if error.kind() != std::io::ErrorKind::Interrupted {
+                return Err(error);
+            }
+        } else {
+            filled += n as usize;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn fill_insecure(b: &mut Bencher, bytes: usize) {
+    if getrandom_flags(&mut [0], libc::GRND_INSECURE).is_err() {
+        return b.skip("no GRND_INSECURE (Linux 5.6)");
+    }
+    fill_with(b, bytes, |buf| {
+        getrandom_flags(buf, libc::GRND_INSECURE).unwrap()
+    });
+}
+
+#[cfg(unix)]
+fn fill_urandom(b: &mut Bencher, bytes: usize) {
+    use std::{
+        fs::File,
+        io::{BufReader, Read},
+    };
+
+    let mut urandom = BufReader::new(File::open("/dev/urandom").unwrap());
+    fill_with(b, bytes, |buf| urandom.read_exact(buf).unwrap());
+}
+
+thread_local! {
+    static CHACHA12: RefCell<ChaCha12Rng> = RefCell::new({
+        let mut seed = [0; 32];
+        fill(&mut seed).unwrap();
+        ChaCha12Rng::from_seed(seed)
+    });
+}
+
+fn fill_thread_rng(b: &mut Bencher, bytes: usize) {
+    let mut rng = rand::rng();
+    fill_with(b, bytes, |buf| rng.fill_bytes(buf));
+}
+
+fn fill_chacha12(b: &mut Bencher, bytes: usize) {
+    fill_with(b, bytes, |buf| {
+        CHACHA12.with(|rng| rng.borrow_mut().fill_bytes(buf))
+    });
+}
+
+/// `$name` fills `$bytes` from `$source`.
+macro_rules! fill_benches {
+    ($($name:ident: $source:ident, $bytes:expr, tags = [$($tag:literal),*];)*) => {$(
+        fn $name(b: &mut Bencher) {
+            $source(b, $bytes);
+        }
+
+        register_bench!($name, tags = ["random", "fill" $(, $tag)*]);
+    )*};
+}
+
+fill_benches! {
+    fill_getrandom_8: fill_getrandom, 8, tags = ["syscall"];
+    fill_getrandom_256: fill_getrandom, 256, tags = ["syscall"];
+    fill_getrandom_4k: fill_getrandom, 4 << 10, tags = ["syscall"];
+    fill_getrandom_64k: fill_getrandom, 64 << 10, tags = ["syscall"];
+    fill_getrandom_1m: fill_getrandom, 1 << 20, tags = ["syscall"];
+    fill_thread_rng_8: fill_thread_rng, 8, tags = [];
+    fill_thread_rng_256: fill_thread_rng, 256, tags = [];
+    fill_thread_rng_4k: fill_thread_rng, 4 << 10, tags = [];
+    fill_thread_rng_64k: fill_thread_rng, 64 << 10, tags = [];
+    fill_thread_rng_1m: fill_thread_rng, 1 << 20, tags = [];
+    fill_chacha12_8: fill_chacha12, 8, tags = [];
+    fill_chacha12_256: fill_chacha12, 256, tags = [];
+    fill_chacha12_4k: fill_chacha12, 4 << 10, tags = [];
+    fill_chacha12_64k: fill_chacha12, 64 << 10, tags = [];
+    fill_chacha12_1m: fill_chacha12, 1 << 20, tags = [];
+}
+
+#[cfg(target_os = "linux")]
+fill_benches! {
+    fill_insecure_8: fill_insecure, 8, tags = ["syscall"];
+    fill_insecure_256: fill_insecure, 256, tags = ["syscall"];
+    fill_insecure_4k: fill_insecure, 4 << 10, tags = ["syscall"];
+    fill_insecure_64k: fill_insecure, 64 << 10, tags = ["syscall"];
+    fill_insecure_1m: fill_insecure, 1 << 20, tags = ["syscall"];
+}
+
+#[cfg(unix)]
+fill_benches! {
+    fill_urandom_8: fill_urandom, 8, tags = ["syscall"];
+    fill_urandom_256: fill_urandom, 256, tags = ["syscall"];
+    fill_urandom_4k: fill_urandom, 4 << 10, tags = ["syscall"];
+    fill_urandom_64k: fill_urandom, 64 << 10, tags = ["syscall"];
+    fill_urandom_1m: fill_urandom, 1 << 20, tags = ["syscall"];
+}
+
+#[cfg(target_arch = "x86_64")]
+use std::{
+    arch::x86_64::*,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+/// `$name` draws `$ty` with `$step` (`rdrand` or `rdseed`), retrying until it succeeds if
+/// `$retry`, while `$threads - 1` other threads draw with it too.
+#[cfg(target_arch = "x86_64")]
+macro_rules! hardware_benches {
+    ($($name:ident: $feature:tt, $step:ident, $ty:ty, retry = $retry:literal,
+       threads = $threads:literal, tags = [$($tag:literal),*];)*) => {$(
+        fn $name(b: &mut Bencher) {
+            if !std::is_x86_feature_detected!($feature) {
+                return b.skip(concat!("no ", $feature, " support"));
+            }
+            b.set_ops(OPS as u64);
+            b.limit_iterations(SLOW_ITERATIONS);
+            let threads: usize = $threads;
+            let stop = AtomicBool::new(false);
+            thread::scope(|scope| {
+                for _ in 1..threads {
+                    scope.spawn(|| {
+                        while !stop.load(Ordering::Relaxed) {
+                            // Safety: the feature was detected above.
+                            unsafe { draw(true) };
+                        }
+                    });
+                }
+                // Safety: as above.
+                b.iter(|| unsafe { draw($retry) });
+                stop.store(true, Ordering::Relaxed);
+            });
+
+            #[target_feature(enable = $feature)]
+            unsafe fn draw(retry: bool) -> $ty {
+                let mut x = 0;
+                for _ in 0..OPS {
+                    let mut value = 0;
+                    while $step(&mut value) == 0 && retry {}
+                    x ^= value;
+                }
+                x
+            }
+        }
+
+        register_bench!($name, tags = ["random", $feature $(, $tag)*]);
+    )*};
+}
+
+#[cfg(target_arch = "x86_64")]
+hardware_benches! {
+    rdrand_16: "rdrand", _rdrand16_step, u16, retry = true, threads = 1, tags = [];
+    rdrand_32: "rdrand", _rdrand32_step, u32, retry = true, threads = 1, tags = [];
+    rdrand_64: "rdrand", _rdrand64_step, u64, retry = true, threads = 1, tags = [];
+    rdrand_64_once: "rdrand", _rdrand64_step, u64, retry = false, threads = 1, tags = [];
+    rdrand_64_threads_2: "rdrand", _rdrand64_step, u64, retry = true, threads = 2,
+        tags = ["multi-threaded"];
+    rdrand_64_threads_4: "rdrand", _rdrand64_step, u64, retry = true, threads = 4,
+        tags = ["multi-threaded"];
+    rdrand_64_threads_8: "rdrand", _rdrand64_step, u64, retry = true, threads = 8,
+        tags = ["multi-threaded"];
+    rdseed_16: "rdseed", _rdseed16_step, u16, retry = true, threads = 1, tags = [];
+    rdseed_32: "rdseed", _rdseed32_step, u32, retry = true, threads = 1, tags = [];
+    rdseed_64: "rdseed", _rdseed64_step, u64, retry = true, threads = 1, tags = [];
+    rdseed_64_once: "rdseed", _rdseed64_step, u64, retry = false, threads = 1, tags = [];
+    rdseed_64_threads_2: "rdseed", _rdseed64_step, u64, retry = true, threads = 2,
+        tags = ["multi-threaded"];
+    rdseed_64_threads_4: "rdseed", _rdseed64_step, u64, retry = true, threads = 4,
+        tags = ["multi-threaded"];
+    rdseed_64_threads_8: "rdseed", _rdseed64_step, u64, retry = true, threads = 8,
+        tags = ["multi-threaded"];
+}
@@ -0,0 +1,162 @@
+//! SHA-256 of whole messages from 64 bytes to 1 MiB, padding included, reported per byte
+//! (cycles per byte with the default timer) and as bandwidth: in software through the `sha2`
+//! crate (built with `force-soft`, so it never picks the extensions itself) and with the x86
+//! SHA extensions, skipped on CPUs without them.
+
+use sha2::{Digest, Sha256};
+
+use crate::{bench::Bencher, register_bench};
+
+/// Bytes hashed per benchmark, spread over as many samples as fit.
+const BUDGET: u64 = 1 << 30;
+
+/// A message of `bytes`, its samples limited so the largest finish in seconds.
+fn message(b: &mut Bencher, bytes: usize) -> Vec<u8> {
+    b.set_ops(bytes as u64);
+    b.set_bytes(bytes as u64);
+    b.limit_iterations((BUDGET / bytes as u64).max(100));
+    vec![0xa5; bytes]
+}
+
+fn software(b: &mut Bencher, bytes: usize) {
+    let message = message(b, bytes);
+    b.iter(|| Sha256::digest(&message));
+}
+
+#[cfg(target_arch = "x86_64")]
+fn shani(b: &mut Bencher, bytes: usize) {
+    if !std::is_x86_feature_detected!("sha") || !std::is_x86_feature_detected!("sse4.1") {
+        return b.skip("no sha support");
+    }
+    let message = message(b, bytes);
+    // Safety: the features were detected above.
+    b.iter(|| unsafe { shani::digest(&message) });
+}
+
+/// `$name` runs `$run` over a message of `$bytes`.
+macro_rules! sha_benches {
+    ($($name:ident: $run:ident, $bytes:expr;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            $run(b, $bytes);
+        }
+
+        register_bench!($name, tags = ["sha", "crypto"]);
+    )*};
+}
+
+sha_benches! {
+    software_64: software, 64;
+    software_1k: software, 1 << 10;
+    software_64k: software, 64 << 10;
+    software_1m: software, 1 << 20;
+}
+
+#[cfg(target_arch = "x86_64")]
+sha_benches! {
+    shani_64: shani, 64;
+    shani_1k: shani, 1 << 10;
+    shani_64k: shani, 64 << 10;
+    shani_1m: shani, 1 << 20;
+}
+
+#[cfg(target_arch = "x86_64")]
+mod shani {
+    use std::arch::x86_64::*;
+
+    const H: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    const K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4,
+        0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe,
+        0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f,
+        0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
+        0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc,
+        0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+        0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116,
+        0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
+        0xc67178f2,
+    ];
+
+    /// The digest of `message`, padded as the standard requires.
+    #[target_feature(enable = "sha,sse4.1")]
+    pub fn digest(message: &[u8]) -> [u8; 32] {
+        let mut state = H;
+        let (blocks, rest) = message.as_chunks::<64>();
+        compress(&mut state, blocks);
+
+        // The rest, the `0x80` terminator and the length in bits fill one or two more blocks.
+        let mut tail = [[0; 64]; 2];
+        let bytes = tail.as_flattened_mut();
+        bytes[..rest.len()].copy_from_slice(rest);
+        bytes[rest.len()] = 0x80;
+        let tail = &tail[..if rest.len() < 56 { 1 } else { 2 }];
+        let mut last = *tail.last().unwrap();
+        last[56..].copy_from_slice(&(message.len() as u64 * 8).to_be_bytes());
+        compress(&mut state, &tail[..tail.len() - 1]);
+        compress(&mut state, &[last]);
+
+        let mut digest = [0; 32];
+        for (bytes, word) in digest.as_chunks_mut::<4>().0.iter_mut().zip(state) {
+            *bytes = word.to_be_bytes();
+        }
+        digest
+    }
+
+    /// Runs the blocks through the state, four rounds per pair of `sha256rnds2`. The extensions
+    /// keep the state as the words ABEF and CDGH, and the message schedule as groups of four
+    /// words, each computed from the four groups before it.
+    #[target_feature(enable = "sha,sse4.1")]
+    fn compress(state: &mut [u32; 8], blocks: &[[u8; 64]]) {
+        let big_endian = _mm_set_epi64x(0x0c0d_0e0f_0809_0a0b, 0x0405_0607_0001_0203);
+        // Safety: `state` is 32 bytes long.
+        let (dcba, hgfe) = unsafe {
+            (
+                _mm_loadu_si128(state.as_ptr().cast()),
+                _mm_loadu_si128(state[4..].as_ptr().cast()),
+            )
+        };
+        let cdab = _mm_shuffle_epi32(dcba, 0xb1);
+        let efgh = _mm_shuffle_epi32(hgfe, 0x1b);
+        let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+        let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xf0);
+
+        for block in blocks {
+            let (abef_start, cdgh_start) = (abef, cdgh);
+            let mut schedule = [_mm_setzero_si128(); 4];
+            for group in 0..16 {
+                let words = if group < 4 {
+                    // Safety: `block` is 64 bytes long.
+                    let words = unsafe { _mm_loadu_si128(block[16 * group..].as_ptr().cast()) };
+                    _mm_shuffle_epi8(words, big_endian)
+                } else {
+                    let [w16, w12, w8, w4] = schedule;
+                    let w7 = _mm_alignr_epi8(w4, w8, 4);
+                    _mm_sha256msg2_epu32(_mm_add_epi32(_mm_sha256msg1_epu32(w16, w12), w7), w4)
+                };
+                schedule = [schedule[1], schedule[2], schedule[3], words];
+                // Safety: `K` has four words from every multiple of four below 64.
+                let k = unsafe { _mm_loadu_si128(K[4 * group..].as_ptr().cast()) };
+                let words = _mm_add_epi32(words, k);
+                cdgh = _mm_sha256rnds2_epu32(cdgh, abef, words);
+                abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32(words, 0x0e));
+            }
+            abef = _mm_add_epi32(abef, abef_start);
+            cdgh = _mm_add_epi32(cdgh, cdgh_start);
+        }
+
+        let feba = _mm_shuffle_epi32(abef, 0x1b);
+        let dchg = _mm_shuffle_epi32(cdgh, 0xb1);
+        // Safety: as above.
+        unsafe {
+            _mm_storeu_si128(state.as_mut_ptr().cast(), _mm_blend_epi16(feba, dchg, 0xf0));
+            _mm_storeu_si128(
+                state[4..].as_mut_ptr().cast(),
+                _mm_alignr_epi8(dchg, feba, 8),
+            );
+        }
+    }
+}
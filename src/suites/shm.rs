@@ -0,0 +1,354 @@
+//! Streaming between two processes on Linux: the measuring process sends 64 byte to 64 KiB
+//! messages to a forked partner pinned to the same core (`*_same_core`) or another one
+//! (`*_cross_core`) through a lock-free single-producer single-consumer ring buffer in a
+//! `memfd` both map shared (`ring_*`), a pipe (`pipe_*`) or a Unix stream socket pair
+//! (`unix_stream_*`). Each sample streams 256 KiB of messages and waits for the partner's
+//! acknowledgement, as `ipc`'s `*_throughput_*` does between threads; reported as bandwidth.
+//! Every message is copied twice on every transport, into the ring or the kernel and out into
+//! the partner's buffer, but the ring makes no syscall: each side spins on the other's index,
+//! yielding the core after a while so a partner on the same core gets to run.
+
+use std::{
+    ffi::CStr,
+    hint, process, ptr,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
+
+use super::{
+    migration::{cpu_set, set_affinity},
+    partner::Placement,
+};
+use crate::{bench::Bencher, os, register_bench};
+
+/// Bytes streamed per sample.
+const STREAMED: usize = 256 << 10;
+/// Samples at most; streams of small messages take milliseconds.
+const ITERATIONS: u64 = 1000;
+/// Bytes of the ring's data, a power of two.
+const CAPACITY: usize = 1 << 20;
+/// Where the data starts in the mapping, after the indices.
+const DATA: usize = 4096;
+/// Spins on an index before yielding the core on every further check.
+const SPINS: u32 = 1000;
+const NAME: &CStr = c"microbench-ring";
+
+/// A one-way stream of messages, each batch acknowledged, usable from a forked partner: only
+/// plain syscalls and memory accesses, no allocation.
+trait Transport {
+    fn send(&self, message: &[u8]);
+    /// False once the sender has gone.
+    fn receive(&self, message: &mut [u8]) -> bool;
+    fn acknowledge(&self);
+    fn wait_acknowledged(&self);
+    /// Closes the sender's ends in the partner, so it sees the end of the stream once the
+    /// sender is gone.
+    fn close_sender(&self) {}
+}
+
+/// Descriptors of a byte stream each way, on a pipe each or the two ends of a socket pair.
+struct Stream {
+    /// The sender's end, and the partner's.
+    here: libc::c_int,
+    there: libc::c_int,
+    /// The same for acknowledgements, the other way.
+    ack_here: libc::c_int,
+    ack_there: libc::c_int,
+}
+
+impl Stream {
+    fn pipes() -> Self {
+        let (mut data, mut ack) = ([0; 2], [0; 2]);
+        // Safety: `pipe` fills two descriptors.
+        unsafe {
+            assert_eq!(libc::pipe(data.as_mut_ptr()), 0, "pipe failed");
+            assert_eq!(libc::pipe(ack.as_mut_ptr()), 0, "pipe failed");
+        }
+        Self {
+            here: data[1],
+            there: data[0],
+            ack_here: ack[0],
+            ack_there: ack[1],
+        }
+    }
+
+    fn unix_stream() -> Self {
+        let mut pair = [0; 2];
+        // Safety: `socketpair` fills two descriptors.
+        let created =
+            unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, pair.as_mut_ptr()) };
+        assert_eq!(created, 0, "socketpair failed");
+        // Safety: duplicates descriptors just created.
+        let (ack_here, ack_there) = unsafe { (libc::dup(pair[0]), libc::dup(pair[1])) };
+        Self {
+            here: pair[0],
+            there: pair[1],
+            ack_here,
+            ack_there,
+        }
+    }
+
+    fn write(fd: libc::c_int, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            // Safety: writes from `bytes`.
+            let written = unsafe { libc::write(fd, bytes.as_ptr().cast(), bytes.len()) };
+            assert!(written > 0, "write failed");
+            bytes = &bytes[written as usize..];
+        }
+    }
+
+    /// Fills `bytes`; false at the end of the stream.
+    fn read(fd: libc::c_int, mut bytes: &mut [u8]) -> bool {
+        while !bytes.is_empty() {
+            // Safety: reads into `bytes`.
+            let read = unsafe { libc::read(fd, bytes.as_mut_ptr().cast(), bytes.len()) };
+            assert!(read >= 0, "read failed");
+            if read == 0 {
+                return false;
+            }
+            bytes = &mut bytes[read as usize..];
+        }
+        true
+    }
+}
+
+impl Transport for Stream {
+    fn send(&self, message: &[u8]) {
+        Self::write(self.here, message);
+    }
+
+    fn receive(&self, message: &mut [u8]) -> bool {
+        Self::read(self.there, message)
+    }
+
+    fn acknowledge(&self) {
+        Self::write(self.ack_there, &[1]);
+    }
+
+    fn wait_acknowledged(&self) {
+        assert!(Self::read(self.ack_here, &mut [0]), "partner gone");
+    }
+
+    fn close_sender(&self) {
+        for fd in [self.here, self.ack_here] {
+            // Safety: the partner's copies of the sender's descriptors, which it never drops.
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        for fd in [self.here, self.there, self.ack_here, self.ack_there] {
+            // Safety: the descriptor is ours and closed once.
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+/// An index alone on its cache line (and the adjacent-line prefetcher's pair).
+#[repr(align(128))]
+struct Padded<T>(T);
+
+/// The start of the ring's mapping: bytes written and read so far, and whether the partner has
+/// acknowledged a batch.
+struct Indices {
+    head: Padded<AtomicU64>,
+    tail: Padded<AtomicU64>,
+    acknowledged: Padded<AtomicU32>,
+}
+
+/// The ring buffer, `CAPACITY` bytes after the indices in a `memfd`, which an unrelated process
+/// could map too given the descriptor; mapped before the fork, the partner inherits it.
+struct Ring {
+    map: *mut u8,
+}
+
+impl Ring {
+    fn new() -> Self {
+        let len = DATA + CAPACITY;
+        // Safety: a fresh memfd, sized and mapped shared before it is closed; the mapping keeps
+        // the file alive.
+        let map = unsafe {
+            let fd = libc::memfd_create(NAME.as_ptr(), libc::MFD_CLOEXEC);
+            assert!(fd >= 0, "memfd_create failed");
+            assert_eq!(
+                libc::ftruncate(fd, len as libc::off_t),
+                0,
+                "ftruncate failed"
+            );
+            let map = libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            );
+            libc::close(fd);
+            map
+        };
+        assert_ne!(map, libc::MAP_FAILED, "mmap failed");
+        Self { map: map.cast() }
+    }
+
+    fn indices(&self) -> &Indices {
+        // Safety: the mapping starts zeroed and page aligned, and lives as long as `self`.
+        unsafe { &*self.map.cast::<Indices>() }
+    }
+
+    /// Copies `len` bytes between the ring at stream position `at` and `bytes`, into the ring
+    /// if `write`, wrapping around its end.
+    fn copy(&self, at: u64, bytes: *mut u8, len: usize, write: bool) {
+        let offset = at as usize % CAPACITY;
+        let first = len.min(CAPACITY - offset);
+        // Safety: both ranges are in the ring's data, which the indices reserve for this side
+        // until published, and in `bytes`.
+        unsafe {
+            let data = self.map.add(DATA);
+            let parts = [
+                (data.add(offset), bytes, first),
+                (data, bytes.add(first), len - first),
+            ];
+            for (ring, bytes, len) in parts {
+                if write {
+                    ptr::copy_nonoverlapping(bytes, ring, len);
+                } else {
+                    ptr::copy_nonoverlapping(ring, bytes, len);
+                }
+            }
+        }
+    }
+}
+
+/// Spins until `ready`, then yields the core between checks.
+fn wait(ready: impl Fn() -> bool) {
+    let mut spins = 0;
+    while !ready() {
+        if spins < SPINS {
+            spins += 1;
+            hint::spin_loop();
+        } else {
+            // Safety: no arguments.
+            unsafe { libc::sched_yield() };
+        }
+    }
+}
+
+impl Transport for Ring {
+    fn send(&self, message: &[u8]) {
+        let indices = self.indices();
+        let head = indices.head.0.load(Ordering::Relaxed);
+        let len = message.len() as u64;
+        wait(|| head + len - indices.tail.0.load(Ordering::Acquire) <= CAPACITY as u64);
+        self.copy(head, message.as_ptr().cast_mut(), message.len(), true);
+        indices.head.0.store(head + len, Ordering::Release);
+    }
+
+    fn receive(&self, message: &mut [u8]) -> bool {
+        let indices = self.indices();
+        let tail = indices.tail.0.load(Ordering::Relaxed);
+        let len = message.len() as u64;
+        wait(|| indices.head.0.load(Ordering::Acquire) - tail >= len);
+        self.copy(tail, message.as_mut_ptr(), message.len(), false);
+        indices.tail.0.store(tail + len, Ordering::Release);
+        true
+    }
+
+    fn acknowledge(&self) {
+        self.indices().acknowledged.0.store(1, Ordering::Release);
+    }
+
+    fn wait_acknowledged(&self) {
+        let acknowledged = &self.indices().acknowledged.0;
+        wait(|| acknowledged.load(Ordering::Acquire) != 0);
+        acknowledged.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        // Safety: unmaps the mapping made in `new`.
+        unsafe { libc::munmap(self.map.cast(), DATA + CAPACITY) };
+    }
+}
+
+/// Times streams of `size`-byte messages over `transport` to a forked partner placed per
+/// `placement`, which is killed once measured.
+fn stream(b: &mut Bencher, transport: &impl Transport, size: usize, placement: Placement) {
+    let cpu = match placement.partner() {
+        Ok(cpu) => cpu,
+        Err(reason) => return b.skip(reason),
+    };
+    let messages = STREAMED / size;
+    b.set_bytes((messages * size) as u64);
+    b.limit_iterations(ITERATIONS);
+    let mut message = vec![0xa5u8; size];
+    let parent = process::id() as libc::pid_t;
+    // Safety: the child only pins itself and serves the transport with plain syscalls and
+    // memory accesses into `message`, allocated before the fork, none of which allocates or
+    // takes a lock another thread may have held at the fork. It dies with the parent, however
+    // that exits, rather than spin on the ring or block on the stream forever.
+    match unsafe { libc::fork() } {
+        -1 => panic!("fork failed: {}", std::io::Error::last_os_error()),
+        0 => {
+            os::die_with_parent(parent);
+            transport.close_sender();
+            set_affinity(&cpu_set(&[cpu]));
+            loop {
+                for _ in 0..messages {
+                    if !transport.receive(&mut message) {
+                        // Safety: ends the child without unwinding into the parent's code.
+                        unsafe { libc::_exit(0) };
+                    }
+                }
+                transport.acknowledge();
+            }
+        }
+        child => {
+            b.iter(|| {
+                for _ in 0..messages {
+                    transport.send(&message);
+                }
+                transport.wait_acknowledged();
+            });
+            // Safety: kills and reaps our child.
+            unsafe {
+                libc::kill(child, libc::SIGKILL);
+                libc::waitpid(child, ptr::null_mut(), 0);
+            }
+        }
+    }
+}
+
+/// `$name` streams `$size`-byte messages over `$transport` to a partner placed per
+/// `$placement`.
+macro_rules! shm_benches {
+    ($($name:ident: $transport:expr, $size:expr, $placement:ident;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            stream(b, &$transport, $size, Placement::$placement);
+        }
+
+        register_bench!($name, tags = ["ipc", "shm", "multi-threaded"]);
+    )*};
+}
+
+shm_benches! {
+    ring_64_same_core: Ring::new(), 64, SameCore;
+    ring_4k_same_core: Ring::new(), 4 << 10, SameCore;
+    ring_64k_same_core: Ring::new(), 64 << 10, SameCore;
+    ring_64_cross_core: Ring::new(), 64, CrossCore;
+    ring_4k_cross_core: Ring::new(), 4 << 10, CrossCore;
+    ring_64k_cross_core: Ring::new(), 64 << 10, CrossCore;
+    pipe_64_same_core: Stream::pipes(), 64, SameCore;
+    pipe_4k_same_core: Stream::pipes(), 4 << 10, SameCore;
+    pipe_64k_same_core: Stream::pipes(), 64 << 10, SameCore;
+    pipe_64_cross_core: Stream::pipes(), 64, CrossCore;
+    pipe_4k_cross_core: Stream::pipes(), 4 << 10, CrossCore;
+    pipe_64k_cross_core: Stream::pipes(), 64 << 10, CrossCore;
+    unix_stream_64_same_core: Stream::unix_stream(), 64, SameCore;
+    unix_stream_4k_same_core: Stream::unix_stream(), 4 << 10, SameCore;
+    unix_stream_64k_same_core: Stream::unix_stream(), 64 << 10, SameCore;
+    unix_stream_64_cross_core: Stream::unix_stream(), 64, CrossCore;
+    unix_stream_4k_cross_core: Stream::unix_stream(), 4 << 10, CrossCore;
+    unix_stream_64k_cross_core: Stream::unix_stream(), 64 << 10, CrossCore;
+}
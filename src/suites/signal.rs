@@ -0,0 +1,209 @@
+//! Signal delivery on Linux, with a `SIGUSR1` handler that only takes timestamps: from queueing
+//! the signal to the handler's first instruction (`*_entry`), and from its last one to the
+//! interrupted code running again (`*_return`, through `sigreturn`). `self_*` queues it to the
+//! measuring thread itself, which takes it on the way out of the syscall; `altstack_*` does the
+//! same with the handler on an alternate signal stack; `thread_*` has a thread pinned to another
+//! core queue it while the measuring thread spins, so `*_entry` includes the interrupt that makes
+//! the core notice, and takes both cores' timers to agree, as invariant TSCs do. The signal
+//! carries a pointer to the iteration's timestamps, so it is sent with `pthread_sigqueue`, the
+//! queued form of `tgkill`.
+
+use std::{
+    hint, ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering},
+    thread,
+};
+
+use super::partner::{spawn_pinned, Placement};
+use crate::{bench::Bencher, register_bench, timer::Timer};
+
+/// Samples at most; every one takes a signal.
+const ITERATIONS: u64 = 20_000;
+/// Bytes of the alternate signal stack.
+const ALTSTACK: usize = 64 << 10;
+
+/// What one delivery measures.
+#[derive(Clone, Copy)]
+enum Span {
+    Entry,
+    Return,
+}
+
+/// Timestamps of one delivery, which the signal carries a pointer to.
+struct Probe<'a> {
+    timer: &'a dyn Timer,
+    sent: AtomicU64,
+    entry: AtomicU64,
+    exit: AtomicU64,
+    handled: AtomicBool,
+}
+
+impl<'a> Probe<'a> {
+    fn new(timer: &'a dyn Timer) -> Self {
+        Self {
+            timer,
+            sent: AtomicU64::new(0),
+            entry: AtomicU64::new(0),
+            exit: AtomicU64::new(0),
+            handled: AtomicBool::new(false),
+        }
+    }
+
+    /// Stamps `sent` and queues the signal for `thread`.
+    fn send(&self, thread: libc::pthread_t) {
+        let value = libc::sigval {
+            sival_ptr: ptr::from_ref(self).cast_mut().cast(),
+        };
+        self.sent.store(self.timer.now(), Ordering::Relaxed);
+        // Safety: `thread` is alive until it has taken the signal.
+        assert_eq!(
+            unsafe { libc::pthread_sigqueue(thread, libc::SIGUSR1, value) },
+            0
+        );
+    }
+
+    /// The span measured, once `resumed` is when the interrupted code ran again; zero where the
+    /// sender's core's timer runs ahead of the handler's.
+    fn span(&self, span: Span, resumed: u64) -> u64 {
+        match span {
+            Span::Entry => self
+                .entry
+                .load(Ordering::Relaxed)
+                .saturating_sub(self.sent.load(Ordering::Relaxed)),
+            Span::Return => resumed - self.exit.load(Ordering::Relaxed),
+        }
+    }
+}
+
+extern "C" fn record(_: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+    // Safety: every `SIGUSR1` while the handler is installed carries a pointer to a live probe.
+    let probe = unsafe { &*(*info).si_value().sival_ptr.cast::<Probe>() };
+    probe.entry.store(probe.timer.now(), Ordering::Relaxed);
+    probe.handled.store(true, Ordering::Release);
+    probe.exit.store(probe.timer.now(), Ordering::Relaxed);
+}
+
+/// `record` installed for `SIGUSR1`, on an alternate stack of this thread's if `altstack`, both
+/// restored on drop.
+struct Handler {
+    action: libc::sigaction,
+    stack: Option<(libc::stack_t, Vec<u8>)>,
+}
+
+impl Handler {
+    fn install(altstack: bool) -> Self {
+        let stack = altstack.then(|| {
+            let mut memory = vec![0u8; ALTSTACK];
+            let stack = libc::stack_t {
+                ss_sp: memory.as_mut_ptr().cast(),
+                ss_flags: 0,
+                ss_size: ALTSTACK,
+            };
+            // Safety: zero is a valid `stack_t`.
+            let mut previous = unsafe { std::mem::zeroed() };
+            // Safety: `memory` outlives the stack's use, which ends when `drop` restores the
+            // previous one.
+            assert_eq!(unsafe { libc::sigaltstack(&stack, &mut previous) }, 0);
+            (previous, memory)
+        });
+        // Safety: zero is a valid `sigaction`, with an empty mask.
+        let mut action = unsafe { std::mem::zeroed::<libc::sigaction>() };
+        action.sa_sigaction = record as *const () as usize;
+        action.sa_flags = libc::SA_SIGINFO | if altstack { libc::SA_ONSTACK } else { 0 };
+        // Safety: as above.
+        let mut previous = unsafe { std::mem::zeroed() };
+        // Safety: `record` only touches the probe the signal points to.
+        assert_eq!(
+            unsafe { libc::sigaction(libc::SIGUSR1, &action, &mut previous) },
+            0
+        );
+        Self {
+            action: previous,
+            stack,
+        }
+    }
+}
+
+impl Drop for Handler {
+    fn drop(&mut self) {
+        // Safety: restores what `install` replaced.
+        unsafe {
+            libc::sigaction(libc::SIGUSR1, &self.action, ptr::null_mut());
+            if let Some((previous, _)) = &self.stack {
+                libc::sigaltstack(previous, ptr::null_mut());
+            }
+        }
+    }
+}
+
+/// Queues the signal to the measuring thread itself.
+fn to_self(b: &mut Bencher, span: Span, altstack: bool) {
+    b.limit_iterations(ITERATIONS);
+    let _handler = Handler::install(altstack);
+    // Safety: always succeeds.
+    let this = unsafe { libc::pthread_self() };
+    b.iter_custom(|timer| {
+        let probe = Probe::new(timer);
+        probe.send(this);
+        let resumed = timer.now();
+        assert!(probe.handled.load(Ordering::Acquire));
+        probe.span(span, resumed)
+    });
+}
+
+/// Has a thread pinned to another core queue the signal while the measuring thread spins.
+fn from_thread(b: &mut Bencher, span: Span) {
+    let partner = match Placement::CrossCore.partner() {
+        Ok(partner) => partner,
+        Err(reason) => return b.skip(reason),
+    };
+    b.limit_iterations(ITERATIONS);
+    let _handler = Handler::install(false);
+    // Safety: always succeeds.
+    let this = unsafe { libc::pthread_self() };
+    let request = &AtomicPtr::<Probe>::new(ptr::null_mut());
+    let stop = &AtomicBool::new(false);
+    thread::scope(|scope| {
+        let spawned = spawn_pinned(scope, partner, move || {
+            while !stop.load(Ordering::Relaxed) {
+                let probe = request.swap(ptr::null_mut(), Ordering::Acquire);
+                // Safety: the measuring thread keeps the probe alive until it is handled.
+                if let Some(probe) = unsafe { probe.as_ref() } {
+                    probe.send(this);
+                }
+            }
+        });
+        if let Err(reason) = spawned {
+            return b.skip(reason);
+        }
+        b.iter_custom(|timer| {
+            let probe = Probe::new(timer);
+            request.store(ptr::from_ref(&probe).cast_mut().cast(), Ordering::Release);
+            while !probe.handled.load(Ordering::Acquire) {
+                hint::spin_loop();
+            }
+            let resumed = timer.now();
+            probe.span(span, resumed)
+        });
+        stop.store(true, Ordering::Relaxed);
+    });
+}
+
+macro_rules! signal_benches {
+    ($($name:ident: $run:ident($($arg:expr),*), tags = [$($tag:literal),*];)*) => {$(
+        fn $name(b: &mut Bencher) {
+            $run(b $(, $arg)*);
+        }
+
+        register_bench!($name, tags = ["signal" $(, $tag)*]);
+    )*};
+}
+
+signal_benches! {
+    self_entry: to_self(Span::Entry, false), tags = [];
+    self_return: to_self(Span::Return, false), tags = [];
+    altstack_entry: to_self(Span::Entry, true), tags = [];
+    altstack_return: to_self(Span::Return, true), tags = [];
+    thread_entry: from_thread(Span::Entry), tags = ["multi-threaded"];
+    thread_return: from_thread(Span::Return), tags = ["multi-threaded"];
+}
@@ -0,0 +1,71 @@
+//! Vector add, mul and FMA throughput on `f32` at 128, 256 and 512 bits, one ISA level each:
+//! SSE2, AVX2 (with FMA3, as in x86-64-v3) and AVX-512F. Every sample is `OPS` vector
+//! operations over `LANES` independent accumulators and is reported per element, so the
+//! levels compare directly: a level twice as wide at the same rate halves the time per op.
+
+use std::arch::x86_64::*;
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+const OPS: usize = 1024;
+/// Enough accumulators to cover FMA latency on two ports in all current cores.
+const LANES: usize = 8;
+
+/// `$name` times `$op` on `$vec`s of `$elements` floats, skipped unless the CPU has every
+/// feature in `requires`. The accumulators start at 1.0 and `$y` is broadcast from `$y0`, both
+/// hidden from the compiler so the loop is not folded; floats forbid shortening it by
+/// reassociation. Every chain stays finite and normal: adds reach 129, products decay to
+/// 0.999^128 and `x * 0.5 + 0.5` converges on 1.0.
+macro_rules! simd_benches {
+    ($($name:ident: $vec:ty, $elements:literal, requires = [$($feature:tt),+],
+       splat = $splat:ident, y = $y0:expr, |$x:ident, $y:ident| $op:expr;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            $(if !is_x86_feature_detected!($feature) {
+                return b.skip(concat!("no ", $feature, " support"));
+            })+
+            b.set_ops((OPS * $elements) as u64);
+            let (x, y) = (opaque(1.0f32), opaque($y0));
+            // Safety: the required features were detected above.
+            b.iter(|| unsafe { lanes(x, y) });
+
+            $(#[target_feature(enable = $feature)])+
+            unsafe fn lanes(x: f32, y: f32) -> [$vec; LANES] {
+                let $y = $splat(y);
+                let mut lanes = [$splat(x); LANES];
+                for _ in 0..OPS / LANES {
+                    for lane in &mut lanes {
+                        let $x = *lane;
+                        *lane = $op;
+                    }
+                }
+                lanes
+            }
+        }
+
+        register_bench!($name, tags = ["simd", "throughput", $($feature),+]);
+    )*};
+}
+
+simd_benches! {
+    add_128_sse2: __m128, 4, requires = ["sse2"], splat = _mm_set1_ps, y = 1.0,
+        |x, y| _mm_add_ps(x, y);
+    add_256_avx2: __m256, 8, requires = ["avx2", "fma"], splat = _mm256_set1_ps, y = 1.0,
+        |x, y| _mm256_add_ps(x, y);
+    add_512_avx512: __m512, 16, requires = ["avx512f"], splat = _mm512_set1_ps, y = 1.0,
+        |x, y| _mm512_add_ps(x, y);
+    mul_128_sse2: __m128, 4, requires = ["sse2"], splat = _mm_set1_ps, y = 0.999,
+        |x, y| _mm_mul_ps(x, y);
+    mul_256_avx2: __m256, 8, requires = ["avx2", "fma"], splat = _mm256_set1_ps, y = 0.999,
+        |x, y| _mm256_mul_ps(x, y);
+    mul_512_avx512: __m512, 16, requires = ["avx512f"], splat = _mm512_set1_ps, y = 0.999,
+        |x, y| _mm512_mul_ps(x, y);
+    fma_128_avx2: __m128, 4, requires = ["avx2", "fma"], splat = _mm_set1_ps, y = 0.5,
+        |x, y| _mm_fmadd_ps(x, y, y);
+    fma_256_avx2: __m256, 8, requires = ["avx2", "fma"], splat = _mm256_set1_ps, y = 0.5,
+        |x, y| _mm256_fmadd_ps(x, y, y);
+    fma_512_avx512: __m512, 16, requires = ["avx512f"], splat = _mm512_set1_ps, y = 0.5,
+        |x, y| _mm512_fmadd_ps(x, y, y);
+}
@@ -0,0 +1,216 @@
+//! Sleep accuracy on Linux: `std::thread::sleep` (`thread_*`), `nanosleep` (`nanosleep_*`),
+//! `clock_nanosleep` to an absolute `CLOCK_MONOTONIC` deadline (`clock_nanosleep_*`) and
+//! `tokio::time::sleep` awaited on a current-thread runtime (`tokio_*`), for requests from 1 µs
+//! to 25 ms. Each sample is the oversleep, the time past the request until the call returned,
+//! so the histogram is its distribution; `*_0` requests nothing, which is the call's overhead
+//! (`std::thread::sleep` then makes no syscall at all). The kernel's timer slack (50 µs by
+//! default) and tokio's 1 ms timer wheel, which even a zero sleep waits a tick of, bound the
+//! short requests from below.
+//!
+//! The waits event loops use, at the sub-millisecond requests: a `timerfd` expiring after the
+//! request (`timerfd_*`), whose timer the slack does not apply to; `epoll_pwait2` on an empty
+//! epoll set with the request as its timeout (`epoll_pwait2_*`), skipped on kernels before 5.11,
+//! and `epoll_wait`, whose timeout counts whole milliseconds (`epoll_wait_1ms`); and, as the
+//! floor, busy-waiting on `Instant` (`spin_*`), which reads the TSC through the vDSO.
+
+use std::{
+    hint, io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    time::{Duration, Instant},
+};
+
+use crate::{bench::Bencher, register_bench};
+
+/// Time slept per benchmark, spread over as many samples as fit.
+const BUDGET: Duration = Duration::from_secs(1);
+/// Samples at most, for the shortest requests.
+const ITERATIONS: u64 = 10_000;
+
+fn thread_sleep(duration: Duration) {
+    std::thread::sleep(duration);
+}
+
+fn timespec(duration: Duration) -> libc::timespec {
+    libc::timespec {
+        tv_sec: duration.as_secs() as libc::time_t,
+        tv_nsec: duration.subsec_nanos() as libc::c_long,
+    }
+}
+
+fn nanosleep(duration: Duration) {
+    let request = timespec(duration);
+    // Safety: `request` is valid, and no remainder is asked for.
+    unsafe { libc::nanosleep(&request, std::ptr::null_mut()) };
+}
+
+fn clock_nanosleep(duration: Duration) {
+    let mut now = timespec(Duration::ZERO);
+    // Safety: `now` is valid to write.
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+    let deadline = Duration::new(now.tv_sec as u64, now.tv_nsec as u32) + duration;
+    let deadline = timespec(deadline);
+    // Safety: `deadline` is valid, and an absolute sleep has no remainder.
+    unsafe {
+        libc::clock_nanosleep(
+            libc::CLOCK_MONOTONIC,
+            libc::TIMER_ABSTIME,
+            &deadline,
+            std::ptr::null_mut(),
+        )
+    };
+}
+
+/// Times `sleep` asked for `duration`, less the duration.
+fn oversleep(b: &mut Bencher, duration: Duration, mut sleep: impl FnMut(Duration)) {
+    let samples = BUDGET.as_nanos() / duration.as_nanos().max(1);
+    b.limit_iterations((samples as u64).clamp(20, ITERATIONS));
+    b.iter_custom(|timer| {
+        let requested = (duration.as_nanos() * timer.frequency() as u128 / 1_000_000_000) as u64;
+        let start = timer.now();
+        sleep(duration);
+        (timer.now() - start).saturating_sub(requested)
+    });
+}
+
+thread_local! {
+    static RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+}
+
+fn tokio_sleep(duration: Duration) {
+    RUNTIME.with(|runtime| runtime.block_on(async { tokio::time::sleep(duration).await }));
+}
+
+/// A new descriptor from `fd`, a syscall's result.
+fn owned(fd: libc::c_int) -> OwnedFd {
+    assert!(fd >= 0, "{}", io::Error::last_os_error());
+    // Safety: `fd` was just opened and is owned by nothing else.
+    unsafe { OwnedFd::from_raw_fd(fd) }
+}
+
+thread_local! {
+    // Safety: plain descriptor-creating calls.
+    static TIMERFD: OwnedFd = owned(unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, 0) });
+    static EPOLL: OwnedFd = owned(unsafe { libc::epoll_create1(0) });
+}
+
+fn timerfd(duration: Duration) {
+    let timer = libc::itimerspec {
+        it_interval: timespec(Duration::ZERO),
+        it_value: timespec(duration),
+    };
+    TIMERFD.with(|fd| {
+        let mut expirations = 0u64;
+        // Safety: `timer` is valid, and the read fills the eight bytes of `expirations`.
+        unsafe {
+            libc::timerfd_settime(fd.as_raw_fd(), 0, &timer, std::ptr::null_mut());
+            libc::read(fd.as_raw_fd(), (&mut expirations as *mut u64).cast(), 8);
+        }
+    });
+}
+
+/// `epoll_pwait2` on the empty epoll set until `timeout`, the raw result.
+fn epoll_pwait2_raw(timeout: Duration) -> libc::c_long {
+    let timeout = timespec(timeout);
+    let mut event = libc::epoll_event { events: 0, u64: 0 };
+    // Safety: room for one event, a valid timeout and no signal mask.
+    EPOLL.with(|fd| unsafe {
+        libc::syscall(
+            libc::SYS_epoll_pwait2,
+            fd.as_raw_fd(),
+            &mut event,
+            1,
+            &timeout,
+            std::ptr::null::<libc::sigset_t>(),
+            0,
+        )
+    })
+}
+
+fn epoll_pwait2(duration: Duration) {
+    epoll_pwait2_raw(duration);
+}
+
+fn epoll_pwait2_unsupported() -> Option<&'static str> {
+    (epoll_pwait2_raw(Duration::ZERO) < 0).then_some("no epoll_pwait2 (Linux 5.11)")
+}
+
+fn epoll_wait(duration: Duration) {
+    let mut event = libc::epoll_event { events: 0, u64: 0 };
+    // Safety: room for one event.
+    EPOLL.with(|fd| unsafe {
+        libc::epoll_wait(
+            fd.as_raw_fd(),
+            &mut event,
+            1,
+            duration.as_millis() as libc::c_int,
+        )
+    });
+}
+
+fn spin(duration: Duration) {
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline {
+        hint::spin_loop();
+    }
+}
+
+/// `$name` sleeps for `$duration` through `$sleep`, unless `$unsupported` gives a reason not to.
+macro_rules! sleep_benches {
+    ($($name:ident: $sleep:ident, $duration:expr $(, unsupported = $unsupported:ident)?;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            $(if let Some(reason) = $unsupported() {
+                return b.skip(reason);
+            })?
+            oversleep(b, $duration, $sleep);
+        }
+
+        register_bench!($name, tags = ["sleep", "timer"]);
+    )*};
+}
+
+sleep_benches! {
+    thread_0: thread_sleep, Duration::ZERO;
+    thread_1us: thread_sleep, Duration::from_micros(1);
+    thread_10us: thread_sleep, Duration::from_micros(10);
+    thread_100us: thread_sleep, Duration::from_micros(100);
+    thread_1ms: thread_sleep, Duration::from_millis(1);
+    thread_25ms: thread_sleep, Duration::from_millis(25);
+    nanosleep_0: nanosleep, Duration::ZERO;
+    nanosleep_1us: nanosleep, Duration::from_micros(1);
+    nanosleep_10us: nanosleep, Duration::from_micros(10);
+    nanosleep_100us: nanosleep, Duration::from_micros(100);
+    nanosleep_1ms: nanosleep, Duration::from_millis(1);
+    nanosleep_25ms: nanosleep, Duration::from_millis(25);
+    clock_nanosleep_0: clock_nanosleep, Duration::ZERO;
+    clock_nanosleep_1us: clock_nanosleep, Duration::from_micros(1);
+    clock_nanosleep_10us: clock_nanosleep, Duration::from_micros(10);
+    clock_nanosleep_100us: clock_nanosleep, Duration::from_micros(100);
+    clock_nanosleep_1ms: clock_nanosleep, Duration::from_millis(1);
+    clock_nanosleep_25ms: clock_nanosleep, Duration::from_millis(25);
+    tokio_0: tokio_sleep, Duration::ZERO;
+    tokio_1us: tokio_sleep, Duration::from_micros(1);
+    tokio_10us: tokio_sleep, Duration::from_micros(10);
+    tokio_100us: tokio_sleep, Duration::from_micros(100);
+    tokio_1ms: tokio_sleep, Duration::from_millis(1);
+    tokio_25ms: tokio_sleep, Duration::from_millis(25);
+    timerfd_1us: timerfd, Duration::from_micros(1);
+    timerfd_10us: timerfd, Duration::from_micros(10);
+    timerfd_100us: timerfd, Duration::from_micros(100);
+    timerfd_1ms: timerfd, Duration::from_millis(1);
+    epoll_pwait2_1us: epoll_pwait2, Duration::from_micros(1),
+        unsupported = epoll_pwait2_unsupported;
+    epoll_pwait2_10us: epoll_pwait2, Duration::from_micros(10),
+        unsupported = epoll_pwait2_unsupported;
+    epoll_pwait2_100us: epoll_pwait2, Duration::from_micros(100),
+        unsupported = epoll_pwait2_unsupported;
+    epoll_pwait2_1ms: epoll_pwait2, Duration::from_millis(1),
+        unsupported = epoll_pwait2_unsupported;
+    epoll_wait_1ms: epoll_wait, Duration::from_millis(1);
+    spin_1us: spin, Duration::from_micros(1);
+    spin_10us: spin, Duration::from_micros(10);
+    spin_100us: spin, Duration::from_micros(100);
+    spin_1ms: spin, Duration::from_millis(1);
+}
@@ -0,0 +1,100 @@
+//! What speculation barriers cost in a hot loop: summing a 1024-element array in L1 with an
+//! `lfence` before every `n`th load (`lfence_every_<n>`), the Spectre v1 mitigation that stops
+//! loads from running ahead of a bounds check, against `lfence_never`. Both keep the sum scalar
+//! with `opaque`. `barrier_<kind>` prices the ways code hides a value from the optimizer on the
+//! same loop: `black_box` (a store and a reload), `opaque` (an empty asm on a register), an
+//! empty asm that may touch memory, and `compiler_fence`, against `barrier_none`, which the
+//! compiler is free to vectorize. All reported per element.
+
+use std::{
+    arch::{asm, x86_64::_mm_lfence},
+    hint::black_box,
+    sync::atomic::{compiler_fence, Ordering},
+};
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+const OPS: usize = 1024;
+
+fn data(b: &mut Bencher) -> Vec<u64> {
+    b.set_ops(OPS as u64);
+    (0..OPS as u64).collect()
+}
+
+/// `$name` sums the array with an `lfence` before every `$every` loads.
+macro_rules! lfence_benches {
+    ($($name:ident: $every:literal;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            let data = data(b);
+            b.iter(|| {
+                let mut sum = 0u64;
+                for chunk in data.as_chunks::<$every>().0 {
+                    // Safety: `lfence` has no preconditions.
+                    unsafe { _mm_lfence() };
+                    for &x in chunk {
+                        sum = sum.wrapping_add(opaque(x));
+                    }
+                }
+                sum
+            });
+        }
+
+        register_bench!($name, tags = ["speculation", "fence"]);
+    )*};
+}
+
+lfence_benches! {
+    lfence_every_1: 1;
+    lfence_every_4: 4;
+    lfence_every_16: 16;
+    lfence_every_64: 64;
+}
+
+fn lfence_never(b: &mut Bencher) {
+    let data = data(b);
+    b.iter(|| {
+        let mut sum = 0u64;
+        for &x in &data {
+            sum = sum.wrapping_add(opaque(x));
+        }
+        sum
+    });
+}
+
+register_bench!(lfence_never, tags = ["speculation", "fence"]);
+
+/// `$name` sums the array, passing each element through `$hide`.
+macro_rules! barrier_benches {
+    ($($name:ident: |$x:ident| $hide:expr;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            let data = data(b);
+            b.iter(|| {
+                let mut sum = 0u64;
+                for &$x in black_box(&data) {
+                    sum = sum.wrapping_add($hide);
+                }
+                sum
+            });
+        }
+
+        register_bench!($name, tags = ["speculation", "barrier"]);
+    )*};
+}
+
+barrier_benches! {
+    barrier_none: |x| x;
+    barrier_black_box: |x| black_box(x);
+    barrier_opaque: |x| opaque(x);
+    barrier_asm_memory: |x| {
+        // Safety: an empty asm block, which the compiler must assume reads and writes memory.
+        unsafe { asm!("", options(nostack, preserves_flags)) };
+        x
+    };
+    barrier_compiler_fence: |x| {
+        compiler_fence(Ordering::SeqCst);
+        x
+    };
+}
@@ -0,0 +1,192 @@
+//! Spin-wait loops as a spinlock would write them, waiting with `std::hint::spin_loop` (`pause`
+//! on x86, `isb` on AArch64), a `nop`, or nothing between loads. `handoff_<wait>` bounces a
+//! cache line between two threads on different cores, both waiting the same way, and reports
+//! the round trip, as `core-to-core` does. `sibling_<wait>` runs integer adds on the measuring
+//! thread while a second thread spins forever on the other hardware thread of its core, the cost
+//! every spinning waiter puts on its SMT sibling, against `sibling_idle` without the spinner.
+//! Benchmarks whose core the topology does not offer are skipped.
+
+use std::{
+    arch::asm,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread,
+};
+
+use super::partner::spawn_pinned;
+use crate::{
+    bench::{opaque, Bencher},
+    os, register_bench,
+};
+
+/// Round trips per sample, reported per round trip.
+const ROUND_TRIPS: u64 = 100;
+/// Adds per sample on the measuring thread of `sibling_*`, reported per add.
+const OPS: usize = 1024;
+const LANES: usize = 8;
+
+/// A flag alone on its cache line (and the adjacent-line prefetcher's pair).
+#[repr(align(128))]
+struct Flag(AtomicU64);
+
+/// What a waiter does between two loads of the flag.
+#[derive(Clone, Copy)]
+enum Wait {
+    Pause,
+    Nop,
+    Load,
+}
+
+impl Wait {
+    #[inline(always)]
+    fn relax(self) {
+        match self {
+            Wait::Pause => std::hint::spin_loop(),
+            // Safety: does nothing.
+            Wait::Nop => unsafe { asm!("nop", options(nomem, nostack, preserves_flags)) },
+            Wait::Load => {}
+        }
+    }
+
+    /// Spins until `flag` holds `value`.
+    #[inline(always)]
+    fn until(self, flag: &AtomicU64, value: u64) {
+        while flag.load(Ordering::Acquire) != value {
+            self.relax();
+        }
+    }
+}
+
+/// A core for the partner thread of the calling one: the first of `candidates` other than the
+/// current CPU, or why there is none.
+fn partner(
+    candidates: impl Fn(usize) -> Vec<usize>,
+    missing: &'static str,
+) -> Result<usize, &'static str> {
+    let cpu = os::current_cpu().ok_or("current cpu unknown")?;
+    candidates(cpu)
+        .into_iter()
+        .find(|&other| other != cpu)
+        .ok_or(missing)
+}
+
+/// Pins a thread to `cpu` to run `partner` while `measure` runs on the calling thread; `measure`
+/// is to make `partner` return.
+fn with_partner(
+    b: &mut Bencher,
+    cpu: usize,
+    partner: impl FnOnce() + Send,
+    measure: impl FnOnce(&mut Bencher),
+) {
+    thread::scope(|scope| match spawn_pinned(scope, cpu, partner) {
+        Ok(_) => measure(b),
+        Err(reason) => b.skip(reason),
+    });
+}
+
+/// Round trips to a responder on another core, both waiting with `wait`: the caller writes an
+/// odd value to the flag and waits for the responder to write the next even one.
+fn handoff(b: &mut Bencher, wait: Wait) {
+    const STOP: u64 = u64::MAX;
+    const WARMUP: u64 = 1000;
+    let cpu = match partner(
+        |cpu| {
+            let siblings = os::smt_siblings(cpu);
+            (0..os::cpu_limits().online)
+                .filter(|other| !siblings.contains(other))
+                .collect()
+        },
+        "no other core",
+    ) {
+        Ok(cpu) => cpu,
+        Err(reason) => return b.skip(reason),
+    };
+    b.set_ops(ROUND_TRIPS);
+    let flag = &Flag(AtomicU64::new(0));
+    let respond = move || loop {
+        match flag.0.load(Ordering::Acquire) {
+            STOP => break,
+            ping if ping % 2 == 1 => flag.0.store(ping + 1, Ordering::Release),
+            _ => wait.relax(),
+        }
+    };
+    with_partner(b, cpu, respond, |b| {
+        let mut value = 0;
+        let mut round_trip = || {
+            flag.0.store(value + 1, Ordering::Release);
+            wait.until(&flag.0, value + 2);
+            value += 2;
+        };
+        for _ in 0..WARMUP {
+            round_trip();
+        }
+        b.iter(|| {
+            for _ in 0..ROUND_TRIPS {
+                round_trip();
+            }
+        });
+        flag.0.store(STOP, Ordering::Release);
+    });
+}
+
+/// `LANES` chains of adds, the work the SMT sibling of a spinner gets done.
+fn adds(b: &mut Bencher) {
+    b.set_ops(OPS as u64);
+    b.iter(|| {
+        let mut lanes = [1u64; LANES].map(opaque);
+        for _ in 0..OPS / LANES {
+            for lane in &mut lanes {
+                *lane = opaque(lane.wrapping_add(1));
+            }
+        }
+        lanes
+    });
+}
+
+/// `adds` while the other hardware thread of the core spins with `wait`.
+fn sibling(b: &mut Bencher, wait: Wait) {
+    let cpu = match partner(os::smt_siblings, "no SMT sibling") {
+        Ok(cpu) => cpu,
+        Err(reason) => return b.skip(reason),
+    };
+    let stop = &AtomicBool::new(false);
+    let spin = move || {
+        while !stop.load(Ordering::Acquire) {
+            wait.relax();
+        }
+    };
+    with_partner(b, cpu, spin, |b| {
+        adds(b);
+        stop.store(true, Ordering::Release);
+    });
+}
+
+fn sibling_idle(b: &mut Bencher) {
+    if let Err(reason) = partner(os::smt_siblings, "no SMT sibling") {
+        return b.skip(reason);
+    }
+    adds(b);
+}
+
+register_bench!(sibling_idle, tags = ["spin", "smt"]);
+
+/// `handoff_<wait>` and `sibling_<wait>` for each way to wait.
+macro_rules! spin_benches {
+    ($($handoff:ident, $sibling:ident: $wait:ident;)*) => {$(
+        fn $handoff(b: &mut Bencher) {
+            handoff(b, Wait::$wait);
+        }
+
+        fn $sibling(b: &mut Bencher) {
+            sibling(b, Wait::$wait);
+        }
+
+        register_bench!($handoff, tags = ["spin", "multi-threaded"]);
+        register_bench!($sibling, tags = ["spin", "smt", "multi-threaded"]);
+    )*};
+}
+
+spin_benches! {
+    handoff_pause, sibling_pause: Pause;
+    handoff_nop, sibling_nop: Nop;
+    handoff_load, sibling_load: Load;
+}
@@ -0,0 +1,82 @@
+//! The cost of entering the kernel, for calls that do almost nothing once there: each through
+//! `syscalls::raw_syscall!` (`<call>`) and through its libc wrapper (`<call>_libc`). The libc
+//! `clock_gettime`, `gettimeofday` and `getcpu` (as `sched_getcpu`) are served by the vDSO
+//! without entering the kernel, so those pairs, tagged `vdso`, show what it saves. `read` and
+//! `write` move one byte to or from `/dev/null` and `/dev/zero`, and `close(-1)` fails with
+//! `EBADF` straight away. `--format sorted` prints them as a table, cheapest first.
+
+use std::{fs::File, os::fd::AsRawFd};
+
+use syscalls::{raw_syscall, Sysno};
+
+use crate::{bench::Bencher, register_bench};
+
+/// `$raw` and `$libc` make the same call, after `$setup`, if any.
+macro_rules! syscall_benches {
+    ($($raw:ident, $libc:ident: $(let $v:pat = $setup:expr,)* raw = $raw_call:expr,
+       libc = $libc_call:expr $(, tags = [$($tag:literal),*])?;)*) => {$(
+        fn $raw(b: &mut Bencher) {
+            $(let $v = $setup;)*
+            // Safety: the call only reads and writes the buffers set up above.
+            b.iter(|| unsafe { $raw_call });
+        }
+
+        fn $libc(b: &mut Bencher) {
+            $(let $v = $setup;)*
+            // Safety: as above.
+            b.iter(|| unsafe { $libc_call });
+        }
+
+        register_bench!($raw, tags = ["syscall" $($(, $tag)*)?]);
+        register_bench!($libc, tags = ["syscall", "libc" $($(, $tag)*)?]);
+    )*};
+}
+
+syscall_benches! {
+    getpid, getpid_libc: raw = raw_syscall!(Sysno::getpid), libc = libc::getpid();
+    gettid, gettid_libc: raw = raw_syscall!(Sysno::gettid), libc = libc::gettid();
+    getuid, getuid_libc: raw = raw_syscall!(Sysno::getuid), libc = libc::getuid();
+    sched_yield, sched_yield_libc:
+        raw = raw_syscall!(Sysno::sched_yield),
+        libc = libc::sched_yield();
+    clock_gettime, clock_gettime_libc:
+        let mut time = libc::timespec { tv_sec: 0, tv_nsec: 0 },
+        raw = raw_syscall!(Sysno::clock_gettime, libc::CLOCK_MONOTONIC, &mut time as *mut _),
+        libc = libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut time),
+        tags = ["vdso"];
+    gettimeofday, gettimeofday_libc:
+        let mut time = libc::timeval { tv_sec: 0, tv_usec: 0 },
+        raw = raw_syscall!(Sysno::gettimeofday, &mut time as *mut _, 0),
+        libc = libc::gettimeofday(&mut time, std::ptr::null_mut()),
+        tags = ["vdso"];
+    getcpu, getcpu_libc:
+        raw = {
+            let mut cpu = 0u32;
+            raw_syscall!(Sysno::getcpu, &mut cpu as *mut u32, 0, 0)
+        },
+        libc = libc::sched_getcpu(),
+        tags = ["vdso"];
+    read_null, read_null_libc:
+        let file = File::open("/dev/null").unwrap(),
+        let mut byte = 0u8,
+        raw = raw_syscall!(Sysno::read, file.as_raw_fd(), &mut byte as *mut u8, 1),
+        libc = libc::read(file.as_raw_fd(), (&mut byte as *mut u8).cast(), 1);
+    read_zero, read_zero_libc:
+        let file = File::open("/dev/zero").unwrap(),
+        let mut byte = 0u8,
+        raw = raw_syscall!(Sysno::read, file.as_raw_fd(), &mut byte as *mut u8, 1),
+        libc = libc::read(file.as_raw_fd(), (&mut byte as *mut u8).cast(), 1);
+    write_null, write_null_libc:
+        let file = File::create("/dev/null").unwrap(),
+        let byte = 0u8,
+        raw = raw_syscall!(Sysno::write, file.as_raw_fd(), &byte as *const u8, 1),
+        libc = libc::write(file.as_raw_fd(), (&byte as *const u8).cast(), 1);
+    write_zero, write_zero_libc:
+        let file = File::create("/dev/zero").unwrap(),
+        let byte = 0u8,
+        raw = raw_syscall!(Sysno::write, file.as_raw_fd(), &byte as *const u8, 1),
+        libc = libc::write(file.as_raw_fd(), (&byte as *const u8).cast(), 1);
+    close_invalid, close_invalid_libc:
+        raw = raw_syscall!(Sysno::close, -1i32 as usize),
+        libc = libc::close(-1);
+}
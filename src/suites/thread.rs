@@ -0,0 +1,94 @@
+//! Thread creation on Linux, layer by layer, each thread doing nothing and joined before the
+//! next: `std::thread::spawn` (`std_spawn`), which adds an `Arc` for the result, the thread's
+//! name and handle, and Rust's thread-local setup to glibc's; `pthread_create` and
+//! `pthread_join` (`pthread_create`), which take a stack from glibc's cache of exited threads'
+//! and set up TLS and the guard page; and the `clone` syscall with the thread flags on a stack
+//! reused across samples (`clone`), waiting for the kernel to clear the child's TID on exit as
+//! `pthread_join` does, the kernel's share alone.
+
+use std::{
+    ffi::c_void,
+    ptr,
+    sync::atomic::{AtomicU32, Ordering},
+    thread,
+};
+
+use super::futex::futex;
+use crate::{bench::Bencher, register_bench};
+
+/// Samples at most; each creates and joins a thread.
+const ITERATIONS: u64 = 10_000;
+const STACK: usize = 64 << 10;
+/// What glibc passes to `clone` for a thread, but `CLONE_SETTLS`: the child never touches TLS.
+const THREAD_FLAGS: libc::c_int = libc::CLONE_VM
+    | libc::CLONE_FS
+    | libc::CLONE_FILES
+    | libc::CLONE_SIGHAND
+    | libc::CLONE_THREAD
+    | libc::CLONE_SYSVSEM
+    | libc::CLONE_PARENT_SETTID
+    | libc::CLONE_CHILD_CLEARTID;
+
+fn std_spawn(b: &mut Bencher) {
+    b.limit_iterations(ITERATIONS);
+    b.iter(|| thread::spawn(|| {}).join().unwrap());
+}
+
+extern "C" fn pthread_start(_: *mut c_void) -> *mut c_void {
+    ptr::null_mut()
+}
+
+fn pthread_create(b: &mut Bencher) {
+    b.limit_iterations(ITERATIONS);
+    b.iter(|| {
+        let mut thread = 0;
+        // Safety: default attributes, and the thread is joined right away.
+        unsafe {
+            let created =
+                libc::pthread_create(&mut thread, ptr::null(), pthread_start, ptr::null_mut());
+            assert_eq!(created, 0, "pthread_create failed");
+            assert_eq!(libc::pthread_join(thread, ptr::null_mut()), 0);
+        }
+    });
+}
+
+extern "C" fn clone_start(_: *mut c_void) -> libc::c_int {
+    0
+}
+
+fn clone(b: &mut Bencher) {
+    b.limit_iterations(ITERATIONS);
+    let mut stack = vec![0u8; STACK];
+    let top = stack.as_mut_ptr_range().end;
+    let top = top.wrapping_sub(top.addr() % 16);
+    let tid = AtomicU32::new(0);
+    b.iter(|| {
+        // Safety: the child returns at once on its own stack, which glibc's wrapper follows
+        // with the `exit` syscall, and the stack is reused only once the kernel has cleared
+        // `tid`, after the child is gone.
+        let created = unsafe {
+            libc::clone(
+                clone_start,
+                top.cast(),
+                THREAD_FLAGS,
+                ptr::null_mut(),
+                tid.as_ptr(),
+                ptr::null_mut::<c_void>(),
+                tid.as_ptr(),
+            )
+        };
+        assert!(created > 0, "clone failed");
+        // The kernel wakes the word as a shared futex.
+        loop {
+            let current = tid.load(Ordering::Acquire);
+            if current == 0 {
+                break;
+            }
+            futex(&tid, libc::FUTEX_WAIT, current);
+        }
+    });
+}
+
+register_bench!(std_spawn, tags = ["thread", "multi-threaded"]);
+register_bench!(pthread_create, tags = ["thread", "multi-threaded"]);
+register_bench!(clone, tags = ["thread", "multi-threaded"]);
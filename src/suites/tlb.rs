@@ -0,0 +1,78 @@
+//! TLB reach: a random pointer chase through one cache line per 4 KiB page, so every load needs
+//! a translation of its own, over regions backed by 4 KiB pages, transparent huge pages and
+//! reserved 2 MiB huge pages. Past the TLB's reach in 4 KiB pages, each load adds a page walk
+//! that the huge pages avoid. The line within each page is random, so that the lines spread
+//! over the cache sets even where huge pages keep the page number in the physical address.
+
+use std::ptr;
+
+use crate::{
+    bench::{random_u64s, shuffle, Bencher},
+    memory::{Mapping, Pages, SMALL_PAGE},
+    register_bench,
+};
+
+const LINE: usize = 64;
+/// Dependent loads per sample, reported per load.
+const HOPS: usize = 1024;
+const ITERATIONS: u64 = 10_000;
+const SEED: u64 = 0x5eed;
+
+fn chase(b: &mut Bencher, bytes: usize, pages: Pages) {
+    let mut mapping = match Mapping::new(bytes, pages) {
+        Ok(mapping) => mapping,
+        Err(err) => return b.skip(err.to_string()),
+    };
+    b.set_ops(HOPS as u64);
+    b.limit_iterations(ITERATIONS);
+
+    let base = mapping.as_mut_ptr();
+    let lines = random_u64s(SEED, bytes / SMALL_PAGE);
+    let node = |page: usize| page * SMALL_PAGE + lines[page] as usize % (SMALL_PAGE / LINE) * LINE;
+    let mut order = (0..bytes / SMALL_PAGE).collect::<Vec<_>>();
+    shuffle(&mut order, SEED);
+    for (i, &page) in order.iter().enumerate() {
+        let next = base.wrapping_add(node(order[(i + 1) % order.len()]));
+        // Safety: every node lies within the mapping.
+        unsafe {
+            base.add(node(page))
+                .cast::<usize>()
+                .write(next.expose_provenance())
+        };
+    }
+
+    let mut address = base.wrapping_add(node(order[0])).expose_provenance();
+    b.iter(|| {
+        for _ in 0..HOPS {
+            // Safety: every link is the address of a node in the mapping.
+            address = unsafe { *ptr::with_exposed_provenance::<usize>(address) };
+        }
+        address
+    });
+}
+
+/// `<pages>_<size>`: `$pages` over `$bytes`.
+macro_rules! tlb_benches {
+    ($($name:ident: $pages:ident, $bytes:expr, tags = [$($tag:literal),*];)*) => {$(
+        fn $name(b: &mut Bencher) {
+            chase(b, $bytes, Pages::$pages);
+        }
+
+        register_bench!($name, tags = ["memory", "tlb" $(, $tag)*]);
+    )*};
+}
+
+tlb_benches! {
+    small_256k: Small, 256 << 10, tags = [];
+    small_4m: Small, 4 << 20, tags = [];
+    small_64m: Small, 64 << 20, tags = [];
+    small_1g: Small, 1 << 30, tags = ["slow"];
+    thp_256k: Transparent, 256 << 10, tags = [];
+    thp_4m: Transparent, 4 << 20, tags = [];
+    thp_64m: Transparent, 64 << 20, tags = [];
+    thp_1g: Transparent, 1 << 30, tags = ["slow"];
+    huge_256k: Huge, 256 << 10, tags = [];
+    huge_4m: Huge, 4 << 20, tags = [];
+    huge_64m: Huge, 64 << 20, tags = [];
+    huge_1g: Huge, 1 << 30, tags = ["slow"];
+}
@@ -0,0 +1,96 @@
+//! The front-end cliff between the µop cache (DSB) and the legacy decoders, on loops whose body
+//! stays in the L1i. `body_<size>` loops over 1 to 32 KiB of four-byte adds: the body fits the
+//! µop cache (a few thousand µops, 1.5K on Skylake) until it no longer does, and then every
+//! iteration goes through the decoders. `density_<n>b` loops over 1 KiB of `n`-byte
+//! instructions: Intel's µop cache holds at most 18 µops of a 32-byte window, so one-byte
+//! instructions never fit. `offset_<n>` starts a 24-byte body of adds `n` bytes past a 64-byte
+//! boundary, which from 4 to 6 puts the loop's `dec`/`jnz` across a 32-byte boundary; CPUs with
+//! the jump conditional code erratum mitigation refuse to cache those. Reported per instruction
+//! of the body.
+
+use core::arch::asm;
+
+use crate::{bench::Bencher, register_bench};
+
+/// Bytes of body run per sample at least.
+const SAMPLE: usize = 256 << 10;
+
+/// An instruction mix as one template, or with `@size` its instruction count and bytes. They
+/// may use `rax`, `rcx`, `rdx`, `rsi` and `rdi`.
+macro_rules! mix {
+    (adds) => {
+        "add rax, 1\nadd rcx, 1\nadd rdx, 1\nadd rsi, 1"
+    };
+    (@size adds) => {
+        (4, 16)
+    };
+    (short_adds) => {
+        "add eax, ecx\nadd edx, ecx\nadd esi, ecx\nadd edi, ecx"
+    };
+    (@size short_adds) => {
+        (4, 8)
+    };
+    (nops) => {
+        "nop\nnop\nnop\nnop"
+    };
+    (@size nops) => {
+        (4, 4)
+    };
+    (add) => {
+        "add rax, 1"
+    };
+    (@size add) => {
+        (1, 4)
+    };
+}
+
+/// `$name` loops over `$groups` copies of `$mix`, placed `$offset` bytes past a 64-byte
+/// boundary.
+macro_rules! uop_cache_benches {
+    ($($name:ident: $groups:literal x $mix:ident, offset = $offset:literal;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            let (count, bytes) = mix!(@size $mix);
+            let iterations = (SAMPLE / ($groups * bytes)).max(1);
+            b.set_ops((iterations * $groups * count) as u64);
+            b.iter(|| {
+                // Safety: the body only touches the clobbered registers.
+                unsafe {
+                    asm!(
+                        ".p2align 6",
+                        ".fill {offset}, 1, 0x90",
+                        "2:",
+                        ".rept {groups}",
+                        mix!($mix),
+                        ".endr",
+                        "dec {n}",
+                        "jnz 2b",
+                        offset = const $offset,
+                        groups = const $groups,
+                        n = inout(reg) iterations => _,
+                        out("rax") _, out("rcx") _, out("rdx") _, out("rsi") _, out("rdi") _,
+                        options(nomem, nostack),
+                    )
+                };
+            });
+        }
+
+        register_bench!($name, tags = ["uop-cache", "frontend"]);
+    )*};
+}
+
+uop_cache_benches! {
+    body_1k: 64 x adds, offset = 0;
+    body_2k: 128 x adds, offset = 0;
+    body_4k: 256 x adds, offset = 0;
+    body_8k: 512 x adds, offset = 0;
+    body_16k: 1024 x adds, offset = 0;
+    body_32k: 2048 x adds, offset = 0;
+    density_4b: 64 x adds, offset = 0;
+    density_2b: 128 x short_adds, offset = 0;
+    density_1b: 256 x nops, offset = 0;
+    offset_0: 6 x add, offset = 0;
+    offset_2: 6 x add, offset = 2;
+    offset_4: 6 x add, offset = 4;
+    offset_6: 6 x add, offset = 6;
+    offset_8: 6 x add, offset = 8;
+}
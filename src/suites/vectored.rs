@@ -0,0 +1,123 @@
+//! Vectored IO on Linux: a payload of `<count>x<size>` buffers written to one end of a Unix
+//! stream socket pair and read back from the other by the same thread, as a `write` and a
+//! `read` per buffer (`plain_*`), one `writev` and one `readv` (`vectored_*`), or one `sendmsg`
+//! and one `recvmsg` (`msg_*`). Every payload fits the socket's buffer, so nothing blocks and
+//! the difference is the syscalls saved against the work of walking the vectors. Reported per
+//! payload, and as bandwidth.
+
+use std::{
+    io::{IoSlice, IoSliceMut, Read, Write},
+    os::{fd::AsRawFd, unix::net::UnixStream},
+};
+
+use crate::{bench::Bencher, register_bench};
+
+/// Samples at most; a payload takes microseconds.
+const ITERATIONS: u64 = 20_000;
+
+/// How a payload crosses the socket.
+#[derive(Clone, Copy)]
+enum Calls {
+    Plain,
+    Vectored,
+    Msg,
+}
+
+/// A `msghdr` over `iov`, without an address or control data.
+fn msghdr(iov: *mut libc::iovec, count: usize) -> libc::msghdr {
+    // Safety: zero is a valid `msghdr`, with no address or control data.
+    let mut header = unsafe { std::mem::zeroed::<libc::msghdr>() };
+    header.msg_iov = iov;
+    header.msg_iovlen = count as _;
+    header
+}
+
+/// Vectors over `buffers`, for `sendmsg` and `recvmsg`.
+fn iovecs(buffers: &mut [Vec<u8>]) -> Vec<libc::iovec> {
+    buffers
+        .iter_mut()
+        .map(|buffer| libc::iovec {
+            iov_base: buffer.as_mut_ptr().cast(),
+            iov_len: buffer.len(),
+        })
+        .collect()
+}
+
+fn transfer(b: &mut Bencher, calls: Calls, count: usize, size: usize) {
+    b.set_bytes((count * size) as u64);
+    b.limit_iterations(ITERATIONS);
+    let (mut sender, mut receiver) = UnixStream::pair().unwrap();
+    let mut sent = vec![vec![0xa5u8; size]; count];
+    let mut received = vec![vec![0u8; size]; count];
+    let total = count * size;
+    // The vectors are built before timing, as a caller reusing them would.
+    match calls {
+        Calls::Plain => b.iter(|| {
+            for buffer in &sent {
+                assert_eq!(sender.write(buffer).unwrap(), size);
+            }
+            for buffer in &mut received {
+                assert_eq!(receiver.read(buffer).unwrap(), size);
+            }
+        }),
+        Calls::Vectored => {
+            let sent = sent
+                .iter()
+                .map(|buffer| IoSlice::new(buffer))
+                .collect::<Vec<_>>();
+            let mut received = received
+                .iter_mut()
+                .map(|buffer| IoSliceMut::new(buffer))
+                .collect::<Vec<_>>();
+            b.iter(|| {
+                assert_eq!(sender.write_vectored(&sent).unwrap(), total);
+                assert_eq!(receiver.read_vectored(&mut received).unwrap(), total);
+            });
+        }
+        Calls::Msg => {
+            let mut sent = iovecs(&mut sent);
+            let mut received = iovecs(&mut received);
+            let sent = msghdr(sent.as_mut_ptr(), count);
+            let mut received = msghdr(received.as_mut_ptr(), count);
+            b.iter(|| {
+                // Safety: the headers point at vectors of `count` live buffers of `size` bytes.
+                unsafe {
+                    assert_eq!(libc::sendmsg(sender.as_raw_fd(), &sent, 0), total as isize);
+                    assert_eq!(
+                        libc::recvmsg(receiver.as_raw_fd(), &mut received, 0),
+                        total as isize
+                    );
+                }
+            });
+        }
+    }
+}
+
+/// `$name` moves `$count` buffers of `$size` bytes per sample with `$calls`.
+macro_rules! vectored_benches {
+    ($($name:ident: $calls:ident, $count:expr, $size:expr;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            transfer(b, Calls::$calls, $count, $size);
+        }
+
+        register_bench!($name, tags = ["vectored", "syscall"]);
+    )*};
+}
+
+vectored_benches! {
+    plain_2x64: Plain, 2, 64;
+    plain_8x64: Plain, 8, 64;
+    plain_32x256: Plain, 32, 256;
+    plain_8x1k: Plain, 8, 1 << 10;
+    plain_8x8k: Plain, 8, 8 << 10;
+    vectored_2x64: Vectored, 2, 64;
+    vectored_8x64: Vectored, 8, 64;
+    vectored_32x256: Vectored, 32, 256;
+    vectored_8x1k: Vectored, 8, 1 << 10;
+    vectored_8x8k: Vectored, 8, 8 << 10;
+    msg_2x64: Msg, 2, 64;
+    msg_8x64: Msg, 8, 64;
+    msg_32x256: Msg, 32, 256;
+    msg_8x1k: Msg, 8, 1 << 10;
+    msg_8x8k: Msg, 8, 8 << 10;
+}
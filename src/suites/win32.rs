@@ -0,0 +1,16 @@
+use windows_sys::Win32::System::Threading::{GetCurrentProcessId, SwitchToThread};
+
+use crate::{bench::Bencher, register_bench};
+
+// Served from the TEB without entering the kernel.
+fn get_current_process_id(b: &mut Bencher) {
+    b.iter(|| unsafe { GetCurrentProcessId() });
+}
+
+// NtYieldExecution: a cheap round trip through the kernel when nothing else is runnable.
+fn switch_to_thread(b: &mut Bencher) {
+    b.iter(|| unsafe { SwitchToThread() });
+}
+
+register_bench!(get_current_process_id, tags = ["syscall"]);
+register_bench!(switch_to_thread, tags = ["syscall", "scheduling"]);
@@ -0,0 +1,119 @@
+//! The legacy x87 floating-point unit against its SSE2 replacement: the latency of scalar
+//! `f64` add, mul, div and sqrt as `<op>_x87` and `<op>_sse`, each a dependent chain in an asm
+//! loop, and of sine and cosine as the `fsin`/`fcos` instructions against the `libm` functions
+//! Rust calls on SSE2 (`<op>_libm`). x87 registers cannot be asm operands, so its chains load
+//! from memory before the loop and store after it. It computes at the 64-bit mantissa precision
+//! Linux and macOS leave set, which makes `fdiv` and `fsqrt` slower than they would be at 53 bits.
+
+use std::arch::asm;
+
+use crate::{
+    bench::{opaque, Bencher},
+    register_bench,
+};
+
+/// Operations per sample, reported per operation.
+const OPS: usize = 1024;
+
+/// `$name` runs `$op` on `st(0)`, starting from `$x`, with `$y` in `st(1)`.
+macro_rules! x87_benches {
+    ($($name:ident: x = $x0:expr, y = $y0:expr, $op:literal;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            b.iter(|| {
+                let mut x: f64 = $x0;
+                let y: f64 = $y0;
+                // Safety: the loop leaves the x87 stack empty, as it found it.
+                unsafe {
+                    asm!(
+                        "fld qword ptr [{y}]",
+                        "fld qword ptr [{x}]",
+                        "2:",
+                        $op,
+                        "dec {n}",
+                        "jnz 2b",
+                        "fstp qword ptr [{x}]",
+                        "fstp st(0)",
+                        x = in(reg) &mut x,
+                        y = in(reg) &y,
+                        n = inout(reg) OPS => _,
+                        out("st(0)") _, out("st(1)") _, out("st(2)") _, out("st(3)") _,
+                        out("st(4)") _, out("st(5)") _, out("st(6)") _, out("st(7)") _,
+                        options(nostack),
+                    )
+                };
+                x
+            });
+        }
+
+        register_bench!($name, tags = ["fp", "x87", "latency"]);
+    )*};
+}
+
+/// `$name` runs `$op` on `{x}`, starting from `$x`, with `$y`, if any, in `{y}`.
+macro_rules! sse_benches {
+    ($($name:ident: x = $x0:expr, $(y = $y0:expr,)? $op:literal;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            b.iter(|| {
+                let mut x: f64 = $x0;
+                // Safety: the loop only touches its operands.
+                unsafe {
+                    asm!(
+                        "2:",
+                        $op,
+                        "dec {n}",
+                        "jnz 2b",
+                        x = inout(xmm_reg) x,
+                        $(y = in(xmm_reg) $y0,)?
+                        n = inout(reg) OPS => _,
+                        options(nomem, nostack),
+                    )
+                };
+                x
+            });
+        }
+
+        register_bench!($name, tags = ["fp", "x87", "latency"]);
+    )*};
+}
+
+// Multiplying and dividing by 0.999 keeps the chains within a factor of three of 1.0.
+x87_benches! {
+    add_x87: x = 1.0, y = 1.0, "fadd st(0), st(1)";
+    mul_x87: x = 1.0, y = 0.999, "fmul st(0), st(1)";
+    div_x87: x = 1.0, y = 0.999, "fdiv st(0), st(1)";
+    sqrt_x87: x = 2.0, y = 0.0, "fsqrt";
+    sin_x87: x = 1.0, y = 0.0, "fsin";
+    cos_x87: x = 1.0, y = 0.0, "fcos";
+}
+
+sse_benches! {
+    add_sse: x = 1.0, y = 1.0f64, "addsd {x}, {y}";
+    mul_sse: x = 1.0, y = 0.999f64, "mulsd {x}, {y}";
+    div_sse: x = 1.0, y = 0.999f64, "divsd {x}, {y}";
+    sqrt_sse: x = 2.0, "sqrtsd {x}, {x}";
+}
+
+/// `$name` chains the `libm` function `$op`, starting from 1.0.
+macro_rules! libm_benches {
+    ($($name:ident: $op:ident;)*) => {$(
+        fn $name(b: &mut Bencher) {
+            b.set_ops(OPS as u64);
+            b.iter(|| {
+                let mut x = opaque(1.0f64);
+                for _ in 0..OPS {
+                    x = opaque(x.$op());
+                }
+                x
+            });
+        }
+
+        register_bench!($name, tags = ["fp", "x87", "latency"]);
+    )*};
+}
+
+libm_benches! {
+    sin_libm: sin;
+    cos_libm: cos;
+}
@@ -0,0 +1,194 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use crate::arch::{counter_frequency, serialized_time};
+
+pub trait Timer {
+    fn name(&self) -> &'static str;
+
+    fn unit(&self) -> &'static str;
+
+    fn now(&self) -> u64;
+
+    /// Ticks per second.
+    fn frequency(&self) -> u64;
+
+    fn to_nanos(&self, ticks: f64) -> f64 {
+        ticks * 1e9 / self.frequency() as f64
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum TimerKind {
+    /// Serialized cycle counter (`rdtsc` / `CNTVCT_EL0`)
+    Cycles,
+    /// CLOCK_MONOTONIC_RAW (or `Instant` where unavailable), for VMs with an emulated TSC
+    Monotonic,
+    /// QueryPerformanceCounter
+    #[cfg(windows)]
+    Qpc,
+    /// mach_absolute_time
+    #[cfg(target_os = "macos")]
+    Mach,
+}
+
+impl TimerKind {
+    pub fn timer(self) -> &'static dyn Timer {
+        match self {
+            TimerKind::Cycles => &SerializedCounter,
+            TimerKind::Monotonic => &Monotonic,
+            #[cfg(windows)]
+            TimerKind::Qpc => &PerformanceCounter,
+            #[cfg(target_os = "macos")]
+            TimerKind::Mach => &MachAbsoluteTime,
+        }
+    }
+}
+
+pub struct SerializedCounter;
+
+impl Timer for SerializedCounter {
+    fn name(&self) -> &'static str {
+        if cfg!(target_arch = "x86_64") {
+            "serialized rdtsc"
+        } else if cfg!(feature = "pmccntr") {
+            "serialized pmccntr_el0"
+        } else {
+            "serialized cntvct_el0"
+        }
+    }
+
+    fn unit(&self) -> &'static str {
+        if cfg!(any(target_arch = "x86_64", feature = "pmccntr")) {
+            "cycles"
+        } else {
+            "ticks"
+        }
+    }
+
+    #[inline(always)]
+    fn now(&self) -> u64 {
+        serialized_time()
+    }
+
+    fn frequency(&self) -> u64 {
+        static FREQUENCY: OnceLock<u64> = OnceLock::new();
+        *FREQUENCY.get_or_init(|| {
+            counter_frequency().unwrap_or_else(|| calibrate_frequency(serialized_time))
+        })
+    }
+}
+
+pub struct Monotonic;
+
+impl Timer for Monotonic {
+    fn name(&self) -> &'static str {
+        if cfg!(unix) {
+            "clock_gettime(CLOCK_MONOTONIC_RAW)"
+        } else {
+            "Instant"
+        }
+    }
+
+    fn unit(&self) -> &'static str {
+        "ns"
+    }
+
+    #[cfg(unix)]
+    #[inline(always)]
+    fn now(&self) -> u64 {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC_RAW, &mut ts) };
+        ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+    }
+
+    #[cfg(not(unix))]
+    #[inline(always)]
+    fn now(&self) -> u64 {
+        static EPOCH: OnceLock<Instant> = OnceLock::new();
+        EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+    }
+
+    fn frequency(&self) -> u64 {
+        1_000_000_000
+    }
+}
+
+#[cfg(windows)]
+pub struct PerformanceCounter;
+
+#[cfg(windows)]
+impl Timer for PerformanceCounter {
+    fn name(&self) -> &'static str {
+        "QueryPerformanceCounter"
+    }
+
+    fn unit(&self) -> &'static str {
+        "ticks"
+    }
+
+    #[inline(always)]
+    fn now(&self) -> u64 {
+        crate::os::windows::performance_counter()
+    }
+
+    fn frequency(&self) -> u64 {
+        crate::os::windows::performance_frequency()
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub struct MachAbsoluteTime;
+
+#[cfg(target_os = "macos")]
+impl Timer for MachAbsoluteTime {
+    fn name(&self) -> &'static str {
+        "mach_absolute_time"
+    }
+
+    fn unit(&self) -> &'static str {
+        "ticks"
+    }
+
+    #[inline(always)]
+    fn now(&self) -> u64 {
+        crate::os::macos::absolute_time()
+    }
+
+    fn frequency(&self) -> u64 {
+        crate::os::macos::absolute_time_frequency()
+    }
+}
+
+const CALIBRATION_TOLERANCE: f64 = 0.001;
+const CALIBRATION_ROUNDS: u32 = 10;
+
+/// Estimates the tick rate of `now` against the OS wall clock, doubling the measurement window
+/// until two consecutive estimates agree within `CALIBRATION_TOLERANCE`.
+pub fn calibrate_frequency(now: impl Fn() -> u64) -> u64 {
+    let mut window = Duration::from_millis(10);
+    let mut previous = 0.0;
+
+    for _ in 0..CALIBRATION_ROUNDS {
+        let start_instant = Instant::now();
+        let start = now();
+        while start_instant.elapsed() < window {}
+        let end = now();
+        let elapsed = start_instant.elapsed();
+
+        let estimate = (end - start) as f64 / elapsed.as_secs_f64();
+        if ((estimate - previous) / estimate).abs() < CALIBRATION_TOLERANCE {
+            return estimate as u64;
+        }
+
+        previous = estimate;
+        window *= 2;
+    }
+
+    previous as u64
+}
@@ -0,0 +1,124 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Exit status when a benchmark had to be aborted, as with coreutils' `timeout`.
+pub const ABORT_STATUS: i32 = 124;
+
+enum Command {
+    Arm { name: String, deadline: Deadline },
+    Disarm(Deadline),
+}
+
+/// Bounds the wall-clock time of every benchmark. Once one exceeds the budget its `Deadline`
+/// expires, so a `Bencher` stops after the current iteration and reports what it has. If the
+/// benchmark is still running after a second budget (stuck inside an iteration, or a Criterion
+/// benchmark that cannot be cut short), the whole process exits with `ABORT_STATUS` rather than
+/// hang.
+pub struct Watchdog {
+    tx: Sender<Command>,
+}
+
+impl Watchdog {
+    /// Spawn before pinning, so the watchdog does not compete with the measurement core.
+    pub fn spawn(budget: Duration) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("watchdog".into())
+            .spawn(move || {
+                let mut armed = Vec::<(String, Deadline, Instant)>::new();
+                loop {
+                    let next = armed
+                        .iter()
+                        .map(|(_, deadline, start)| *start + deadline.budget(budget))
+                        .min();
+                    let command = match next {
+                        None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                        Some(at) => rx.recv_timeout(at.saturating_duration_since(Instant::now())),
+                    };
+                    match command {
+                        Ok(Command::Arm { name, deadline }) => {
+                            armed.push((name, deadline, Instant::now()))
+                        }
+                        Ok(Command::Disarm(deadline)) => {
+                            armed.retain(|(_, other, _)| !Arc::ptr_eq(&other.0, &deadline.0))
+                        }
+                        Err(RecvTimeoutError::Disconnected) => return,
+                        Err(RecvTimeoutError::Timeout) => {
+                            let now = Instant::now();
+                            for (name, deadline, start) in &armed {
+                                if now < *start + deadline.budget(budget) {
+                                    continue;
+                                }
+                                if !deadline.expired() {
+                                    eprintln!("watchdog: {name} exceeded {budget:?}");
+                                    deadline.0.store(true, Ordering::Relaxed);
+                                } else {
+                                    eprintln!("watchdog: {name} is still running, aborting");
+                                    std::process::exit(ABORT_STATUS);
+                                }
+                            }
+                        }
+                    }
+                }
+            })
+            .expect("failed to spawn the watchdog");
+        Self { tx }
+    }
+
+    /// Starts the clock for `name` until the returned guard is dropped. Several benchmarks may
+    /// be armed at once.
+    pub fn arm(&self, name: &str) -> Armed {
+        let deadline = Deadline::default();
+        let _ = self.tx.send(Command::Arm {
+            name: name.to_string(),
+            deadline: deadline.clone(),
+        });
+        Armed {
+            tx: self.tx.clone(),
+            deadline,
+        }
+    }
+}
+
+pub struct Armed {
+    tx: Sender<Command>,
+    deadline: Deadline,
+}
+
+impl Armed {
+    pub fn deadline(&self) -> Deadline {
+        self.deadline.clone()
+    }
+}
+
+impl Drop for Armed {
+    fn drop(&mut self) {
+        let _ = self.tx.send(Command::Disarm(self.deadline.clone()));
+    }
+}
+
+/// Set by the `Watchdog` once a benchmark has used up its budget.
+#[derive(Clone, Default)]
+pub struct Deadline(Arc<AtomicBool>);
+
+impl Deadline {
+    pub fn expired(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Time from arming to the next stage: expiry, then the abort.
+    fn budget(&self, budget: Duration) -> Duration {
+        if self.expired() {
+            2 * budget
+        } else {
+            budget
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use instruction_benchmark::stats::{
+    log_histogram, mean, percentile, variance_components, welch_t_test, Summary,
+};
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected} ± {tolerance}, got {actual}"
+    );
+}
+
+#[test]
+fn summary_of_one_to_hundred() {
+    let mut samples = (1..=100).collect::<Vec<u64>>();
+    samples.reverse();
+    let summary = Summary::new(&samples);
+
+    assert_eq!(summary.count, 100);
+    assert_close(summary.mean, 50.5, 1e-12);
+    // Sample standard deviation, sqrt(841.67).
+    assert_close(summary.stddev, 29.011_491_975_882_016, 1e-9);
+    assert_eq!(
+        summary.percentiles(),
+        [
+            ("min", 1),
+            ("p50", 50),
+            ("p90", 90),
+            ("p99", 99),
+            ("p99.9", 100),
+            ("max", 100)
+        ]
+    );
+}
+
+#[test]
+fn summary_of_nothing_and_of_one() {
+    let empty = Summary::new(&[]);
+    assert_eq!((empty.count, empty.min, empty.max), (0, 0, 0));
+    assert_eq!(empty.mean, 0.0);
+
+    let one = Summary::new(&[7]);
+    assert_eq!((one.min, one.p50, one.p999, one.max), (7, 7, 7, 7));
+    assert_eq!(one.stddev, 0.0);
+}
+
+#[test]
+fn nearest_rank_percentiles() {
+    let sorted = [15, 20, 35, 40, 50];
+    assert_eq!(percentile(&sorted, 0.0), 15);
+    assert_eq!(percentile(&sorted, 30.0), 20);
+    assert_eq!(percentile(&sorted, 40.0), 20);
+    assert_eq!(percentile(&sorted, 50.0), 35);
+    assert_eq!(percentile(&sorted, 100.0), 50);
+    assert_eq!(percentile(&[], 50.0), 0);
+    assert_eq!(mean(&sorted), 32.0);
+}
+
+#[test]
+fn log_histogram_covers_every_sample_once() {
+    let samples = (0..1000u64).map(|i| i * i % 9973 + 1).collect::<Vec<_>>();
+    let histogram = log_histogram(&samples, 16);
+
+    assert!(histogram.len() <= 16);
+    assert_eq!(histogram.first().unwrap().start, 1);
+    assert_eq!(
+        histogram.last().unwrap().end,
+        samples.iter().max().unwrap() + 1
+    );
+    for pair in histogram.windows(2) {
+        assert_eq!(pair[0].end, pair[1].start);
+        // Logarithmic: later buckets are at least as wide.
+        assert!(pair[1].end - pair[1].start >= pair[0].end - pair[0].start);
+    }
+    assert_eq!(
+        histogram.iter().map(|b| b.count).sum::<usize>(),
+        samples.len()
+    );
+    for bucket in &histogram {
+        let inside = samples
+            .iter()
+            .filter(|&&s| bucket.start <= s && s < bucket.end)
+            .count();
+        assert_eq!(bucket.count, inside);
+    }
+
+    assert!(log_histogram(&[], 16).is_empty());
+    let constant = log_histogram(&[5; 10], 16);
+    assert_eq!(constant.len(), 1);
+    assert_eq!(constant[0].count, 10);
+}
+
+#[test]
+fn welch_t_test_matches_reference() {
+    // R: t.test(1:5, c(2, 4, 6, 8, 10)) gives t = -1.8974, df = 5.8824, p-value = 0.1075.
+    let test = welch_t_test(&[1.0, 2.0, 3.0, 4.0, 5.0], &[2.0, 4.0, 6.0, 8.0, 10.0]).unwrap();
+    assert_close(test.t, -1.897_366_596_101_027_5, 1e-12);
+    assert_close(test.df, 5.882_352_941_176_471, 1e-12);
+    assert_close(test.p, 0.107_531_194_930_723_3, 1e-6);
+
+    let same = welch_t_test(&[3.0, 3.0], &[3.0, 3.0]).unwrap();
+    assert_eq!(same.p, 1.0);
+    assert!(welch_t_test(&[1.0], &[1.0, 2.0]).is_none());
+}
+
+#[test]
+fn variance_components_of_separated_groups() {
+    let groups = [
+        Summary::new(&[1, 2, 3]),
+        Summary::new(&[4, 5, 6]),
+        Summary::new(&[7, 8, 9]),
+    ];
+    let components = variance_components(&groups).unwrap();
+    assert_close(components.grand_mean, 5.0, 1e-12);
+    assert_close(components.within, 1.0, 1e-12);
+    // Variance of the group means (9) less the noise of a mean of three samples (1/3).
+    assert_close(components.between, 9.0 - 1.0 / 3.0, 1e-12);
+
+    let overlapping = [Summary::new(&[1, 5, 9]), Summary::new(&[2, 5, 8])];
+    assert_eq!(variance_components(&overlapping).unwrap().between, 0.0);
+    assert!(variance_components(&groups[..1]).is_none());
+}
@@ -0,0 +1,70 @@
+use std::time::Instant;
+
+use instruction_benchmark::{
+    arch::serialized_time,
+    bench::Bencher,
+    timer::{calibrate_frequency, Monotonic, Timer, TimerKind},
+};
+
+#[test]
+fn serialized_time_is_monotonic() {
+    let mut previous = serialized_time();
+    for _ in 0..100_000 {
+        let now = serialized_time();
+        assert!(
+            now >= previous,
+            "counter went back from {previous} to {now}"
+        );
+        previous = now;
+    }
+}
+
+#[test]
+fn monotonic_timer_is_monotonic() {
+    let mut previous = Monotonic.now();
+    for _ in 0..100_000 {
+        let now = Monotonic.now();
+        assert!(now >= previous, "clock went back from {previous} to {now}");
+        previous = now;
+    }
+}
+
+#[test]
+fn calibration_converges_on_a_known_rate() {
+    let start = Instant::now();
+    let nanos = || start.elapsed().as_nanos() as u64;
+    let frequency = calibrate_frequency(nanos) as f64;
+    assert!(
+        (frequency - 1e9).abs() / 1e9 < 0.01,
+        "calibrated a 1 GHz clock at {frequency} Hz"
+    );
+}
+
+#[test]
+fn calibration_is_repeatable() {
+    let first = calibrate_frequency(serialized_time) as f64;
+    let second = calibrate_frequency(serialized_time) as f64;
+    assert!(first > 0.0);
+    assert!(
+        (first - second).abs() / first < 0.05,
+        "calibrated {first} and then {second}"
+    );
+}
+
+#[test]
+fn overhead_subtraction_never_wraps() {
+    for timer in [TimerKind::Cycles, TimerKind::Monotonic] {
+        let overhead = Bencher::overhead(timer);
+        let mut bencher = Bencher::new(timer, overhead, 10_000);
+        bencher.iter(|| {});
+        // A sample below the overhead would wrap to near u64::MAX without saturation.
+        assert!(bencher
+            .samples()
+            .iter()
+            .all(|&sample| sample < u64::MAX / 2));
+
+        let mut bencher = Bencher::new(timer, u64::MAX, 1_000);
+        bencher.iter(|| {});
+        assert!(bencher.samples().iter().all(|&sample| sample == 0));
+    }
+}